@@ -0,0 +1,19 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+#[bench]
+fn dhcp_discover(bencher: &mut Bencher) {
+    let mut parser = DhcpParser;
+    let mut input = vec![0x01, 0x01, 0x06, 0x00, 0x39, 0x03, 0xf3, 0x26];
+    input.extend_from_slice(&[0x00; 228]);
+    input.extend_from_slice(&[0x63, 0x82, 0x53, 0x63, 0x35, 0x01, 0x01, 0x3d, 0x07, 0x01, 0x00, 0x0b, 0x82, 0x01,
+                              0xfc, 0x42, 0x37, 0x04, 0x01, 0x03, 0x06, 0x2a, 0xff]);
+    bencher.iter(|| {
+        parser.parse(&input, None, None).unwrap();
+    });
+    bencher.bytes = input.len() as u64;
+}
@@ -0,0 +1,20 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static PACKET: &'static [u8] = &[0x01, 0x12, 0x34, 0x56, 0x00, 0x01, 0x00, 0x0e, 0x00, 0x01, 0x00, 0x01, 0x1c, 0x39,
+                                 0xcf, 0x88, 0x08, 0x00, 0x27, 0xfe, 0x8f, 0x95, 0x00, 0x08, 0x00, 0x02, 0x00, 0x00,
+                                 0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18, 0x00, 0x0e, 0x00, 0x00, 0x00, 0x03,
+                                 0x00, 0x0c, 0x27, 0xfe, 0x8f, 0x95, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x15, 0x18];
+
+#[bench]
+fn dhcpv6_solicit(bencher: &mut Bencher) {
+    let mut parser = Dhcpv6Parser;
+    bencher.iter(|| {
+        parser.parse(PACKET, None, None).unwrap();
+    });
+    bencher.bytes = PACKET.len() as u64;
+}
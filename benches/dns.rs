@@ -0,0 +1,21 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static PACKET: &'static [u8] = &[0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x07, 0x65,
+                                 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00,
+                                 0x01, 0xc0, 0x0c, 0x00, 0x05, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x06, 0x03,
+                                 0x77, 0x77, 0x77, 0xc0, 0x0c, 0xc0, 0x29, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01,
+                                 0x2c, 0x00, 0x04, 0x5d, 0xb8, 0xd8, 0x22];
+
+#[bench]
+fn dns_response(bencher: &mut Bencher) {
+    let mut parser = DnsParser::default();
+    bencher.iter(|| {
+        parser.parse(PACKET, None, None).unwrap();
+    });
+    bencher.bytes = PACKET.len() as u64;
+}
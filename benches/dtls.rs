@@ -0,0 +1,33 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static PACKET: &'static [u8] =
+    &[0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3f, 0x01, 0x00, 0x00, 0x33, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x33, 0xfe, 0xfd, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+      0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+      0x22, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00, 0x00, 0x02, 0xc0, 0x2b, 0x01, 0x00, 0x00, 0x07, 0x00, 0x36, 0x00,
+      0x03, 0x02, 0xaa, 0xbb];
+
+#[bench]
+fn dtls_small_packet(bencher: &mut Bencher) {
+    let mut parser = DtlsParser;
+    bencher.iter(|| {
+        parser.parse(PACKET, None, None).unwrap();
+    });
+    bencher.bytes = PACKET.len() as u64;
+}
+
+#[bench]
+fn dtls_big_packet(bencher: &mut Bencher) {
+    let mut parser = DtlsParser;
+    let mut input = Vec::from(PACKET);
+    input.extend_from_slice(&[0xff; 1450]);
+    bencher.iter(|| {
+        parser.parse(&input, None, None).unwrap();
+    });
+    bencher.bytes = input.len() as u64;
+}
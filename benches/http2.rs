@@ -0,0 +1,29 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static PACKET: &'static [u8] = &[0x00, 0x00, 0x11, 0x01, 0x05, 0x00, 0x00, 0x00, 0x01, 0x82, 0x86, 0x84, 0x41, 0x8c,
+                                 0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab, 0x90, 0xf4, 0xff];
+
+#[bench]
+fn http2_headers(bencher: &mut Bencher) {
+    let mut parser = Http2Parser;
+    bencher.iter(|| {
+        parser.parse(PACKET, None, None).unwrap();
+    });
+    bencher.bytes = PACKET.len() as u64;
+}
+
+#[bench]
+fn http2_data(bencher: &mut Bencher) {
+    let mut parser = Http2Parser;
+    let mut input = vec![0x00, 0x05, 0xaa, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01];
+    input.extend_from_slice(&[0xff; 1450]);
+    bencher.iter(|| {
+        parser.parse(&input, None, None).unwrap();
+    });
+    bencher.bytes = input.len() as u64;
+}
@@ -11,7 +11,7 @@ static PACKET: &'static [u8] = &[0xca, 0x45, 0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a,
 
 #[bench]
 fn tcp_small_packet(bencher: &mut Bencher) {
-    let mut parser = TcpParser;
+    let mut parser = TcpParser::new();
     bencher.iter(|| {
         parser.parse(PACKET, None, None).unwrap();
     });
@@ -20,7 +20,7 @@ fn tcp_small_packet(bencher: &mut Bencher) {
 
 #[bench]
 fn tcp_big_packet(bencher: &mut Bencher) {
-    let mut parser = TcpParser;
+    let mut parser = TcpParser::new();
     let mut input = Vec::from(PACKET);
     input.extend_from_slice(&[0xff; 1450]);
     bencher.iter(|| {
@@ -9,7 +9,7 @@ static PACKET: &'static [u8] = &[0x00, 0x35, 0xc7, 0xde, 0x00, 0x92, 0xad, 0x1b]
 
 #[bench]
 fn udp_small_packet(bencher: &mut Bencher) {
-    let mut parser = UdpParser;
+    let mut parser = UdpParser::new();
     bencher.iter(|| {
         parser.parse(PACKET, None, None).unwrap();
     });
@@ -18,7 +18,7 @@ fn udp_small_packet(bencher: &mut Bencher) {
 
 #[bench]
 fn udp_big_packet(bencher: &mut Bencher) {
-    let mut parser = UdpParser;
+    let mut parser = UdpParser::new();
     let mut input = Vec::from(PACKET);
     input.extend_from_slice(&[0xff; 1450]);
     bencher.iter(|| {
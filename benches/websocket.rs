@@ -0,0 +1,28 @@
+#![feature(test)]
+extern crate peel_ip;
+extern crate test;
+
+use test::Bencher;
+use peel_ip::prelude::*;
+
+static PACKET: &'static [u8] = &[0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+
+#[bench]
+fn websocket_small_frame(bencher: &mut Bencher) {
+    let mut parser = WebSocketParser;
+    bencher.iter(|| {
+        parser.parse(PACKET, None, None).unwrap();
+    });
+    bencher.bytes = PACKET.len() as u64;
+}
+
+#[bench]
+fn websocket_big_frame(bencher: &mut Bencher) {
+    let mut parser = WebSocketParser;
+    let mut input = vec![0x82, 0xfe, 0x05, 0xaa, 0x37, 0xfa, 0x21, 0x3d];
+    input.extend_from_slice(&[0xff; 1450]);
+    bencher.iter(|| {
+        parser.parse(&input, None, None).unwrap();
+    });
+    bencher.bytes = input.len() as u64;
+}
@@ -0,0 +1,216 @@
+//! The compiled form of capture filters, which is evaluated on raw frames or translated to BPF
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// An arithmetic operator on unsigned 32 bit values
+pub enum ArithmeticOperator {
+    /// `+`
+    Add,
+
+    /// `-`
+    Subtract,
+
+    /// `*`
+    Multiply,
+
+    /// `/`
+    Divide,
+
+    /// `%`
+    Modulo,
+
+    /// `&`
+    And,
+
+    /// `|`
+    Or,
+
+    /// `^`
+    Xor,
+
+    /// `<<`
+    ShiftLeft,
+
+    /// `>>`
+    ShiftRight,
+}
+
+impl ArithmeticOperator {
+    /// Apply the operator, which is `None` for a division by zero. Overflows wrap around like
+    /// within the BPF machine.
+    pub fn apply(&self, left: u32, right: u32) -> Option<u32> {
+        Some(match *self {
+            ArithmeticOperator::Add => left.wrapping_add(right),
+            ArithmeticOperator::Subtract => left.wrapping_sub(right),
+            ArithmeticOperator::Multiply => left.wrapping_mul(right),
+            ArithmeticOperator::Divide => left.checked_div(right)?,
+            ArithmeticOperator::Modulo => left.checked_rem(right)?,
+            ArithmeticOperator::And => left & right,
+            ArithmeticOperator::Or => left | right,
+            ArithmeticOperator::Xor => left ^ right,
+            ArithmeticOperator::ShiftLeft => left.checked_shl(right).unwrap_or(0),
+            ArithmeticOperator::ShiftRight => left.checked_shr(right).unwrap_or(0),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The start of a packet load
+pub enum LoadBase {
+    /// The start of the frame
+    Frame,
+
+    /// The payload of the IPv4 header at the given offset, whose length is read from the frame
+    Ipv4Payload(u32),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// An unsigned 32 bit value
+pub enum CaptureValue {
+    /// A constant
+    Number(u32),
+
+    /// The length of the frame
+    Length,
+
+    /// A big endian load of 1, 2 or 4 bytes
+    Load(LoadBase, Box<CaptureValue>, u8),
+
+    /// An arithmetic operation
+    Arithmetic(ArithmeticOperator, Box<CaptureValue>, Box<CaptureValue>),
+}
+
+impl CaptureValue {
+    /// Create a load of the given size at a constant offset
+    pub fn load(base: LoadBase, offset: u32, size: u8) -> Self {
+        CaptureValue::Load(base, Box::new(CaptureValue::Number(offset)), size)
+    }
+
+    /// Create an arithmetic operation, where operations on constants are folded
+    pub fn arithmetic(operator: ArithmeticOperator, left: CaptureValue, right: CaptureValue) -> Self {
+        match (left, right) {
+            (CaptureValue::Number(left), CaptureValue::Number(right)) if operator.apply(left, right).is_some() => {
+                CaptureValue::Number(operator.apply(left, right).unwrap_or_default())
+            }
+            (left, right) => CaptureValue::Arithmetic(operator, Box::new(left), Box::new(right)),
+        }
+    }
+
+    /// Evaluate the value on a frame, which is `None` for loads beyond the end of the frame and
+    /// divisions by zero
+    pub fn evaluate(&self, frame: &[u8]) -> Option<u32> {
+        match *self {
+            CaptureValue::Number(number) => Some(number),
+            CaptureValue::Length => Some(frame.len() as u32),
+            CaptureValue::Load(base, ref offset, size) => {
+                let start = match base {
+                    LoadBase::Frame => 0,
+                    LoadBase::Ipv4Payload(header) => header + u32::from(*frame.get(header as usize)? & 0xf) * 4,
+                };
+                let start = start.checked_add(offset.evaluate(frame)?)? as usize;
+                let bytes = frame.get(start..start.checked_add(usize::from(size))?)?;
+                Some(bytes.iter().fold(0, |value, byte| value << 8 | u32::from(*byte)))
+            }
+            CaptureValue::Arithmetic(operator, ref left, ref right) => {
+                operator.apply(left.evaluate(frame)?, right.evaluate(frame)?)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// A comparison of two values, the others are expressed by negation
+pub enum Relation {
+    /// `==`
+    Equal,
+
+    /// `>`
+    Greater,
+
+    /// `>=`
+    GreaterEqual,
+}
+
+impl Relation {
+    /// Check if the relation holds
+    pub fn holds(&self, left: u32, right: u32) -> bool {
+        match *self {
+            Relation::Equal => left == right,
+            Relation::Greater => left > right,
+            Relation::GreaterEqual => left >= right,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A boolean expression over a frame
+pub enum CaptureExpression {
+    /// Matches every frame
+    True,
+
+    /// Matches no frame
+    False,
+
+    /// Both expressions have to match, the second one is only evaluated if the first matched
+    And(Box<CaptureExpression>, Box<CaptureExpression>),
+
+    /// One of the expressions has to match, the second one is only evaluated if the first did
+    /// not match
+    Or(Box<CaptureExpression>, Box<CaptureExpression>),
+
+    /// The expression must not match
+    Not(Box<CaptureExpression>),
+
+    /// The relation between both values has to hold
+    Compare(CaptureValue, Relation, CaptureValue),
+}
+
+impl CaptureExpression {
+    /// Combine two expressions with a logical and, where constant expressions are folded
+    pub fn and(self, other: CaptureExpression) -> Self {
+        match (self, other) {
+            (CaptureExpression::True, other) | (other, CaptureExpression::True) => other,
+            (CaptureExpression::False, _) => CaptureExpression::False,
+            (left, right) => CaptureExpression::And(Box::new(left), Box::new(right)),
+        }
+    }
+
+    /// Combine two expressions with a logical or, where constant expressions are folded
+    pub fn or(self, other: CaptureExpression) -> Self {
+        match (self, other) {
+            (CaptureExpression::False, other) | (other, CaptureExpression::False) => other,
+            (CaptureExpression::True, _) => CaptureExpression::True,
+            (left, right) => CaptureExpression::Or(Box::new(left), Box::new(right)),
+        }
+    }
+
+    /// Negate the expression, where constant expressions are folded
+    pub fn negate(self) -> Self {
+        match self {
+            CaptureExpression::True => CaptureExpression::False,
+            CaptureExpression::False => CaptureExpression::True,
+            CaptureExpression::Not(expression) => *expression,
+            expression => CaptureExpression::Not(Box::new(expression)),
+        }
+    }
+
+    /// Create a comparison of a value against a constant
+    pub fn compare(value: CaptureValue, relation: Relation, number: u32) -> Self {
+        CaptureExpression::Compare(value, relation, CaptureValue::Number(number))
+    }
+
+    /// Evaluate the expression on a frame, which is `None` if the frame has to be rejected
+    /// because of a load beyond its end or a division by zero. This follows the BPF machine,
+    /// which rejects the whole frame in these cases.
+    pub fn evaluate(&self, frame: &[u8]) -> Option<bool> {
+        Some(match *self {
+            CaptureExpression::True => true,
+            CaptureExpression::False => false,
+            CaptureExpression::And(ref left, ref right) => left.evaluate(frame)? && right.evaluate(frame)?,
+            CaptureExpression::Or(ref left, ref right) => left.evaluate(frame)? || right.evaluate(frame)?,
+            CaptureExpression::Not(ref expression) => !expression.evaluate(frame)?,
+            CaptureExpression::Compare(ref left, relation, ref right) => {
+                relation.holds(left.evaluate(frame)?, right.evaluate(frame)?)
+            }
+        })
+    }
+}
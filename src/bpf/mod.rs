@@ -0,0 +1,75 @@
+//! Capture filters in the pcap-filter syntax of libpcap, which are evaluated on raw frames or
+//! compiled to classic BPF programs
+//!
+//! The supported primitives are:
+//!
+//! - The protocols `ip`, `ip6`, `arp`, `rarp`, `tcp`, `udp`, `sctp`, `icmp`, `icmp6` and `igmp`
+//! - `[ether|ip|ip6] proto <protocol>`, like `ip6 proto 58` or `ether proto \arp`
+//! - `[ether|ip|ip6|arp|rarp] [src|dst|src or dst|src and dst] host <address>`
+//! - `[ip|ip6|arp|rarp] [src|dst] net <network>`, like `10.0.0.0/8`, `192.168` or
+//!   `10.0.0.0 mask 255.0.0.0`
+//! - `[ip|ip6|tcp|udp|sctp] [src|dst] port <port>` and `portrange <low>-<high>`
+//! - `vlan [<id>]`, which moves the network layer of all following primitives by the tag
+//! - `less <length>`, `greater <length>`, `[ether] broadcast` and `[ether|ip|ip6] multicast`
+//! - Relations of arithmetic expressions, like `tcp[tcpflags] & (tcp-syn|tcp-ack) != 0` or
+//!   `ip[2:2] - ((ip[0] & 0xf) << 2) > 100`
+//! - The boolean operators `and`, `or` and `not` (or `&&`, `||` and `!`) and parentheses, where
+//!   `and` and `or` have the same precedence and associate from left to right
+//!
+//! A bare identifier inherits the qualifiers of the previous primitive, so `port 80 or 443` is
+//! the same as `port 80 or port 443`. Like within libpcap, the loads of `tcp`, `udp` and the
+//! other transport protocols refer to IPv4 packets only and IPv6 extension headers are not
+//! followed. Host names and port names beyond a few well known ones are not resolved.
+use prelude::*;
+
+mod expression;
+mod parser;
+mod program;
+
+pub use self::expression::*;
+pub use self::program::{BpfInstruction, BpfProgram, BPF_ACCEPT};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A compiled capture filter
+pub struct CaptureFilter {
+    /// The expression evaluated on the frames
+    pub expression: CaptureExpression,
+
+    /// The link type of the frames
+    pub link_type: LinkType,
+}
+
+impl CaptureFilter {
+    /// Compile a filter for Ethernet frames
+    pub fn new(input: &str) -> Result<Self, FilterError> {
+        CaptureFilter::with_link_type(input, LinkType::Ethernet)
+    }
+
+    /// Compile a filter for frames of the given link type, which fails for primitives not
+    /// available on the link type, like `ether host` on raw IP packets
+    pub fn with_link_type(input: &str, link_type: LinkType) -> Result<Self, FilterError> {
+        Ok(CaptureFilter {
+            expression: parser::parse(input, link_type)?,
+            link_type: link_type,
+        })
+    }
+
+    /// Check if a raw frame matches the filter
+    pub fn matches(&self, frame: &[u8]) -> bool {
+        self.expression.evaluate(frame).unwrap_or(false)
+    }
+
+    /// Generate a classic BPF program of the filter, which fails if the filter needs more than
+    /// the scratch memory or conditional jumps over more than 255 instructions
+    pub fn to_bpf(&self) -> Result<BpfProgram, String> {
+        program::Generator::generate(&self.expression)
+    }
+}
+
+impl FromStr for CaptureFilter {
+    type Err = FilterError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        CaptureFilter::new(input)
+    }
+}
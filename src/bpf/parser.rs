@@ -0,0 +1,1047 @@
+//! The parser of the pcap-filter syntax, which generates the expressions while parsing like
+//! libpcap does
+use super::*;
+use std::mem;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A token of a capture filter
+enum Token {
+    /// `(`
+    Open,
+
+    /// `)`
+    Close,
+
+    /// `[`
+    OpenIndex,
+
+    /// `]`
+    CloseIndex,
+
+    /// `:`
+    Colon,
+
+    /// `!` or `not`
+    Not,
+
+    /// `&&` or `and`
+    And,
+
+    /// `||` or `or`
+    Or,
+
+    /// A relational operator and whether it is negated, like `!=` or `<`
+    Relation(Relation, bool, bool),
+
+    /// An arithmetic operator
+    Arithmetic(ArithmeticOperator),
+
+    /// A keyword, identifier, number or address
+    Word(String),
+}
+
+/// Split an expression into tokens with their byte positions
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, FilterError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    let mut index_depth = 0;
+    while let Some((position, c)) = chars.next() {
+        let mut followed_by = |expected: char| chars.next_if(|&(_, c)| c == expected).is_some();
+
+        // The relations are written as (relation, swapped operands, negated)
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '[' => {
+                index_depth += 1;
+                Token::OpenIndex
+            }
+            ']' => {
+                index_depth -= 1;
+                Token::CloseIndex
+            }
+            ':' => Token::Colon,
+            '&' if followed_by('&') => Token::And,
+            '|' if followed_by('|') => Token::Or,
+            '!' if followed_by('=') => Token::Relation(Relation::Equal, false, true),
+            '!' => Token::Not,
+            '=' => {
+                followed_by('=');
+                Token::Relation(Relation::Equal, false, false)
+            }
+            '<' if followed_by('<') => Token::Arithmetic(ArithmeticOperator::ShiftLeft),
+            '<' if followed_by('=') => Token::Relation(Relation::GreaterEqual, true, false),
+            '<' => Token::Relation(Relation::Greater, true, false),
+            '>' if followed_by('>') => Token::Arithmetic(ArithmeticOperator::ShiftRight),
+            '>' if followed_by('=') => Token::Relation(Relation::GreaterEqual, false, false),
+            '>' => Token::Relation(Relation::Greater, false, false),
+            '+' => Token::Arithmetic(ArithmeticOperator::Add),
+            '-' => Token::Arithmetic(ArithmeticOperator::Subtract),
+            '*' => Token::Arithmetic(ArithmeticOperator::Multiply),
+            '/' => Token::Arithmetic(ArithmeticOperator::Divide),
+            '%' => Token::Arithmetic(ArithmeticOperator::Modulo),
+            '&' => Token::Arithmetic(ArithmeticOperator::And),
+            '|' => Token::Arithmetic(ArithmeticOperator::Or),
+            '^' => Token::Arithmetic(ArithmeticOperator::Xor),
+            c if c.is_alphanumeric() || c == '_' || c == '\\' => {
+                // Colons belong to addresses outside of indices, slashes to networks
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.next_if(|&(_, c)| {
+                    c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || (c == ':' && index_depth == 0) ||
+                    (c == '/' && (word.contains('.') || word.contains(':')))
+                }) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                }
+            }
+            c => return Err(error(position, format!("Unexpected character '{}'", c))),
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+/// Create an error at the given position
+fn error<S: Into<String>>(position: usize, message: S) -> FilterError {
+    FilterError {
+        position: position,
+        message: message.into(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// A protocol qualifier
+enum Qualifier {
+    Ether,
+    Ip,
+    Ip6,
+    Arp,
+    Rarp,
+    Tcp,
+    Udp,
+    Sctp,
+    Icmp,
+    Icmp6,
+    Igmp,
+}
+
+impl Qualifier {
+    /// Parse a qualifier keyword
+    fn from_word(word: &str) -> Option<Self> {
+        Some(match word {
+            "ether" => Qualifier::Ether,
+            "ip" => Qualifier::Ip,
+            "ip6" => Qualifier::Ip6,
+            "arp" => Qualifier::Arp,
+            "rarp" => Qualifier::Rarp,
+            "tcp" => Qualifier::Tcp,
+            "udp" => Qualifier::Udp,
+            "sctp" => Qualifier::Sctp,
+            "icmp" => Qualifier::Icmp,
+            "icmp6" => Qualifier::Icmp6,
+            "igmp" => Qualifier::Igmp,
+            _ => return None,
+        })
+    }
+
+    /// The keyword of the qualifier
+    fn name(&self) -> &'static str {
+        match *self {
+            Qualifier::Ether => "ether",
+            Qualifier::Ip => "ip",
+            Qualifier::Ip6 => "ip6",
+            Qualifier::Arp => "arp",
+            Qualifier::Rarp => "rarp",
+            Qualifier::Tcp => "tcp",
+            Qualifier::Udp => "udp",
+            Qualifier::Sctp => "sctp",
+            Qualifier::Icmp => "icmp",
+            Qualifier::Icmp6 => "icmp6",
+            Qualifier::Igmp => "igmp",
+        }
+    }
+
+    /// The IP protocol number of transport protocols
+    fn ip_protocol(&self) -> Option<u32> {
+        match *self {
+            Qualifier::Tcp => Some(6),
+            Qualifier::Udp => Some(17),
+            Qualifier::Sctp => Some(132),
+            Qualifier::Icmp => Some(1),
+            Qualifier::Icmp6 => Some(58),
+            Qualifier::Igmp => Some(2),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// A direction qualifier
+enum Direction {
+    /// `src`
+    Source,
+
+    /// `dst`
+    Destination,
+
+    /// `src or dst`, which is the default
+    Any,
+
+    /// `src and dst`
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// A type qualifier
+enum Kind {
+    Host,
+    Net,
+    Port,
+    PortRange,
+}
+
+/// The qualifiers of a primitive, which are inherited by following bare identifiers
+type Qualifiers = (Option<Qualifier>, Direction, Kind);
+
+/// The ethertypes of VLAN tags
+const VLAN_ETHERTYPES: [u32; 3] = [0x8100, 0x88a8, 0x9100];
+
+/// Named constants of the arithmetic expressions
+const CONSTANTS: [(&str, u32); 31] = [("tcpflags", 13),
+                                      ("tcp-fin", 0x01),
+                                      ("tcp-syn", 0x02),
+                                      ("tcp-rst", 0x04),
+                                      ("tcp-push", 0x08),
+                                      ("tcp-ack", 0x10),
+                                      ("tcp-urg", 0x20),
+                                      ("tcp-ece", 0x40),
+                                      ("tcp-cwr", 0x80),
+                                      ("icmptype", 0),
+                                      ("icmpcode", 1),
+                                      ("icmp-echoreply", 0),
+                                      ("icmp-unreach", 3),
+                                      ("icmp-sourcequench", 4),
+                                      ("icmp-redirect", 5),
+                                      ("icmp-echo", 8),
+                                      ("icmp-routeradvert", 9),
+                                      ("icmp-routersolicit", 10),
+                                      ("icmp-timxceed", 11),
+                                      ("icmp-paramprob", 12),
+                                      ("icmp-tstamp", 13),
+                                      ("icmp-tstampreply", 14),
+                                      ("icmp6type", 0),
+                                      ("icmp6code", 1),
+                                      ("icmp6-destinationunreach", 1),
+                                      ("icmp6-echo", 128),
+                                      ("icmp6-echoreply", 129),
+                                      ("icmp6-routersolicit", 133),
+                                      ("icmp6-routeradvert", 134),
+                                      ("icmp6-neighborsolicit", 135),
+                                      ("icmp6-neighboradvert", 136)];
+
+/// Well known port names
+const PORTS: [(&str, u32); 12] = [("ftp-data", 20),
+                                  ("ftp", 21),
+                                  ("ssh", 22),
+                                  ("telnet", 23),
+                                  ("smtp", 25),
+                                  ("domain", 53),
+                                  ("http", 80),
+                                  ("pop3", 110),
+                                  ("ntp", 123),
+                                  ("imap", 143),
+                                  ("https", 443),
+                                  ("mdns", 5353)];
+
+/// Parse a capture filter for frames of the given link type
+pub fn parse(input: &str, link_type: LinkType) -> Result<CaptureExpression, FilterError> {
+    match link_type {
+        LinkType::Ethernet | LinkType::Raw | LinkType::Ipv4 | LinkType::Ipv6 => {}
+        LinkType::Unknown(link_type) => return Err(error(0, format!("Unsupported link type {}", link_type))),
+    }
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        end: input.len(),
+        link_type: link_type,
+        vlans: 0,
+        qualifiers: None,
+        committed: false,
+    };
+
+    // An empty filter matches all frames
+    if tokens.is_empty() {
+        return Ok(CaptureExpression::True);
+    }
+    let expression = parser.expression()?;
+    match parser.peek() {
+        Some(&(position, _)) => Err(error(position, "Unexpected token")),
+        None => Ok(expression),
+    }
+}
+
+/// A recursive descent parser over the tokens
+struct Parser<'a> {
+    /// All tokens of the expression
+    tokens: &'a [(usize, Token)],
+
+    /// The index of the next token
+    position: usize,
+
+    /// The length of the expression, which is the position of errors at its end
+    end: usize,
+
+    /// The link type of the frames
+    link_type: LinkType,
+
+    /// The amount of VLAN tags, which move the network layer for all following primitives
+    vlans: u32,
+
+    /// The qualifiers of the last primitive
+    qualifiers: Option<Qualifiers>,
+
+    /// The current relation contains a load or a relational operator
+    committed: bool,
+}
+
+impl<'a> Parser<'a> {
+    /// The next token without consuming it
+    fn peek(&self) -> Option<&'a (usize, Token)> {
+        self.tokens.get(self.position)
+    }
+
+    /// The next word without consuming it
+    fn peek_word(&self) -> Option<&'a str> {
+        match self.peek() {
+            Some(&(_, Token::Word(ref word))) => Some(word),
+            _ => None,
+        }
+    }
+
+    /// The position of the next token, or the end of the expression
+    fn next_position(&self) -> usize {
+        self.peek().map_or(self.end, |token| token.0)
+    }
+
+    /// Consume the next token, which is an error at the end of the expression
+    fn next(&mut self, expected: &str) -> Result<&'a (usize, Token), FilterError> {
+        let token = self.tokens
+            .get(self.position)
+            .ok_or_else(|| error(self.end, format!("Missing {}", expected)))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Consume the next token if it is the given one
+    fn accept(&mut self, token: &Token) -> bool {
+        let accepted = self.peek().is_some_and(|next| next.1 == *token);
+        if accepted {
+            self.position += 1;
+        }
+        accepted
+    }
+
+    /// Consume the next token if it is the given keyword
+    fn accept_word(&mut self, keyword: &str) -> bool {
+        let accepted = self.peek_word() == Some(keyword);
+        if accepted {
+            self.position += 1;
+        }
+        accepted
+    }
+
+    /// Consume the next word
+    fn word(&mut self, expected: &str) -> Result<(usize, &'a str), FilterError> {
+        match *self.next(expected)? {
+            (position, Token::Word(ref word)) => Ok((position, word)),
+            (position, _) => Err(error(position, format!("Expected {}", expected))),
+        }
+    }
+
+    /// Parse alternatives and concatenations, which have the same precedence and associate from
+    /// left to right
+    fn expression(&mut self) -> Result<CaptureExpression, FilterError> {
+        let mut expression = self.term()?;
+        loop {
+            if self.accept(&Token::And) {
+                expression = expression.and(self.term()?);
+            } else if self.accept(&Token::Or) {
+                expression = expression.or(self.term()?);
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    /// Parse a negation, a parenthesized expression, a relation or a primitive
+    fn term(&mut self) -> Result<CaptureExpression, FilterError> {
+        if self.accept(&Token::Not) {
+            return Ok(self.term()?.negate());
+        }
+
+        // Parentheses may enclose an arithmetic expression as well, so a relation is tried first.
+        // Errors are reported once a load or a relational operator was parsed.
+        let start = self.position;
+        self.committed = false;
+        match self.relation() {
+            Ok(Some(relation)) => return Ok(relation),
+            Err(e) if self.committed => return Err(e),
+            _ => self.position = start,
+        }
+        if self.accept(&Token::Open) {
+            let expression = self.expression()?;
+            return match *self.next("')'")? {
+                (_, Token::Close) => Ok(expression),
+                (position, _) => Err(error(position, "Expected ')'")),
+            };
+        }
+        self.primitive()
+    }
+
+    /// Parse a relation of two arithmetic expressions, which is `None` if there is no relational
+    /// operator after the first expression
+    fn relation(&mut self) -> Result<Option<CaptureExpression>, FilterError> {
+        let mut conditions = CaptureExpression::True;
+        let left = self.arithmetic(&mut conditions, 0)?;
+        let (relation, swapped, negated) = match self.peek() {
+            Some(&(_, Token::Relation(relation, swapped, negated))) => (relation, swapped, negated),
+            _ => return Ok(None),
+        };
+        self.position += 1;
+        self.committed = true;
+        let right = self.arithmetic(&mut conditions, 0)?;
+        let comparison = match swapped {
+            true => CaptureExpression::Compare(right, relation, left),
+            false => CaptureExpression::Compare(left, relation, right),
+        };
+        Ok(Some(conditions.and(if negated { comparison.negate() } else { comparison })))
+    }
+
+    /// Parse an arithmetic expression with operators of at least the given precedence, the
+    /// protocol conditions of the loads are added to the conditions
+    fn arithmetic(&mut self,
+                  conditions: &mut CaptureExpression,
+                  precedence: u8)
+                  -> Result<CaptureValue, FilterError> {
+        let mut left = self.operand(conditions)?;
+        while let Some(&(position, Token::Arithmetic(operator))) = self.peek() {
+            let operator_precedence = match operator {
+                ArithmeticOperator::Or | ArithmeticOperator::Xor => 0,
+                ArithmeticOperator::And => 1,
+                ArithmeticOperator::ShiftLeft | ArithmeticOperator::ShiftRight => 2,
+                ArithmeticOperator::Add | ArithmeticOperator::Subtract => 3,
+                ArithmeticOperator::Multiply | ArithmeticOperator::Divide | ArithmeticOperator::Modulo => 4,
+            };
+            if operator_precedence < precedence {
+                break;
+            }
+            self.position += 1;
+            let right = self.arithmetic(conditions, operator_precedence + 1)?;
+            let division = operator == ArithmeticOperator::Divide || operator == ArithmeticOperator::Modulo;
+            if division && right == CaptureValue::Number(0) {
+                return Err(error(position, "Division by zero"));
+            }
+            left = CaptureValue::arithmetic(operator, left, right);
+        }
+        Ok(left)
+    }
+
+    /// Parse a number, a constant, the frame length, a packet load or a parenthesized arithmetic
+    /// expression
+    fn operand(&mut self, conditions: &mut CaptureExpression) -> Result<CaptureValue, FilterError> {
+        let (position, word) = match *self.next("value")? {
+            (_, Token::Open) => {
+                let value = self.arithmetic(conditions, 0)?;
+                return match *self.next("')'")? {
+                    (_, Token::Close) => Ok(value),
+                    (position, _) => Err(error(position, "Expected ')'")),
+                };
+            }
+            (_, Token::Arithmetic(ArithmeticOperator::Subtract)) => {
+                let value = self.operand(conditions)?;
+                return Ok(CaptureValue::arithmetic(ArithmeticOperator::Subtract, CaptureValue::Number(0), value));
+            }
+            (position, Token::Word(ref word)) => (position, word.as_str()),
+            (position, _) => return Err(error(position, "Expected value")),
+        };
+        if word == "len" {
+            return Ok(CaptureValue::Length);
+        }
+        if let Some(&(_, value)) = CONSTANTS.iter().find(|constant| constant.0 == word) {
+            return Ok(CaptureValue::Number(value));
+        }
+        if let Some(number) = parse_number(word) {
+            return Ok(CaptureValue::Number(number));
+        }
+        let qualifier = Qualifier::from_word(word)
+            .filter(|_| self.peek().is_some_and(|token| token.1 == Token::OpenIndex))
+            .ok_or_else(|| error(position, format!("Invalid value '{}'", word)))?;
+
+        // A load like `tcp[13]` or `ip[2:2]`
+        self.position += 1;
+        self.committed = true;
+        let offset = self.arithmetic(conditions, 0)?;
+        let size = if self.accept(&Token::Colon) {
+            match self.word("load size")? {
+                (_, "1") => 1,
+                (_, "2") => 2,
+                (_, "4") => 4,
+                (position, size) => return Err(error(position, format!("Invalid load size '{}'", size))),
+            }
+        } else {
+            1
+        };
+        match *self.next("']'")? {
+            (_, Token::CloseIndex) => {}
+            (position, _) => return Err(error(position, "Expected ']'")),
+        }
+        let (base, start, condition) = self.load_base(qualifier, position)?;
+        let offset = match offset {
+            CaptureValue::Number(offset) if start.checked_add(offset).is_some() => CaptureValue::Number(start + offset),
+            offset if start == 0 => offset,
+            offset => CaptureValue::arithmetic(ArithmeticOperator::Add, offset, CaptureValue::Number(start)),
+        };
+        *conditions = mem::replace(conditions, CaptureExpression::True).and(condition);
+        Ok(CaptureValue::Load(base, Box::new(offset), size))
+    }
+
+    /// The start of the loads of a protocol and the condition that the protocol is present
+    fn load_base(&self,
+                 qualifier: Qualifier,
+                 position: usize)
+                 -> Result<(LoadBase, u32, CaptureExpression), FilterError> {
+        let network = self.network_offset();
+        Ok(match qualifier {
+            Qualifier::Ether => (LoadBase::Frame, self.ether_offset(position, "ether")?, CaptureExpression::True),
+            Qualifier::Ip => (LoadBase::Frame, network, self.ethertype(0x800)),
+            Qualifier::Ip6 => (LoadBase::Frame, network, self.ethertype(0x86dd)),
+            Qualifier::Arp => (LoadBase::Frame, network, self.ethertype(0x806)),
+            Qualifier::Rarp => (LoadBase::Frame, network, self.ethertype(0x8035)),
+            Qualifier::Icmp6 => (LoadBase::Frame, network + 40, self.ip_protocol(Qualifier::Ip6, 58)),
+            transport => {
+                let protocol = transport.ip_protocol().unwrap_or_default();
+                (LoadBase::Ipv4Payload(network), 0, self.ip_protocol(Qualifier::Ip, protocol).and(self.unfragmented()))
+            }
+        })
+    }
+
+    /// The offset of the Ethernet header, which is an error for other link types
+    fn ether_offset(&self, position: usize, keyword: &str) -> Result<u32, FilterError> {
+        match self.link_type {
+            LinkType::Ethernet => Ok(0),
+            link_type => Err(error(position, format!("'{}' is not supported on link type {:?}", keyword, link_type))),
+        }
+    }
+
+    /// The offset of the network layer
+    fn network_offset(&self) -> u32 {
+        match self.link_type {
+            LinkType::Ethernet => 14 + 4 * self.vlans,
+            _ => 0,
+        }
+    }
+
+    /// The condition that the network layer has the given ethertype
+    fn ethertype(&self, ethertype: u32) -> CaptureExpression {
+        let version = |version| {
+            let value = CaptureValue::arithmetic(ArithmeticOperator::And,
+                                                 CaptureValue::load(LoadBase::Frame, 0, 1),
+                                                 CaptureValue::Number(0xf0));
+            CaptureExpression::compare(value, Relation::Equal, version)
+        };
+        match (self.link_type, ethertype) {
+            (LinkType::Ethernet, _) => {
+                CaptureExpression::compare(CaptureValue::load(LoadBase::Frame, 12 + 4 * self.vlans, 2),
+                                           Relation::Equal,
+                                           ethertype)
+            }
+            (LinkType::Raw, 0x800) => version(0x40),
+            (LinkType::Raw, 0x86dd) => version(0x60),
+            (LinkType::Ipv4, 0x800) | (LinkType::Ipv6, 0x86dd) => CaptureExpression::True,
+            _ => CaptureExpression::False,
+        }
+    }
+
+    /// The condition that the IP layer of the given version carries the protocol
+    fn ip_protocol(&self, version: Qualifier, protocol: u32) -> CaptureExpression {
+        let network = self.network_offset();
+        match version {
+            Qualifier::Ip => {
+                self.ethertype(0x800).and(CaptureExpression::compare(CaptureValue::load(LoadBase::Frame,
+                                                                                        network + 9,
+                                                                                        1),
+                                                                     Relation::Equal,
+                                                                     protocol))
+            }
+            _ => {
+                self.ethertype(0x86dd).and(CaptureExpression::compare(CaptureValue::load(LoadBase::Frame,
+                                                                                         network + 6,
+                                                                                         1),
+                                                                      Relation::Equal,
+                                                                      protocol))
+            }
+        }
+    }
+
+    /// The condition that an IPv4 packet is no fragment or the first one
+    fn unfragmented(&self) -> CaptureExpression {
+        let flags = CaptureValue::load(LoadBase::Frame, self.network_offset() + 6, 2);
+        let fragment_offset = CaptureValue::arithmetic(ArithmeticOperator::And, flags, CaptureValue::Number(0x1fff));
+        CaptureExpression::compare(fragment_offset, Relation::Equal, 0)
+    }
+
+    /// The condition that a transport protocol is present in IPv4 or IPv6
+    fn transport(&self, qualifier: Qualifier) -> CaptureExpression {
+        let protocol = qualifier.ip_protocol().unwrap_or_default();
+        match qualifier {
+            Qualifier::Icmp | Qualifier::Igmp => self.ip_protocol(Qualifier::Ip, protocol),
+            Qualifier::Icmp6 => self.ip_protocol(Qualifier::Ip6, protocol),
+            _ => self.ip_protocol(Qualifier::Ip, protocol).or(self.ip_protocol(Qualifier::Ip6, protocol)),
+        }
+    }
+
+    /// Parse a primitive like `tcp`, `src host 10.0.0.1`, `vlan 100` or a bare identifier, which
+    /// inherits the qualifiers of the previous primitive
+    fn primitive(&mut self) -> Result<CaptureExpression, FilterError> {
+        let position = self.next_position();
+        let qualifier = self.peek_word().and_then(Qualifier::from_word);
+        if qualifier.is_some() {
+            self.position += 1;
+        }
+
+        // Primitives with their own syntax
+        match self.peek_word() {
+            Some("proto") => {
+                self.position += 1;
+                return self.protocol(qualifier, position);
+            }
+            Some(keyword @ "broadcast") | Some(keyword @ "multicast") => {
+                self.position += 1;
+                return self.broadcast(qualifier, keyword == "broadcast", position);
+            }
+            Some(keyword @ "vlan") | Some(keyword @ "less") | Some(keyword @ "greater") | Some(keyword @ "gateway")
+                if qualifier.is_none() => {
+                self.position += 1;
+                return self.keyword(keyword, position);
+            }
+            _ => {}
+        }
+
+        let direction = if self.accept_word("src") {
+            Some(self.direction(Direction::Source))
+        } else if self.accept_word("dst") {
+            Some(self.direction(Direction::Destination))
+        } else {
+            None
+        };
+        let kind = match self.peek_word() {
+            Some("host") => Some(Kind::Host),
+            Some("net") => Some(Kind::Net),
+            Some("port") => Some(Kind::Port),
+            Some("portrange") => Some(Kind::PortRange),
+            _ => None,
+        };
+        if kind.is_some() {
+            self.position += 1;
+        }
+
+        let qualifiers = match (qualifier, direction, kind) {
+            (Some(qualifier), None, None) if !self.peek_word().is_some_and(is_identifier) => {
+                return match qualifier {
+                    Qualifier::Ether => Err(error(position, "'ether' requires a qualifier like 'host'")),
+                    Qualifier::Ip => Ok(self.ethertype(0x800)),
+                    Qualifier::Ip6 => Ok(self.ethertype(0x86dd)),
+                    Qualifier::Arp => Ok(self.ethertype(0x806)),
+                    Qualifier::Rarp => Ok(self.ethertype(0x8035)),
+                    transport => Ok(self.transport(transport)),
+                };
+            }
+            (None, None, None) => {
+                let (position, word) = match self.peek() {
+                    Some(&(position, Token::Word(ref word))) => (position, word.as_str()),
+                    _ => return Err(error(self.next_position(), "Expected primitive")),
+                };
+                match self.qualifiers {
+                    Some(qualifiers) if is_identifier(word) => qualifiers,
+                    None if word.parse::<IpAddr>().is_ok() => (None, Direction::Any, Kind::Host),
+                    _ => return Err(error(position, format!("Unknown primitive '{}'", word))),
+                }
+            }
+            (qualifier, direction, kind) => {
+                (qualifier, direction.unwrap_or(Direction::Any), kind.unwrap_or(Kind::Host))
+            }
+        };
+        self.qualifiers = Some(qualifiers);
+        let (position, id) = self.word("identifier")?;
+        match qualifiers.2 {
+            Kind::Host => self.host(qualifiers, id, position),
+            Kind::Net => self.net(qualifiers, id, position),
+            Kind::Port | Kind::PortRange => self.port(qualifiers, id, position),
+        }
+    }
+
+    /// Complete a direction, which may be `src or dst` and `src and dst`
+    fn direction(&mut self, direction: Direction) -> Direction {
+        let combined = |token: &Token| match *token {
+            Token::Or => Some(Direction::Any),
+            Token::And => Some(Direction::Both),
+            _ => None,
+        };
+        let other = match direction {
+            Direction::Source => "dst",
+            _ => "src",
+        };
+        match (self.peek().and_then(|token| combined(&token.1)), self.tokens.get(self.position + 1)) {
+            (Some(direction), Some(&(_, Token::Word(ref word)))) if word == other => {
+                self.position += 2;
+                direction
+            }
+            _ => direction,
+        }
+    }
+
+    /// Parse the protocol of `proto`, `ip proto`, `ip6 proto` or `ether proto`
+    fn protocol(&mut self, qualifier: Option<Qualifier>, position: usize) -> Result<CaptureExpression, FilterError> {
+        let (id_position, id) = self.word("protocol")?;
+        let name = id.trim_start_matches('\\');
+        let invalid = || error(id_position, format!("Unknown protocol '{}'", id));
+        match qualifier {
+            Some(Qualifier::Ether) => {
+                let ethertype = match name {
+                    "ip" => 0x800,
+                    "ip6" => 0x86dd,
+                    "arp" => 0x806,
+                    "rarp" => 0x8035,
+                    name => parse_number(name).ok_or_else(invalid)?,
+                };
+                self.ether_offset(position, "ether proto")?;
+                Ok(self.ethertype(ethertype))
+            }
+            None | Some(Qualifier::Ip) | Some(Qualifier::Ip6) => {
+                let protocol = match Qualifier::from_word(name).and_then(|q| q.ip_protocol()) {
+                    Some(protocol) => protocol,
+                    None => parse_number(name).filter(|number| *number <= 0xff).ok_or_else(invalid)?,
+                };
+                Ok(match qualifier {
+                    Some(version) => self.ip_protocol(version, protocol),
+                    None => self.ip_protocol(Qualifier::Ip, protocol).or(self.ip_protocol(Qualifier::Ip6, protocol)),
+                })
+            }
+            Some(qualifier) => Err(error(position, format!("'{}' modifier applied to 'proto'", qualifier.name()))),
+        }
+    }
+
+    /// Parse `broadcast` or `multicast` with an optional protocol
+    fn broadcast(&mut self,
+                 qualifier: Option<Qualifier>,
+                 broadcast: bool,
+                 position: usize)
+                 -> Result<CaptureExpression, FilterError> {
+        let byte = |offset: u32| CaptureValue::load(LoadBase::Frame, offset, 1);
+        match (qualifier, broadcast) {
+            (None, _) | (Some(Qualifier::Ether), _) => {
+                self.ether_offset(position, if broadcast { "broadcast" } else { "multicast" })?;
+                Ok(match broadcast {
+                    true => self.mac_address(0, &[0xff; 6]),
+                    false => {
+                        let group = CaptureValue::arithmetic(ArithmeticOperator::And, byte(0), CaptureValue::Number(1));
+                        CaptureExpression::compare(group, Relation::Equal, 1)
+                    }
+                })
+            }
+            (Some(Qualifier::Ip), false) => {
+                let network = self.network_offset();
+                let multicast = CaptureExpression::compare(byte(network + 16), Relation::GreaterEqual, 224);
+                Ok(self.ethertype(0x800).and(multicast))
+            }
+            (Some(Qualifier::Ip6), false) => {
+                let network = self.network_offset();
+                Ok(self.ethertype(0x86dd).and(CaptureExpression::compare(byte(network + 24), Relation::Equal, 0xff)))
+            }
+            (Some(Qualifier::Ip), true) => {
+                Err(error(position, "'ip broadcast' requires a netmask, which is not supported"))
+            }
+            (Some(qualifier), _) => {
+                let keyword = if broadcast { "broadcast" } else { "multicast" };
+                Err(error(position, format!("'{}' modifier applied to '{}'", qualifier.name(), keyword)))
+            }
+        }
+    }
+
+    /// Parse the primitives `vlan`, `less` and `greater`
+    fn keyword(&mut self, keyword: &str, position: usize) -> Result<CaptureExpression, FilterError> {
+        match keyword {
+            "vlan" => {
+                self.ether_offset(position, "vlan")?;
+                let ethertype = CaptureValue::load(LoadBase::Frame, 12 + 4 * self.vlans, 2);
+                let mut expression = VLAN_ETHERTYPES.iter().fold(CaptureExpression::False, |expression, tag| {
+                    expression.or(CaptureExpression::compare(ethertype.clone(), Relation::Equal, *tag))
+                });
+                if let Some(id) = self.peek_word().and_then(parse_number) {
+                    if id > 0xfff {
+                        return Err(error(self.next_position(), format!("Invalid VLAN id {}", id)));
+                    }
+                    self.position += 1;
+                    let tci = CaptureValue::load(LoadBase::Frame, 14 + 4 * self.vlans, 2);
+                    let vid = CaptureValue::arithmetic(ArithmeticOperator::And, tci, CaptureValue::Number(0xfff));
+                    expression = expression.and(CaptureExpression::compare(vid, Relation::Equal, id));
+                }
+                self.vlans += 1;
+                Ok(expression)
+            }
+            "less" | "greater" => {
+                let (position, length) = self.word("length")?;
+                let length = parse_number(length)
+                    .ok_or_else(|| error(position, format!("Invalid length '{}'", length)))?;
+                Ok(match keyword {
+                    "less" => CaptureExpression::compare(CaptureValue::Length, Relation::Greater, length).negate(),
+                    _ => CaptureExpression::compare(CaptureValue::Length, Relation::GreaterEqual, length),
+                })
+            }
+            _ => Err(error(position, "'gateway' is not supported")),
+        }
+    }
+
+    /// Combine the source and destination conditions depending on the direction
+    fn directed<F>(&self, direction: Direction, condition: F) -> CaptureExpression
+        where F: Fn(bool) -> CaptureExpression
+    {
+        match direction {
+            Direction::Source => condition(true),
+            Direction::Destination => condition(false),
+            Direction::Any => condition(true).or(condition(false)),
+            Direction::Both => condition(true).and(condition(false)),
+        }
+    }
+
+    /// The condition that the bytes at the offset equal the address under the mask
+    fn masked(&self, offset: u32, address: &[u8], mask: &[u8]) -> CaptureExpression {
+        let word = |bytes: &[u8]| bytes.iter().fold(0, |value, byte| value << 8 | u32::from(*byte));
+        let words = address.chunks(4).zip(mask.chunks(4)).enumerate();
+        words.fold(CaptureExpression::True, |expression, (index, (address, mask))| {
+            let load = CaptureValue::load(LoadBase::Frame, offset + 4 * index as u32, 4);
+            let condition = match word(mask) {
+                0 => return expression,
+                0xffff_ffff => CaptureExpression::compare(load, Relation::Equal, word(address)),
+                mask => {
+                    let value = CaptureValue::arithmetic(ArithmeticOperator::And, load, CaptureValue::Number(mask));
+                    CaptureExpression::compare(value, Relation::Equal, word(address) & mask)
+                }
+            };
+            expression.and(condition)
+        })
+    }
+
+    /// The condition that the MAC address at the offset equals the given one
+    fn mac_address(&self, offset: u32, address: &[u8; 6]) -> CaptureExpression {
+        let high = address[..2].iter().fold(0, |value, byte| value << 8 | u32::from(*byte));
+        let low = address[2..].iter().fold(0, |value, byte| value << 8 | u32::from(*byte));
+        CaptureExpression::compare(CaptureValue::load(LoadBase::Frame, offset + 2, 4), Relation::Equal, low)
+            .and(CaptureExpression::compare(CaptureValue::load(LoadBase::Frame, offset, 2), Relation::Equal, high))
+    }
+
+    /// The condition that the network addresses match under the mask
+    fn addresses(&self,
+                 qualifier: Option<Qualifier>,
+                 direction: Direction,
+                 address: IpAddr,
+                 mask: &[u8],
+                 kind: &str,
+                 position: usize)
+                 -> Result<CaptureExpression, FilterError> {
+        let network = self.network_offset();
+        match (qualifier, address) {
+            (None, IpAddr::V4(_)) | (Some(Qualifier::Ip), IpAddr::V4(_)) | (Some(Qualifier::Arp), IpAddr::V4(_)) |
+            (Some(Qualifier::Rarp), IpAddr::V4(_)) => {}
+            (None, IpAddr::V6(_)) | (Some(Qualifier::Ip6), IpAddr::V6(_)) => {}
+            (Some(qualifier), _) => {
+                let message = format!("'{}' modifier applied to '{}' {}", qualifier.name(), kind, address);
+                return Err(error(position, message));
+            }
+        }
+        Ok(match address {
+            IpAddr::V4(address) => {
+                let address = address.octets();
+                let ip = self.ethertype(0x800).and(self.directed(direction, |source| {
+                    self.masked(network + if source { 12 } else { 16 }, &address, mask)
+                }));
+                let arp = |ethertype| {
+                    self.ethertype(ethertype).and(self.directed(direction, |source| {
+                        self.masked(network + if source { 14 } else { 24 }, &address, mask)
+                    }))
+                };
+                match qualifier {
+                    Some(Qualifier::Ip) => ip,
+                    Some(Qualifier::Arp) => arp(0x806),
+                    Some(Qualifier::Rarp) => arp(0x8035),
+                    _ => ip.or(arp(0x806)).or(arp(0x8035)),
+                }
+            }
+            IpAddr::V6(address) => {
+                let address = address.octets();
+                self.ethertype(0x86dd).and(self.directed(direction, |source| {
+                    self.masked(network + if source { 8 } else { 24 }, &address, mask)
+                }))
+            }
+        })
+    }
+
+    /// Generate a `host` primitive
+    fn host(&self, qualifiers: Qualifiers, id: &str, position: usize) -> Result<CaptureExpression, FilterError> {
+        let (qualifier, direction, _) = qualifiers;
+        if qualifier == Some(Qualifier::Ether) || (qualifier.is_none() && parse_mac_address(id).is_some()) {
+            self.ether_offset(position, "ether host")?;
+            let address = parse_mac_address(id)
+                .ok_or_else(|| error(position, format!("Invalid MAC address '{}'", id)))?;
+            return Ok(self.directed(direction, |source| self.mac_address(if source { 6 } else { 0 }, &address)));
+        }
+        let address: IpAddr = id.parse().map_err(|_| error(position, format!("Invalid host '{}'", id)))?;
+        self.addresses(qualifier, direction, address, &[0xff; 16], "host", position)
+    }
+
+    /// Generate a `net` primitive, like `10.0.0.0/8`, `10.0`, `10.0.0.0 mask 255.0.0.0` or
+    /// `fe80::/10`
+    fn net(&mut self, qualifiers: Qualifiers, id: &str, position: usize) -> Result<CaptureExpression, FilterError> {
+        let invalid = || error(position, format!("Invalid network '{}'", id));
+        let (address, prefix) = match id.find('/') {
+            Some(index) => {
+                let address: IpAddr = id[..index].parse().map_err(|_| invalid())?;
+                (address, id[index + 1..].parse::<u32>().map_err(|_| invalid())?)
+            }
+            None if self.accept_word("mask") => {
+                let (mask_position, mask) = self.word("mask")?;
+                let mask: Ipv4Addr = mask.parse()
+                    .map_err(|_| error(mask_position, format!("Invalid mask '{}'", mask)))?;
+                let mask = u32::from(mask);
+                if mask.leading_ones() + mask.trailing_zeros() != 32 {
+                    return Err(error(mask_position, format!("Invalid mask '{}'", Ipv4Addr::from(mask))));
+                }
+                (IpAddr::V4(id.parse().map_err(|_| invalid())?), mask.leading_ones())
+            }
+            None => {
+                // Abbreviated IPv4 networks like `10` or `192.168`
+                let parts: Vec<u8> = id.split('.').map(str::parse).collect::<Result<_, _>>().map_err(|_| invalid())?;
+                if parts.len() > 4 {
+                    return Err(invalid());
+                }
+                let mut octets = [0; 4];
+                octets[..parts.len()].copy_from_slice(&parts);
+                (IpAddr::V4(Ipv4Addr::from(octets)), 8 * parts.len() as u32)
+            }
+        };
+        let bits = if address.is_ipv4() { 32 } else { 128 };
+        if prefix > bits {
+            return Err(invalid());
+        }
+        let mask: Vec<u8> = (0..bits / 8).map(|index| {
+            let ones = prefix.saturating_sub(index * 8).min(8);
+            (0xff00u16 >> ones) as u8
+        }).collect();
+        let octets = match address {
+            IpAddr::V4(address) => address.octets().to_vec(),
+            IpAddr::V6(address) => address.octets().to_vec(),
+        };
+        if octets.iter().zip(&mask).any(|(octet, mask)| octet & !mask != 0) {
+            return Err(error(position, format!("Non-network bits set in '{}'", id)));
+        }
+        self.addresses(qualifiers.0, qualifiers.1, address, &mask, "net", position)
+    }
+
+    /// Generate a `port` or `portrange` primitive
+    fn port(&self, qualifiers: Qualifiers, id: &str, position: usize) -> Result<CaptureExpression, FilterError> {
+        let (qualifier, direction, kind) = qualifiers;
+        let port = |port: &str| {
+            let number = match PORTS.iter().find(|named| named.0 == port) {
+                Some(&(_, number)) => Some(number),
+                None => parse_number(port).filter(|number| *number <= 0xffff),
+            };
+            number.ok_or_else(|| error(position, format!("Invalid port '{}'", port)))
+        };
+        let (low, high) = match (kind, id.find('-').filter(|_| kind == Kind::PortRange)) {
+            (Kind::PortRange, Some(index)) => (port(&id[..index])?, port(&id[index + 1..])?),
+            (Kind::PortRange, None) => return Err(error(position, format!("Invalid port range '{}'", id))),
+            _ => (port(id)?, port(id)?),
+        };
+        let (low, high) = (low.min(high), low.max(high));
+        let (protocols, versions): (&[Qualifier], &[Qualifier]) = match qualifier {
+            None => (&[Qualifier::Tcp, Qualifier::Udp, Qualifier::Sctp], &[Qualifier::Ip, Qualifier::Ip6]),
+            Some(Qualifier::Ip) => (&[Qualifier::Tcp, Qualifier::Udp, Qualifier::Sctp], &[Qualifier::Ip]),
+            Some(Qualifier::Ip6) => (&[Qualifier::Tcp, Qualifier::Udp, Qualifier::Sctp], &[Qualifier::Ip6]),
+            Some(Qualifier::Tcp) => (&[Qualifier::Tcp], &[Qualifier::Ip, Qualifier::Ip6]),
+            Some(Qualifier::Udp) => (&[Qualifier::Udp], &[Qualifier::Ip, Qualifier::Ip6]),
+            Some(Qualifier::Sctp) => (&[Qualifier::Sctp], &[Qualifier::Ip, Qualifier::Ip6]),
+            Some(qualifier) => {
+                return Err(error(position, format!("'{}' modifier applied to 'port' {}", qualifier.name(), id)))
+            }
+        };
+
+        let network = self.network_offset();
+        let matches = |port: CaptureValue| match low == high {
+            true => CaptureExpression::compare(port, Relation::Equal, low),
+            false => {
+                CaptureExpression::compare(port.clone(), Relation::GreaterEqual, low)
+                    .and(CaptureExpression::compare(port, Relation::Greater, high).negate())
+            }
+        };
+        Ok(versions.iter().fold(CaptureExpression::False, |expression, version| {
+            let carried = protocols.iter().fold(CaptureExpression::False, |carried, protocol| {
+                let offset = if *version == Qualifier::Ip { 9 } else { 6 };
+                let value = protocol.ip_protocol().unwrap_or_default();
+                carried.or(CaptureExpression::compare(CaptureValue::load(LoadBase::Frame, network + offset, 1),
+                                                      Relation::Equal,
+                                                      value))
+            });
+            let ports = match *version {
+                Qualifier::Ip => {
+                    self.ethertype(0x800).and(carried).and(self.unfragmented()).and(self.directed(direction, |source| {
+                        matches(CaptureValue::load(LoadBase::Ipv4Payload(network), if source { 0 } else { 2 }, 2))
+                    }))
+                }
+                _ => {
+                    self.ethertype(0x86dd).and(carried).and(self.directed(direction, |source| {
+                        matches(CaptureValue::load(LoadBase::Frame, network + if source { 40 } else { 42 }, 2))
+                    }))
+                }
+            };
+            expression.or(ports)
+        }))
+    }
+}
+
+/// Check if a word is an identifier of a primitive rather than a keyword
+fn is_identifier(word: &str) -> bool {
+    match word {
+        "src" | "dst" | "host" | "net" | "port" | "portrange" | "proto" | "vlan" | "less" | "greater" | "broadcast" |
+        "multicast" | "gateway" | "mask" | "len" => false,
+        word => Qualifier::from_word(word).is_none(),
+    }
+}
+
+/// Parse a decimal or hexadecimal number
+fn parse_number(word: &str) -> Option<u32> {
+    match word.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => word.parse().ok(),
+    }
+}
+
+/// Parse a MAC address like `00:1b:21:3a:4c:5d`, `00-1b-21-3a-4c-5d` or `001b.213a.4c5d`
+fn parse_mac_address(word: &str) -> Option<[u8; 6]> {
+    let digits: String = word.chars().filter(|c| !":-.".contains(*c)).collect();
+    let separators = word.len() - digits.len();
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) || (separators != 5 && separators != 2) {
+        return None;
+    }
+    let mut address = [0; 6];
+    for (index, byte) in address.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * index..2 * index + 2], 16).ok()?;
+    }
+    Some(address)
+}
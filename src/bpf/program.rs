@@ -0,0 +1,499 @@
+//! Classic BPF programs, which are generated from capture filters
+use super::*;
+
+// Instruction classes
+const LD: u16 = 0x00;
+const LDX: u16 = 0x01;
+const ST: u16 = 0x02;
+const ALU: u16 = 0x04;
+const JMP: u16 = 0x05;
+const RET: u16 = 0x06;
+const MISC: u16 = 0x07;
+
+// Load sizes
+const W: u16 = 0x00;
+const H: u16 = 0x08;
+const B: u16 = 0x10;
+
+// Load modes
+const IMM: u16 = 0x00;
+const ABS: u16 = 0x20;
+const IND: u16 = 0x40;
+const MEM: u16 = 0x60;
+const LEN: u16 = 0x80;
+const MSH: u16 = 0xa0;
+
+// Arithmetic operations
+const ADD: u16 = 0x00;
+const SUB: u16 = 0x10;
+const MUL: u16 = 0x20;
+const DIV: u16 = 0x30;
+const OR: u16 = 0x40;
+const AND: u16 = 0x50;
+const LSH: u16 = 0x60;
+const RSH: u16 = 0x70;
+const NEG: u16 = 0x80;
+const MOD: u16 = 0x90;
+const XOR: u16 = 0xa0;
+
+// Jump conditions
+const JA: u16 = 0x00;
+const JEQ: u16 = 0x10;
+const JGT: u16 = 0x20;
+const JGE: u16 = 0x30;
+const JSET: u16 = 0x40;
+
+// Operand sources
+const K: u16 = 0x00;
+const X: u16 = 0x08;
+const A: u16 = 0x10;
+
+// Register transfers
+const TAX: u16 = 0x00;
+const TXA: u16 = 0x80;
+
+/// The amount of scratch memory cells
+const MEMORY_CELLS: usize = 16;
+
+/// The return value for accepted frames, which is the amount of bytes to keep
+pub const BPF_ACCEPT: u32 = 262_144;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+/// A classic BPF instruction with the memory layout of `struct sock_filter`
+pub struct BpfInstruction {
+    /// The operation
+    pub code: u16,
+
+    /// The relative jump target if the condition holds
+    pub jt: u8,
+
+    /// The relative jump target if the condition does not hold
+    pub jf: u8,
+
+    /// The constant operand
+    pub k: u32,
+}
+
+impl BpfInstruction {
+    /// Create an instruction without jump targets
+    pub fn new(code: u16, k: u32) -> Self {
+        BpfInstruction {
+            code: code,
+            jt: 0,
+            jf: 0,
+            k: k,
+        }
+    }
+
+    /// Write the instruction in the format of `tcpdump -d`, the index is needed for the
+    /// absolute jump targets
+    fn write(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+        let size = match self.code & 0x18 {
+            H => "h",
+            B => "b",
+            _ => "",
+        };
+        let target = |offset: u32| index as u64 + 1 + u64::from(offset);
+        let (name, operand) = match (self.code & 0x07, self.code & 0xe0) {
+            (LD, IMM) => ("ld".to_owned(), format!("#{:#x}", self.k)),
+            (LD, ABS) => (format!("ld{}", size), format!("[{}]", self.k)),
+            (LD, IND) => (format!("ld{}", size), format!("[x + {}]", self.k)),
+            (LD, MEM) => ("ld".to_owned(), format!("M[{}]", self.k)),
+            (LD, LEN) => ("ld".to_owned(), "#pktlen".to_owned()),
+            (LDX, IMM) => ("ldx".to_owned(), format!("#{:#x}", self.k)),
+            (LDX, MEM) => ("ldx".to_owned(), format!("M[{}]", self.k)),
+            (LDX, LEN) => ("ldx".to_owned(), "#pktlen".to_owned()),
+            (LDX, MSH) => ("ldxb".to_owned(), format!("4*([{}]&0xf)", self.k)),
+            (ST, _) => ("st".to_owned(), format!("M[{}]", self.k)),
+            (0x03, _) => ("stx".to_owned(), format!("M[{}]", self.k)),
+            (ALU, _) => {
+                let name = match self.code & 0xf0 {
+                    ADD => "add",
+                    SUB => "sub",
+                    MUL => "mul",
+                    DIV => "div",
+                    OR => "or",
+                    AND => "and",
+                    LSH => "lsh",
+                    RSH => "rsh",
+                    NEG => "neg",
+                    MOD => "mod",
+                    XOR => "xor",
+                    _ => "unimp",
+                };
+                let operand = match (self.code & 0xf0, self.code & X) {
+                    (NEG, _) => String::new(),
+                    (_, X) => "x".to_owned(),
+                    _ => format!("#{:#x}", self.k),
+                };
+                (name.to_owned(), operand)
+            }
+            (JMP, _) if self.code & 0xf0 == JA => ("ja".to_owned(), target(self.k).to_string()),
+            (JMP, _) => {
+                let name = match self.code & 0xf0 {
+                    JEQ => "jeq",
+                    JGT => "jgt",
+                    JGE => "jge",
+                    JSET => "jset",
+                    _ => "unimp",
+                };
+                let operand = match self.code & X {
+                    X => "x".to_owned(),
+                    _ => format!("#{:#x}", self.k),
+                };
+                let operand = format!("{:<16} jt {}\tjf {}",
+                                      operand,
+                                      target(u32::from(self.jt)),
+                                      target(u32::from(self.jf)));
+                (name.to_owned(), operand)
+            }
+            (RET, _) if self.code & 0x18 == A => ("ret".to_owned(), String::new()),
+            (RET, _) => ("ret".to_owned(), format!("#{}", self.k)),
+            (MISC, _) if self.code & 0xf8 == TXA => ("txa".to_owned(), String::new()),
+            (MISC, _) => ("tax".to_owned(), String::new()),
+            _ => ("unimp".to_owned(), String::new()),
+        };
+        write!(f, "({:03}) {:<8} {}", index, name, operand)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// A classic BPF program, which can be attached to sockets via `SO_ATTACH_FILTER`
+pub struct BpfProgram {
+    /// The instructions of the program
+    pub instructions: Vec<BpfInstruction>,
+}
+
+impl BpfProgram {
+    /// Run the program on a frame, the result is the amount of bytes to keep, where zero
+    /// rejects the frame
+    pub fn run(&self, frame: &[u8]) -> u32 {
+        self.execute(frame).unwrap_or(0)
+    }
+
+    /// Check if the program accepts a frame
+    pub fn matches(&self, frame: &[u8]) -> bool {
+        self.run(frame) != 0
+    }
+
+    /// Run the program, which is `None` for invalid instructions, loads beyond the end of the
+    /// frame, divisions by zero and programs without return
+    fn execute(&self, frame: &[u8]) -> Option<u32> {
+        let load = |offset: u32, size: u16| {
+            let size = match size {
+                W => 4,
+                H => 2,
+                _ => 1,
+            };
+            let start = offset as usize;
+            let bytes = frame.get(start..start.checked_add(size)?)?;
+            Some(bytes.iter().fold(0, |value, byte| value << 8 | u32::from(*byte)))
+        };
+        let (mut a, mut x, mut memory) = (0u32, 0u32, [0u32; MEMORY_CELLS]);
+        let mut index = 0;
+        loop {
+            let instruction = self.instructions.get(index)?;
+            let k = instruction.k;
+            let code = instruction.code;
+            index += 1;
+            match (code & 0x07, code & 0xe0) {
+                (LD, IMM) => a = k,
+                (LD, ABS) => a = load(k, code & 0x18)?,
+                (LD, IND) => a = load(x.checked_add(k)?, code & 0x18)?,
+                (LD, MEM) => a = *memory.get(k as usize)?,
+                (LD, LEN) => a = frame.len() as u32,
+                (LDX, IMM) => x = k,
+                (LDX, MEM) => x = *memory.get(k as usize)?,
+                (LDX, LEN) => x = frame.len() as u32,
+                (LDX, MSH) => x = u32::from(*frame.get(k as usize)? & 0xf) * 4,
+                (ST, _) => *memory.get_mut(k as usize)? = a,
+                (0x03, _) => *memory.get_mut(k as usize)? = x,
+                (ALU, _) => {
+                    let operand = if code & X == X { x } else { k };
+                    a = match code & 0xf0 {
+                        NEG => a.wrapping_neg(),
+                        operation => arithmetic_operator(operation)?.apply(a, operand)?,
+                    };
+                }
+                (JMP, _) => {
+                    let operand = if code & X == X { x } else { k };
+                    let condition = match code & 0xf0 {
+                        JA => {
+                            index = index.checked_add(k as usize)?;
+                            continue;
+                        }
+                        JEQ => a == operand,
+                        JGT => a > operand,
+                        JGE => a >= operand,
+                        JSET => a & operand != 0,
+                        _ => return None,
+                    };
+                    index += usize::from(if condition { instruction.jt } else { instruction.jf });
+                }
+                (RET, _) => return Some(if code & 0x18 == A { a } else { k }),
+                (MISC, _) => {
+                    if code & 0xf8 == TXA {
+                        a = x
+                    } else {
+                        x = a
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Write the program in the format of `tcpdump -d`, or as C array like `tcpdump -dd` with the
+/// alternate flag
+impl fmt::Display for BpfProgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if f.alternate() {
+                writeln!(f,
+                         "{{ {:#04x}, {}, {}, {:#010x} }},",
+                         instruction.code,
+                         instruction.jt,
+                         instruction.jf,
+                         instruction.k)?;
+            } else {
+                instruction.write(f, index)?;
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The arithmetic operator of an ALU operation
+fn arithmetic_operator(operation: u16) -> Option<ArithmeticOperator> {
+    Some(match operation {
+        ADD => ArithmeticOperator::Add,
+        SUB => ArithmeticOperator::Subtract,
+        MUL => ArithmeticOperator::Multiply,
+        DIV => ArithmeticOperator::Divide,
+        MOD => ArithmeticOperator::Modulo,
+        AND => ArithmeticOperator::And,
+        OR => ArithmeticOperator::Or,
+        XOR => ArithmeticOperator::Xor,
+        LSH => ArithmeticOperator::ShiftLeft,
+        RSH => ArithmeticOperator::ShiftRight,
+        _ => return None,
+    })
+}
+
+/// The ALU operation of an arithmetic operator
+fn alu_operation(operator: ArithmeticOperator) -> u16 {
+    match operator {
+        ArithmeticOperator::Add => ADD,
+        ArithmeticOperator::Subtract => SUB,
+        ArithmeticOperator::Multiply => MUL,
+        ArithmeticOperator::Divide => DIV,
+        ArithmeticOperator::Modulo => MOD,
+        ArithmeticOperator::And => AND,
+        ArithmeticOperator::Or => OR,
+        ArithmeticOperator::Xor => XOR,
+        ArithmeticOperator::ShiftLeft => LSH,
+        ArithmeticOperator::ShiftRight => RSH,
+    }
+}
+
+/// A jump target, which is resolved after the code generation
+type Label = usize;
+
+/// The label of the accepting return
+const ACCEPT: Label = 0;
+
+/// The label of the rejecting return
+const REJECT: Label = 1;
+
+/// An instruction whose jump targets are not resolved yet
+enum Code {
+    /// An instruction without jumps
+    Plain(BpfInstruction),
+
+    /// A conditional jump
+    Branch(u16, u32, Label, Label),
+
+    /// The position of a label
+    Mark(Label),
+}
+
+/// The code generator of a capture expression
+pub struct Generator {
+    /// The generated code
+    code: Vec<Code>,
+
+    /// The amount of created labels
+    labels: usize,
+}
+
+impl Generator {
+    /// Generate a program, which fails if the expression is too complex for the scratch memory
+    /// or the conditional jumps
+    pub fn generate(expression: &CaptureExpression) -> Result<BpfProgram, String> {
+        let mut generator = Generator {
+            code: vec![],
+            labels: 2,
+        };
+        generator.expression(expression, ACCEPT, REJECT)?;
+        generator.code.push(Code::Mark(ACCEPT));
+        generator.code.push(Code::Plain(BpfInstruction::new(RET | K, BPF_ACCEPT)));
+        generator.code.push(Code::Mark(REJECT));
+        generator.code.push(Code::Plain(BpfInstruction::new(RET | K, 0)));
+        generator.resolve()
+    }
+
+    /// Create a new label
+    fn label(&mut self) -> Label {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    /// Append an instruction without jumps
+    fn emit(&mut self, code: u16, k: u32) {
+        self.code.push(Code::Plain(BpfInstruction::new(code, k)));
+    }
+
+    /// Generate the code of an expression, which continues at the labels depending on the result
+    fn expression(&mut self, expression: &CaptureExpression, t: Label, f: Label) -> Result<(), String> {
+        match *expression {
+            CaptureExpression::True => self.code.push(Code::Branch(JMP | JA, 0, t, t)),
+            CaptureExpression::False => self.code.push(Code::Branch(JMP | JA, 0, f, f)),
+            CaptureExpression::And(ref left, ref right) => {
+                let next = self.label();
+                self.expression(left, next, f)?;
+                self.code.push(Code::Mark(next));
+                self.expression(right, t, f)?;
+            }
+            CaptureExpression::Or(ref left, ref right) => {
+                let next = self.label();
+                self.expression(left, t, next)?;
+                self.code.push(Code::Mark(next));
+                self.expression(right, t, f)?;
+            }
+            CaptureExpression::Not(ref expression) => self.expression(expression, f, t)?,
+            CaptureExpression::Compare(ref left, relation, ref right) => {
+                let condition = match relation {
+                    Relation::Equal => JEQ,
+                    Relation::Greater => JGT,
+                    Relation::GreaterEqual => JGE,
+                };
+                match *right {
+                    CaptureValue::Number(number) => {
+                        self.value(left, 0)?;
+                        self.code.push(Code::Branch(JMP | condition | K, number, t, f));
+                    }
+                    ref right => {
+                        self.value(right, 0)?;
+                        self.emit(ST, 0);
+                        self.value(left, 1)?;
+                        self.emit(LDX | MEM, 0);
+                        self.code.push(Code::Branch(JMP | condition | X, 0, t, f));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Generate the code to load a value into the accumulator, the depth is the first unused
+    /// scratch memory cell
+    fn value(&mut self, value: &CaptureValue, depth: u32) -> Result<(), String> {
+        if depth as usize >= MEMORY_CELLS {
+            return Err("Expression too complex".to_owned());
+        }
+        let size = |size: u8| match size {
+            1 => B,
+            2 => H,
+            _ => W,
+        };
+        match *value {
+            CaptureValue::Number(number) => self.emit(LD | IMM, number),
+            CaptureValue::Length => self.emit(LD | W | LEN, 0),
+            CaptureValue::Load(LoadBase::Frame, ref offset, length) => {
+                match **offset {
+                    CaptureValue::Number(offset) => self.emit(LD | size(length) | ABS, offset),
+                    ref offset => {
+                        self.value(offset, depth)?;
+                        self.emit(MISC | TAX, 0);
+                        self.emit(LD | size(length) | IND, 0);
+                    }
+                }
+            }
+            CaptureValue::Load(LoadBase::Ipv4Payload(header), ref offset, length) => {
+                match **offset {
+                    CaptureValue::Number(offset) => {
+                        self.emit(LDX | B | MSH, header);
+                        self.emit(LD | size(length) | IND, header + offset);
+                    }
+                    ref offset => {
+                        self.value(offset, depth)?;
+                        self.emit(LDX | B | MSH, header);
+                        self.emit(ALU | ADD | X, 0);
+                        self.emit(MISC | TAX, 0);
+                        self.emit(LD | size(length) | IND, header);
+                    }
+                }
+            }
+            CaptureValue::Arithmetic(operator, ref left, ref right) => {
+                match **right {
+                    CaptureValue::Number(number) => {
+                        self.value(left, depth)?;
+                        self.emit(ALU | alu_operation(operator) | K, number);
+                    }
+                    ref right => {
+                        self.value(right, depth)?;
+                        self.emit(ST, depth);
+                        self.value(left, depth + 1)?;
+                        self.emit(LDX | MEM, depth);
+                        self.emit(ALU | alu_operation(operator) | X, 0);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the labels to relative jumps
+    fn resolve(self) -> Result<BpfProgram, String> {
+        let mut positions = vec![0; self.labels];
+        let mut position = 0;
+        for code in &self.code {
+            match *code {
+                Code::Mark(label) => positions[label] = position,
+                _ => position += 1,
+            }
+        }
+
+        let mut instructions = vec![];
+        for code in self.code {
+            let next = instructions.len() + 1;
+            let instruction = match code {
+                Code::Plain(instruction) => instruction,
+                Code::Branch(code, _, target, _) if code == JMP | JA => {
+                    BpfInstruction::new(code, (positions[target] - next) as u32)
+                }
+                Code::Branch(code, k, t, f) => {
+                    let offset = |target: Label| {
+                        let offset = positions[target] - next;
+                        if offset > usize::from(u8::MAX) {
+                            return Err("Filter too large for conditional jumps".to_owned());
+                        }
+                        Ok(offset as u8)
+                    };
+                    BpfInstruction {
+                        code: code,
+                        jt: offset(t)?,
+                        jf: offset(f)?,
+                        k: k,
+                    }
+                }
+                Code::Mark(_) => continue,
+            };
+            instructions.push(instruction);
+        }
+        Ok(BpfProgram { instructions: instructions })
+    }
+}
@@ -0,0 +1,400 @@
+//! Configurable creation of the parser tree
+use prelude::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+/// A built-in parser of the tree. Parsers whose cargo feature is disabled are never created.
+pub enum Protocol {
+    /// Ethernet
+    Ethernet,
+
+    /// Address Resolution Protocol
+    Arp,
+
+    /// Internet Protocol version 4
+    Ipv4,
+
+    /// Internet Protocol version 6
+    Ipv6,
+
+    /// Internet Control Message Protocol
+    Icmp,
+
+    /// Internet Control Message Protocol for IPv6
+    Icmpv6,
+
+    /// Transmission Control Protocol
+    Tcp,
+
+    /// User Datagram Protocol
+    Udp,
+
+    /// Transport Layer Security
+    Tls,
+
+    /// Datagram Transport Layer Security
+    Dtls,
+
+    /// Hypertext Transfer Protocol
+    Http,
+
+    /// Hypertext Transfer Protocol version 2
+    Http2,
+
+    /// WebSocket
+    WebSocket,
+
+    /// Network Time Protocol
+    Ntp,
+
+    /// Domain Name System
+    Dns,
+
+    /// Multicast DNS
+    Mdns,
+
+    /// Link-Local Multicast Name Resolution
+    Llmnr,
+
+    /// Dynamic Host Configuration Protocol
+    Dhcp,
+
+    /// Dynamic Host Configuration Protocol for IPv6
+    Dhcpv6,
+}
+
+/// All built-in parsers in the order of their creation
+const PROTOCOLS: &[Protocol] = &[Protocol::Ethernet, Protocol::Arp, Protocol::Ipv4, Protocol::Ipv6, Protocol::Icmp,
+                                 Protocol::Icmpv6, Protocol::Tcp, Protocol::Udp, Protocol::Tls, Protocol::Dtls,
+                                 Protocol::Http, Protocol::Http2, Protocol::WebSocket, Protocol::Ntp,
+                                 Protocol::Dns, Protocol::Mdns, Protocol::Llmnr, Protocol::Dhcp, Protocol::Dhcpv6];
+
+/// The links between the built-in parsers. Children are tried in the reverse order of their links.
+const LINKS: &[(Protocol, Protocol)] = &[(Protocol::Ethernet, Protocol::Arp),
+                                         (Protocol::Ethernet, Protocol::Ipv4),
+                                         (Protocol::Ethernet, Protocol::Ipv6),
+                                         (Protocol::Ipv4, Protocol::Ipv4),
+                                         (Protocol::Ipv4, Protocol::Ipv6),
+                                         (Protocol::Ipv6, Protocol::Ipv6),
+                                         (Protocol::Ipv4, Protocol::Icmp),
+                                         (Protocol::Ipv6, Protocol::Icmpv6),
+                                         (Protocol::Ipv4, Protocol::Tcp),
+                                         (Protocol::Ipv6, Protocol::Tcp),
+                                         (Protocol::Ipv4, Protocol::Udp),
+                                         (Protocol::Ipv6, Protocol::Udp),
+                                         (Protocol::Tcp, Protocol::Tls),
+                                         (Protocol::Tcp, Protocol::Http),
+                                         (Protocol::Tls, Protocol::Http),
+                                         (Protocol::Http, Protocol::Http),
+                                         (Protocol::Tcp, Protocol::Http2),
+                                         (Protocol::Tls, Protocol::Http2),
+                                         (Protocol::Http, Protocol::Http2),
+                                         (Protocol::Tcp, Protocol::WebSocket),
+                                         (Protocol::Http, Protocol::WebSocket),
+                                         (Protocol::Udp, Protocol::Ntp),
+                                         (Protocol::Udp, Protocol::Dtls),
+                                         (Protocol::Dtls, Protocol::Dtls),
+                                         (Protocol::Udp, Protocol::Dns),
+                                         (Protocol::Tcp, Protocol::Dns),
+                                         (Protocol::Dns, Protocol::Dns),
+                                         (Protocol::Udp, Protocol::Mdns),
+                                         (Protocol::Udp, Protocol::Llmnr),
+                                         (Protocol::Udp, Protocol::Dhcp),
+                                         (Protocol::Udp, Protocol::Dhcpv6)];
+
+impl Protocol {
+    /// The layer of the protocol
+    pub fn layer(&self) -> LayerKind {
+        match *self {
+            Protocol::Ethernet | Protocol::Arp => LayerKind::Link,
+            Protocol::Ipv4 | Protocol::Ipv6 | Protocol::Icmp | Protocol::Icmpv6 => LayerKind::Internet,
+            Protocol::Tcp | Protocol::Udp | Protocol::Tls | Protocol::Dtls => LayerKind::Transport,
+            _ => LayerKind::Application,
+        }
+    }
+
+    /// The name of the protocol, which is the lowercase variant name like `http2`
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Protocol::Ethernet => "ethernet",
+            Protocol::Arp => "arp",
+            Protocol::Ipv4 => "ipv4",
+            Protocol::Ipv6 => "ipv6",
+            Protocol::Icmp => "icmp",
+            Protocol::Icmpv6 => "icmpv6",
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+            Protocol::Tls => "tls",
+            Protocol::Dtls => "dtls",
+            Protocol::Http => "http",
+            Protocol::Http2 => "http2",
+            Protocol::WebSocket => "websocket",
+            Protocol::Ntp => "ntp",
+            Protocol::Dns => "dns",
+            Protocol::Mdns => "mdns",
+            Protocol::Llmnr => "llmnr",
+            Protocol::Dhcp => "dhcp",
+            Protocol::Dhcpv6 => "dhcpv6",
+        }
+    }
+}
+
+/// Parsed from its name, ignoring the case
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        PROTOCOLS.iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or_else(|| format!("Unknown protocol '{}'", name))
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+/// A layer of the Internet Protocol Suite
+pub enum LayerKind {
+    /// The link layer, like Ethernet
+    Link,
+
+    /// The internet layer, like IPv4 and IPv6
+    Internet,
+
+    /// The transport layer, like TCP and UDP
+    Transport,
+
+    /// The application layer, like HTTP and DNS
+    Application,
+}
+
+/// A builder for a customized parser tree
+pub struct PeelIpBuilder {
+    /// The disabled built-in parsers
+    disabled: Vec<Protocol>,
+
+    /// The parser to start the traversal with
+    root: Protocol,
+
+    /// The highest layer to be parsed
+    max_layer: LayerKind,
+
+    /// The maximum amount of parsing results of a traversal
+    max_depth: Option<usize>,
+
+    /// The registry of the TCP parser
+    tcp_registry: DispatchRegistry,
+
+    /// The registry of the UDP parser
+    udp_registry: DispatchRegistry,
+
+    /// Additional parsers and the built-in parsers they are attached to
+    custom: Vec<(Vec<Protocol>, Box<dyn Parsable<PathIp>>)>,
+}
+
+impl Default for PeelIpBuilder {
+    fn default() -> Self {
+        PeelIpBuilder::new()
+    }
+}
+
+impl PeelIpBuilder {
+    /// Create a builder with all compiled in built-in parsers enabled and Ethernet as root
+    pub fn new() -> Self {
+        PeelIpBuilder {
+            disabled: vec![],
+            root: Protocol::Ethernet,
+            max_layer: LayerKind::Application,
+            max_depth: None,
+            tcp_registry: DispatchRegistry::tcp(),
+            udp_registry: DispatchRegistry::udp(),
+            custom: vec![],
+        }
+    }
+
+    /// Enable a previously disabled built-in parser
+    pub fn enable(mut self, protocol: Protocol) -> Self {
+        self.disabled.retain(|p| *p != protocol);
+        self
+    }
+
+    /// Disable a built-in parser, the parsers which are only reachable via it are skipped as well
+    pub fn disable(mut self, protocol: Protocol) -> Self {
+        if !self.disabled.contains(&protocol) {
+            self.disabled.push(protocol);
+        }
+        self
+    }
+
+    /// Start the traversal with another parser, for example IPv4 for raw IP captures
+    pub fn root(mut self, protocol: Protocol) -> Self {
+        self.root = protocol;
+        self
+    }
+
+    /// Do not create parsers above the given layer
+    pub fn max_layer(mut self, layer: LayerKind) -> Self {
+        self.max_layer = layer;
+        self
+    }
+
+    /// Stop the traversal after the given amount of parsing results, which limits nested
+    /// tunnels and pipelined messages as well
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Dispatch the TCP payload by the given registry
+    pub fn tcp_registry(mut self, registry: DispatchRegistry) -> Self {
+        self.tcp_registry = registry;
+        self
+    }
+
+    /// Dispatch the UDP payload by the given registry
+    pub fn udp_registry(mut self, registry: DispatchRegistry) -> Self {
+        self.udp_registry = registry;
+        self
+    }
+
+    /// Add a custom parser as child of the given built-in parsers. Custom parsers are tried
+    /// before the built-in children of the same parent.
+    pub fn add_parser<T>(mut self, parents: &[Protocol], parser: T) -> Self
+        where T: Parsable<PathIp> + 'static
+    {
+        self.custom.push((parents.to_vec(), Box::new(parser)));
+        self
+    }
+
+    /// Create the parser tree
+    pub fn build(self) -> PeelIp {
+        let PeelIpBuilder { disabled, root, max_layer, max_depth, tcp_registry, udp_registry, custom } = self;
+        let enabled = |protocol: &Protocol| !disabled.contains(protocol) && protocol.layer() <= max_layer;
+        let mut p = Peel::new();
+
+        // Create the parsers, the registries are moved into the single TCP and UDP parser
+        let mut tcp_registry = Some(tcp_registry);
+        let mut udp_registry = Some(udp_registry);
+        let mut nodes = HashMap::new();
+        for protocol in PROTOCOLS.iter().filter(|p| enabled(p)) {
+            let parser: Box<dyn Parsable<PathIp>> = match *protocol {
+                Protocol::Ethernet => Box::new(EthernetParser),
+                #[cfg(feature = "arp")]
+                Protocol::Arp => Box::new(ArpParser),
+                Protocol::Ipv4 => Box::new(Ipv4Parser),
+                Protocol::Ipv6 => Box::new(Ipv6Parser),
+                #[cfg(feature = "icmp")]
+                Protocol::Icmp => Box::new(IcmpParser),
+                #[cfg(feature = "icmpv6")]
+                Protocol::Icmpv6 => Box::new(Icmpv6Parser),
+                Protocol::Tcp => Box::new(TcpParser { registry: tcp_registry.take().unwrap_or_default() }),
+                Protocol::Udp => Box::new(UdpParser { registry: udp_registry.take().unwrap_or_default() }),
+                #[cfg(feature = "tls")]
+                Protocol::Tls => Box::new(TlsParser),
+                #[cfg(feature = "dtls")]
+                Protocol::Dtls => Box::new(DtlsParser),
+                #[cfg(feature = "http")]
+                Protocol::Http => Box::new(HttpParser),
+                #[cfg(feature = "http2")]
+                Protocol::Http2 => Box::new(Http2Parser),
+                #[cfg(feature = "websocket")]
+                Protocol::WebSocket => Box::new(WebSocketParser),
+                #[cfg(feature = "ntp")]
+                Protocol::Ntp => Box::new(NtpParser),
+                #[cfg(feature = "dns")]
+                Protocol::Dns => Box::new(DnsParser::default()),
+                #[cfg(feature = "mdns")]
+                Protocol::Mdns => Box::new(MdnsParser),
+                #[cfg(feature = "llmnr")]
+                Protocol::Llmnr => Box::new(LlmnrParser),
+                #[cfg(feature = "dhcp")]
+                Protocol::Dhcp => Box::new(DhcpParser),
+                #[cfg(feature = "dhcpv6")]
+                Protocol::Dhcpv6 => Box::new(Dhcpv6Parser),
+
+                // The parser is not compiled in
+                #[allow(unreachable_patterns)]
+                _ => continue,
+            };
+            nodes.insert(*protocol, p.new_parser(Node::new(parser, max_depth)));
+        }
+
+        // Link the parsers
+        for &(parent, child) in LINKS {
+            if let (Some(&parent), Some(&child)) = (nodes.get(&parent), nodes.get(&child)) {
+                p.link(parent, child);
+            }
+        }
+
+        // Attach the custom parsers
+        for (parents, parser) in custom {
+            let parents: Vec<_> = parents.iter().filter_map(|p| nodes.get(p)).cloned().collect();
+            if !parents.is_empty() {
+                let node = p.new_parser(Node::new(parser, max_depth));
+                for parent in parents {
+                    p.link(parent, node);
+                }
+            }
+        }
+
+        // A disabled root results in an empty tree
+        p.root = nodes.get(&root).cloned();
+
+        // Create a path instance
+        p.data = Some(Path::new());
+
+        p
+    }
+}
+
+/// A node of the tree, which limits the depth of the traversal
+struct Node {
+    /// The actual parser
+    parser: Box<dyn Parsable<PathIp>>,
+
+    /// The maximum amount of parsing results
+    max_depth: Option<usize>,
+}
+
+impl Node {
+    /// Create a new node
+    fn new(parser: Box<dyn Parsable<PathIp>>, max_depth: Option<usize>) -> Self {
+        Node {
+            parser: parser,
+            max_depth: max_depth,
+        }
+    }
+}
+
+impl Parsable<PathIp> for Node {
+    /// Parse with the actual parser as long as the depth limit is not reached
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 path: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        match (self.max_depth, result) {
+            (Some(depth), Some(vector)) if vector.len() >= depth => {
+                IResult::Error(error_position!(ErrorKind::Custom(0), input))
+            }
+
+            // The root node has no parent to check
+            (_, Some(vector)) if vector.is_empty() => self.parser.parse(input, None, path),
+
+            _ => self.parser.parse(input, result, path),
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.parser.fmt(f)
+    }
+}
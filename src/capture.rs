@@ -0,0 +1,461 @@
+//! Reading of captured frames from pcap and pcapng files
+use prelude::*;
+use std::cell::Cell;
+use std::cmp;
+use std::io::{self, Read};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The largest block or record which is read, to not allocate huge buffers for corrupt files
+const MAX_LENGTH: usize = 0x1000_0000;
+
+#[derive(Debug)]
+/// An error during reading a capture file
+pub enum CaptureError {
+    /// The underlying reader failed
+    Io(io::Error),
+
+    /// The file starts with an unknown magic number
+    UnknownFormat(u32),
+
+    /// The file ends within a block or record
+    Truncated,
+
+    /// A block or record is malformed, with a description
+    Invalid(String),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CaptureError::Io(ref error) => write!(f, "{}", error),
+            CaptureError::UnknownFormat(magic) => write!(f, "Unknown capture format (magic {:#010x})", magic),
+            CaptureError::Truncated => write!(f, "Truncated capture file"),
+            CaptureError::Invalid(ref description) => write!(f, "Invalid capture file: {}", description),
+        }
+    }
+}
+
+impl Error for CaptureError {}
+
+impl From<io::Error> for CaptureError {
+    fn from(error: io::Error) -> Self {
+        CaptureError::Io(error)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+/// The link layer of the captured frames
+pub enum LinkType {
+    /// Ethernet frames (1)
+    Ethernet,
+
+    /// Raw IPv4 or IPv6 packets (101)
+    Raw,
+
+    /// Raw IPv4 packets (228)
+    Ipv4,
+
+    /// Raw IPv6 packets (229)
+    Ipv6,
+
+    /// Any other link type
+    Unknown(u32),
+}
+
+impl LinkType {
+    /// Convert a u32 to a `LinkType`
+    pub fn from_u32(input: u32) -> LinkType {
+        match input {
+            1 => LinkType::Ethernet,
+            101 => LinkType::Raw,
+            228 => LinkType::Ipv4,
+            229 => LinkType::Ipv6,
+            other => LinkType::Unknown(other),
+        }
+    }
+
+    /// The protocol to start the traversal of a frame with, which depends on the IP version for
+    /// raw packets
+    pub fn root(&self, data: &[u8]) -> Option<Protocol> {
+        match *self {
+            LinkType::Ethernet => Some(Protocol::Ethernet),
+            LinkType::Ipv4 => Some(Protocol::Ipv4),
+            LinkType::Ipv6 => Some(Protocol::Ipv6),
+            LinkType::Raw => {
+                match data.first().map(|b| b >> 4) {
+                    Some(4) => Some(Protocol::Ipv4),
+                    Some(6) => Some(Protocol::Ipv6),
+                    _ => None,
+                }
+            }
+            LinkType::Unknown(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A captured frame
+pub struct CaptureRecord {
+    /// The time of the capture since the Unix epoch
+    pub timestamp: Duration,
+
+    /// The link layer of the frame
+    pub link_type: LinkType,
+
+    /// The length of the frame on the wire, which may exceed the captured data
+    pub original_length: usize,
+
+    /// The captured data
+    pub data: Vec<u8>,
+}
+
+thread_local! {
+    /// The capture time of the frame which is currently traversed
+    static CAPTURE_TIME: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// The capture time of the frame which is currently traversed by `NewPeelIp::traverse_at` as
+/// duration since the Unix epoch, or the current system time if none is set. Parsers use it for
+/// latencies and timeouts, which keeps them meaningful when reading capture files.
+pub fn capture_time() -> Duration {
+    CAPTURE_TIME.with(Cell::get)
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default())
+}
+
+/// Run a function with the given capture time, the previous one is restored afterwards
+pub fn with_capture_time<T, F>(time: Duration, function: F) -> T
+    where F: FnOnce() -> T
+{
+    /// Restores the previous capture time, even if the function panics
+    struct Restore(Option<Duration>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CAPTURE_TIME.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(CAPTURE_TIME.with(|current| current.replace(Some(time))));
+    function()
+}
+
+#[derive(Clone, Debug)]
+/// An interface of a pcapng section
+struct Interface {
+    /// The link layer of the interface
+    link_type: LinkType,
+
+    /// The amount of timestamp units per second
+    units_per_second: u64,
+}
+
+#[derive(Clone, Debug)]
+/// The format of the capture file and its state
+enum Format {
+    /// A pcap file, with the units per second of its timestamp fractions
+    Pcap {
+        big_endian: bool,
+        units_per_second: u64,
+        link_type: LinkType,
+    },
+
+    /// A pcapng file, with the interfaces of the current section
+    Pcapng {
+        big_endian: bool,
+        interfaces: Vec<Interface>,
+    },
+}
+
+/// Reads the frames of a pcap or pcapng file, which is detected by its magic number
+pub struct CaptureReader<R> {
+    /// The underlying reader
+    reader: R,
+
+    /// The detected format
+    format: Format,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Create a reader by reading the file header
+    pub fn new(mut reader: R) -> Result<Self, CaptureError> {
+        let mut magic = [0; 4];
+        if !fill(&mut reader, &mut magic)? {
+            return Err(CaptureError::Truncated);
+        }
+        let format = match magic {
+            [0xa1, 0xb2, 0xc3, 0xd4] | [0xd4, 0xc3, 0xb2, 0xa1] | [0xa1, 0xb2, 0x3c, 0x4d] |
+            [0x4d, 0x3c, 0xb2, 0xa1] => {
+                let big_endian = magic[0] == 0xa1;
+                let mut header = [0; 20];
+                if !fill(&mut reader, &mut header)? {
+                    return Err(CaptureError::Truncated);
+                }
+                Format::Pcap {
+                    big_endian: big_endian,
+                    units_per_second: if magic.contains(&0x3c) { 1_000_000_000 } else { 1_000_000 },
+                    link_type: LinkType::from_u32(read_u32(&header[16..], big_endian)),
+                }
+            }
+            [0x0a, 0x0d, 0x0d, 0x0a] => {
+                let mut format = Format::Pcapng {
+                    big_endian: false,
+                    interfaces: vec![],
+                };
+                read_section_header(&mut reader, &mut format)?;
+                format
+            }
+            _ => return Err(CaptureError::UnknownFormat(u32::from_be_bytes(magic))),
+        };
+        Ok(CaptureReader {
+            reader: reader,
+            format: format,
+        })
+    }
+
+    /// Read the next frame, which is `None` at the end of the file
+    pub fn read_record(&mut self) -> Result<Option<CaptureRecord>, CaptureError> {
+        match self.format {
+            Format::Pcap { big_endian, units_per_second, link_type } => {
+                let mut header = [0; 16];
+                if !fill(&mut self.reader, &mut header)? {
+                    return Ok(None);
+                }
+                let length = read_u32(&header[8..], big_endian) as usize;
+                let data = read_data(&mut self.reader, length)?;
+                Ok(Some(CaptureRecord {
+                    timestamp: timestamp(u64::from(read_u32(&header, big_endian)) * units_per_second +
+                                         u64::from(read_u32(&header[4..], big_endian)),
+                                         units_per_second),
+                    link_type: link_type,
+                    original_length: read_u32(&header[12..], big_endian) as usize,
+                    data: data,
+                }))
+            }
+            Format::Pcapng { .. } => self.read_block(),
+        }
+    }
+
+    /// Read pcapng blocks until a packet block is found
+    fn read_block(&mut self) -> Result<Option<CaptureRecord>, CaptureError> {
+        loop {
+            let mut header = [0; 8];
+            if !fill(&mut self.reader, &mut header)? {
+                return Ok(None);
+            }
+
+            // A new section may change the byte order
+            if header[..4] == [0x0a, 0x0d, 0x0d, 0x0a] {
+                read_section_header(&mut Chain(&header[4..], &mut self.reader), &mut self.format)?;
+                continue;
+            }
+
+            let (big_endian, interfaces) = match self.format {
+                Format::Pcapng { big_endian, ref mut interfaces } => (big_endian, interfaces),
+                Format::Pcap { .. } => return Ok(None),
+            };
+            let block_type = read_u32(&header, big_endian);
+            let length = read_u32(&header[4..], big_endian) as usize;
+            if length < 12 || length & 3 != 0 {
+                return Err(CaptureError::Invalid(format!("block length {}", length)));
+            }
+            let body = read_data(&mut self.reader, length - 8)?;
+            let body = &body[..body.len() - 4];
+
+            match block_type {
+                // Interface description block
+                1 => {
+                    if body.len() < 8 {
+                        return Err(CaptureError::Invalid("interface description block".to_owned()));
+                    }
+                    let mut units_per_second = 1_000_000;
+                    for (code, value) in options(&body[8..], big_endian) {
+                        // The timestamp resolution
+                        if let (9, Some(&resolution)) = (code, value.first()) {
+                            units_per_second = resolution_units(resolution);
+                        }
+                    }
+                    interfaces.push(Interface {
+                        link_type: LinkType::from_u32(u32::from(read_u16(body, big_endian))),
+                        units_per_second: units_per_second,
+                    });
+                }
+
+                // Enhanced packet block and the obsolete packet block
+                2 | 6 => {
+                    if body.len() < 20 {
+                        return Err(CaptureError::Invalid("packet block".to_owned()));
+                    }
+                    let interface = match block_type {
+                        2 => u32::from(read_u16(body, big_endian)),
+                        _ => read_u32(body, big_endian),
+                    };
+                    let interface = interfaces.get(interface as usize)
+                        .ok_or_else(|| CaptureError::Invalid(format!("interface {}", interface)))?;
+                    let units = u64::from(read_u32(&body[4..], big_endian)) << 32 |
+                                u64::from(read_u32(&body[8..], big_endian));
+                    let captured = read_u32(&body[12..], big_endian) as usize;
+                    let data = body.get(20..20 + captured)
+                        .ok_or_else(|| CaptureError::Invalid("packet block length".to_owned()))?;
+                    return Ok(Some(CaptureRecord {
+                        timestamp: timestamp(units, interface.units_per_second),
+                        link_type: interface.link_type,
+                        original_length: read_u32(&body[16..], big_endian) as usize,
+                        data: data.to_vec(),
+                    }));
+                }
+
+                // Simple packet block, which belongs to the first interface
+                3 => {
+                    if body.len() < 4 {
+                        return Err(CaptureError::Invalid("simple packet block".to_owned()));
+                    }
+                    let interface = interfaces.first()
+                        .ok_or_else(|| CaptureError::Invalid("interface 0".to_owned()))?;
+                    let original_length = read_u32(body, big_endian) as usize;
+                    let data = &body[4..];
+                    return Ok(Some(CaptureRecord {
+                        timestamp: Duration::default(),
+                        link_type: interface.link_type,
+                        original_length: original_length,
+                        data: data[..cmp::min(original_length, data.len())].to_vec(),
+                    }));
+                }
+
+                // Name resolution, statistics and other blocks
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_record() {
+            Ok(record) => record.map(Ok),
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// A reader which returns already consumed bytes before the remaining ones
+struct Chain<'a, R: 'a>(&'a [u8], &'a mut R);
+
+impl<'a, R: Read> Read for Chain<'a, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return self.1.read(buffer);
+        }
+        let length = cmp::min(self.0.len(), buffer.len());
+        buffer[..length].copy_from_slice(&self.0[..length]);
+        self.0 = &self.0[length..];
+        Ok(length)
+    }
+}
+
+/// Read the section header block after its block type, which resets the interfaces
+fn read_section_header<R: Read>(reader: &mut R, format: &mut Format) -> Result<(), CaptureError> {
+    let mut header = [0; 8];
+    if !fill(reader, &mut header)? {
+        return Err(CaptureError::Truncated);
+    }
+    let big_endian = match header[4..] {
+        [0x1a, 0x2b, 0x3c, 0x4d] => true,
+        [0x4d, 0x3c, 0x2b, 0x1a] => false,
+        _ => return Err(CaptureError::Invalid("byte order magic".to_owned())),
+    };
+    let length = read_u32(&header, big_endian) as usize;
+    if length < 16 || length & 3 != 0 {
+        return Err(CaptureError::Invalid(format!("section header length {}", length)));
+    }
+    read_data(reader, length - 12)?;
+    *format = Format::Pcapng {
+        big_endian: big_endian,
+        interfaces: vec![],
+    };
+    Ok(())
+}
+
+/// Iterate over the options of a pcapng block, until the end of options or of the input
+fn options(mut input: &[u8], big_endian: bool) -> Vec<(u16, &[u8])> {
+    let mut options = vec![];
+    while input.len() >= 4 {
+        let code = read_u16(input, big_endian);
+        let length = read_u16(&input[2..], big_endian) as usize;
+        if code == 0 || input.len() < 4 + length {
+            break;
+        }
+        options.push((code, &input[4..4 + length]));
+        input = &input[cmp::min(4 + ((length + 3) & !3), input.len())..];
+    }
+    options
+}
+
+/// Fill the buffer completely, which returns false if the reader is at its end
+fn fill<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<bool, CaptureError> {
+    let mut position = 0;
+    while position < buffer.len() {
+        match reader.read(&mut buffer[position..]) {
+            Ok(0) if position == 0 => return Ok(false),
+            Ok(0) => return Err(CaptureError::Truncated),
+            Ok(length) => position += length,
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(true)
+}
+
+/// Read the given amount of bytes, where the end of the file is an error
+fn read_data<R: Read>(reader: &mut R, length: usize) -> Result<Vec<u8>, CaptureError> {
+    if length > MAX_LENGTH {
+        return Err(CaptureError::Invalid(format!("length {}", length)));
+    }
+    let mut data = vec![0; length];
+    if length > 0 && !fill(reader, &mut data)? {
+        return Err(CaptureError::Truncated);
+    }
+    Ok(data)
+}
+
+/// Read a u16 in the given byte order
+fn read_u16(input: &[u8], big_endian: bool) -> u16 {
+    let bytes = [input[0], input[1]];
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+/// Read a u32 in the given byte order
+fn read_u32(input: &[u8], big_endian: bool) -> u32 {
+    let bytes = [input[0], input[1], input[2], input[3]];
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+/// The timestamp units per second of a pcapng resolution, which is a negative power of two if the
+/// top bit is set and a negative power of ten otherwise. Unrepresentable resolutions are zero.
+fn resolution_units(resolution: u8) -> u64 {
+    let units = match resolution & 0x80 {
+        0 => 10u64.checked_pow(u32::from(resolution)),
+        _ => 1u64.checked_shl(u32::from(resolution & 0x7f)),
+    };
+    units.unwrap_or_default()
+}
+
+/// Convert a timestamp in the given units per second
+fn timestamp(units: u64, units_per_second: u64) -> Duration {
+    match units_per_second {
+        0 => Duration::default(),
+        _ => {
+            let nanos = u128::from(units % units_per_second) * 1_000_000_000 / u128::from(units_per_second);
+            Duration::new(units / units_per_second, nanos as u32)
+        }
+    }
+}
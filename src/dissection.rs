@@ -0,0 +1,345 @@
+//! Field trees of the packets with their exact position within the frame
+//!
+//! Every field is named by its protocol followed by the path of the struct fields it is taken
+//! from, like `ipv4.src` or `dns.questions.name`. Fields which only exist on the wire, like the
+//! length of a TLS handshake message, are named the same way. The offsets and lengths of the
+//! fields are counted in bits from the start of the frame, so that bit fields like the IPv4 flags
+//! or the TCP flag bits can be represented as well.
+use prelude::*;
+use std::ops::Range;
+use std::{cmp, mem};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The value of a field
+pub enum FieldValue {
+    /// A field which only groups other fields
+    None,
+
+    /// A flag
+    Bool(bool),
+
+    /// An unsigned integer
+    Unsigned(u64),
+
+    /// A signed integer
+    Signed(i64),
+
+    /// An enumerated value with its raw number and its name
+    Named(u64, String),
+
+    /// An IPv4 address
+    Ipv4(Ipv4Addr),
+
+    /// An IPv6 address
+    Ipv6(Ipv6Addr),
+
+    /// A mac address
+    Mac(MacAddress),
+
+    /// A text
+    Str(String),
+
+    /// Raw or undecoded data
+    Bytes(Vec<u8>),
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldValue::None => Ok(()),
+            FieldValue::Bool(value) => write!(f, "{}", value),
+            FieldValue::Unsigned(value) => write!(f, "{}", value),
+            FieldValue::Signed(value) => write!(f, "{}", value),
+            FieldValue::Named(value, ref name) => write!(f, "{} ({})", name, value),
+            FieldValue::Ipv4(ref address) => write!(f, "{}", address),
+            FieldValue::Ipv6(ref address) => write!(f, "{}", address),
+            FieldValue::Mac(ref address) => write!(f, "{}", address),
+            FieldValue::Str(ref text) => write!(f, "{}", text),
+            FieldValue::Bytes(ref data) => {
+                for byte in data {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+macro_rules! field_value_from {
+    ($t:ty, $variant:ident, $target:ty) => {
+        impl From<$t> for FieldValue {
+            fn from(value: $t) -> Self {
+                FieldValue::$variant(value as $target)
+            }
+        }
+    }
+}
+
+field_value_from!(u8, Unsigned, u64);
+field_value_from!(u16, Unsigned, u64);
+field_value_from!(u32, Unsigned, u64);
+field_value_from!(u64, Unsigned, u64);
+field_value_from!(usize, Unsigned, u64);
+field_value_from!(i8, Signed, i64);
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        FieldValue::Bool(value)
+    }
+}
+
+impl From<Ipv4Addr> for FieldValue {
+    fn from(address: Ipv4Addr) -> Self {
+        FieldValue::Ipv4(address)
+    }
+}
+
+impl From<Ipv6Addr> for FieldValue {
+    fn from(address: Ipv6Addr) -> Self {
+        FieldValue::Ipv6(address)
+    }
+}
+
+impl From<MacAddress> for FieldValue {
+    fn from(address: MacAddress) -> Self {
+        FieldValue::Mac(address)
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(text: String) -> Self {
+        FieldValue::Str(text)
+    }
+}
+
+impl<'a> From<&'a str> for FieldValue {
+    fn from(text: &'a str) -> Self {
+        FieldValue::Str(text.to_owned())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A named field of a packet together with its position within the frame
+pub struct Field {
+    /// The name of the field, like `ipv4.src`
+    pub name: String,
+
+    /// The value of the field
+    pub value: FieldValue,
+
+    /// The offset of the field in bits, counted from the start of the frame
+    pub bit_offset: usize,
+
+    /// The length of the field in bits
+    pub bit_length: usize,
+
+    /// The fields which are contained in this one
+    pub children: Vec<Field>,
+}
+
+impl Field {
+    /// Create a new field without children
+    pub fn new<V: Into<FieldValue>>(name: &str, value: V, bit_offset: usize, bit_length: usize) -> Self {
+        Field {
+            name: name.to_owned(),
+            value: value.into(),
+            bit_offset: bit_offset,
+            bit_length: bit_length,
+            children: vec![],
+        }
+    }
+
+    /// The bytes of the frame which contain the field
+    pub fn byte_range(&self) -> Range<usize> {
+        self.bit_offset / 8..self.bit_end().div_ceil(8)
+    }
+
+    /// Check if the field does not start or end at a byte boundary
+    pub fn is_bit_field(&self) -> bool {
+        (self.bit_offset | self.bit_length) & 7 != 0
+    }
+
+    /// The end of the field in bits, counted from the start of the frame
+    pub fn bit_end(&self) -> usize {
+        self.bit_offset + self.bit_length
+    }
+
+    /// Iterate over the field and all of its descendants in depth-first order
+    pub fn iter(&self) -> FieldIter<'_> {
+        FieldIter { stack: vec![self] }
+    }
+
+    /// Find the first field with the given name, which may be the field itself
+    pub fn find(&self, name: &str) -> Option<&Field> {
+        self.iter().find(|field| field.name == name)
+    }
+
+    /// Write the field and its descendants indented by the given depth
+    fn write_tree(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        write!(f, "{:width$}{}", "", self.name, width = depth * 4)?;
+        if self.value != FieldValue::None {
+            write!(f, ": {}", self.value)?;
+        }
+        let range = if self.is_bit_field() {
+            ("bits", self.bit_offset, self.bit_end())
+        } else {
+            ("bytes", self.bit_offset / 8, self.bit_end() / 8)
+        };
+        writeln!(f, " [{} {}..{}]", range.0, range.1, range.2)?;
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the field as an indented tree, one line per field
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+/// A depth-first iterator over a field tree
+pub struct FieldIter<'a> {
+    /// The fields which are not visited yet
+    stack: Vec<&'a Field>,
+}
+
+impl<'a> Iterator for FieldIter<'a> {
+    type Item = &'a Field;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let field = self.stack.pop()?;
+        self.stack.extend(field.children.iter().rev());
+        Some(field)
+    }
+}
+
+/// A packet which can be dissected into a field tree
+pub trait Dissect {
+    /// Dissect the packet from the bytes it was parsed from, which start at the given bit offset
+    /// within the frame. The returned field spans all bytes which belong to the packet, the next
+    /// layer starts right after it.
+    fn dissect(&self, input: &[u8], offset: usize) -> Field;
+}
+
+/// Creates the fields of a packet by walking over its bytes
+pub struct FieldCursor<'a> {
+    /// The bytes of the packet
+    input: &'a [u8],
+
+    /// The offset of the bytes within the frame in bits
+    offset: usize,
+
+    /// The amount of consumed bits
+    position: usize,
+
+    /// The fields created so far
+    fields: Vec<Field>,
+}
+
+impl<'a> FieldCursor<'a> {
+    /// Create a new cursor for the bytes of a packet, which start at the given bit offset within
+    /// the frame
+    pub fn new(input: &'a [u8], offset: usize) -> Self {
+        FieldCursor {
+            input: input,
+            offset: offset,
+            position: 0,
+            fields: vec![],
+        }
+    }
+
+    /// The amount of consumed bits
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The bytes which are not consumed yet, starting at the current byte
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.input[cmp::min(self.position / 8, self.input.len())..]
+    }
+
+    /// Read the given amount of bits at the current position without consuming them. Missing
+    /// bytes at the end of the input are read as zero.
+    pub fn peek(&self, bits: usize) -> u64 {
+        (self.position..self.position + cmp::min(bits, 64)).fold(0, |value, bit| {
+            let byte = self.input.get(bit / 8).cloned().unwrap_or_default();
+            value << 1 | u64::from(byte >> (7 - bit % 8) & 1)
+        })
+    }
+
+    /// Add a field with the given value and length in bits
+    pub fn field<V: Into<FieldValue>>(&mut self, name: &str, bits: usize, value: V) -> &mut Self {
+        self.fields.push(Field::new(name, value, self.offset + self.position, bits));
+        self.position += bits;
+        self
+    }
+
+    /// Add an unsigned integer field, which is read from the input
+    pub fn uint(&mut self, name: &str, bits: usize) -> &mut Self {
+        let value = self.peek(bits);
+        self.field(name, bits, value)
+    }
+
+    /// Add an enumerated field, which is read from the input and named after the given value
+    pub fn named<T: fmt::Debug>(&mut self, name: &str, bits: usize, value: &T) -> &mut Self {
+        let raw = self.peek(bits);
+        self.field(name, bits, FieldValue::Named(raw, format!("{:?}", value)))
+    }
+
+    /// Add a field with the given amount of raw bytes, limited to the available input
+    pub fn bytes(&mut self, name: &str, length: usize) -> &mut Self {
+        let data = self.remaining();
+        let data = data[..cmp::min(length, data.len())].to_vec();
+        let bits = data.len() * 8;
+        self.field(name, bits, FieldValue::Bytes(data))
+    }
+
+    /// Skip the given amount of bits without adding a field
+    pub fn skip(&mut self, bits: usize) -> &mut Self {
+        self.position += bits;
+        self
+    }
+
+    /// Skip all bits up to the given position, which is limited to the end of the input
+    pub fn skip_to(&mut self, bits: usize) -> &mut Self {
+        self.position = cmp::max(self.position, cmp::min(bits, self.input.len() * 8));
+        self
+    }
+
+    /// Add the field tree of an encapsulated packet, which occupies the given amount of bytes
+    pub fn dissect<D: Dissect + ?Sized>(&mut self, packet: &D, length: usize) -> &mut Self {
+        let data = self.remaining();
+        let data = &data[..cmp::min(length, data.len())];
+        let field = packet.dissect(data, self.offset + self.position);
+        self.position += data.len() * 8;
+        self.fields.push(field);
+        self
+    }
+
+    /// Add a field which contains all fields created by the given closure
+    pub fn group<V, F>(&mut self, name: &str, value: V, build: F) -> &mut Self
+        where V: Into<FieldValue>,
+              F: FnOnce(&mut Self)
+    {
+        let start = self.position;
+        let outer = mem::take(&mut self.fields);
+        build(self);
+        let children = mem::replace(&mut self.fields, outer);
+        let mut field = Field::new(name, value, self.offset + start, self.position - start);
+        field.children = children;
+        self.fields.push(field);
+        self
+    }
+
+    /// Create the field of the whole packet, which contains all created fields
+    pub fn finish<V: Into<FieldValue>>(self, name: &str, value: V) -> Field {
+        let mut field = Field::new(name, value, self.offset, self.position);
+        field.children = self.fields;
+        field
+    }
+}
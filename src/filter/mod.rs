@@ -0,0 +1,620 @@
+//! Display filters, which are evaluated against the field trees of dissected packets
+//!
+//! A filter like `ipv4.src == 10.0.0.0/8 && tcp.dest_port in {443 8443}` refers to the fields by
+//! the names of the field tree, see the `dissection` module. The following expressions are
+//! supported:
+//!
+//! - A field or protocol name alone checks for its presence, like `dns` or `tcp.options`.
+//! - The comparisons `==`, `!=`, `<`, `<=`, `>`, `>=` and their names `eq`, `ne`, `lt`, `le`,
+//!   `gt` and `ge`. Numbers are decimal or hexadecimal with a `0x` prefix, addresses may be
+//!   written as networks like `10.0.0.0/8` or `fe80::/10` and byte sequences like `00:1b:21:3a`.
+//!   Enumerated values compare by their number or by their name like `handshake`, where the
+//!   case and underscores are ignored.
+//! - The set membership `field in {80 443 8000..8080}`.
+//! - The operators `contains` for substrings and byte sequences and `matches` (or `~`) for
+//!   regular expressions, where strings are written in double quotes.
+//! - The boolean operators `&&`, `||` and `!` (or `and`, `or` and `not`) and parentheses.
+//!
+//! A comparison is true if any occurrence of the field matches, except for `!=`, which requires
+//! that the field is present and none of its occurrences is equal.
+use prelude::*;
+use std::cmp::Ordering;
+
+mod regex;
+pub use self::regex::Regex;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// An error within a filter expression
+pub struct FilterError {
+    /// The byte position of the error within the expression
+    pub position: usize,
+
+    /// The description of the error
+    pub message: String,
+}
+
+impl FilterError {
+    /// Create a new error at the given position
+    fn new<S: Into<String>>(position: usize, message: S) -> Self {
+        FilterError {
+            position: position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for FilterError {}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// A comparison operator
+pub enum Operator {
+    /// `==`
+    Equal,
+
+    /// `!=`
+    NotEqual,
+
+    /// `<`
+    Less,
+
+    /// `<=`
+    LessEqual,
+
+    /// `>`
+    Greater,
+
+    /// `>=`
+    GreaterEqual,
+}
+
+impl Operator {
+    /// Check if the ordering of a field value against a literal fulfills the operator
+    fn accepts(&self, ordering: Ordering) -> bool {
+        match *self {
+            Operator::Equal | Operator::NotEqual => ordering == Ordering::Equal,
+            Operator::Less => ordering == Ordering::Less,
+            Operator::LessEqual => ordering != Ordering::Greater,
+            Operator::Greater => ordering == Ordering::Greater,
+            Operator::GreaterEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A literal value, which is interpreted depending on the type of the compared field
+pub struct Literal {
+    /// The literal as written, without quotes and escapes
+    pub text: String,
+
+    /// The literal was quoted, which makes it a string only
+    pub quoted: bool,
+
+    /// The literal as number
+    pub number: Option<i128>,
+
+    /// The literal as address
+    pub address: Option<IpAddr>,
+
+    /// The literal as network with its prefix length
+    pub network: Option<(IpAddr, u8)>,
+
+    /// The literal as byte sequence
+    pub bytes: Option<Vec<u8>>,
+}
+
+impl Literal {
+    /// Create a literal from an unquoted word
+    pub fn word(text: &str) -> Self {
+        let number = match text.strip_prefix("0x") {
+            Some(hex) => i128::from_str_radix(hex, 16).ok(),
+            None => text.parse().ok(),
+        };
+        let network = text.find('/').and_then(|index| {
+            let address: IpAddr = text[..index].parse().ok()?;
+            let prefix: u8 = text[index + 1..].parse().ok()?;
+            let bits = if address.is_ipv4() { 32 } else { 128 };
+            if prefix <= bits { Some((address, prefix)) } else { None }
+        });
+        let bytes = if text.contains(':') || text.contains('-') {
+            text.split([':', '-']).map(|byte| u8::from_str_radix(byte, 16).ok().filter(|_| byte.len() == 2)).collect()
+        } else {
+            None
+        };
+        Literal {
+            text: text.to_owned(),
+            quoted: false,
+            number: number,
+            address: text.parse().ok(),
+            network: network,
+            bytes: bytes,
+        }
+    }
+
+    /// Create a literal from a quoted string
+    pub fn string(text: &str) -> Self {
+        Literal {
+            text: text.to_owned(),
+            quoted: true,
+            number: None,
+            address: None,
+            network: None,
+            bytes: Some(text.as_bytes().to_vec()),
+        }
+    }
+
+    /// Compare a field value against the literal, which is `None` if the types do not match
+    fn compare(&self, value: &FieldValue) -> Option<Ordering> {
+        match *value {
+            FieldValue::Bool(flag) => {
+                let literal = match (self.number, self.text.as_str()) {
+                    (Some(number @ 0..=1), _) => number == 1,
+                    (_, "true") if !self.quoted => true,
+                    (_, "false") if !self.quoted => false,
+                    _ => return None,
+                };
+                Some(flag.cmp(&literal))
+            }
+            FieldValue::Unsigned(number) => self.number.map(|literal| i128::from(number).cmp(&literal)),
+            FieldValue::Signed(number) => self.number.map(|literal| i128::from(number).cmp(&literal)),
+            FieldValue::Named(number, ref name) => {
+                match self.number {
+                    Some(literal) if !self.quoted => Some(i128::from(number).cmp(&literal)),
+                    _ if normalize(name) == normalize(&self.text) => Some(Ordering::Equal),
+                    _ => None,
+                }
+            }
+            FieldValue::Ipv4(address) => self.compare_address(IpAddr::V4(address)),
+            FieldValue::Ipv6(address) => self.compare_address(IpAddr::V6(address)),
+            FieldValue::Mac(ref address) => {
+                let address = [address.0, address.1, address.2, address.3, address.4, address.5];
+                self.bytes.as_ref().filter(|_| !self.quoted).map(|bytes| address[..].cmp(bytes))
+            }
+            FieldValue::Str(ref text) => Some(text.as_str().cmp(&self.text)),
+            FieldValue::Bytes(ref data) => self.bytes.as_ref().map(|bytes| data.cmp(bytes)),
+            FieldValue::None => None,
+        }
+    }
+
+    /// Compare an address against the literal address, where a network is equal to all of its
+    /// addresses and has no ordering
+    fn compare_address(&self, address: IpAddr) -> Option<Ordering> {
+        if let Some((network, prefix)) = self.network {
+            let contained = match (address, network) {
+                (IpAddr::V4(address), IpAddr::V4(network)) => {
+                    let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+                    u32::from(address) & mask == u32::from(network) & mask
+                }
+                (IpAddr::V6(address), IpAddr::V6(network)) => {
+                    let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+                    u128::from(address) & mask == u128::from(network) & mask
+                }
+                _ => return None,
+            };
+            return if contained { Some(Ordering::Equal) } else { None };
+        }
+        match (address, self.address?) {
+            (IpAddr::V4(address), IpAddr::V4(literal)) => Some(address.cmp(&literal)),
+            (IpAddr::V6(address), IpAddr::V6(literal)) => Some(address.cmp(&literal)),
+            _ => None,
+        }
+    }
+
+    /// Check if the field value contains the literal as substring or byte sequence
+    fn contained_in(&self, value: &FieldValue) -> bool {
+        match *value {
+            FieldValue::Str(ref text) => text.contains(self.text.as_str()),
+            FieldValue::Bytes(ref data) => {
+                match self.bytes {
+                    Some(ref bytes) if bytes.is_empty() => true,
+                    Some(ref bytes) => data.windows(bytes.len()).any(|window| window == &bytes[..]),
+                    None => false,
+                }
+            }
+            ref value => value.to_string().contains(self.text.as_str()),
+        }
+    }
+}
+
+/// Normalize a name for comparison, by ignoring the case and underscores
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| *c != '_' && *c != '-').flat_map(char::to_lowercase).collect()
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// An element of a set
+pub enum SetElement {
+    /// A single value
+    Value(Literal),
+
+    /// An inclusive range of numbers
+    Range(i128, i128),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A parsed filter expression
+pub enum Expression {
+    /// Both expressions have to match
+    And(Box<Expression>, Box<Expression>),
+
+    /// One of the expressions has to match
+    Or(Box<Expression>, Box<Expression>),
+
+    /// The expression must not match
+    Not(Box<Expression>),
+
+    /// The field has to be present
+    Exists(String),
+
+    /// The field has to fulfill the comparison
+    Compare(String, Operator, Literal),
+
+    /// The field has to be an element of the set
+    In(String, Vec<SetElement>),
+
+    /// The field has to contain the literal
+    Contains(String, Literal),
+
+    /// The field has to match the regular expression
+    Matches(String, Regex),
+}
+
+impl Expression {
+    /// Evaluate the expression against a field tree, like the one of a whole frame
+    pub fn matches(&self, root: &Field) -> bool {
+        let fields = |name| occurrences(root, name);
+        match *self {
+            Expression::And(ref left, ref right) => left.matches(root) && right.matches(root),
+            Expression::Or(ref left, ref right) => left.matches(root) || right.matches(root),
+            Expression::Not(ref expression) => !expression.matches(root),
+            Expression::Exists(ref name) => fields(name).next().is_some(),
+            Expression::Compare(ref name, Operator::NotEqual, ref literal) => {
+                let mut fields = fields(name).peekable();
+                fields.peek().is_some() &&
+                fields.all(|field| literal.compare(&field.value) != Some(Ordering::Equal))
+            }
+            Expression::Compare(ref name, operator, ref literal) => {
+                fields(name).any(|field| literal.compare(&field.value).is_some_and(|o| operator.accepts(o)))
+            }
+            Expression::In(ref name, ref set) => {
+                fields(name).any(|field| {
+                    set.iter().any(|element| match *element {
+                        SetElement::Value(ref literal) => literal.compare(&field.value) == Some(Ordering::Equal),
+                        SetElement::Range(low, high) => {
+                            let number = match field.value {
+                                FieldValue::Unsigned(number) | FieldValue::Named(number, _) => i128::from(number),
+                                FieldValue::Signed(number) => i128::from(number),
+                                _ => return false,
+                            };
+                            low <= number && number <= high
+                        }
+                    })
+                })
+            }
+            Expression::Contains(ref name, ref literal) => fields(name).any(|field| literal.contained_in(&field.value)),
+            Expression::Matches(ref name, ref regex) => {
+                fields(name).any(|field| match field.value {
+                    FieldValue::Str(ref text) => regex.is_match(text),
+                    FieldValue::Bytes(ref data) => regex.is_match(&String::from_utf8_lossy(data)),
+                    ref value => regex.is_match(&value.to_string()),
+                })
+            }
+        }
+    }
+}
+
+/// All fields of the tree with the given name
+fn occurrences<'a>(root: &'a Field, name: &'a str) -> impl Iterator<Item = &'a Field> {
+    root.iter().filter(move |field| field.name == name)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A compiled display filter
+pub struct DisplayFilter {
+    /// The root of the expression
+    pub expression: Expression,
+}
+
+impl DisplayFilter {
+    /// Compile a filter expression
+    pub fn new(input: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            end: input.len(),
+        };
+        let expression = parser.or()?;
+        match parser.peek() {
+            Some(&(position, _)) => Err(FilterError::new(position, "Unexpected token")),
+            None => Ok(DisplayFilter { expression: expression }),
+        }
+    }
+
+    /// Check if a field tree matches the filter
+    pub fn matches(&self, root: &Field) -> bool {
+        self.expression.matches(root)
+    }
+
+    /// Check if a packet matches the filter, the frame has to be the input of the traversal
+    /// which created the packet
+    pub fn matches_packet(&self, packet: &Packet, frame: &[u8]) -> bool {
+        self.matches(&packet.dissect(frame))
+    }
+}
+
+impl FromStr for DisplayFilter {
+    type Err = FilterError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        DisplayFilter::new(input)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A token of a filter expression
+enum Token {
+    /// `(`
+    Open,
+
+    /// `)`
+    Close,
+
+    /// `{`
+    OpenSet,
+
+    /// `}`
+    CloseSet,
+
+    /// `,`
+    Comma,
+
+    /// `&&` or `and`
+    And,
+
+    /// `||` or `or`
+    Or,
+
+    /// `!` or `not`
+    Not,
+
+    /// A comparison operator
+    Compare(Operator),
+
+    /// `in`
+    In,
+
+    /// `contains`
+    Contains,
+
+    /// `matches` or `~`
+    Matches,
+
+    /// A quoted string
+    Str(String),
+
+    /// A field name or an unquoted literal
+    Word(String),
+}
+
+/// Split an expression into tokens with their byte positions
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, FilterError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let mut followed_by = |expected: char| chars.next_if(|&(_, c)| c == expected).is_some();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '{' => Token::OpenSet,
+            '}' => Token::CloseSet,
+            ',' => Token::Comma,
+            '~' => Token::Matches,
+            '&' if followed_by('&') => Token::And,
+            '|' if followed_by('|') => Token::Or,
+            '=' if followed_by('=') => Token::Compare(Operator::Equal),
+            '!' if followed_by('=') => Token::Compare(Operator::NotEqual),
+            '!' => Token::Not,
+            '<' if followed_by('=') => Token::Compare(Operator::LessEqual),
+            '<' => Token::Compare(Operator::Less),
+            '>' if followed_by('=') => Token::Compare(Operator::GreaterEqual),
+            '>' => Token::Compare(Operator::Greater),
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => {
+                            match chars.next() {
+                                Some((_, 'n')) => text.push('\n'),
+                                Some((_, 'r')) => text.push('\r'),
+                                Some((_, 't')) => text.push('\t'),
+                                Some((_, c)) => text.push(c),
+                                None => return Err(FilterError::new(input.len(), "Unterminated string")),
+                            }
+                        }
+                        Some((_, c)) => text.push(c),
+                        None => return Err(FilterError::new(input.len(), "Unterminated string")),
+                    }
+                }
+                Token::Str(text)
+            }
+            c if is_word_char(c) => {
+                let mut end = position + c.len_utf8();
+                while let Some((index, c)) = chars.next_if(|&(_, c)| is_word_char(c)) {
+                    end = index + c.len_utf8();
+                }
+                match &input[position..end] {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "eq" => Token::Compare(Operator::Equal),
+                    "ne" => Token::Compare(Operator::NotEqual),
+                    "lt" => Token::Compare(Operator::Less),
+                    "le" => Token::Compare(Operator::LessEqual),
+                    "gt" => Token::Compare(Operator::Greater),
+                    "ge" => Token::Compare(Operator::GreaterEqual),
+                    "in" => Token::In,
+                    "contains" => Token::Contains,
+                    "matches" => Token::Matches,
+                    word => Token::Word(word.to_owned()),
+                }
+            }
+            c => return Err(FilterError::new(position, format!("Unexpected character '{}'", c))),
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+/// Characters of field names and unquoted literals, like addresses and networks
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || "_.:/-".contains(c)
+}
+
+/// A recursive descent parser over the tokens
+struct Parser<'a> {
+    /// All tokens of the expression
+    tokens: &'a [(usize, Token)],
+
+    /// The index of the next token
+    position: usize,
+
+    /// The length of the expression, which is the position of errors at its end
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// The next token without consuming it
+    fn peek(&self) -> Option<&'a (usize, Token)> {
+        self.tokens.get(self.position)
+    }
+
+    /// Consume the next token, which is an error at the end of the expression
+    fn next(&mut self, expected: &str) -> Result<&'a (usize, Token), FilterError> {
+        let token = self.tokens
+            .get(self.position)
+            .ok_or_else(|| FilterError::new(self.end, format!("Missing {}", expected)))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Consume the next token if it is the given one
+    fn accept(&mut self, token: &Token) -> bool {
+        let accepted = self.peek().is_some_and(|next| next.1 == *token);
+        if accepted {
+            self.position += 1;
+        }
+        accepted
+    }
+
+    /// Parse alternatives
+    fn or(&mut self) -> Result<Expression, FilterError> {
+        let mut expression = self.and()?;
+        while self.accept(&Token::Or) {
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    /// Parse conjunctions
+    fn and(&mut self) -> Result<Expression, FilterError> {
+        let mut expression = self.not()?;
+        while self.accept(&Token::And) {
+            expression = Expression::And(Box::new(expression), Box::new(self.not()?));
+        }
+        Ok(expression)
+    }
+
+    /// Parse negations
+    fn not(&mut self) -> Result<Expression, FilterError> {
+        if self.accept(&Token::Not) {
+            return Ok(Expression::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    /// Parse a parenthesized expression or a field test
+    fn primary(&mut self) -> Result<Expression, FilterError> {
+        let (position, name) = match *self.next("field name")? {
+            (_, Token::Open) => {
+                let expression = self.or()?;
+                return match *self.next("')'")? {
+                    (_, Token::Close) => Ok(expression),
+                    (position, _) => Err(FilterError::new(position, "Expected ')'")),
+                };
+            }
+            (position, Token::Word(ref name)) => (position, name),
+            (position, _) => return Err(FilterError::new(position, "Expected field name")),
+        };
+        let valid = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+        if !name.starts_with(char::is_alphabetic) || !name.chars().all(valid) {
+            return Err(FilterError::new(position, format!("Invalid field name '{}'", name)));
+        }
+        let name = name.clone();
+
+        Ok(match self.peek() {
+            Some(&(_, Token::Compare(operator))) => {
+                self.position += 1;
+                Expression::Compare(name, operator, self.literal()?.1)
+            }
+            Some(&(_, Token::Contains)) => {
+                self.position += 1;
+                Expression::Contains(name, self.literal()?.1)
+            }
+            Some(&(_, Token::Matches)) => {
+                self.position += 1;
+                let (position, literal) = self.literal()?;
+                let regex = Regex::new(&literal.text).map_err(|message| FilterError::new(position, message))?;
+                Expression::Matches(name, regex)
+            }
+            Some(&(_, Token::In)) => {
+                self.position += 1;
+                Expression::In(name, self.set()?)
+            }
+            _ => Expression::Exists(name),
+        })
+    }
+
+    /// Parse a literal value
+    fn literal(&mut self) -> Result<(usize, Literal), FilterError> {
+        match *self.next("value")? {
+            (position, Token::Word(ref word)) => Ok((position, Literal::word(word))),
+            (position, Token::Str(ref text)) => Ok((position, Literal::string(text))),
+            (position, _) => Err(FilterError::new(position, "Expected value")),
+        }
+    }
+
+    /// Parse the elements of a set, which are separated by whitespace or commas
+    fn set(&mut self) -> Result<Vec<SetElement>, FilterError> {
+        match *self.next("'{'")? {
+            (_, Token::OpenSet) => {}
+            (position, _) => return Err(FilterError::new(position, "Expected '{'")),
+        }
+        let mut elements = vec![];
+        while !self.accept(&Token::CloseSet) {
+            if !elements.is_empty() {
+                self.accept(&Token::Comma);
+            }
+            let (position, literal) = self.literal()?;
+            let range = match literal.text.find("..") {
+                Some(index) if !literal.quoted => {
+                    let bound = |text: &str| Literal::word(text).number;
+                    match (bound(&literal.text[..index]), bound(&literal.text[index + 2..])) {
+                        (Some(low), Some(high)) if low <= high => Some(SetElement::Range(low, high)),
+                        _ => return Err(FilterError::new(position, format!("Invalid range '{}'", literal.text))),
+                    }
+                }
+                _ => None,
+            };
+            elements.push(range.unwrap_or(SetElement::Value(literal)));
+        }
+        Ok(elements)
+    }
+}
@@ -0,0 +1,435 @@
+//! A small regular expression engine for the `matches` operator
+//!
+//! Expressions are compiled to a Thompson automaton, which is simulated for all start positions
+//! at once. Matching therefore takes time linear in the length of the text and a constant
+//! amount of stack, independent of the expression.
+//!
+//! Supported are literals, `.`, character classes like `[a-z_]` or `[^0-9]`, the escapes `\d`,
+//! `\w`, `\s` and their negations, the anchors `^` and `$`, groups with alternatives like
+//! `(get|post)`, the repetitions `*`, `+`, `?` and `{n,m}` as well as a leading `(?i)` to ignore
+//! the case.
+use prelude::*;
+use std::mem;
+
+/// The maximum amount of instructions of a compiled expression
+const MAX_PROGRAM_LENGTH: usize = 10_000;
+
+/// The maximum nesting of groups
+const MAX_NESTING: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+/// A compiled regular expression
+pub struct Regex {
+    /// The instructions of the automaton, the last one is always a match
+    program: Vec<Instruction>,
+
+    /// The case of letters is ignored
+    ignore_case: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A single element of an expression
+enum Node {
+    /// A literal character
+    Char(char),
+
+    /// Any character
+    Any,
+
+    /// A set of character ranges, which may be negated
+    Class(Vec<(char, char)>, bool),
+
+    /// The start of the text
+    Start,
+
+    /// The end of the text
+    End,
+
+    /// Alternative sequences of repeated nodes
+    Group(Vec<Vec<(Node, usize, Option<usize>)>>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// An instruction of the automaton
+enum Instruction {
+    /// Consume a literal character
+    Char(char),
+
+    /// Consume any character
+    Any,
+
+    /// Consume a character within the ranges, which may be negated
+    Class(Vec<(char, char)>, bool),
+
+    /// Continue only at the start of the text
+    Start,
+
+    /// Continue only at the end of the text
+    End,
+
+    /// Continue at both instructions
+    Split(usize, usize),
+
+    /// Continue at the instruction
+    Jump(usize),
+
+    /// The expression matched
+    Match,
+}
+
+/// A set of threads of the automaton at one position of the text
+struct Threads {
+    /// The instructions waiting for the next character, in insertion order
+    waiting: Vec<usize>,
+
+    /// The instructions already visited at this position
+    visited: Vec<bool>,
+
+    /// The instructions still to be visited
+    stack: Vec<usize>,
+}
+
+impl Threads {
+    /// Create an empty set for a program of the given length
+    fn new(length: usize) -> Self {
+        Threads {
+            waiting: vec![],
+            visited: vec![false; length],
+            stack: vec![],
+        }
+    }
+
+    /// Remove all threads
+    fn clear(&mut self) {
+        self.waiting.clear();
+        self.visited.iter_mut().for_each(|visited| *visited = false);
+    }
+}
+
+impl Regex {
+    /// Compile an expression, the error is a description of the problem
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let (ignore_case, pattern) = match pattern.strip_prefix("(?i)") {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut position = 0;
+        let root = parse_group(&chars, &mut position, 0)?;
+        if position < chars.len() {
+            return Err(format!("Unmatched ')' at position {}", position));
+        }
+        let mut program = vec![];
+        compile(&root, &mut program)?;
+        program.push(Instruction::Match);
+        Ok(Regex {
+            program: program,
+            ignore_case: ignore_case,
+        })
+    }
+
+    /// Check if the expression matches anywhere within the text
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = match self.ignore_case {
+            true => text.chars().flat_map(char::to_lowercase).collect(),
+            false => text.chars().collect(),
+        };
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        for position in 0..=text.len() {
+            // A new thread starts at every position, since the expression may match anywhere
+            if self.add_thread(&mut current, 0, &text, position) {
+                return true;
+            }
+            let c = match text.get(position) {
+                Some(&c) => c,
+                None => break,
+            };
+            next.clear();
+            for &pc in &current.waiting {
+                let consumed = match self.program[pc] {
+                    Instruction::Char(expected) => self.fold(c) == self.fold(expected),
+                    Instruction::Any => true,
+                    Instruction::Class(ref ranges, negated) => self.in_class(ranges, c) != negated,
+                    _ => false,
+                };
+                if consumed && self.add_thread(&mut next, pc + 1, &text, position + 1) {
+                    return true;
+                }
+            }
+            mem::swap(&mut current, &mut next);
+        }
+        false
+    }
+
+    /// Add a thread and all threads reachable without consuming a character, returns true if
+    /// one of them is a match
+    fn add_thread(&self, threads: &mut Threads, pc: usize, text: &[char], position: usize) -> bool {
+        threads.stack.clear();
+        threads.stack.push(pc);
+        while let Some(pc) = threads.stack.pop() {
+            if mem::replace(&mut threads.visited[pc], true) {
+                continue;
+            }
+            match self.program[pc] {
+                Instruction::Match => return true,
+                Instruction::Jump(target) => threads.stack.push(target),
+                Instruction::Split(first, second) => {
+                    threads.stack.push(second);
+                    threads.stack.push(first);
+                }
+                Instruction::Start if position == 0 => threads.stack.push(pc + 1),
+                Instruction::End if position == text.len() => threads.stack.push(pc + 1),
+                Instruction::Start | Instruction::End => {}
+                _ => threads.waiting.push(pc),
+            }
+        }
+        false
+    }
+
+    /// Check if a character is within one of the ranges
+    fn in_class(&self, ranges: &[(char, char)], c: char) -> bool {
+        let matches = |c: char| ranges.iter().any(|&(low, high)| low <= c && c <= high);
+        matches(c) || (self.ignore_case && c.to_uppercase().any(matches))
+    }
+
+    /// Fold the case of a character if the case is ignored
+    fn fold(&self, c: char) -> char {
+        match self.ignore_case {
+            true => c.to_lowercase().next().unwrap_or(c),
+            false => c,
+        }
+    }
+}
+
+/// Append the instructions of a node to the program
+fn compile(node: &Node, program: &mut Vec<Instruction>) -> Result<(), String> {
+    if program.len() > MAX_PROGRAM_LENGTH {
+        return Err("Expression too large".to_owned());
+    }
+    match *node {
+        Node::Char(c) => program.push(Instruction::Char(c)),
+        Node::Any => program.push(Instruction::Any),
+        Node::Class(ref ranges, negated) => program.push(Instruction::Class(ranges.clone(), negated)),
+        Node::Start => program.push(Instruction::Start),
+        Node::End => program.push(Instruction::End),
+        Node::Group(ref alternatives) => {
+            // Every alternative but the last one is preceded by a split to the next alternative
+            // and followed by a jump to the end of the group
+            let mut jumps = vec![];
+            for (index, sequence) in alternatives.iter().enumerate() {
+                let last = index + 1 == alternatives.len();
+                let split = program.len();
+                if !last {
+                    program.push(Instruction::Split(split + 1, split + 1));
+                }
+                for &(ref node, min, max) in sequence {
+                    compile_repeated(node, min, max, program)?;
+                }
+                if !last {
+                    jumps.push(program.len());
+                    program.push(Instruction::Jump(0));
+                    program[split] = Instruction::Split(split + 1, program.len());
+                }
+            }
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Instruction::Jump(end);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Append the instructions of a node repeated between the minimum and maximum amount of times
+fn compile_repeated(node: &Node, min: usize, max: Option<usize>, program: &mut Vec<Instruction>) -> Result<(), String> {
+    if max.unwrap_or(min) > MAX_PROGRAM_LENGTH {
+        return Err("Expression too large".to_owned());
+    }
+    for _ in 0..min {
+        compile(node, program)?;
+    }
+    match max {
+        None => {
+            let split = program.len();
+            program.push(Instruction::Split(split + 1, split + 1));
+            compile(node, program)?;
+            program.push(Instruction::Jump(split));
+            program[split] = Instruction::Split(split + 1, program.len());
+        }
+        Some(max) => {
+            let mut splits = vec![];
+            for _ in min..max {
+                splits.push(program.len());
+                program.push(Instruction::Split(0, 0));
+                compile(node, program)?;
+            }
+            let end = program.len();
+            for split in splits {
+                program[split] = Instruction::Split(split + 1, end);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse alternatives until the end of the pattern or a closing parenthesis
+fn parse_group(chars: &[char], position: &mut usize, depth: usize) -> Result<Node, String> {
+    if depth > MAX_NESTING {
+        return Err("Too many nested groups".to_owned());
+    }
+    let mut alternatives = vec![vec![]];
+    while let Some(&c) = chars.get(*position) {
+        *position += 1;
+        let node = match c {
+            ')' => {
+                *position -= 1;
+                break;
+            }
+            '|' => {
+                alternatives.push(vec![]);
+                continue;
+            }
+            '(' => {
+                let group = parse_group(chars, position, depth + 1)?;
+                if chars.get(*position) != Some(&')') {
+                    return Err("Missing ')'".to_owned());
+                }
+                *position += 1;
+                group
+            }
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '[' => parse_class(chars, position)?,
+            '\\' => parse_escape(chars, position)?,
+            '*' | '+' | '?' | '{' => return Err(format!("Nothing to repeat at position {}", *position - 1)),
+            c => Node::Char(c),
+        };
+        let (min, max) = parse_repetition(chars, position)?;
+        if let Some(sequence) = alternatives.last_mut() {
+            sequence.push((node, min, max));
+        }
+    }
+    Ok(Node::Group(alternatives))
+}
+
+/// Parse an optional repetition after a node
+fn parse_repetition(chars: &[char], position: &mut usize) -> Result<(usize, Option<usize>), String> {
+    let repetition = match chars.get(*position) {
+        Some(&'*') => (0, None),
+        Some(&'+') => (1, None),
+        Some(&'?') => (0, Some(1)),
+        Some(&'{') => {
+            let end = chars[*position..]
+                .iter()
+                .position(|&c| c == '}')
+                .ok_or_else(|| "Missing '}'".to_owned())?;
+            let bounds: String = chars[*position + 1..*position + end].iter().collect();
+            let parse = |bound: &str| bound.trim().parse().map_err(|_| format!("Invalid repetition '{{{}}}'", bounds));
+            let repetition = match bounds.find(',') {
+                Some(index) if bounds[index + 1..].trim().is_empty() => (parse(&bounds[..index])?, None),
+                Some(index) => (parse(&bounds[..index])?, Some(parse(&bounds[index + 1..])?)),
+                None => (parse(&bounds)?, Some(parse(&bounds)?)),
+            };
+            if repetition.1.is_some_and(|max| max < repetition.0) {
+                return Err(format!("Invalid repetition '{{{}}}'", bounds));
+            }
+            *position += end;
+            repetition
+        }
+        _ => return Ok((1, Some(1))),
+    };
+    *position += 1;
+    Ok(repetition)
+}
+
+/// Parse an escape sequence after the backslash
+fn parse_escape(chars: &[char], position: &mut usize) -> Result<Node, String> {
+    let c = *chars.get(*position).ok_or_else(|| "Trailing '\\'".to_owned())?;
+    *position += 1;
+    Ok(match escape_class(c) {
+        Some((ranges, negated)) => Node::Class(ranges, negated),
+        None => Node::Char(escape_char(c)),
+    })
+}
+
+/// The ranges of the class escapes `\d`, `\w` and `\s` and their negations
+fn escape_class(c: char) -> Option<(Vec<(char, char)>, bool)> {
+    let ranges = match c.to_ascii_lowercase() {
+        'd' => vec![('0', '9')],
+        'w' => vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+        's' => vec![(' ', ' '), ('\t', '\r')],
+        _ => return None,
+    };
+    Some((ranges, c.is_ascii_uppercase()))
+}
+
+/// The ranges of all characters which are not within the given ranges
+fn complement(mut ranges: Vec<(char, char)>) -> Vec<(char, char)> {
+    ranges.sort_unstable();
+    let mut result = vec![];
+    let mut next = Some('\0');
+    for (low, high) in ranges {
+        if let Some(start) = next {
+            if start < low {
+                result.push((start, char::from_u32(low as u32 - 1).unwrap_or(start)));
+            }
+        }
+        next = next.max(char::from_u32(high as u32 + 1));
+    }
+    if let Some(start) = next {
+        result.push((start, char::MAX));
+    }
+    result
+}
+
+/// The character of a literal escape like `\n` or `\.`
+fn escape_char(c: char) -> char {
+    match c {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        c => c,
+    }
+}
+
+/// Parse a character class after the opening bracket
+fn parse_class(chars: &[char], position: &mut usize) -> Result<Node, String> {
+    let negated = chars.get(*position) == Some(&'^');
+    if negated {
+        *position += 1;
+    }
+    let mut ranges = vec![];
+    let mut first = true;
+    loop {
+        let c = *chars.get(*position).ok_or_else(|| "Missing ']'".to_owned())?;
+        *position += 1;
+        let low = match c {
+            ']' if !first => break,
+            '\\' => {
+                let c = *chars.get(*position).ok_or_else(|| "Trailing '\\'".to_owned())?;
+                *position += 1;
+                if let Some((class, negated)) = escape_class(c) {
+                    ranges.extend(if negated { complement(class) } else { class });
+                    first = false;
+                    continue;
+                }
+                escape_char(c)
+            }
+            c => c,
+        };
+        first = false;
+        match (chars.get(*position), chars.get(*position + 1)) {
+            (Some(&'-'), Some(&high)) if high != ']' => {
+                *position += 2;
+                if high < low {
+                    return Err(format!("Invalid class range '{}-{}'", low, high));
+                }
+                ranges.push((low, high));
+            }
+            _ => ranges.push((low, low)),
+        }
+    }
+    Ok(Node::Class(ranges, negated))
+}
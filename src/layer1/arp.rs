@@ -65,6 +65,7 @@ impl fmt::Display for ArpParser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of the Arp structure
 pub struct ArpPacket {
     /// This field specifies the network protocol type. Example: Ethernet is 1.
@@ -98,8 +99,64 @@ pub struct ArpPacket {
     pub target_protocol_address: Ipv4Addr,
 }
 
+impl Summary for ArpPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        if verbosity == Verbosity::Brief {
+            return write!(w, "ARP");
+        }
+        match self.operation {
+            ArpOperation::Request => {
+                write!(w,
+                       "ARP who-has {} tell {}",
+                       self.target_protocol_address,
+                       self.sender_protocol_address)?
+            }
+            ArpOperation::Reply => {
+                write!(w,
+                       "ARP {} is-at {}",
+                       self.sender_protocol_address,
+                       self.sender_hardware_address)?
+            }
+            ArpOperation::ReverseRequest => {
+                write!(w,
+                       "RARP who-is {} tell {}",
+                       self.target_hardware_address,
+                       self.sender_hardware_address)?
+            }
+            ArpOperation::ReverseReply => {
+                write!(w,
+                       "RARP {} at {}",
+                       self.target_hardware_address,
+                       self.target_protocol_address)?
+            }
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w, " hardware={:?} protocol={:?}", self.hardware_type, self.protocol_type)?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(ArpPacket);
+
+impl Dissect for ArpPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.named("arp.hardware_type", 16, &self.hardware_type)
+            .named("arp.protocol_type", 16, &self.protocol_type)
+            .field("arp.hardware_length", 8, self.hardware_length)
+            .field("arp.protocol_length", 8, self.protocol_length)
+            .named("arp.operation", 16, &self.operation)
+            .field("arp.sender_hardware_address", 48, self.sender_hardware_address.clone())
+            .field("arp.sender_protocol_address", 32, self.sender_protocol_address)
+            .field("arp.target_hardware_address", 48, self.target_hardware_address.clone())
+            .field("arp.target_protocol_address", 32, self.target_protocol_address);
+        cursor.finish("arp", self.summary(Verbosity::Normal))
+    }
+}
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Supported ARP Hardware Types
 pub enum ArpHardwareType {
     /// Ethernet (10Mb)
@@ -118,6 +175,7 @@ impl ArpHardwareType {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Supported ARP operations
 pub enum ArpOperation {
     /// ARP requests
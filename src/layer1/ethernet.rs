@@ -32,6 +32,7 @@ impl fmt::Display for EthernetParser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of the Ethernet structure
 pub struct EthernetPacket {
     /// Destination mac address
@@ -44,11 +45,52 @@ pub struct EthernetPacket {
     pub ethertype: EtherType,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+impl Summary for EthernetPacket {
+    fn endpoints(&self) -> Option<(String, String)> {
+        Some((self.src.to_string(), self.dst.to_string()))
+    }
+
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "Ethernet")?;
+        if verbosity > Verbosity::Brief {
+            write!(w, " {:?}", self.ethertype)?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(EthernetPacket);
+
+impl Dissect for EthernetPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.field("ethernet.dst", 48, self.dst.clone())
+            .field("ethernet.src", 48, self.src.clone())
+            .named("ethernet.ethertype", 16, &self.ethertype);
+        cursor.finish("ethernet", self.summary(Verbosity::Normal))
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of a mac network address, usually in the format "ff:ff:ff:ff:ff:ff"
 pub struct MacAddress(pub u8, pub u8, pub u8, pub u8, pub u8, pub u8);
 
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+               self.0,
+               self.1,
+               self.2,
+               self.3,
+               self.4,
+               self.5)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Supported `EtherType`
 pub enum EtherType {
     /// Internet Protocol Version 4
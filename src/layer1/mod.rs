@@ -1,3 +1,4 @@
 //! Data Link Layer packets
 pub mod ethernet;
+#[cfg(feature = "arp")]
 pub mod arp;
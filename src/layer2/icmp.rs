@@ -61,6 +61,7 @@ impl fmt::Display for IcmpParser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of an Internet Control Message Protocol packet
 pub struct IcmpPacket {
     /// The ICMP type
@@ -77,7 +78,51 @@ pub struct IcmpPacket {
     pub data: Option<IcmpData>,
 }
 
+impl Summary for IcmpPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "ICMP")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        match self.message_type {
+            IcmpType::EchoRequest => write!(w, " echo request")?,
+            IcmpType::EchoReply => write!(w, " echo reply")?,
+        }
+        if let Some(IcmpData::Echo(ref echo)) = self.data {
+            write!(w, " id={} seq={}", echo.identifier, echo.sequence_number)?;
+            if verbosity == Verbosity::Verbose {
+                let length = echo.payload.as_ref().map_or(0, Vec::len);
+                write!(w, " len={}", length)?;
+            }
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w, " code={} checksum={:#x}", self.code, self.checksum)?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(IcmpPacket);
+
+impl Dissect for IcmpPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.named("icmp.message_type", 8, &self.message_type)
+            .field("icmp.code", 8, self.code)
+            .field("icmp.checksum", 16, self.checksum);
+        if let Some(IcmpData::Echo(ref echo)) = self.data {
+            cursor.field("icmp.data.identifier", 16, echo.identifier)
+                .field("icmp.data.sequence_number", 16, echo.sequence_number);
+            if let Some(ref payload) = echo.payload {
+                cursor.bytes("icmp.data.payload", payload.len());
+            }
+        }
+        cursor.finish("icmp", self.summary(Verbosity::Normal))
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Available ICMP control messages
 pub enum IcmpType {
     /// Echo reply used to ping
@@ -100,6 +145,7 @@ impl IcmpType {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of a certain ICMP message
 pub enum IcmpData {
     /// ICMP ping request and reply
@@ -107,6 +153,7 @@ pub enum IcmpData {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// ICMP echo request and replies
 pub struct IcmpEcho {
     /// Identifier
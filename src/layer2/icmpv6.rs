@@ -61,6 +61,7 @@ impl fmt::Display for Icmpv6Parser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of an Internet Control Message Protocol packet
 pub struct Icmpv6Packet {
     /// The ICMPv6 type
@@ -77,7 +78,51 @@ pub struct Icmpv6Packet {
     pub data: Option<Icmpv6Data>,
 }
 
+impl Summary for Icmpv6Packet {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "ICMPv6")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        match self.message_type {
+            Icmpv6Type::EchoRequest => write!(w, " echo request")?,
+            Icmpv6Type::EchoReply => write!(w, " echo reply")?,
+        }
+        if let Some(Icmpv6Data::Echo(ref echo)) = self.data {
+            write!(w, " id={} seq={}", echo.identifier, echo.sequence_number)?;
+            if verbosity == Verbosity::Verbose {
+                let length = echo.payload.as_ref().map_or(0, Vec::len);
+                write!(w, " len={}", length)?;
+            }
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w, " code={} checksum={:#x}", self.code, self.checksum)?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(Icmpv6Packet);
+
+impl Dissect for Icmpv6Packet {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.named("icmpv6.message_type", 8, &self.message_type)
+            .field("icmpv6.code", 8, self.code)
+            .field("icmpv6.checksum", 16, self.checksum);
+        if let Some(Icmpv6Data::Echo(ref echo)) = self.data {
+            cursor.field("icmpv6.data.identifier", 16, echo.identifier)
+                .field("icmpv6.data.sequence_number", 16, echo.sequence_number);
+            if let Some(ref payload) = echo.payload {
+                cursor.bytes("icmpv6.data.payload", payload.len());
+            }
+        }
+        cursor.finish("icmpv6", self.summary(Verbosity::Normal))
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Available `ICMPv6` control messages
 pub enum Icmpv6Type {
     /// Echo reply used to ping
@@ -100,6 +145,7 @@ impl Icmpv6Type {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of a certain `ICMPv6` message
 pub enum Icmpv6Data {
     /// ICMPv6 ping request and reply
@@ -83,6 +83,7 @@ impl fmt::Display for Ipv4Parser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of an Internet Protocol version 4 packet
 pub struct Ipv4Packet {
     /// Protocol version, should be '4'
@@ -122,7 +123,59 @@ pub struct Ipv4Packet {
     pub dst: Ipv4Addr,
 }
 
+impl Summary for Ipv4Packet {
+    fn endpoints(&self) -> Option<(String, String)> {
+        Some((self.src.to_string(), self.dst.to_string()))
+    }
+
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "IPv4")?;
+        if verbosity > Verbosity::Brief {
+            write!(w, " proto={:?} len={}", self.protocol, self.length)?;
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w, " ttl={} id={} tos={:#x}", self.ttl, self.id, self.tos)?;
+            if self.flags & 0b010 != 0 {
+                write!(w, " [DF]")?;
+            }
+            if self.flags & 0b001 != 0 {
+                write!(w, " [MF]")?;
+            }
+            if self.fragment_offset != 0 {
+                write!(w, " offset={}", self.fragment_offset)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(Ipv4Packet);
+
+impl Dissect for Ipv4Packet {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.field("ipv4.version", 4, self.version)
+            .field("ipv4.ihl", 4, self.ihl)
+            .field("ipv4.tos", 8, self.tos)
+            .field("ipv4.length", 16, self.length)
+            .field("ipv4.id", 16, self.id)
+            .group("ipv4.flags", self.flags, |flags| {
+                flags.field("ipv4.flags.reserved", 1, self.flags & 0b100 != 0)
+                    .field("ipv4.flags.df", 1, self.flags & 0b010 != 0)
+                    .field("ipv4.flags.mf", 1, self.flags & 0b001 != 0);
+            })
+            .field("ipv4.fragment_offset", 13, self.fragment_offset)
+            .field("ipv4.ttl", 8, self.ttl)
+            .named("ipv4.protocol", 8, &self.protocol)
+            .field("ipv4.checksum", 16, self.checksum)
+            .field("ipv4.src", 32, self.src)
+            .field("ipv4.dst", 32, self.dst);
+        cursor.finish("ipv4", self.summary(Verbosity::Normal))
+    }
+}
+
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Current supported IPv4 protocols
 pub enum IpProtocol {
     /// Internet Control Message Protocol
@@ -86,6 +86,7 @@ impl fmt::Display for Ipv6Parser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of an Internet Protocol version 6 packet
 pub struct Ipv6Packet {
     /// The constant 6 (bit sequence 0110).
@@ -124,3 +125,41 @@ pub struct Ipv6Packet {
     /// Destination address
     pub dst: Ipv6Addr,
 }
+
+impl Summary for Ipv6Packet {
+    fn endpoints(&self) -> Option<(String, String)> {
+        Some((self.src.to_string(), self.dst.to_string()))
+    }
+
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "IPv6")?;
+        if verbosity > Verbosity::Brief {
+            write!(w, " next={:?} len={}", self.next_header, self.payload_length)?;
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w,
+                   " hlim={} class={:#x} flow={:#x}",
+                   self.hop_limit,
+                   self.traffic_class,
+                   self.flow_label)?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(Ipv6Packet);
+
+impl Dissect for Ipv6Packet {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.field("ipv6.version", 4, self.version)
+            .field("ipv6.traffic_class", 8, self.traffic_class)
+            .field("ipv6.flow_label", 20, self.flow_label)
+            .field("ipv6.payload_length", 16, self.payload_length)
+            .named("ipv6.next_header", 8, &self.next_header)
+            .field("ipv6.hop_limit", 8, self.hop_limit)
+            .field("ipv6.src", 128, self.src)
+            .field("ipv6.dst", 128, self.dst);
+        cursor.finish("ipv6", self.summary(Verbosity::Normal))
+    }
+}
@@ -1,5 +1,7 @@
 //! Internet Layer packets
 pub mod ipv4;
 pub mod ipv6;
+#[cfg(feature = "icmp")]
 pub mod icmp;
+#[cfg(feature = "icmpv6")]
 pub mod icmpv6;
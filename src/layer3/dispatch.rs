@@ -0,0 +1,255 @@
+//! Port and heuristic based dispatching of the transport layer payload to application protocols
+use prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// A content heuristic, which returns true if the payload looks like the protocol
+pub type Heuristic = fn(&[u8]) -> bool;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// An application protocol the transport layer payload can be dispatched to
+pub enum ApplicationProtocol {
+    /// Transport Layer Security
+    Tls,
+
+    /// Datagram Transport Layer Security
+    Dtls,
+
+    /// Hypertext Transfer Protocol, as plain text within TCP or encrypted within TLS. Segments
+    /// of a connection which already transferred a message are parsed regardless of the dispatching.
+    Http,
+
+    /// Network Time Protocol
+    Ntp,
+
+    /// Domain Name System
+    Dns,
+
+    /// Multicast DNS
+    Mdns,
+
+    /// Link-Local Multicast Name Resolution
+    Llmnr,
+
+    /// Dynamic Host Configuration Protocol
+    Dhcp,
+
+    /// Dynamic Host Configuration Protocol for IPv6
+    Dhcpv6,
+
+    /// A custom protocol with its name, which is owned if it has been deserialized
+    Custom(Cow<'static, str>),
+}
+
+impl ApplicationProtocol {
+    /// The name of the protocol
+    pub fn name(&self) -> &str {
+        match *self {
+            ApplicationProtocol::Tls => "tls",
+            ApplicationProtocol::Dtls => "dtls",
+            ApplicationProtocol::Http => "http",
+            ApplicationProtocol::Ntp => "ntp",
+            ApplicationProtocol::Dns => "dns",
+            ApplicationProtocol::Mdns => "mdns",
+            ApplicationProtocol::Llmnr => "llmnr",
+            ApplicationProtocol::Dhcp => "dhcp",
+            ApplicationProtocol::Dhcpv6 => "dhcpv6",
+            ApplicationProtocol::Custom(ref name) => name,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+/// Serialized as its name
+impl Serialize for ApplicationProtocol {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+/// Deserialized from its name, unknown names result in a custom protocol
+impl<'de> Deserialize<'de> for ApplicationProtocol {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        let protocol = [ApplicationProtocol::Tls,
+                        ApplicationProtocol::Dtls,
+                        ApplicationProtocol::Http,
+                        ApplicationProtocol::Ntp,
+                        ApplicationProtocol::Dns,
+                        ApplicationProtocol::Mdns,
+                        ApplicationProtocol::Llmnr,
+                        ApplicationProtocol::Dhcp,
+                        ApplicationProtocol::Dhcpv6]
+            .iter()
+            .find(|p| p.name() == name)
+            .cloned();
+        Ok(protocol.unwrap_or_else(|| ApplicationProtocol::Custom(name.into())))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// A registry which maps ports and content heuristics to application protocols
+pub struct DispatchRegistry {
+    /// The protocols of every port
+    ports: HashMap<u16, Vec<ApplicationProtocol>>,
+
+    /// The heuristics which are applied to the payload of every packet
+    heuristics: Vec<(ApplicationProtocol, Heuristic)>,
+}
+
+impl DispatchRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        DispatchRegistry::default()
+    }
+
+    /// Create a registry with the well known ports and heuristics of the TCP based protocols
+    pub fn tcp() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = DispatchRegistry::new();
+        #[cfg(feature = "dns")]
+        registry.add_port(DNS_PORT, ApplicationProtocol::Dns);
+        #[cfg(feature = "tls")]
+        registry.add_port(443, ApplicationProtocol::Tls).add_heuristic(ApplicationProtocol::Tls, is_tls_record);
+        #[cfg(feature = "http")]
+        registry.add_port(HTTP_PORT, ApplicationProtocol::Http)
+            .add_port(HTTP_ALTERNATIVE_PORT, ApplicationProtocol::Http)
+            .add_port(443, ApplicationProtocol::Http)
+            .add_heuristic(ApplicationProtocol::Http, is_http_message);
+        registry
+    }
+
+    /// Create a registry with the well known ports and heuristics of the UDP based protocols
+    pub fn udp() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = DispatchRegistry::new();
+        #[cfg(feature = "dns")]
+        registry.add_port(DNS_PORT, ApplicationProtocol::Dns);
+        #[cfg(feature = "mdns")]
+        registry.add_port(MDNS_PORT, ApplicationProtocol::Mdns);
+        #[cfg(feature = "llmnr")]
+        registry.add_port(LLMNR_PORT, ApplicationProtocol::Llmnr);
+        #[cfg(feature = "dhcp")]
+        registry.add_port(DHCP_SERVER_PORT, ApplicationProtocol::Dhcp)
+            .add_port(DHCP_CLIENT_PORT, ApplicationProtocol::Dhcp);
+        #[cfg(feature = "dhcpv6")]
+        registry.add_port(DHCPV6_CLIENT_PORT, ApplicationProtocol::Dhcpv6)
+            .add_port(DHCPV6_SERVER_PORT, ApplicationProtocol::Dhcpv6);
+        #[cfg(feature = "ntp")]
+        registry.add_port(NTP_PORT, ApplicationProtocol::Ntp);
+        #[cfg(feature = "dtls")]
+        registry.add_heuristic(ApplicationProtocol::Dtls, is_dtls_record);
+        #[cfg(feature = "ntp")]
+        registry.add_heuristic(ApplicationProtocol::Ntp, is_ntp_packet);
+        registry
+    }
+
+    /// Dispatch the payload of the given port to a protocol
+    pub fn add_port(&mut self, port: u16, protocol: ApplicationProtocol) -> &mut Self {
+        {
+            let protocols = self.ports.entry(port).or_default();
+            if !protocols.contains(&protocol) {
+                protocols.push(protocol);
+            }
+        }
+        self
+    }
+
+    /// Stop dispatching the payload of the given port to a protocol
+    pub fn remove_port(&mut self, port: u16, protocol: &ApplicationProtocol) -> &mut Self {
+        if let Some(protocols) = self.ports.get_mut(&port) {
+            protocols.retain(|p| p != protocol);
+        }
+        self
+    }
+
+    /// Dispatch the payload to a protocol if the heuristic matches, regardless of the ports
+    pub fn add_heuristic(&mut self, protocol: ApplicationProtocol, heuristic: Heuristic) -> &mut Self {
+        self.heuristics.push((protocol, heuristic));
+        self
+    }
+
+    /// Remove all heuristics of a protocol
+    pub fn remove_heuristics(&mut self, protocol: &ApplicationProtocol) -> &mut Self {
+        self.heuristics.retain(|h| h.0 != *protocol);
+        self
+    }
+
+    /// Get the protocols for a payload, the ones of the destination port come first, then the
+    /// ones of the source port, then the ones with a matching heuristic
+    pub fn dispatch(&self, source_port: u16, dest_port: u16, payload: &[u8]) -> Vec<ApplicationProtocol> {
+        let mut result: Vec<ApplicationProtocol> = vec![];
+        for port in &[dest_port, source_port] {
+            for protocol in self.ports.get(port).into_iter().flat_map(|p| p.iter()) {
+                if !result.contains(protocol) {
+                    result.push(protocol.clone());
+                }
+            }
+        }
+        for &(ref protocol, heuristic) in &self.heuristics {
+            if !result.contains(protocol) && heuristic(payload) {
+                result.push(protocol.clone());
+            }
+        }
+        result
+    }
+}
+
+/// The payload starts with a TLS record header of a known content type and SSL 3.0 or TLS version
+#[cfg(feature = "tls")]
+fn is_tls_record(input: &[u8]) -> bool {
+    matches!(input, [20..=24, 3, 0..=4, ..])
+}
+
+/// The payload starts with a DTLS record header of a known content type and DTLS version. The
+/// unified header of DTLS 1.3 is not self describing and only recognized for known connections.
+#[cfg(feature = "dtls")]
+fn is_dtls_record(input: &[u8]) -> bool {
+    matches!(input, [20..=26, 0xfe, 0xfc..=0xff, ..])
+}
+
+/// The payload starts with the request line of a standard method or the status line of HTTP/1
+#[cfg(feature = "http")]
+fn is_http_message(input: &[u8]) -> bool {
+    input.starts_with(b"HTTP/1.") ||
+    ["GET", "POST", "HEAD", "PUT", "DELETE", "TRACE", "OPTIONS", "CONNECT", "PATCH"]
+        .iter()
+        .any(|m| input.starts_with(m.as_bytes()) && input.get(m.len()) == Some(&b' '))
+}
+
+/// The payload has a valid version and the exact length of a NTP message, and a client, server or
+/// broadcast packet has a valid stratum. A packet may be followed by extension fields (RFC 7822)
+/// and a packet or control message by a MAC, which consists of a key identifier and a digest.
+#[cfg(feature = "ntp")]
+fn is_ntp_packet(input: &[u8]) -> bool {
+    let be_u16 = |i: usize| usize::from(input[i]) << 8 | usize::from(input[i + 1]);
+    let is_mac = |length: usize| matches!(length, 0 | 20 | 24);
+    match input.first().map(|first| (first >> 3 & 0x7, first & 0x7)) {
+        // Client, server and broadcast packets, where a crypto-NAK is a MAC without digest
+        Some((version @ 1..=4, 1..=5)) if input.len() >= 48 && input[1] <= 16 => {
+            let mut rest = &input[48..];
+            while version == 4 && rest.len() > 24 {
+                let length = be_u16(input.len() - rest.len() + 2);
+                if length < 16 || length % 4 != 0 || length > rest.len() {
+                    return false;
+                }
+                rest = &rest[length..];
+            }
+            rest.len() == 4 || is_mac(rest.len())
+        }
+
+        // Control messages with their data, which is padded to a multiple of four octets
+        Some((1..=4, 6)) if input.len() >= 12 => {
+            input.len().checked_sub((12 + be_u16(10) + 3) & !3).is_some_and(is_mac)
+        }
+
+        // Private messages of a known implementation with their data items, where requests may
+        // carry padded request data and an authenticator
+        Some((1..=4, 7)) if input.len() >= 8 && input.len() & 3 == 0 && matches!(input[2], 0 | 2 | 3) => {
+            8 + (be_u16(4) & 0xfff) * (be_u16(6) & 0xfff) <= input.len()
+        }
+
+        _ => false,
+    }
+}
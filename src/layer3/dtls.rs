@@ -0,0 +1,628 @@
+//! Datagram transport layer security related packet processing
+use prelude::*;
+use std::collections::HashMap;
+
+/// The maximum length of a handshake message which will be reassembled
+const MAX_MESSAGE_LENGTH: u32 = 0x40000;
+
+/// The maximum amount of incomplete handshake messages per connection
+const MAX_PENDING_MESSAGES: usize = 16;
+
+/// The DTLS parser
+pub struct DtlsParser;
+
+impl Parsable<PathIp> for DtlsParser {
+    /// Parse a `DtlsPacket` from an `&[u8]`
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 path: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        // Retrieve the connection related information
+        let endpoints = connection_endpoints(result);
+        let mut data = connection_data(path, result);
+
+        do_parse!(input,
+            // Check the transport protocol from the parent parser (UDP or a previous DTLS record)
+            expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    // Check the parent node for the correct transport protocol
+                    Some(ref any) => match (any.downcast_ref::<UdpPacket>(), any.downcast_ref::<DtlsPacket>()) {
+                        // UDP, records with a unified header are only recognized after a DTLS 1.3 handshake
+                        (Some(udp), _) if udp.dispatches_to(&ApplicationProtocol::Dtls) ||
+                                          data.as_ref().is_some_and(|d| d.tls_version == Some(TlsVersion::Dtls13)) => {
+                            Some(())
+                        }
+
+                        // Multiple records within a single datagram
+                        (_, Some(_)) if !input.is_empty() => Some(()),
+
+                        _ => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(()),
+            }) >>
+
+            header: apply!(DtlsRecordHeader::parse,
+                           data.as_deref(),
+                           endpoints.as_ref().map(|e| &e.1)) >>
+
+            // Encrypted records of the unified header may omit the length
+            fragment: alt!(cond_reduce!(header.length().is_some(),
+                                        take!(header.length().unwrap_or_default())) |
+                           rest) >>
+
+            content: expr_opt!(Some(DtlsRecordContent::from_fragment(&header,
+                                                                     fragment,
+                                                                     data.as_mut().map(|d| &mut d.dtls),
+                                                                     endpoints.as_ref().map(|e| &e.0)))) >>
+
+            // Remember the negotiated connection parameters
+            negotiated_version: expr_opt!(Some(match data {
+                Some(data) => {
+                    content.update_connection(data, endpoints.as_ref().map(|e| &e.0));
+                    data.tls_version.clone()
+                }
+                None => content.negotiated_version(),
+            })) >>
+
+            (Box::new(DtlsPacket {
+                header: header,
+                content: content,
+                negotiated_version: negotiated_version,
+            }))
+        )
+    }
+}
+
+impl fmt::Display for DtlsParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DTLS")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a Datagram transport layer security packet
+pub struct DtlsPacket {
+    /// The header of the record
+    pub header: DtlsRecordHeader,
+
+    /// The decoded content of the record
+    pub content: DtlsRecordContent,
+
+    /// The effective protocol version of the connection, which is derived from the `ServerHello`
+    /// message
+    pub negotiated_version: Option<TlsVersion>,
+}
+
+impl Summary for DtlsPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "DTLS")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        match self.content {
+            DtlsRecordContent::Alert(ref alert) => write!(w, " Alert {:?} {:?}", alert.level, alert.description)?,
+            DtlsRecordContent::Handshake(ref messages) => {
+                let types: Vec<_> = messages.iter().map(|m| format!("{:?}", m.handshake_type)).collect();
+                write!(w, " Handshake {}", types.join(", "))?
+            }
+            _ => {
+                match self.header {
+                    DtlsRecordHeader::Full(ref header) => write!(w, " {:?}", header.content_type)?,
+                    DtlsRecordHeader::Unified(_) => write!(w, " Ciphertext")?,
+                }
+            }
+        }
+        match self.header {
+            DtlsRecordHeader::Full(ref header) => {
+                write!(w, " epoch={} seq={}", header.epoch, header.sequence_number)?;
+                if verbosity == Verbosity::Verbose {
+                    write!(w,
+                           " len={} version={}.{}",
+                           header.length,
+                           header.version.major,
+                           header.version.minor)?;
+                }
+            }
+            DtlsRecordHeader::Unified(ref header) => {
+                write!(w, " epoch={} seq={}", header.epoch, header.sequence_number)?;
+                if let (Verbosity::Verbose, Some(length)) = (verbosity, header.length) {
+                    write!(w, " len={}", length)?;
+                }
+            }
+        }
+        if verbosity == Verbosity::Verbose {
+            if let Some(ref version) = self.negotiated_version {
+                write!(w, " negotiated={:?}", version)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(DtlsPacket);
+
+impl Dissect for DtlsPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        match self.header {
+            DtlsRecordHeader::Full(ref header) => {
+                cursor.named("dtls.header.content_type", 8, &header.content_type)
+                    .field("dtls.header.version.major", 8, header.version.major)
+                    .field("dtls.header.version.minor", 8, header.version.minor)
+                    .field("dtls.header.epoch", 16, header.epoch)
+                    .field("dtls.header.sequence_number", 48, header.sequence_number);
+                if let Some(ref connection_id) = header.connection_id {
+                    cursor.bytes("dtls.header.connection_id", connection_id.len());
+                }
+                cursor.field("dtls.header.length", 16, header.length);
+            }
+            DtlsRecordHeader::Unified(ref header) => {
+                let sequence_number_bits = if cursor.peek(5) & 0b1 == 1 { 16 } else { 8 };
+                cursor.uint("dtls.header.fixed", 3)
+                    .field("dtls.header.connection_id_present", 1, header.connection_id.is_some())
+                    .field("dtls.header.sequence_number_16", 1, sequence_number_bits == 16)
+                    .field("dtls.header.length_present", 1, header.length.is_some())
+                    .field("dtls.header.epoch", 2, header.epoch);
+                if let Some(ref connection_id) = header.connection_id {
+                    cursor.bytes("dtls.header.connection_id", connection_id.len());
+                }
+                cursor.field("dtls.header.sequence_number", sequence_number_bits, header.sequence_number);
+                if let Some(length) = header.length {
+                    cursor.field("dtls.header.length", 16, length);
+                }
+            }
+        }
+        let start = cursor.position();
+        match self.content {
+            DtlsRecordContent::Alert(ref alert) => {
+                cursor.named("dtls.alert.level", 8, &alert.level)
+                    .named("dtls.alert.description", 8, &alert.description);
+            }
+            DtlsRecordContent::Handshake(ref messages) => {
+                for message in messages {
+                    cursor.group("dtls.handshake", format!("{:?}", message.handshake_type), |handshake| {
+                        handshake.named("dtls.handshake.handshake_type", 8, &message.handshake_type)
+                            .field("dtls.handshake.length", 24, message.length)
+                            .field("dtls.handshake.message_seq", 16, message.message_seq)
+                            .field("dtls.handshake.fragment_offset", 24, message.fragment_offset)
+                            .field("dtls.handshake.fragment_length", 24, message.fragment_length)
+                            .bytes("dtls.handshake.fragment", message.fragment_length as usize);
+                    });
+                }
+            }
+            _ => {
+                let length = self.header.length().map_or(usize::MAX, usize::from);
+                cursor.bytes("dtls.fragment", length);
+            }
+        }
+        if let Some(length) = self.header.length() {
+            cursor.skip_to(start + length as usize * 8);
+        }
+        cursor.finish("dtls", self.summary(Verbosity::Normal))
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// DTLS record header
+pub enum DtlsRecordHeader {
+    /// The full header of DTLS 1.0 and 1.2 records, as well as DTLS 1.3 plain text records
+    Full(DtlsFullHeader),
+
+    /// The variable length unified header of DTLS 1.3 encrypted records
+    Unified(DtlsUnifiedHeader),
+}
+
+impl DtlsRecordHeader {
+    /// Parse a record header. The connection data is needed to determine the length of
+    /// connection IDs, which are not self describing, and to recognize the unified header.
+    pub fn parse<'a>(input: &'a [u8],
+                     data: Option<&ConnectionData>,
+                     destination: Option<&Endpoint>)
+                     -> IResult<&'a [u8], DtlsRecordHeader> {
+        // The length of the connection ID expected by the receiver
+        let cid_length = match (data, destination) {
+            (Some(data), Some(destination)) => data.dtls.connection_id_lengths.get(destination).cloned(),
+            _ => None,
+        };
+
+        // The unified header is only valid after a DTLS 1.3 handshake
+        let unified_allowed = match data {
+            Some(data) => data.tls_version == Some(TlsVersion::Dtls13),
+            None => true,
+        };
+
+        alt!(input,
+            cond_reduce!(unified_allowed, map!(apply!(DtlsUnifiedHeader::parse, cid_length),
+                                               DtlsRecordHeader::Unified)) |
+            map!(apply!(DtlsFullHeader::parse, cid_length), DtlsRecordHeader::Full)
+        )
+    }
+
+    /// Get the length of the record if available
+    pub fn length(&self) -> Option<u16> {
+        match *self {
+            DtlsRecordHeader::Full(ref header) => Some(header.length),
+            DtlsRecordHeader::Unified(ref header) => header.length,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The full DTLS record header
+pub struct DtlsFullHeader {
+    /// Content type of the record
+    pub content_type: TlsRecordContentType,
+
+    /// DTLS version of the record
+    pub version: TlsRecordVersion,
+
+    /// The epoch number, which is incremented on every cipher state change
+    pub epoch: u16,
+
+    /// The sequence number (48 Bit) of the record within the epoch
+    pub sequence_number: u64,
+
+    /// The connection ID of the receiver, only available for `Tls12Cid` records
+    pub connection_id: Option<Vec<u8>>,
+
+    /// Record length
+    pub length: u16,
+}
+
+impl DtlsFullHeader {
+    /// Parse a full record header, connection IDs are parsed if their length is known
+    pub fn parse(input: &[u8], cid_length: Option<usize>) -> IResult<&[u8], DtlsFullHeader> {
+        do_parse!(input,
+            content_type: map_opt!(be_u8, TlsRecordContentType::from_u8) >>
+            version: verify!(take!(2), |v: &[u8]| v[0] == 0xfe && [0xff, 0xfd, 0xfc].contains(&v[1])) >>
+            epoch: be_u16 >>
+            sequence_number: map!(take!(6), |s: &[u8]| s.iter().fold(0, |acc, &x| acc << 8 | x as u64)) >>
+            connection_id: cond_with_error!(content_type == TlsRecordContentType::Tls12Cid,
+                                            apply!(parse_connection_id, cid_length)) >>
+            length: be_u16 >>
+
+            (DtlsFullHeader {
+                content_type: content_type,
+                version: TlsRecordVersion {
+                    major: version[0],
+                    minor: version[1],
+                },
+                epoch: epoch,
+                sequence_number: sequence_number,
+                connection_id: connection_id,
+                length: length,
+            })
+        )
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The unified DTLS 1.3 record header
+pub struct DtlsUnifiedHeader {
+    /// The low order two bits of the epoch
+    pub epoch: u8,
+
+    /// The low order 8 or 16 bits of the (encrypted) record sequence number
+    pub sequence_number: u16,
+
+    /// The connection ID of the receiver, if present
+    pub connection_id: Option<Vec<u8>>,
+
+    /// Record length, if present
+    pub length: Option<u16>,
+}
+
+impl DtlsUnifiedHeader {
+    /// Parse a unified header, connection IDs are parsed if their length is known
+    pub fn parse(input: &[u8], cid_length: Option<usize>) -> IResult<&[u8], DtlsUnifiedHeader> {
+        do_parse!(input,
+            // Fixed bits (001), connection ID, sequence number length, length and epoch bits
+            flags: bits!(tuple!(tag_bits!(u8, 3, 0b001),
+                                take_bits!(u8, 1),
+                                take_bits!(u8, 1),
+                                take_bits!(u8, 1),
+                                take_bits!(u8, 2))) >>
+            connection_id: cond_with_error!(flags.1 == 1, apply!(parse_connection_id, cid_length)) >>
+            sequence_number: alt!(cond_reduce!(flags.2 == 1, be_u16) | map!(be_u8, u16::from)) >>
+            length: cond!(flags.3 == 1, be_u16) >>
+
+            (DtlsUnifiedHeader {
+                epoch: flags.4,
+                sequence_number: sequence_number,
+                connection_id: connection_id,
+                length: length,
+            })
+        )
+    }
+}
+
+/// Parse a connection ID, which fails if its length is not known
+fn parse_connection_id(input: &[u8], cid_length: Option<usize>) -> IResult<&[u8], Vec<u8>> {
+    do_parse!(input,
+        length: expr_opt!(cid_length) >>
+        connection_id: take!(length) >>
+        (connection_id.to_vec())
+    )
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The decoded content of a DTLS record
+pub enum DtlsRecordContent {
+    /// The sender switches to the negotiated cipher suite and keys
+    ChangeCipherSpec,
+
+    /// A plain text alert message
+    Alert(TlsAlert),
+
+    /// One or more plain text handshake message fragments
+    Handshake(Vec<DtlsHandshake>),
+
+    /// Encrypted or otherwise not decodable content
+    Opaque,
+}
+
+impl DtlsRecordContent {
+    /// Decode the fragment of a record. Only records of the initial epoch are plain text,
+    /// handshake fragments are reassembled if a state is available.
+    pub fn from_fragment(header: &DtlsRecordHeader,
+                         fragment: &[u8],
+                         state: Option<&mut DtlsState>,
+                         sender: Option<&Endpoint>)
+                         -> DtlsRecordContent {
+        let header = match *header {
+            DtlsRecordHeader::Full(ref header) if header.epoch == 0 => header,
+            _ => return DtlsRecordContent::Opaque,
+        };
+        match header.content_type {
+            TlsRecordContentType::ChangeCipherSpec if fragment == [1] => DtlsRecordContent::ChangeCipherSpec,
+            TlsRecordContentType::Alert if header.length == 2 => {
+                match TlsAlert::parse(fragment) {
+                    IResult::Done(_, alert) => DtlsRecordContent::Alert(alert),
+                    _ => DtlsRecordContent::Opaque,
+                }
+            }
+            TlsRecordContentType::Handshake => {
+                match DtlsHandshake::parse_all(fragment, state, sender) {
+                    Some(messages) => DtlsRecordContent::Handshake(messages),
+                    None => DtlsRecordContent::Opaque,
+                }
+            }
+            _ => DtlsRecordContent::Opaque,
+        }
+    }
+
+    /// Retrieve the protocol version selected by the server, if the content contains a complete
+    /// `ServerHello` message
+    pub fn negotiated_version(&self) -> Option<TlsVersion> {
+        self.messages()
+            .into_iter()
+            .filter_map(|m| match *m {
+                TlsHandshakeMessage::ServerHello(ref hello) => hello.selected_version(),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Update the connection data with the negotiated protocol version and connection IDs
+    pub fn update_connection(&self, data: &mut ConnectionData, sender: Option<&Endpoint>) {
+        if let Some(version) = self.negotiated_version() {
+            data.tls_version = Some(version);
+        }
+        if let Some(protocol) = self.messages()
+            .into_iter()
+            .filter_map(|m| match *m {
+                TlsHandshakeMessage::ServerHello(ref hello) => hello.application_protocol(),
+                _ => None,
+            })
+            .next() {
+            data.application_protocol = Some(protocol);
+        }
+
+        // The connection ID of a hello message has to be used by the peer of the sender
+        if let Some(sender) = sender {
+            for message in self.messages() {
+                let cid = match *message {
+                    TlsHandshakeMessage::ClientHello(ref hello) => TlsExtension::connection_id(&hello.extensions),
+                    TlsHandshakeMessage::ServerHello(ref hello) => TlsExtension::connection_id(&hello.extensions),
+                    _ => None,
+                };
+                if let Some(cid) = cid {
+                    data.dtls.connection_id_lengths.insert(*sender, cid.len());
+                }
+            }
+        }
+    }
+
+    /// Get all handshake messages of the record
+    fn messages(&self) -> Vec<&TlsHandshakeMessage> {
+        match *self {
+            DtlsRecordContent::Handshake(ref messages) => messages.iter().map(|m| &m.message).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A DTLS handshake message fragment
+pub struct DtlsHandshake {
+    /// The type of the handshake message
+    pub handshake_type: TlsHandshakeType,
+
+    /// The length of the complete message body
+    pub length: u32,
+
+    /// The sequence number of the message
+    pub message_seq: u16,
+
+    /// The offset of the fragment within the message body
+    pub fragment_offset: u32,
+
+    /// The length of the fragment
+    pub fragment_length: u32,
+
+    /// The decoded message body, which is only available if the message is complete
+    pub message: TlsHandshakeMessage,
+}
+
+impl DtlsHandshake {
+    /// Parse all handshake fragments of a record. Returns None if the record does not look like a
+    /// plain text handshake.
+    pub fn parse_all(mut input: &[u8],
+                     mut state: Option<&mut DtlsState>,
+                     sender: Option<&Endpoint>)
+                     -> Option<Vec<DtlsHandshake>> {
+        let mut messages = vec![];
+        while !input.is_empty() {
+            let (left, (handshake_type, length, message_seq, fragment_offset, fragment)) =
+                match DtlsHandshake::parse_fragment(input) {
+                    IResult::Done(left, fragment) => (left, fragment),
+                    _ => return None,
+                };
+            if fragment_offset + fragment.len() as u32 > length {
+                return None;
+            }
+
+            // Reassemble the message body if needed
+            let body = match (state.as_mut(), sender) {
+                (Some(state), Some(sender)) => {
+                    state.reassemble(*sender, message_seq, length, fragment_offset, fragment)
+                }
+                _ if fragment_offset == 0 && fragment.len() as u32 == length => Some(fragment.to_vec()),
+                _ => None,
+            };
+            let message = match body {
+                Some(body) => TlsHandshakeMessage::from_dtls_body(&handshake_type, &body),
+                None => TlsHandshakeMessage::Incomplete,
+            };
+
+            messages.push(DtlsHandshake {
+                handshake_type: handshake_type,
+                length: length,
+                message_seq: message_seq,
+                fragment_offset: fragment_offset,
+                fragment_length: fragment.len() as u32,
+                message: message,
+            });
+            input = left;
+        }
+        Some(messages)
+    }
+
+    named!(#[doc = "Parse the header and the data of a single handshake fragment"],
+           pub parse_fragment<&[u8], (TlsHandshakeType, u32, u16, u32, &[u8])>,
+        do_parse!(
+            handshake_type: map_opt!(be_u8, TlsHandshakeType::from_u8) >>
+            length: bits!(take_bits!(u32, 24)) >>
+            message_seq: be_u16 >>
+            fragment_offset: bits!(take_bits!(u32, 24)) >>
+            fragment: length_bytes!(bits!(take_bits!(u32, 24))) >>
+            (handshake_type, length, message_seq, fragment_offset, fragment)
+        )
+    );
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The DTLS related state of a connection
+pub struct DtlsState {
+    /// Handshake messages which are not complete yet, identified by their sender and message
+    /// sequence number
+    pub fragments: HashMap<(Endpoint, u16), DtlsReassembly>,
+
+    /// The length of the connection IDs within records sent to an endpoint
+    pub connection_id_lengths: HashMap<Endpoint, usize>,
+}
+
+impl DtlsState {
+    /// Add a handshake message fragment of a sender. Returns the message body if the message is
+    /// complete.
+    pub fn reassemble(&mut self,
+                      sender: Endpoint,
+                      message_seq: u16,
+                      length: u32,
+                      offset: u32,
+                      fragment: &[u8])
+                      -> Option<Vec<u8>> {
+        // Unfragmented messages need no buffering
+        if offset == 0 && fragment.len() as u32 == length {
+            return Some(fragment.to_vec());
+        }
+
+        // Limit the memory usage
+        let key = (sender, message_seq);
+        if length > MAX_MESSAGE_LENGTH ||
+           (!self.fragments.contains_key(&key) && self.fragments.len() >= MAX_PENDING_MESSAGES) {
+            return None;
+        }
+
+        let complete = {
+            let reassembly = self.fragments.entry(key).or_insert_with(|| DtlsReassembly::new(length));
+            if reassembly.body.len() != length as usize {
+                // The message length changed, start again
+                *reassembly = DtlsReassembly::new(length);
+            }
+            reassembly.insert(offset as usize, fragment);
+            reassembly.is_complete()
+        };
+
+        if complete {
+            self.fragments.remove(&key).map(|r| r.body)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A handshake message which is reassembled from its fragments
+pub struct DtlsReassembly {
+    /// The message body
+    pub body: Vec<u8>,
+
+    /// Sorted and merged byte ranges of the body which are already received
+    pub received: Vec<(usize, usize)>,
+}
+
+impl DtlsReassembly {
+    /// Create a new reassembly for a message of the given length
+    pub fn new(length: u32) -> Self {
+        DtlsReassembly {
+            body: vec![0; length as usize],
+            received: vec![],
+        }
+    }
+
+    /// Insert a fragment at the given offset
+    pub fn insert(&mut self, offset: usize, fragment: &[u8]) {
+        let end = offset + fragment.len();
+        self.body[offset..end].copy_from_slice(fragment);
+
+        // Merge the received ranges
+        self.received.push((offset, end));
+        self.received.sort();
+        let mut merged: Vec<(usize, usize)> = vec![];
+        for &(start, end) in &self.received {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        self.received = merged;
+    }
+
+    /// Check if the complete message is available
+    pub fn is_complete(&self) -> bool {
+        self.received == [(0, self.body.len())]
+    }
+}
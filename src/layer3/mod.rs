@@ -1,11 +1,54 @@
 //! Transport Layer packets
+pub mod dispatch;
 pub mod tcp;
+#[cfg(feature = "tls")]
 pub mod tls;
+#[cfg(feature = "dtls")]
+pub mod dtls;
 pub mod udp;
 
 use prelude::*;
 use path::error::PathResult;
 
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// Custom data which is stored for every tracked connection
+pub struct ConnectionData {
+    /// The TLS or DTLS protocol version negotiated between client and server
+    #[cfg(feature = "tls")]
+    pub tls_version: Option<TlsVersion>,
+
+    /// The application protocol negotiated via the TLS or DTLS ALPN extension
+    #[cfg(feature = "tls")]
+    pub application_protocol: Option<String>,
+
+    /// The senders which switched to encrypted TLS records by a ChangeCipherSpec message
+    #[cfg(feature = "tls")]
+    pub tls_encrypted: Vec<Endpoint>,
+
+    /// The state of the DTLS record layer
+    #[cfg(feature = "dtls")]
+    pub dtls: DtlsState,
+
+    /// The state of the HTTP message parsing
+    #[cfg(feature = "http")]
+    pub http: HttpState,
+
+    /// The state of the HTTP/2 frame parsing
+    #[cfg(feature = "http2")]
+    pub http2: Http2State,
+
+    /// The state of the WebSocket frame parsing
+    #[cfg(feature = "websocket")]
+    pub websocket: WebSocketState,
+
+    /// The state of the DNS message reassembly
+    #[cfg(feature = "dns")]
+    pub dns: DnsState,
+}
+
+/// An endpoint of a connection, consisting of its address and port
+pub type Endpoint = (IpAddr, u16);
+
 /// Track a connection based in the current parsing result and return the connection data
 pub fn track_connection<'a>(path: Option<&'a mut PathIp>,
                             result: Option<&ParserResultVec>,
@@ -14,37 +57,7 @@ pub fn track_connection<'a>(path: Option<&'a mut PathIp>,
                             -> PathResult<()> {
     // Get the identifier
     let identifier = match result {
-        Some(vector) => {
-            match vector.get(1) {
-
-                Some(ref any) => {
-                    match (any.downcast_ref::<Ipv4Packet>(), any.downcast_ref::<Ipv6Packet>()) {
-                        // IPv4
-                        (Some(p), _) => {
-                            Some(Identifier::new(IpAddr::V4(p.src),
-                                                 src_port,
-                                                 IpAddr::V4(p.dst),
-                                                 dst_port,
-                                                 p.protocol))
-                        }
-
-                        // IPv6
-                        (_, Some(p)) => {
-                            Some(Identifier::new(IpAddr::V6(p.src),
-                                                 src_port,
-                                                 IpAddr::V6(p.dst),
-                                                 dst_port,
-                                                 p.next_header))
-                        }
-
-                        _ => None,
-                    }
-                }
-
-                // Previous result found, but not the correct one
-                _ => None,
-            }
-        }
+        Some(vector) => connection_identifier(vector, src_port, dst_port),
         None => None,
     };
 
@@ -55,3 +68,99 @@ pub fn track_connection<'a>(path: Option<&'a mut PathIp>,
 
     Ok(())
 }
+
+/// Retrieve the data of the connection which has been tracked by the transport layer parser
+/// (TCP or UDP) within the current parsing result
+pub fn connection_data<'a>(path: Option<&'a mut PathIp>,
+                           result: Option<&ParserResultVec>)
+                           -> Option<&'a mut ConnectionData> {
+    // Get the identifier from the latest transport layer result
+    let identifier = match result {
+        Some(vector) => {
+            transport_ports(vector).and_then(|(src_port, dst_port)| connection_identifier(vector, src_port, dst_port))
+        }
+        None => None,
+    };
+
+    // The tracked connection is always the most recently used one
+    match (path, identifier) {
+        (Some(path), Some(identifier)) => {
+            match path.last_mut() {
+                Some(Connection { identifier: last, data }) if *last == identifier => {
+                    Some(data.custom.get_or_insert_with(ConnectionData::default))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Retrieve the source and destination `Endpoint` of the current parsing result
+pub fn connection_endpoints(result: Option<&ParserResultVec>) -> Option<(Endpoint, Endpoint)> {
+    let vector = result?;
+    let (src_port, dst_port) = transport_ports(vector)?;
+    match ip_layer(vector) {
+        Some(any) => {
+            match (any.downcast_ref::<Ipv4Packet>(), any.downcast_ref::<Ipv6Packet>()) {
+                (Some(p), _) => Some(((IpAddr::V4(p.src), src_port), (IpAddr::V4(p.dst), dst_port))),
+                (_, Some(p)) => Some(((IpAddr::V6(p.src), src_port), (IpAddr::V6(p.dst), dst_port))),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Get the source and destination port of the latest transport layer result
+fn transport_ports(vector: &ParserResultVec) -> Option<(u16, u16)> {
+    vector.iter()
+        .rev()
+        .filter_map(|any| match (any.downcast_ref::<TcpPacket>(), any.downcast_ref::<UdpPacket>()) {
+            (Some(tcp), _) => Some((tcp.header.source_port, tcp.header.dest_port)),
+            (_, Some(udp)) => Some((udp.header.source_port, udp.header.dest_port)),
+            _ => None,
+        })
+        .next()
+}
+
+/// Get the IP layer which directly encapsulates the latest transport layer result, or the latest
+/// result if the transport layer parser itself is running
+fn ip_layer(vector: &ParserResultVec) -> Option<&ParserResult> {
+    match vector.iter().rposition(|any| any.is::<TcpPacket>() || any.is::<UdpPacket>()) {
+        Some(transport) => vector.get(transport.checked_sub(1)?),
+        None => vector.last(),
+    }
+}
+
+/// Create a connection identifier from the IP layer of the parsing result
+fn connection_identifier(vector: &ParserResultVec, src_port: u16, dst_port: u16) -> Option<Identifier<IpProtocol>> {
+    match ip_layer(vector) {
+        Some(any) => {
+            match (any.downcast_ref::<Ipv4Packet>(), any.downcast_ref::<Ipv6Packet>()) {
+                // IPv4
+                (Some(p), _) => {
+                    Some(Identifier::new(IpAddr::V4(p.src),
+                                         src_port,
+                                         IpAddr::V4(p.dst),
+                                         dst_port,
+                                         p.protocol))
+                }
+
+                // IPv6
+                (_, Some(p)) => {
+                    Some(Identifier::new(IpAddr::V6(p.src),
+                                         src_port,
+                                         IpAddr::V6(p.dst),
+                                         dst_port,
+                                         p.next_header))
+                }
+
+                _ => None,
+            }
+        }
+
+        // Previous result found, but not the correct one
+        _ => None,
+    }
+}
@@ -2,7 +2,23 @@
 use prelude::*;
 
 /// The TCP parser
-pub struct TcpParser;
+pub struct TcpParser {
+    /// The registry which dispatches the payload to the application protocols
+    pub registry: DispatchRegistry,
+}
+
+impl TcpParser {
+    /// Create a new parser with the well known ports and heuristics
+    pub fn new() -> Self {
+        TcpParser { registry: DispatchRegistry::tcp() }
+    }
+}
+
+impl Default for TcpParser {
+    fn default() -> Self {
+        TcpParser::new()
+    }
+}
 
 impl Parsable<PathIp> for TcpParser {
     /// Parse a `TcpPacket` from an `&[u8]`
@@ -57,6 +73,10 @@ impl Parsable<PathIp> for TcpParser {
             options_check: expr_opt!((data_offset_res_flags.0 * 4).checked_sub(20)) >>
             options: take!(options_check) >>
 
+            // Dispatch the payload to the application protocols
+            payload: peek!(call!(rest)) >>
+            protocols: expr_opt!(Some(self.registry.dispatch(src, dst, payload))) >>
+
             // Try to track the connection
             path_error: expr_opt!(match track_connection(path, result, src, dst) {
                 Err(e) => Some(Some(e.code)),
@@ -83,6 +103,7 @@ impl Parsable<PathIp> for TcpParser {
                     options: options.to_vec()
                 },
                 path_error: path_error,
+                protocols: protocols,
             }))
         )
     }
@@ -95,16 +116,96 @@ impl fmt::Display for TcpParser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of an User Datagram Protocol packet
 pub struct TcpPacket {
     /// The header of the TCP packet
     pub header: TcpHeader,
 
     /// Set to some error code if the connection tracking failed
+    #[cfg_attr(feature = "serde", serde(with = "path_error"))]
     pub path_error: Option<PathErrorType>,
+
+    /// The application protocols the payload is dispatched to
+    pub protocols: Vec<ApplicationProtocol>,
+}
+
+impl TcpPacket {
+    /// Check if the payload is dispatched to the given application protocol
+    pub fn dispatches_to(&self, protocol: &ApplicationProtocol) -> bool {
+        self.protocols.contains(protocol)
+    }
+}
+
+impl Summary for TcpPacket {
+    fn endpoints(&self) -> Option<(String, String)> {
+        Some((self.header.source_port.to_string(), self.header.dest_port.to_string()))
+    }
+
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "TCP")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        let flags: Vec<_> = [(self.header.flag_syn, "SYN"),
+                             (self.header.flag_fin, "FIN"),
+                             (self.header.flag_rst, "RST"),
+                             (self.header.flag_psh, "PSH"),
+                             (self.header.flag_ack, "ACK"),
+                             (self.header.flag_urg, "URG")]
+            .iter()
+            .filter(|&&(set, _)| set)
+            .map(|&(_, name)| name)
+            .collect();
+        write!(w, " [{}] seq={}", flags.join(", "), self.header.sequence_no)?;
+        if self.header.flag_ack {
+            write!(w, " ack={}", self.header.ack_no)?;
+        }
+        write!(w, " win={}", self.header.window)?;
+        if verbosity == Verbosity::Verbose {
+            write!(w, " offset={} checksum={:#x}", self.header.data_offset, self.header.checksum)?;
+            if !self.protocols.is_empty() {
+                let names: Vec<_> = self.protocols.iter().map(ApplicationProtocol::name).collect();
+                write!(w, " dispatch={}", names.join(","))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(TcpPacket);
+
+impl Dissect for TcpPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let header = &self.header;
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.field("tcp.source_port", 16, header.source_port)
+            .field("tcp.dest_port", 16, header.dest_port)
+            .field("tcp.sequence_no", 32, header.sequence_no)
+            .field("tcp.ack_no", 32, header.ack_no)
+            .field("tcp.data_offset", 4, header.data_offset)
+            .field("tcp.reserved", 6, header.reserved);
+        let flags = cursor.peek(6);
+        cursor.group("tcp.flags", flags, |flags| {
+                flags.field("tcp.flag_urg", 1, header.flag_urg)
+                    .field("tcp.flag_ack", 1, header.flag_ack)
+                    .field("tcp.flag_psh", 1, header.flag_psh)
+                    .field("tcp.flag_rst", 1, header.flag_rst)
+                    .field("tcp.flag_syn", 1, header.flag_syn)
+                    .field("tcp.flag_fin", 1, header.flag_fin);
+            })
+            .field("tcp.window", 16, header.window)
+            .field("tcp.checksum", 16, header.checksum)
+            .field("tcp.urgent_pointer", 16, header.urgent_pointer);
+        if !header.options.is_empty() {
+            cursor.bytes("tcp.options", header.options.len());
+        }
+        cursor.finish("tcp", self.summary(Verbosity::Normal))
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of a Transmission Control Protocol packet header
 pub struct TcpHeader {
     /// Identifies the sending port
@@ -9,17 +9,24 @@ impl Parsable<PathIp> for TlsParser {
     fn parse<'a>(&mut self,
                  input: &'a [u8],
                  result: Option<&ParserResultVec>,
-                 _: Option<&mut PathIp>)
+                 path: Option<&mut PathIp>)
                  -> IResult<&'a [u8], ParserResult> {
+        // Heartbeats can't be decoded after the sender switched to encryption, which happens
+        // right after the ServerHello for TLS 1.3
+        let sender = connection_endpoints(result).map(|e| e.0);
+        let data = connection_data(path, result);
+        let encrypted = data.as_ref().is_some_and(|d| {
+            d.tls_version == Some(TlsVersion::Tls13) || sender.is_some_and(|s| d.tls_encrypted.contains(&s))
+        });
+
         do_parse!(input,
             // Check the transport protocol from the parent parser (TCP)
             expr_opt!(match result {
                 Some(vector) => match vector.last() {
                     // Check the parent node for the correct transport protocol
-                    Some(ref any) => if let Some(_) = any.downcast_ref::<TcpPacket>() {
-                        Some(())
-                    } else {
-                        None
+                    Some(ref any) => match any.downcast_ref::<TcpPacket>() {
+                        Some(tcp) if tcp.dispatches_to(&ApplicationProtocol::Tls) => Some(()),
+                        _ => None,
                     },
 
                     // Previous result found, but not correct parent
@@ -33,6 +40,29 @@ impl Parsable<PathIp> for TlsParser {
             version: take!(2) >>
             length: be_u16 >>
 
+            // The record may be split over multiple segments, so take what is available
+            fragment: alt!(complete!(take!(length)) | rest) >>
+            content: expr_opt!(Some(TlsRecordContent::from_fragment(&content_type, length, fragment, encrypted))) >>
+
+            // Remember the negotiated protocol version and the encrypting senders of the connection
+            negotiated_version: expr_opt!(Some(match data {
+                Some(data) => {
+                    if let Some(version) = content.negotiated_version() {
+                        data.tls_version = Some(version);
+                    }
+                    if let Some(protocol) = content.application_protocol() {
+                        data.application_protocol = Some(protocol);
+                    }
+                    if let (TlsRecordContent::ChangeCipherSpec, Some(sender)) = (&content, sender) {
+                        if !data.tls_encrypted.contains(&sender) {
+                            data.tls_encrypted.push(sender);
+                        }
+                    }
+                    data.tls_version.clone()
+                }
+                None => content.negotiated_version(),
+            })) >>
+
             (Box::new(TlsPacket {
                 content_type: content_type,
                 version: TlsRecordVersion {
@@ -40,6 +70,8 @@ impl Parsable<PathIp> for TlsParser {
                     minor: version[1],
                 },
                 length: length,
+                content: content,
+                negotiated_version: negotiated_version,
             }))
         )
     }
@@ -52,6 +84,7 @@ impl fmt::Display for TlsParser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of a Transport layer security packet
 pub struct TlsPacket {
     /// Content type of the record
@@ -62,9 +95,86 @@ pub struct TlsPacket {
 
     /// Record length
     pub length: u16,
+
+    /// The decoded content of the record
+    pub content: TlsRecordContent,
+
+    /// The effective protocol version of the connection, which is derived from the `ServerHello`
+    /// message. The record version is frozen to TLS 1.2 (or even 1.0) since TLS 1.3 and therefore
+    /// not usable for this purpose.
+    pub negotiated_version: Option<TlsVersion>,
+}
+
+impl Summary for TlsPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "TLS")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        match self.content {
+            TlsRecordContent::Alert(ref alert) => {
+                write!(w, " Alert {:?} {:?}", alert.level, alert.description)?
+            }
+            TlsRecordContent::Handshake(ref messages) => {
+                let types: Vec<_> = messages.iter().map(|m| format!("{:?}", m.handshake_type)).collect();
+                write!(w, " Handshake {}", types.join(", "))?
+            }
+            TlsRecordContent::Heartbeat(ref heartbeat) => {
+                write!(w, " Heartbeat {:?}", heartbeat.message_type)?
+            }
+            _ => write!(w, " {:?}", self.content_type)?,
+        }
+        write!(w, " len={}", self.length)?;
+        if verbosity == Verbosity::Verbose {
+            write!(w, " version={}.{}", self.version.major, self.version.minor)?;
+            if let Some(ref version) = self.negotiated_version {
+                write!(w, " negotiated={:?}", version)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(TlsPacket);
+
+impl Dissect for TlsPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.named("tls.content_type", 8, &self.content_type)
+            .field("tls.version.major", 8, self.version.major)
+            .field("tls.version.minor", 8, self.version.minor)
+            .field("tls.length", 16, self.length);
+        match self.content {
+            TlsRecordContent::Alert(ref alert) => {
+                cursor.named("tls.alert.level", 8, &alert.level)
+                    .named("tls.alert.description", 8, &alert.description);
+            }
+            TlsRecordContent::Handshake(ref messages) => {
+                for message in messages {
+                    cursor.group("tls.handshake", format!("{:?}", message.handshake_type), |handshake| {
+                        handshake.named("tls.handshake.handshake_type", 8, &message.handshake_type)
+                            .field("tls.handshake.length", 24, message.length)
+                            .bytes("tls.handshake.message", message.length as usize);
+                    });
+                }
+            }
+            TlsRecordContent::Heartbeat(ref heartbeat) => {
+                cursor.named("tls.heartbeat.message_type", 8, &heartbeat.message_type)
+                    .field("tls.heartbeat.payload_length", 16, heartbeat.payload_length)
+                    .bytes("tls.heartbeat.payload", heartbeat.payload.len())
+                    .bytes("tls.heartbeat.padding", heartbeat.padding_length);
+            }
+            _ => {
+                cursor.bytes("tls.fragment", self.length as usize);
+            }
+        }
+        cursor.skip_to((5 + self.length as usize) * 8);
+        cursor.finish("tls", self.summary(Verbosity::Normal))
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// TLS record protocol content type
 pub enum TlsRecordContentType {
     /// Change Cipher Spec
@@ -81,6 +191,12 @@ pub enum TlsRecordContentType {
 
     /// Heartbeat
     Heartbeat,
+
+    /// Encrypted record with connection ID, only used by DTLS 1.2
+    Tls12Cid,
+
+    /// Acknowledgement, only used by DTLS 1.3
+    Ack,
 }
 
 impl TlsRecordContentType {
@@ -93,12 +209,15 @@ impl TlsRecordContentType {
             22 => Some(TlsRecordContentType::Handshake),
             23 => Some(TlsRecordContentType::ApplicationData),
             24 => Some(TlsRecordContentType::Heartbeat),
+            25 => Some(TlsRecordContentType::Tls12Cid),
+            26 => Some(TlsRecordContentType::Ack),
             _ => None,
         }
     }
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// TLS record protocol version
 pub struct TlsRecordVersion {
     /// Major part of the TLS version
@@ -107,3 +226,855 @@ pub struct TlsRecordVersion {
     /// Minor part of the TLS version
     pub minor: u8,
 }
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Known SSL and TLS protocol versions
+pub enum TlsVersion {
+    /// SSL 3.0
+    Ssl30,
+
+    /// TLS 1.0
+    Tls10,
+
+    /// TLS 1.1
+    Tls11,
+
+    /// TLS 1.2
+    Tls12,
+
+    /// TLS 1.3
+    Tls13,
+
+    /// DTLS 1.0
+    Dtls10,
+
+    /// DTLS 1.2
+    Dtls12,
+
+    /// DTLS 1.3
+    Dtls13,
+}
+
+impl TlsVersion {
+    /// Convert a u16 to a `TlsVersion`. Returns None if the version is not supported or generally
+    /// invalid.
+    pub fn from_u16(input: u16) -> Option<TlsVersion> {
+        match input {
+            0x0300 => Some(TlsVersion::Ssl30),
+            0x0301 => Some(TlsVersion::Tls10),
+            0x0302 => Some(TlsVersion::Tls11),
+            0x0303 => Some(TlsVersion::Tls12),
+            0x0304 => Some(TlsVersion::Tls13),
+            0xfeff => Some(TlsVersion::Dtls10),
+            0xfefd => Some(TlsVersion::Dtls12),
+            0xfefc => Some(TlsVersion::Dtls13),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The decoded content of a TLS record
+pub enum TlsRecordContent {
+    /// The sender switches to the negotiated cipher suite and keys
+    ChangeCipherSpec,
+
+    /// A plain text alert message
+    Alert(TlsAlert),
+
+    /// One or more plain text handshake messages
+    Handshake(Vec<TlsHandshake>),
+
+    /// A plain text heartbeat message
+    Heartbeat(TlsHeartbeat),
+
+    /// Encrypted or otherwise not decodable content, like application data or encrypted alerts
+    Opaque,
+}
+
+impl TlsRecordContent {
+    /// Decode the (possibly truncated) fragment of a record. Content which does not fit the
+    /// structure of its type is considered to be encrypted. Heartbeats of an `encrypted` sender
+    /// are never decoded, since their structure is too weak to detect encryption.
+    pub fn from_fragment(content_type: &TlsRecordContentType,
+                         length: u16,
+                         fragment: &[u8],
+                         encrypted: bool)
+                         -> TlsRecordContent {
+        match *content_type {
+            TlsRecordContentType::ChangeCipherSpec if fragment == [1] => TlsRecordContent::ChangeCipherSpec,
+            TlsRecordContentType::Alert if length == 2 => {
+                match TlsAlert::parse(fragment) {
+                    IResult::Done(_, alert) => TlsRecordContent::Alert(alert),
+                    _ => TlsRecordContent::Opaque,
+                }
+            }
+            TlsRecordContentType::Handshake => {
+                match TlsHandshake::parse_all(fragment, length as usize) {
+                    Some(messages) => TlsRecordContent::Handshake(messages),
+                    None => TlsRecordContent::Opaque,
+                }
+            }
+            TlsRecordContentType::Heartbeat if !encrypted => {
+                match TlsHeartbeat::parse(fragment, length) {
+                    IResult::Done(_, heartbeat) => TlsRecordContent::Heartbeat(heartbeat),
+                    _ => TlsRecordContent::Opaque,
+                }
+            }
+            _ => TlsRecordContent::Opaque,
+        }
+    }
+
+    /// Retrieve the protocol version selected by the server, if the content contains a
+    /// `ServerHello` message
+    pub fn negotiated_version(&self) -> Option<TlsVersion> {
+        match *self {
+            TlsRecordContent::Handshake(ref messages) => {
+                messages.iter()
+                    .filter_map(|m| match m.message {
+                        TlsHandshakeMessage::ServerHello(ref hello) => hello.selected_version(),
+                        _ => None,
+                    })
+                    .next()
+            }
+            _ => None,
+        }
+    }
+
+    /// Retrieve the application protocol selected by the server, if the content contains a
+    /// `ServerHello` message with an ALPN extension
+    pub fn application_protocol(&self) -> Option<String> {
+        match *self {
+            TlsRecordContent::Handshake(ref messages) => {
+                messages.iter()
+                    .filter_map(|m| match m.message {
+                        TlsHandshakeMessage::ServerHello(ref hello) => hello.application_protocol(),
+                        _ => None,
+                    })
+                    .next()
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A TLS alert message
+pub struct TlsAlert {
+    /// The severity of the alert
+    pub level: TlsAlertLevel,
+
+    /// The reason for the alert
+    pub description: TlsAlertDescription,
+}
+
+impl TlsAlert {
+    named!(#[doc = "Parse a plain text TLS alert message"],
+           pub parse<&[u8], TlsAlert>,
+        do_parse!(
+            level: map_opt!(be_u8, TlsAlertLevel::from_u8) >>
+            description: map_opt!(be_u8, TlsAlertDescription::from_u8) >>
+
+            (TlsAlert {
+                level: level,
+                description: description,
+            })
+        )
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The severity of a TLS alert
+pub enum TlsAlertLevel {
+    /// The connection may continue
+    Warning,
+
+    /// The connection is terminated immediately
+    Fatal,
+}
+
+impl TlsAlertLevel {
+    /// Convert a u8 to a `TlsAlertLevel`. Returns None if the level is not supported or generally
+    /// invalid.
+    pub fn from_u8(input: u8) -> Option<TlsAlertLevel> {
+        match input {
+            1 => Some(TlsAlertLevel::Warning),
+            2 => Some(TlsAlertLevel::Fatal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The reason for a TLS alert
+pub enum TlsAlertDescription {
+    /// The sender will not send any more messages on this connection
+    CloseNotify,
+
+    /// An inappropriate message was received
+    UnexpectedMessage,
+
+    /// A record was received which cannot be deprotected
+    BadRecordMac,
+
+    /// Decryption failed, only used by SSL 3.0 and TLS 1.0
+    DecryptionFailed,
+
+    /// A record exceeded the maximum length
+    RecordOverflow,
+
+    /// Decompression of a record failed
+    DecompressionFailure,
+
+    /// No acceptable set of security parameters could be negotiated
+    HandshakeFailure,
+
+    /// No certificate available, only used by SSL 3.0
+    NoCertificate,
+
+    /// A certificate was corrupt or contained invalid signatures
+    BadCertificate,
+
+    /// A certificate was of an unsupported type
+    UnsupportedCertificate,
+
+    /// A certificate was revoked by its signer
+    CertificateRevoked,
+
+    /// A certificate has expired or is not currently valid
+    CertificateExpired,
+
+    /// Some unspecified issue arose in processing the certificate
+    CertificateUnknown,
+
+    /// A field in the handshake was incorrect or inconsistent with other fields
+    IllegalParameter,
+
+    /// The CA certificate could not be located or matched with a known trust anchor
+    UnknownCa,
+
+    /// A valid certificate or PSK was received, but access control was not proceeded
+    AccessDenied,
+
+    /// A message could not be decoded
+    DecodeError,
+
+    /// A handshake cryptographic operation failed
+    DecryptError,
+
+    /// An attempt to export a key failed, only used by TLS 1.0
+    ExportRestriction,
+
+    /// The protocol version offered by the peer is recognized but not supported
+    ProtocolVersion,
+
+    /// The server requires parameters more secure than those supported by the client
+    InsufficientSecurity,
+
+    /// An internal error unrelated to the peer occurred
+    InternalError,
+
+    /// A downgrade attack by a retrying client has been detected
+    InappropriateFallback,
+
+    /// The user cancels the handshake
+    UserCanceled,
+
+    /// A renegotiation request was refused
+    NoRenegotiation,
+
+    /// A mandatory extension was not sent
+    MissingExtension,
+
+    /// An extension was sent which is not allowed in this context
+    UnsupportedExtension,
+
+    /// A certificate could not be obtained from a provided URL
+    CertificateUnobtainable,
+
+    /// No server exists which is identified by the provided name
+    UnrecognizedName,
+
+    /// An invalid or unacceptable OCSP response was provided
+    BadCertificateStatusResponse,
+
+    /// A certificate hash value did not match
+    BadCertificateHashValue,
+
+    /// No acceptable PSK identity was provided
+    UnknownPskIdentity,
+
+    /// The client did not send a certificate, but the server requires one
+    CertificateRequired,
+
+    /// None of the application protocols advertised by the client are supported
+    NoApplicationProtocol,
+}
+
+impl TlsAlertDescription {
+    /// Convert a u8 to a `TlsAlertDescription`. Returns None if the description is not supported
+    /// or generally invalid.
+    pub fn from_u8(input: u8) -> Option<TlsAlertDescription> {
+        match input {
+            0 => Some(TlsAlertDescription::CloseNotify),
+            10 => Some(TlsAlertDescription::UnexpectedMessage),
+            20 => Some(TlsAlertDescription::BadRecordMac),
+            21 => Some(TlsAlertDescription::DecryptionFailed),
+            22 => Some(TlsAlertDescription::RecordOverflow),
+            30 => Some(TlsAlertDescription::DecompressionFailure),
+            40 => Some(TlsAlertDescription::HandshakeFailure),
+            41 => Some(TlsAlertDescription::NoCertificate),
+            42 => Some(TlsAlertDescription::BadCertificate),
+            43 => Some(TlsAlertDescription::UnsupportedCertificate),
+            44 => Some(TlsAlertDescription::CertificateRevoked),
+            45 => Some(TlsAlertDescription::CertificateExpired),
+            46 => Some(TlsAlertDescription::CertificateUnknown),
+            47 => Some(TlsAlertDescription::IllegalParameter),
+            48 => Some(TlsAlertDescription::UnknownCa),
+            49 => Some(TlsAlertDescription::AccessDenied),
+            50 => Some(TlsAlertDescription::DecodeError),
+            51 => Some(TlsAlertDescription::DecryptError),
+            60 => Some(TlsAlertDescription::ExportRestriction),
+            70 => Some(TlsAlertDescription::ProtocolVersion),
+            71 => Some(TlsAlertDescription::InsufficientSecurity),
+            80 => Some(TlsAlertDescription::InternalError),
+            86 => Some(TlsAlertDescription::InappropriateFallback),
+            90 => Some(TlsAlertDescription::UserCanceled),
+            100 => Some(TlsAlertDescription::NoRenegotiation),
+            109 => Some(TlsAlertDescription::MissingExtension),
+            110 => Some(TlsAlertDescription::UnsupportedExtension),
+            111 => Some(TlsAlertDescription::CertificateUnobtainable),
+            112 => Some(TlsAlertDescription::UnrecognizedName),
+            113 => Some(TlsAlertDescription::BadCertificateStatusResponse),
+            114 => Some(TlsAlertDescription::BadCertificateHashValue),
+            115 => Some(TlsAlertDescription::UnknownPskIdentity),
+            116 => Some(TlsAlertDescription::CertificateRequired),
+            120 => Some(TlsAlertDescription::NoApplicationProtocol),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A TLS heartbeat message, see [RFC6520]
+pub struct TlsHeartbeat {
+    /// The type of the heartbeat message
+    pub message_type: TlsHeartbeatMessageType,
+
+    /// The payload length claimed by the sender
+    pub payload_length: u16,
+
+    /// The payload which is really available within the record
+    pub payload: Vec<u8>,
+
+    /// The amount of padding bytes following the payload
+    pub padding_length: usize,
+
+    /// Set if the claimed payload length (plus the message header) exceeds the record length.
+    /// This is the signature of the Heartbleed attack (CVE-2014-0160), where the peer answers
+    /// with the amount of data requested by the payload length and therefore leaks its memory.
+    pub payload_overflow: bool,
+
+    /// Set if the payload fits into the record, but is followed by less than the minimal
+    /// padding of 16 bytes
+    pub padding_shortfall: bool,
+}
+
+impl TlsHeartbeat {
+    /// Parse a plain text heartbeat message from a record fragment of the given record length
+    pub fn parse(input: &[u8], record_length: u16) -> IResult<&[u8], TlsHeartbeat> {
+        do_parse!(input,
+            message_type: map_opt!(be_u8, TlsHeartbeatMessageType::from_u8) >>
+            payload_length: be_u16 >>
+            payload: alt!(complete!(take!(payload_length)) | rest) >>
+            padding: call!(rest) >>
+
+            (TlsHeartbeat {
+                message_type: message_type,
+                payload_length: payload_length,
+                payload: payload.to_vec(),
+                padding_length: padding.len(),
+                payload_overflow: 3 + payload_length as usize > record_length as usize,
+                padding_shortfall: (3 + payload_length as usize..3 + payload_length as usize + 16)
+                    .contains(&(record_length as usize)),
+            })
+        )
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Supported TLS heartbeat message types
+pub enum TlsHeartbeatMessageType {
+    /// Heartbeat request
+    Request,
+
+    /// Heartbeat response
+    Response,
+}
+
+impl TlsHeartbeatMessageType {
+    /// Convert a u8 to a `TlsHeartbeatMessageType`. Returns None if the type is not supported or
+    /// generally invalid.
+    pub fn from_u8(input: u8) -> Option<TlsHeartbeatMessageType> {
+        match input {
+            1 => Some(TlsHeartbeatMessageType::Request),
+            2 => Some(TlsHeartbeatMessageType::Response),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A TLS handshake message
+pub struct TlsHandshake {
+    /// The type of the handshake message
+    pub handshake_type: TlsHandshakeType,
+
+    /// The length of the message body
+    pub length: u32,
+
+    /// The decoded message body
+    pub message: TlsHandshakeMessage,
+}
+
+impl TlsHandshake {
+    /// Parse all handshake messages of a record fragment. The fragment may be truncated compared
+    /// to the record length, but the messages have to fit into the record. Returns None if the
+    /// fragment does not look like a plain text handshake.
+    pub fn parse_all(fragment: &[u8], record_length: usize) -> Option<Vec<TlsHandshake>> {
+        let mut messages = vec![];
+        let mut offset = 0;
+        while offset < fragment.len() {
+            let (handshake_type, length) = match TlsHandshake::parse_header(&fragment[offset..]) {
+                IResult::Done(_, header) => header,
+                _ => return None,
+            };
+            let end = offset + 4 + length as usize;
+            if end > record_length {
+                return None;
+            }
+            let message = match fragment.get(offset + 4..end) {
+                Some(body) => TlsHandshakeMessage::from_body(&handshake_type, body),
+                None => TlsHandshakeMessage::Incomplete,
+            };
+            messages.push(TlsHandshake {
+                handshake_type: handshake_type,
+                length: length,
+                message: message,
+            });
+            offset = end;
+        }
+        Some(messages)
+    }
+
+    named!(#[doc = "Parse the type and the body length of a handshake message"],
+           pub parse_header<&[u8], (TlsHandshakeType, u32)>,
+        do_parse!(
+            handshake_type: map_opt!(be_u8, TlsHandshakeType::from_u8) >>
+            length: bits!(take_bits!(u32, 24)) >>
+            (handshake_type, length)
+        )
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Supported TLS handshake message types
+pub enum TlsHandshakeType {
+    /// Hello request
+    HelloRequest,
+
+    /// Client hello
+    ClientHello,
+
+    /// Server hello
+    ServerHello,
+
+    /// Hello verify request, only used by DTLS
+    HelloVerifyRequest,
+
+    /// New session ticket
+    NewSessionTicket,
+
+    /// End of early data
+    EndOfEarlyData,
+
+    /// Encrypted extensions
+    EncryptedExtensions,
+
+    /// Certificate
+    Certificate,
+
+    /// Server key exchange
+    ServerKeyExchange,
+
+    /// Certificate request
+    CertificateRequest,
+
+    /// Server hello done
+    ServerHelloDone,
+
+    /// Certificate verify
+    CertificateVerify,
+
+    /// Client key exchange
+    ClientKeyExchange,
+
+    /// Finished
+    Finished,
+
+    /// Certificate URL
+    CertificateUrl,
+
+    /// Certificate status
+    CertificateStatus,
+
+    /// Key update
+    KeyUpdate,
+
+    /// Message hash
+    MessageHash,
+}
+
+impl TlsHandshakeType {
+    /// Convert a u8 to a `TlsHandshakeType`. Returns None if the type is not supported or
+    /// generally invalid.
+    pub fn from_u8(input: u8) -> Option<TlsHandshakeType> {
+        match input {
+            0 => Some(TlsHandshakeType::HelloRequest),
+            1 => Some(TlsHandshakeType::ClientHello),
+            2 => Some(TlsHandshakeType::ServerHello),
+            3 => Some(TlsHandshakeType::HelloVerifyRequest),
+            4 => Some(TlsHandshakeType::NewSessionTicket),
+            5 => Some(TlsHandshakeType::EndOfEarlyData),
+            8 => Some(TlsHandshakeType::EncryptedExtensions),
+            11 => Some(TlsHandshakeType::Certificate),
+            12 => Some(TlsHandshakeType::ServerKeyExchange),
+            13 => Some(TlsHandshakeType::CertificateRequest),
+            14 => Some(TlsHandshakeType::ServerHelloDone),
+            15 => Some(TlsHandshakeType::CertificateVerify),
+            16 => Some(TlsHandshakeType::ClientKeyExchange),
+            20 => Some(TlsHandshakeType::Finished),
+            21 => Some(TlsHandshakeType::CertificateUrl),
+            22 => Some(TlsHandshakeType::CertificateStatus),
+            24 => Some(TlsHandshakeType::KeyUpdate),
+            254 => Some(TlsHandshakeType::MessageHash),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The decoded body of a handshake message
+pub enum TlsHandshakeMessage {
+    /// A client hello message
+    ClientHello(TlsClientHello),
+
+    /// A server hello message
+    ServerHello(TlsServerHello),
+
+    /// A hello verify request message, only used by DTLS
+    HelloVerifyRequest(TlsHelloVerifyRequest),
+
+    /// The message body is not complete within the current fragment
+    Incomplete,
+
+    /// The message body is not decoded
+    Other,
+}
+
+impl TlsHandshakeMessage {
+    /// Decode a complete handshake message body
+    pub fn from_body(handshake_type: &TlsHandshakeType, body: &[u8]) -> TlsHandshakeMessage {
+        match *handshake_type {
+            TlsHandshakeType::ClientHello => {
+                match TlsClientHello::parse(body) {
+                    IResult::Done(_, hello) => TlsHandshakeMessage::ClientHello(hello),
+                    _ => TlsHandshakeMessage::Other,
+                }
+            }
+            TlsHandshakeType::ServerHello => {
+                match TlsServerHello::parse(body) {
+                    IResult::Done(_, hello) => TlsHandshakeMessage::ServerHello(hello),
+                    _ => TlsHandshakeMessage::Other,
+                }
+            }
+            _ => TlsHandshakeMessage::Other,
+        }
+    }
+
+    /// Decode a complete DTLS handshake message body
+    pub fn from_dtls_body(handshake_type: &TlsHandshakeType, body: &[u8]) -> TlsHandshakeMessage {
+        match *handshake_type {
+            TlsHandshakeType::ClientHello => {
+                match TlsClientHello::parse_dtls(body) {
+                    IResult::Done(_, hello) => TlsHandshakeMessage::ClientHello(hello),
+                    _ => TlsHandshakeMessage::Other,
+                }
+            }
+            TlsHandshakeType::HelloVerifyRequest => {
+                match TlsHelloVerifyRequest::parse(body) {
+                    IResult::Done(_, request) => TlsHandshakeMessage::HelloVerifyRequest(request),
+                    _ => TlsHandshakeMessage::Other,
+                }
+            }
+            _ => TlsHandshakeMessage::from_body(handshake_type, body),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A TLS client hello message
+pub struct TlsClientHello {
+    /// The highest protocol version supported by the client, frozen to TLS 1.2 since TLS 1.3
+    pub legacy_version: u16,
+
+    /// Random data generated by the client
+    pub random: Vec<u8>,
+
+    /// The session to be resumed
+    pub session_id: Vec<u8>,
+
+    /// The stateless cookie, only used by DTLS
+    pub cookie: Option<Vec<u8>>,
+
+    /// The offered cipher suites, in order of the client preference
+    pub cipher_suites: Vec<u16>,
+
+    /// The offered compression methods
+    pub compression_methods: Vec<u8>,
+
+    /// The hello extensions
+    pub extensions: Vec<TlsExtension>,
+}
+
+impl TlsClientHello {
+    named!(#[doc = "Parse a client hello message body"],
+           pub parse<&[u8], TlsClientHello>,
+        do_parse!(
+            legacy_version: be_u16 >>
+            random: take!(32) >>
+            session_id: length_bytes!(be_u8) >>
+            cipher_suites: length_value!(be_u16, many0!(be_u16)) >>
+            compression_methods: length_bytes!(be_u8) >>
+            extensions: call!(TlsExtension::parse_all) >>
+
+            (TlsClientHello {
+                legacy_version: legacy_version,
+                random: random.to_vec(),
+                session_id: session_id.to_vec(),
+                cookie: None,
+                cipher_suites: cipher_suites,
+                compression_methods: compression_methods.to_vec(),
+                extensions: extensions,
+            })
+        )
+    );
+
+    named!(#[doc = "Parse a DTLS client hello message body"],
+           pub parse_dtls<&[u8], TlsClientHello>,
+        do_parse!(
+            legacy_version: be_u16 >>
+            random: take!(32) >>
+            session_id: length_bytes!(be_u8) >>
+            cookie: length_bytes!(be_u8) >>
+            cipher_suites: length_value!(be_u16, many0!(be_u16)) >>
+            compression_methods: length_bytes!(be_u8) >>
+            extensions: call!(TlsExtension::parse_all) >>
+
+            (TlsClientHello {
+                legacy_version: legacy_version,
+                random: random.to_vec(),
+                session_id: session_id.to_vec(),
+                cookie: Some(cookie.to_vec()),
+                cipher_suites: cipher_suites,
+                compression_methods: compression_methods.to_vec(),
+                extensions: extensions,
+            })
+        )
+    );
+
+    /// Retrieve the protocol versions offered by the `supported_versions` extension
+    pub fn supported_versions(&self) -> Vec<TlsVersion> {
+        self.extensions
+            .iter()
+            .filter(|e| e.extension_type == TlsExtension::SUPPORTED_VERSIONS)
+            .filter_map(|e| e.data.split_first())
+            .flat_map(|(_, list)| list.chunks(2).filter_map(|v| TlsVersion::from_u16(read_u16(v))))
+            .collect()
+    }
+
+    /// Retrieve the application protocols offered by the ALPN extension
+    pub fn application_protocols(&self) -> Vec<String> {
+        TlsExtension::application_protocols(&self.extensions)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A TLS server hello message
+pub struct TlsServerHello {
+    /// The negotiated protocol version, frozen to TLS 1.2 since TLS 1.3
+    pub legacy_version: u16,
+
+    /// Random data generated by the server
+    pub random: Vec<u8>,
+
+    /// The session identifier
+    pub session_id: Vec<u8>,
+
+    /// The selected cipher suite
+    pub cipher_suite: u16,
+
+    /// The selected compression method
+    pub compression_method: u8,
+
+    /// The hello extensions
+    pub extensions: Vec<TlsExtension>,
+}
+
+impl TlsServerHello {
+    named!(#[doc = "Parse a server hello message body"],
+           pub parse<&[u8], TlsServerHello>,
+        do_parse!(
+            legacy_version: be_u16 >>
+            random: take!(32) >>
+            session_id: length_bytes!(be_u8) >>
+            cipher_suite: be_u16 >>
+            compression_method: be_u8 >>
+            extensions: call!(TlsExtension::parse_all) >>
+
+            (TlsServerHello {
+                legacy_version: legacy_version,
+                random: random.to_vec(),
+                session_id: session_id.to_vec(),
+                cipher_suite: cipher_suite,
+                compression_method: compression_method,
+                extensions: extensions,
+            })
+        )
+    );
+
+    /// Retrieve the effective protocol version selected by the server. This is the version of the
+    /// `supported_versions` extension if available, otherwise the legacy version.
+    pub fn selected_version(&self) -> Option<TlsVersion> {
+        match self.extensions.iter().find(|e| e.extension_type == TlsExtension::SUPPORTED_VERSIONS) {
+            Some(extension) if extension.data.len() == 2 => TlsVersion::from_u16(read_u16(&extension.data)),
+            _ => TlsVersion::from_u16(self.legacy_version),
+        }
+    }
+
+    /// Retrieve the application protocol selected by the ALPN extension. Since TLS 1.3 the
+    /// extension is part of the encrypted extensions and therefore not available.
+    pub fn application_protocol(&self) -> Option<String> {
+        TlsExtension::application_protocols(&self.extensions).into_iter().next()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A DTLS hello verify request message
+pub struct TlsHelloVerifyRequest {
+    /// The protocol version of the server
+    pub server_version: u16,
+
+    /// The stateless cookie to be returned by the client
+    pub cookie: Vec<u8>,
+}
+
+impl TlsHelloVerifyRequest {
+    named!(#[doc = "Parse a hello verify request message body"],
+           pub parse<&[u8], TlsHelloVerifyRequest>,
+        do_parse!(
+            server_version: be_u16 >>
+            cookie: length_bytes!(be_u8) >>
+
+            (TlsHelloVerifyRequest {
+                server_version: server_version,
+                cookie: cookie.to_vec(),
+            })
+        )
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A generic TLS hello extension
+pub struct TlsExtension {
+    /// The type of the extension
+    pub extension_type: u16,
+
+    /// The raw extension data
+    pub data: Vec<u8>,
+}
+
+impl TlsExtension {
+    /// The `supported_versions` extension type
+    pub const SUPPORTED_VERSIONS: u16 = 43;
+
+    /// The `application_layer_protocol_negotiation` extension type
+    pub const APPLICATION_LAYER_PROTOCOL_NEGOTIATION: u16 = 16;
+
+    /// The `connection_id` extension type
+    pub const CONNECTION_ID: u16 = 54;
+
+    /// Retrieve the protocol names of the ALPN extension from a list of extensions
+    pub fn application_protocols(extensions: &[TlsExtension]) -> Vec<String> {
+        match extensions.iter().find(|e| e.extension_type == TlsExtension::APPLICATION_LAYER_PROTOCOL_NEGOTIATION) {
+            Some(extension) => {
+                match length_value!(&extension.data[..], be_u16, many0!(length_bytes!(be_u8))) {
+                    IResult::Done(_, names) => names.iter().map(|n| String::from_utf8_lossy(n).into_owned()).collect(),
+                    _ => vec![],
+                }
+            }
+            None => vec![],
+        }
+    }
+
+    /// Retrieve the connection ID of the `connection_id` extension from a list of extensions
+    pub fn connection_id(extensions: &[TlsExtension]) -> Option<&[u8]> {
+        extensions.iter()
+            .find(|e| e.extension_type == TlsExtension::CONNECTION_ID)
+            .and_then(|e| e.data.split_first())
+            .map(|(_, cid)| cid)
+    }
+
+    /// Parse the optional extension block at the end of a hello message
+    pub fn parse_all(input: &[u8]) -> IResult<&[u8], Vec<TlsExtension>> {
+        if input.is_empty() {
+            return IResult::Done(input, vec![]);
+        }
+        length_value!(input, be_u16, many0!(TlsExtension::parse))
+    }
+
+    named!(#[doc = "Parse a single hello extension"],
+           pub parse<&[u8], TlsExtension>,
+        do_parse!(
+            extension_type: be_u16 >>
+            data: length_bytes!(be_u16) >>
+
+            (TlsExtension {
+                extension_type: extension_type,
+                data: data.to_vec(),
+            })
+        )
+    );
+}
+
+/// Read a big endian u16 from the first two bytes of a slice
+fn read_u16(input: &[u8]) -> u16 {
+    match input {
+        &[high, low, ..] => (high as u16) << 8 | low as u16,
+        _ => 0,
+    }
+}
@@ -2,7 +2,23 @@
 use prelude::*;
 
 /// The UDP parser
-pub struct UdpParser;
+pub struct UdpParser {
+    /// The registry which dispatches the payload to the application protocols
+    pub registry: DispatchRegistry,
+}
+
+impl UdpParser {
+    /// Create a new parser with the well known ports and heuristics
+    pub fn new() -> Self {
+        UdpParser { registry: DispatchRegistry::udp() }
+    }
+}
+
+impl Default for UdpParser {
+    fn default() -> Self {
+        UdpParser::new()
+    }
+}
 
 impl Parsable<PathIp> for UdpParser {
     /// Parse an `UdpPacket` from an `&[u8]`
@@ -49,6 +65,10 @@ impl Parsable<PathIp> for UdpParser {
             len: be_u16 >>
             checksum: be_u16 >>
 
+            // Dispatch the payload to the application protocols
+            payload: peek!(call!(rest)) >>
+            protocols: expr_opt!(Some(self.registry.dispatch(src, dst, payload))) >>
+
             // Try to track the connection
             path_error: expr_opt!(match track_connection(path, result, src, dst) {
                 Err(e) => Some(Some(e.code)),
@@ -63,6 +83,7 @@ impl Parsable<PathIp> for UdpParser {
                     checksum: checksum,
                 },
                 path_error: path_error,
+                protocols: protocols,
             }))
         )
     }
@@ -75,16 +96,63 @@ impl fmt::Display for UdpParser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of an User Datagram Protocol packet
 pub struct UdpPacket {
     /// The header of the UDP packet
     pub header: UdpHeader,
 
     /// Set to some error code if the connection tracking failed
+    #[cfg_attr(feature = "serde", serde(with = "path_error"))]
     pub path_error: Option<PathErrorType>,
+
+    /// The application protocols the payload is dispatched to
+    pub protocols: Vec<ApplicationProtocol>,
+}
+
+impl UdpPacket {
+    /// Check if the payload is dispatched to the given application protocol
+    pub fn dispatches_to(&self, protocol: &ApplicationProtocol) -> bool {
+        self.protocols.contains(protocol)
+    }
+}
+
+impl Summary for UdpPacket {
+    fn endpoints(&self) -> Option<(String, String)> {
+        Some((self.header.source_port.to_string(), self.header.dest_port.to_string()))
+    }
+
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "UDP")?;
+        if verbosity > Verbosity::Brief {
+            write!(w, " len={}", self.header.length)?;
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w, " checksum={:#x}", self.header.checksum)?;
+            if !self.protocols.is_empty() {
+                let names: Vec<_> = self.protocols.iter().map(ApplicationProtocol::name).collect();
+                write!(w, " dispatch={}", names.join(","))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(UdpPacket);
+
+impl Dissect for UdpPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.field("udp.source_port", 16, self.header.source_port)
+            .field("udp.dest_port", 16, self.header.dest_port)
+            .field("udp.length", 16, self.header.length)
+            .field("udp.checksum", 16, self.header.checksum);
+        cursor.finish("udp", self.summary(Verbosity::Normal))
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of an User Datagram Protocol packet header
 pub struct UdpHeader {
     /// This field identifies the sender's port when meaningful and should be assumed to be the
@@ -0,0 +1,462 @@
+//! Dynamic Host Configuration Protocol related packet processing
+use prelude::*;
+
+/// The well known port of DHCP servers
+pub const DHCP_SERVER_PORT: u16 = 67;
+
+/// The well known port of DHCP clients
+pub const DHCP_CLIENT_PORT: u16 = 68;
+
+/// The magic cookie which precedes the options
+const MAGIC_COOKIE: &[u8] = &[0x63, 0x82, 0x53, 0x63];
+
+/// The DHCP parser
+pub struct DhcpParser;
+
+impl Parsable<PathIp> for DhcpParser {
+    /// Parse a `DhcpPacket` from an `&[u8]`
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 _: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        do_parse!(input,
+            // Check the transport protocol from the parent parser (UDP)
+            expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    Some(ref any) => match any.downcast_ref::<UdpPacket>() {
+                        Some(udp) if udp.dispatches_to(&ApplicationProtocol::Dhcp) => Some(()),
+                        _ => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(()),
+            }) >>
+
+            packet: complete!(DhcpPacket::parse) >>
+
+            (Box::new(packet))
+        )
+    }
+}
+
+impl fmt::Display for DhcpParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DHCP")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a DHCP or BOOTP message
+pub struct DhcpPacket {
+    /// The message direction
+    pub op: DhcpOperation,
+
+    /// The hardware address type, like 1 for Ethernet
+    pub hardware_type: u8,
+
+    /// The length of the hardware address
+    pub hardware_length: u8,
+
+    /// The amount of relay agents which forwarded the message
+    pub hops: u8,
+
+    /// The transaction identifier chosen by the client
+    pub xid: u32,
+
+    /// The seconds elapsed since the client began the address acquisition
+    pub secs: u16,
+
+    /// The client is not able to receive unicast messages before its address is configured
+    pub broadcast: bool,
+
+    /// The current address of the client
+    pub ciaddr: Ipv4Addr,
+
+    /// The address assigned to the client
+    pub yiaddr: Ipv4Addr,
+
+    /// The address of the next server to use in bootstrap
+    pub siaddr: Ipv4Addr,
+
+    /// The address of the relay agent
+    pub giaddr: Ipv4Addr,
+
+    /// The hardware address of the client
+    pub chaddr: MacAddress,
+
+    /// The optional server host name
+    pub sname: String,
+
+    /// The boot file name
+    pub file: String,
+
+    /// The options, which are missing for plain BOOTP messages
+    pub options: Vec<DhcpOption>,
+}
+
+impl Summary for DhcpPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "DHCP")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        match self.message_type() {
+            Some(message_type) => write!(w, " {:?}", message_type)?,
+            None => write!(w, " BOOTP {:?}", self.op)?,
+        }
+        write!(w, " xid={:#010x}", self.xid)?;
+        if verbosity == Verbosity::Verbose {
+            write!(w, " chaddr={} ciaddr={} yiaddr={}", self.chaddr, self.ciaddr, self.yiaddr)?;
+            if self.hops > 0 {
+                write!(w, " hops={} giaddr={}", self.hops, self.giaddr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(DhcpPacket);
+
+impl Dissect for DhcpPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.named("dhcp.op", 8, &self.op)
+            .field("dhcp.hardware_type", 8, self.hardware_type)
+            .field("dhcp.hardware_length", 8, self.hardware_length)
+            .field("dhcp.hops", 8, self.hops)
+            .field("dhcp.xid", 32, self.xid)
+            .field("dhcp.secs", 16, self.secs)
+            .field("dhcp.broadcast", 1, self.broadcast)
+            .skip(15)
+            .field("dhcp.ciaddr", 32, self.ciaddr)
+            .field("dhcp.yiaddr", 32, self.yiaddr)
+            .field("dhcp.siaddr", 32, self.siaddr)
+            .field("dhcp.giaddr", 32, self.giaddr)
+            .field("dhcp.chaddr", 48, self.chaddr.clone())
+            .skip(80)
+            .field("dhcp.sname", 64 * 8, self.sname.as_str())
+            .field("dhcp.file", 128 * 8, self.file.as_str());
+        if !cursor.remaining().starts_with(MAGIC_COOKIE) {
+            return cursor.finish("dhcp", self.summary(Verbosity::Normal));
+        }
+
+        // The options are dissected from the wire, since split options are concatenated
+        cursor.bytes("dhcp.magic_cookie", MAGIC_COOKIE.len());
+        loop {
+            match cursor.remaining().first() {
+                Some(&255) => {
+                    cursor.uint("dhcp.end", 8);
+                    break;
+                }
+                None => break,
+                Some(&0) => {
+                    cursor.skip(8);
+                }
+                Some(&code) => {
+                    let length = cursor.remaining().get(1).cloned().unwrap_or_default();
+                    cursor.group("dhcp.options", code, |option| {
+                        option.uint("dhcp.options.code", 8)
+                            .uint("dhcp.options.length", 8)
+                            .bytes("dhcp.options.value", length as usize);
+                    });
+                }
+            }
+        }
+        cursor.finish("dhcp", self.summary(Verbosity::Normal))
+    }
+}
+
+impl DhcpPacket {
+    named!(#[doc = "Parse a DHCP or BOOTP message"],
+           pub parse<&[u8], DhcpPacket>,
+        do_parse!(
+            op: map!(be_u8, DhcpOperation::from_u8) >>
+            hardware_type: be_u8 >>
+            hardware_length: be_u8 >>
+            hops: be_u8 >>
+            xid: be_u32 >>
+            secs: be_u16 >>
+            flags: be_u16 >>
+            ciaddr: map!(be_u32, Ipv4Addr::from) >>
+            yiaddr: map!(be_u32, Ipv4Addr::from) >>
+            siaddr: map!(be_u32, Ipv4Addr::from) >>
+            giaddr: map!(be_u32, Ipv4Addr::from) >>
+            chaddr: take!(16) >>
+            sname: map!(take!(64), null_terminated) >>
+            file: map!(take!(128), null_terminated) >>
+            options: opt!(preceded!(complete!(tag!(MAGIC_COOKIE)), call!(DhcpOption::parse_all))) >>
+
+            (DhcpPacket {
+                op: op,
+                hardware_type: hardware_type,
+                hardware_length: hardware_length,
+                hops: hops,
+                xid: xid,
+                secs: secs,
+                broadcast: flags & 0x8000 != 0,
+                ciaddr: ciaddr,
+                yiaddr: yiaddr,
+                siaddr: siaddr,
+                giaddr: giaddr,
+                chaddr: MacAddress(chaddr[0], chaddr[1], chaddr[2], chaddr[3], chaddr[4], chaddr[5]),
+                sname: sname,
+                file: file,
+                options: options.unwrap_or_default(),
+            })
+        )
+    );
+
+    /// Retrieve the DHCP message type, which is missing for BOOTP messages
+    pub fn message_type(&self) -> Option<&DhcpMessageType> {
+        self.options
+            .iter()
+            .filter_map(|o| match *o {
+                DhcpOption::MessageType(ref message_type) => Some(message_type),
+                _ => None,
+            })
+            .next()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The direction of a DHCP message
+pub enum DhcpOperation {
+    /// A message from the client to the server
+    BootRequest,
+
+    /// A message from the server to the client
+    BootReply,
+
+    /// An unassigned operation
+    Unknown(u8),
+}
+
+impl DhcpOperation {
+    /// Convert a u8 to a `DhcpOperation`
+    pub fn from_u8(input: u8) -> DhcpOperation {
+        match input {
+            1 => DhcpOperation::BootRequest,
+            2 => DhcpOperation::BootReply,
+            other => DhcpOperation::Unknown(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The DHCP message types
+pub enum DhcpMessageType {
+    /// The client locates servers
+    Discover,
+
+    /// The server offers configuration parameters
+    Offer,
+
+    /// The client requests the offered parameters or renews its lease
+    Request,
+
+    /// The client indicates that the address is already in use
+    Decline,
+
+    /// The server acknowledges the parameters
+    Ack,
+
+    /// The server refuses the request
+    Nak,
+
+    /// The client relinquishes its address
+    Release,
+
+    /// The client asks for local configuration parameters only
+    Inform,
+
+    /// Any other message type
+    Unknown(u8),
+}
+
+impl DhcpMessageType {
+    /// Convert a u8 to a `DhcpMessageType`
+    pub fn from_u8(input: u8) -> DhcpMessageType {
+        match input {
+            1 => DhcpMessageType::Discover,
+            2 => DhcpMessageType::Offer,
+            3 => DhcpMessageType::Request,
+            4 => DhcpMessageType::Decline,
+            5 => DhcpMessageType::Ack,
+            6 => DhcpMessageType::Nak,
+            7 => DhcpMessageType::Release,
+            8 => DhcpMessageType::Inform,
+            other => DhcpMessageType::Unknown(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A decoded DHCP option
+pub enum DhcpOption {
+    /// The subnet mask of the client (1)
+    SubnetMask(Ipv4Addr),
+
+    /// The routers on the subnet of the client (3)
+    Router(Vec<Ipv4Addr>),
+
+    /// The DNS servers available to the client (6)
+    DomainNameServer(Vec<Ipv4Addr>),
+
+    /// The name of the client (12)
+    HostName(String),
+
+    /// The domain name the client should use (15)
+    DomainName(String),
+
+    /// The address the client requests (50)
+    RequestedAddress(Ipv4Addr),
+
+    /// The lease time of the address in seconds (51)
+    LeaseTime(u32),
+
+    /// The DHCP message type (53)
+    MessageType(DhcpMessageType),
+
+    /// The address of the selected server (54)
+    ServerIdentifier(Ipv4Addr),
+
+    /// The option codes requested by the client (55)
+    ParameterRequestList(Vec<u8>),
+
+    /// The time until the client renews its lease in seconds (58)
+    RenewalTime(u32),
+
+    /// The time until the client rebinds its lease in seconds (59)
+    RebindingTime(u32),
+
+    /// The hardware type and identifier of the client (61)
+    ClientIdentifier(u8, Vec<u8>),
+
+    /// The sub-options added by a relay agent (82)
+    RelayAgentInformation(Vec<DhcpRelayAgentOption>),
+
+    /// Any other option with its code
+    Unknown(u8, Vec<u8>),
+
+    /// A known option with data which does not match its format
+    Invalid(u8, Vec<u8>),
+}
+
+impl DhcpOption {
+    /// Parse the options until the end option. Options which are split into multiple parts are
+    /// concatenated before they are decoded (RFC 3396).
+    pub fn parse_all(input: &[u8]) -> IResult<&[u8], Vec<DhcpOption>> {
+        let mut raw: Vec<(u8, Vec<u8>)> = vec![];
+        let mut input = input;
+        loop {
+            match input.split_first() {
+                // The end option or the end of the message terminates the options
+                Some((&255, rest)) => {
+                    input = rest;
+                    break;
+                }
+                None => break,
+
+                // Padding
+                Some((&0, rest)) => input = rest,
+
+                Some((&code, rest)) => {
+                    let (rest, data) = match length_bytes!(rest, be_u8) {
+                        IResult::Done(rest, data) => (rest, data),
+                        _ => return IResult::Incomplete(Needed::Unknown),
+                    };
+                    match raw.iter_mut().find(|o| o.0 == code) {
+                        Some(option) => option.1.extend_from_slice(data),
+                        None => raw.push((code, data.to_vec())),
+                    }
+                    input = rest;
+                }
+            }
+        }
+        IResult::Done(input, raw.into_iter().map(|(code, data)| DhcpOption::new(code, data)).collect())
+    }
+
+    /// Decode the data of an option by its code
+    pub fn new(code: u8, data: Vec<u8>) -> DhcpOption {
+        let result = match code {
+            1 => map!(&data[..], address, DhcpOption::SubnetMask),
+            3 => map!(&data[..], many1!(complete!(address)), DhcpOption::Router),
+            6 => map!(&data[..], many1!(complete!(address)), DhcpOption::DomainNameServer),
+            12 => IResult::Done(&[][..], DhcpOption::HostName(String::from_utf8_lossy(&data).into_owned())),
+            15 => IResult::Done(&[][..], DhcpOption::DomainName(null_terminated(&data))),
+            50 => map!(&data[..], address, DhcpOption::RequestedAddress),
+            51 => map!(&data[..], be_u32, DhcpOption::LeaseTime),
+            53 => map!(&data[..], map!(be_u8, DhcpMessageType::from_u8), DhcpOption::MessageType),
+            54 => map!(&data[..], address, DhcpOption::ServerIdentifier),
+            55 => IResult::Done(&[][..], DhcpOption::ParameterRequestList(data.clone())),
+            58 => map!(&data[..], be_u32, DhcpOption::RenewalTime),
+            59 => map!(&data[..], be_u32, DhcpOption::RebindingTime),
+            61 => {
+                map!(&data[..],
+                     pair!(be_u8, rest),
+                     |(kind, identifier): (u8, &[u8])| DhcpOption::ClientIdentifier(kind, identifier.to_vec()))
+            }
+            82 => {
+                map!(&data[..],
+                     many1!(complete!(DhcpRelayAgentOption::parse)),
+                     DhcpOption::RelayAgentInformation)
+            }
+            _ => return DhcpOption::Unknown(code, data),
+        };
+
+        // The option data has to be consumed completely
+        match result {
+            IResult::Done(&[], option) => option,
+            _ => DhcpOption::Invalid(code, data),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A sub-option of the relay agent information option
+pub enum DhcpRelayAgentOption {
+    /// The circuit the request was received on (1)
+    CircuitId(Vec<u8>),
+
+    /// The remote host end of the circuit (2)
+    RemoteId(Vec<u8>),
+
+    /// Any other sub-option with its code
+    Unknown(u8, Vec<u8>),
+}
+
+impl DhcpRelayAgentOption {
+    named!(#[doc = "Parse a single relay agent sub-option"],
+           pub parse<&[u8], DhcpRelayAgentOption>,
+        do_parse!(
+            code: be_u8 >>
+            data: length_bytes!(be_u8) >>
+
+            (match code {
+                1 => DhcpRelayAgentOption::CircuitId(data.to_vec()),
+                2 => DhcpRelayAgentOption::RemoteId(data.to_vec()),
+                _ => DhcpRelayAgentOption::Unknown(code, data.to_vec()),
+            })
+        )
+    );
+}
+
+named!(#[doc = "Parse an IPv4 address"],
+       address<&[u8], Ipv4Addr>,
+    map!(be_u32, Ipv4Addr::from)
+);
+
+/// Convert a field which may be terminated by a null character to a string
+fn null_terminated(input: &[u8]) -> String {
+    let end = input.iter().position(|&c| c == 0).unwrap_or(input.len());
+    String::from_utf8_lossy(&input[..end]).into_owned()
+}
@@ -0,0 +1,626 @@
+//! Dynamic Host Configuration Protocol for IPv6 related packet processing
+use prelude::*;
+
+/// The well known port of DHCPv6 clients
+pub const DHCPV6_CLIENT_PORT: u16 = 546;
+
+/// The well known port of DHCPv6 servers and relay agents
+pub const DHCPV6_SERVER_PORT: u16 = 547;
+
+/// The maximum amount of nested relay messages, which is the hop count limit of relay agents
+const MAX_RELAY_DEPTH: usize = 32;
+
+/// The maximum nesting of options, which are relay messages and the addresses within identity
+/// associations
+const MAX_OPTION_DEPTH: usize = MAX_RELAY_DEPTH + 2;
+
+/// The maximum length of a domain name in its wire format
+const MAX_NAME_LENGTH: usize = 255;
+
+/// The DHCPv6 parser
+pub struct Dhcpv6Parser;
+
+impl Parsable<PathIp> for Dhcpv6Parser {
+    /// Parse a `Dhcpv6Packet` from an `&[u8]`
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 _: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        do_parse!(input,
+            // Check the transport protocol from the parent parser (UDP)
+            expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    Some(ref any) => match any.downcast_ref::<UdpPacket>() {
+                        Some(udp) if udp.dispatches_to(&ApplicationProtocol::Dhcpv6) => Some(()),
+                        _ => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(()),
+            }) >>
+
+            packet: complete!(Dhcpv6Packet::parse) >>
+
+            (Box::new(packet))
+        )
+    }
+}
+
+impl fmt::Display for Dhcpv6Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DHCPv6")
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a DHCPv6 message
+pub enum Dhcpv6Packet {
+    /// A message between client and server
+    Message(Dhcpv6Message),
+
+    /// A message between relay agents and servers, which encapsulates another message
+    Relay(Dhcpv6RelayMessage),
+}
+
+impl Summary for Dhcpv6Packet {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "DHCPv6")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        write!(w, " {:?}", self.message_type())?;
+        match *self {
+            Dhcpv6Packet::Message(ref message) => {
+                write!(w, " xid={:#08x}", message.transaction_id)?;
+                if verbosity == Verbosity::Verbose {
+                    write!(w, " options={}", message.options.len())?;
+                }
+            }
+            Dhcpv6Packet::Relay(ref relay) => {
+                write!(w, " hops={} peer={}", relay.hop_count, relay.peer_address)?;
+                if verbosity == Verbosity::Verbose {
+                    write!(w, " link={} options={}", relay.link_address, relay.options.len())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(Dhcpv6Packet);
+
+impl Dissect for Dhcpv6Packet {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.named("dhcpv6.message_type", 8, self.message_type());
+        match *self {
+            Dhcpv6Packet::Message(ref message) => {
+                cursor.field("dhcpv6.transaction_id", 24, message.transaction_id);
+            }
+            Dhcpv6Packet::Relay(ref relay) => {
+                cursor.field("dhcpv6.hop_count", 8, relay.hop_count)
+                    .field("dhcpv6.link_address", 128, relay.link_address)
+                    .field("dhcpv6.peer_address", 128, relay.peer_address);
+            }
+        }
+        for option in self.options() {
+            let length = cursor.remaining().get(2..4).map_or(0, |l| u16::from_be_bytes([l[0], l[1]]));
+            let code = cursor.peek(16);
+            cursor.group("dhcpv6.options", code, |field| {
+                field.uint("dhcpv6.options.code", 16).uint("dhcpv6.options.length", 16);
+                match *option {
+                    // Relayed messages are dissected as well
+                    Dhcpv6Option::RelayMessage(ref packet) => field.dissect(&**packet, length as usize),
+                    _ => field.bytes("dhcpv6.options.value", length as usize),
+                };
+            });
+        }
+        cursor.finish("dhcpv6", self.summary(Verbosity::Normal))
+    }
+}
+
+impl Dhcpv6Packet {
+    /// Parse a DHCPv6 message, including all encapsulated relay messages
+    pub fn parse(input: &[u8]) -> IResult<&[u8], Dhcpv6Packet> {
+        Dhcpv6Packet::parse_nested(input, 0)
+    }
+
+    /// Parse a message at the given depth of relay message nesting
+    fn parse_nested(input: &[u8], depth: usize) -> IResult<&[u8], Dhcpv6Packet> {
+        if depth > MAX_RELAY_DEPTH {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+        do_parse!(input,
+            message_type: map!(be_u8, Dhcpv6MessageType::from_u8) >>
+            packet: alt!(
+                cond_reduce!(message_type.is_relay(), do_parse!(
+                    hop_count: be_u8 >>
+                    link_address: call!(address) >>
+                    peer_address: call!(address) >>
+                    options: apply!(Dhcpv6Option::parse_all, depth) >>
+
+                    (Dhcpv6Packet::Relay(Dhcpv6RelayMessage {
+                        message_type: message_type.clone(),
+                        hop_count: hop_count,
+                        link_address: link_address,
+                        peer_address: peer_address,
+                        options: options,
+                    }))
+                )) |
+                do_parse!(
+                    transaction_id: bits!(take_bits!(u32, 24)) >>
+                    options: apply!(Dhcpv6Option::parse_all, depth) >>
+
+                    (Dhcpv6Packet::Message(Dhcpv6Message {
+                        message_type: message_type.clone(),
+                        transaction_id: transaction_id,
+                        options: options,
+                    }))
+                )
+            ) >>
+
+            (packet)
+        )
+    }
+
+    /// Retrieve the message type
+    pub fn message_type(&self) -> &Dhcpv6MessageType {
+        match *self {
+            Dhcpv6Packet::Message(ref message) => &message.message_type,
+            Dhcpv6Packet::Relay(ref relay) => &relay.message_type,
+        }
+    }
+
+    /// Retrieve the options
+    pub fn options(&self) -> &[Dhcpv6Option] {
+        match *self {
+            Dhcpv6Packet::Message(ref message) => &message.options,
+            Dhcpv6Packet::Relay(ref relay) => &relay.options,
+        }
+    }
+
+    /// Retrieve the innermost message of a relay chain
+    pub fn innermost(&self) -> &Dhcpv6Packet {
+        let relayed = self.options()
+            .iter()
+            .filter_map(|o| match *o {
+                Dhcpv6Option::RelayMessage(ref packet) => Some(packet),
+                _ => None,
+            })
+            .next();
+        match relayed {
+            Some(packet) => packet.innermost(),
+            None => self,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A message between client and server
+pub struct Dhcpv6Message {
+    /// The message type
+    pub message_type: Dhcpv6MessageType,
+
+    /// The 24 bit transaction identifier
+    pub transaction_id: u32,
+
+    /// The options of the message
+    pub options: Vec<Dhcpv6Option>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A message between relay agents and servers
+pub struct Dhcpv6RelayMessage {
+    /// The message type, which is either Relay-Forward or Relay-Reply
+    pub message_type: Dhcpv6MessageType,
+
+    /// The amount of relay agents which already relayed the message
+    pub hop_count: u8,
+
+    /// The address which identifies the link of the client
+    pub link_address: Ipv6Addr,
+
+    /// The address of the client or relay agent the message was received from
+    pub peer_address: Ipv6Addr,
+
+    /// The options, which contain the relayed message
+    pub options: Vec<Dhcpv6Option>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The DHCPv6 message types
+pub enum Dhcpv6MessageType {
+    /// The client locates servers
+    Solicit,
+
+    /// The server indicates that it is available
+    Advertise,
+
+    /// The client requests configuration parameters
+    Request,
+
+    /// The client checks if its addresses are still appropriate for the link
+    Confirm,
+
+    /// The client extends its leases at the original server
+    Renew,
+
+    /// The client extends its leases at any server
+    Rebind,
+
+    /// The server answers a client message
+    Reply,
+
+    /// The client releases its leases
+    Release,
+
+    /// The client indicates that addresses are already in use
+    Decline,
+
+    /// The server asks the client to renew its configuration
+    Reconfigure,
+
+    /// The client requests configuration parameters without addresses
+    InformationRequest,
+
+    /// A relay agent forwards a message to servers
+    RelayForward,
+
+    /// A server sends a message to be relayed to the client
+    RelayReply,
+
+    /// Any other message type
+    Unknown(u8),
+}
+
+impl Dhcpv6MessageType {
+    /// Convert a u8 to a `Dhcpv6MessageType`
+    pub fn from_u8(input: u8) -> Dhcpv6MessageType {
+        match input {
+            1 => Dhcpv6MessageType::Solicit,
+            2 => Dhcpv6MessageType::Advertise,
+            3 => Dhcpv6MessageType::Request,
+            4 => Dhcpv6MessageType::Confirm,
+            5 => Dhcpv6MessageType::Renew,
+            6 => Dhcpv6MessageType::Rebind,
+            7 => Dhcpv6MessageType::Reply,
+            8 => Dhcpv6MessageType::Release,
+            9 => Dhcpv6MessageType::Decline,
+            10 => Dhcpv6MessageType::Reconfigure,
+            11 => Dhcpv6MessageType::InformationRequest,
+            12 => Dhcpv6MessageType::RelayForward,
+            13 => Dhcpv6MessageType::RelayReply,
+            other => Dhcpv6MessageType::Unknown(other),
+        }
+    }
+
+    /// Check if the message type uses the relay message format
+    pub fn is_relay(&self) -> bool {
+        *self == Dhcpv6MessageType::RelayForward || *self == Dhcpv6MessageType::RelayReply
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A decoded DHCPv6 option
+pub enum Dhcpv6Option {
+    /// The DUID of the client (1)
+    ClientId(Dhcpv6Duid),
+
+    /// The DUID of the server (2)
+    ServerId(Dhcpv6Duid),
+
+    /// An identity association for non-temporary addresses (3)
+    IaNa(Dhcpv6IdentityAssociation),
+
+    /// An address of an identity association (5)
+    IaAddress(Dhcpv6IaAddress),
+
+    /// The option codes requested by the client (6)
+    OptionRequest(Vec<u16>),
+
+    /// The preference of the server (7)
+    Preference(u8),
+
+    /// The time since the client began the exchange in hundredths of a second (8)
+    ElapsedTime(u16),
+
+    /// The message relayed by a relay agent (9)
+    RelayMessage(Box<Dhcpv6Packet>),
+
+    /// The status of a message or option (13)
+    StatusCode(u16, String),
+
+    /// The client requests the two message exchange (14)
+    RapidCommit,
+
+    /// The interface the relay agent received the message on (18)
+    InterfaceId(Vec<u8>),
+
+    /// The addresses of recursive DNS servers (23)
+    DnsServers(Vec<Ipv6Addr>),
+
+    /// The domain search list (24)
+    DomainList(Vec<String>),
+
+    /// An identity association for prefix delegation (25)
+    IaPd(Dhcpv6IdentityAssociation),
+
+    /// A prefix of an identity association for prefix delegation (26)
+    IaPrefix(Dhcpv6IaPrefix),
+
+    /// Any other option with its code
+    Unknown(u16, Vec<u8>),
+
+    /// A known option with data which does not match its format
+    Invalid(u16, Vec<u8>),
+}
+
+impl Dhcpv6Option {
+    /// Parse all options until the end of the input, at the given depth of option nesting
+    pub fn parse_all(input: &[u8], depth: usize) -> IResult<&[u8], Vec<Dhcpv6Option>> {
+        if depth > MAX_OPTION_DEPTH {
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+        many0!(input,
+               complete!(do_parse!(
+                   code: be_u16 >>
+                   data: length_bytes!(be_u16) >>
+                   (Dhcpv6Option::new(code, data, depth))
+               )))
+    }
+
+    /// Decode the data of an option by its code
+    pub fn new(code: u16, data: &[u8], depth: usize) -> Dhcpv6Option {
+        let result = match code {
+            1 => map!(data, call!(Dhcpv6Duid::parse), Dhcpv6Option::ClientId),
+            2 => map!(data, call!(Dhcpv6Duid::parse), Dhcpv6Option::ServerId),
+            3 => map!(data, apply!(Dhcpv6IdentityAssociation::parse, depth + 1), Dhcpv6Option::IaNa),
+            5 => map!(data, apply!(Dhcpv6IaAddress::parse, depth + 1), Dhcpv6Option::IaAddress),
+            6 => map!(data, many0!(complete!(be_u16)), Dhcpv6Option::OptionRequest),
+            7 => map!(data, be_u8, Dhcpv6Option::Preference),
+            8 => map!(data, be_u16, Dhcpv6Option::ElapsedTime),
+            9 => {
+                map!(data,
+                     apply!(Dhcpv6Packet::parse_nested, depth + 1),
+                     |packet| Dhcpv6Option::RelayMessage(Box::new(packet)))
+            }
+            13 => {
+                map!(data,
+                     pair!(be_u16, rest),
+                     |(status, message): (u16, &[u8])| {
+                         Dhcpv6Option::StatusCode(status, String::from_utf8_lossy(message).into_owned())
+                     })
+            }
+            14 => IResult::Done(data, Dhcpv6Option::RapidCommit),
+            18 => IResult::Done(&data[data.len()..], Dhcpv6Option::InterfaceId(data.to_vec())),
+            23 => map!(data, many0!(complete!(address)), Dhcpv6Option::DnsServers),
+            24 => map!(data, many0!(complete!(parse_domain_name)), Dhcpv6Option::DomainList),
+            25 => map!(data, apply!(Dhcpv6IdentityAssociation::parse, depth + 1), Dhcpv6Option::IaPd),
+            26 => map!(data, apply!(Dhcpv6IaPrefix::parse, depth + 1), Dhcpv6Option::IaPrefix),
+            _ => return Dhcpv6Option::Unknown(code, data.to_vec()),
+        };
+
+        // The option data has to be consumed completely
+        match result {
+            IResult::Done(&[], option) => option,
+            _ => Dhcpv6Option::Invalid(code, data.to_vec()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A DHCP unique identifier of a client or server
+pub enum Dhcpv6Duid {
+    /// Link-layer address plus time, with the hardware type and the time since 2000 (1)
+    LinkLayerTime(u16, u32, Vec<u8>),
+
+    /// Vendor assigned unique identifier, with the enterprise number (2)
+    Enterprise(u32, Vec<u8>),
+
+    /// Link-layer address, with the hardware type (3)
+    LinkLayer(u16, Vec<u8>),
+
+    /// Universally unique identifier (4)
+    Uuid(Vec<u8>),
+
+    /// Any other DUID type
+    Unknown(u16, Vec<u8>),
+}
+
+impl Dhcpv6Duid {
+    named!(#[doc = "Parse a DUID, which fills the rest of the input"],
+           pub parse<&[u8], Dhcpv6Duid>,
+        switch!(be_u16,
+            1 => do_parse!(
+                hardware_type: be_u16 >>
+                time: be_u32 >>
+                address: rest >>
+                (Dhcpv6Duid::LinkLayerTime(hardware_type, time, address.to_vec()))
+            ) |
+            2 => do_parse!(
+                enterprise: be_u32 >>
+                identifier: rest >>
+                (Dhcpv6Duid::Enterprise(enterprise, identifier.to_vec()))
+            ) |
+            3 => do_parse!(
+                hardware_type: be_u16 >>
+                address: rest >>
+                (Dhcpv6Duid::LinkLayer(hardware_type, address.to_vec()))
+            ) |
+            4 => map!(take!(16), |uuid: &[u8]| Dhcpv6Duid::Uuid(uuid.to_vec())) |
+            duid_type => map!(rest, |data: &[u8]| Dhcpv6Duid::Unknown(duid_type, data.to_vec()))
+        )
+    );
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// An identity association for non-temporary addresses or prefix delegation
+pub struct Dhcpv6IdentityAssociation {
+    /// The identifier of the association
+    pub iaid: u32,
+
+    /// The time until the client contacts the original server in seconds
+    pub t1: u32,
+
+    /// The time until the client contacts any server in seconds
+    pub t2: u32,
+
+    /// The options, like the addresses or prefixes and status codes
+    pub options: Vec<Dhcpv6Option>,
+}
+
+impl Dhcpv6IdentityAssociation {
+    /// Parse an identity association
+    pub fn parse(input: &[u8], depth: usize) -> IResult<&[u8], Dhcpv6IdentityAssociation> {
+        do_parse!(input,
+            iaid: be_u32 >>
+            t1: be_u32 >>
+            t2: be_u32 >>
+            options: apply!(Dhcpv6Option::parse_all, depth) >>
+
+            (Dhcpv6IdentityAssociation {
+                iaid: iaid,
+                t1: t1,
+                t2: t2,
+                options: options,
+            })
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// An address of an identity association
+pub struct Dhcpv6IaAddress {
+    /// The address
+    pub address: Ipv6Addr,
+
+    /// The preferred lifetime in seconds
+    pub preferred_lifetime: u32,
+
+    /// The valid lifetime in seconds
+    pub valid_lifetime: u32,
+
+    /// The options of the address
+    pub options: Vec<Dhcpv6Option>,
+}
+
+impl Dhcpv6IaAddress {
+    /// Parse an address of an identity association
+    pub fn parse(input: &[u8], depth: usize) -> IResult<&[u8], Dhcpv6IaAddress> {
+        do_parse!(input,
+            address: call!(address) >>
+            preferred_lifetime: be_u32 >>
+            valid_lifetime: be_u32 >>
+            options: apply!(Dhcpv6Option::parse_all, depth) >>
+
+            (Dhcpv6IaAddress {
+                address: address,
+                preferred_lifetime: preferred_lifetime,
+                valid_lifetime: valid_lifetime,
+                options: options,
+            })
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A delegated prefix of an identity association
+pub struct Dhcpv6IaPrefix {
+    /// The preferred lifetime in seconds
+    pub preferred_lifetime: u32,
+
+    /// The valid lifetime in seconds
+    pub valid_lifetime: u32,
+
+    /// The length of the prefix in bits
+    pub prefix_length: u8,
+
+    /// The prefix
+    pub prefix: Ipv6Addr,
+
+    /// The options of the prefix
+    pub options: Vec<Dhcpv6Option>,
+}
+
+impl Dhcpv6IaPrefix {
+    /// Parse a prefix of an identity association
+    pub fn parse(input: &[u8], depth: usize) -> IResult<&[u8], Dhcpv6IaPrefix> {
+        do_parse!(input,
+            preferred_lifetime: be_u32 >>
+            valid_lifetime: be_u32 >>
+            prefix_length: be_u8 >>
+            prefix: call!(address) >>
+            options: apply!(Dhcpv6Option::parse_all, depth) >>
+
+            (Dhcpv6IaPrefix {
+                preferred_lifetime: preferred_lifetime,
+                valid_lifetime: valid_lifetime,
+                prefix_length: prefix_length,
+                prefix: prefix,
+                options: options,
+            })
+        )
+    }
+}
+
+named!(#[doc = "Parse an IPv6 address"],
+       address<&[u8], Ipv6Addr>,
+    map!(take!(16), |a: &[u8]| {
+        let mut address = [0; 16];
+        address.copy_from_slice(a);
+        Ipv6Addr::from(address)
+    })
+);
+
+/// Parse an uncompressed domain name, since the compression of DNS is not allowed within options
+/// (RFC 8415, section 10)
+fn parse_domain_name(input: &[u8]) -> IResult<&[u8], String> {
+    let mut name = String::new();
+    let mut rest = input;
+    loop {
+        match rest.split_first() {
+            // The root label terminates the name
+            Some((&0, remaining)) => {
+                rest = remaining;
+                break;
+            }
+
+            // A regular label
+            Some((&length, remaining)) if length <= 0x3f => {
+                let label = match remaining.get(..length as usize) {
+                    Some(label) => label,
+                    None => return IResult::Incomplete(Needed::Unknown),
+                };
+                if input.len() - remaining.len() + label.len() >= MAX_NAME_LENGTH {
+                    return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                }
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                escape_label(&mut name, label);
+                rest = &remaining[label.len()..];
+            }
+
+            // Compression pointers and extended label types
+            Some(_) => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+            None => return IResult::Incomplete(Needed::Unknown),
+        }
+    }
+    if name.is_empty() {
+        name.push('.');
+    }
+    IResult::Done(rest, name)
+}
@@ -0,0 +1,1740 @@
+//! Domain Name System related packet processing
+use prelude::*;
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// The well known port of DNS over UDP and TCP
+pub const DNS_PORT: u16 = 53;
+
+/// The maximum length of a domain name in its wire format
+const MAX_NAME_LENGTH: usize = 255;
+
+/// The maximum amount of tracked queries
+const MAX_PENDING_QUERIES: usize = 0x1_0000;
+
+/// The time after which a query is considered unanswered
+pub const DNS_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+/// The DNS parser, which correlates the queries and responses of all connections
+pub struct DnsParser {
+    /// The queries which wait for a response
+    pub tracker: DnsTracker,
+}
+
+impl Parsable<PathIp> for DnsParser {
+    /// Parse a `DnsPacket` from an `&[u8]`
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 path: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        // Retrieve the connection related information
+        let endpoints = connection_endpoints(result);
+        let sender = endpoints.map(|e| e.0);
+        let mut data = connection_data(path, result);
+
+        do_parse!(input,
+            // Check the transport protocol from the parent parser (UDP, TCP or a previous message)
+            stream: expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    Some(ref any) => match (any.downcast_ref::<UdpPacket>(),
+                                            any.downcast_ref::<TcpPacket>(),
+                                            any.downcast_ref::<DnsPacket>()) {
+                        // Plain messages within datagrams
+                        (Some(udp), _, _) if udp.dispatches_to(&ApplicationProtocol::Dns) => {
+                            Some(false)
+                        }
+
+                        // Length prefixed messages within a stream
+                        (_, Some(tcp), _) if tcp.dispatches_to(&ApplicationProtocol::Dns) && !input.is_empty() => {
+                            Some(true)
+                        }
+
+                        // Multiple messages within a single segment
+                        (_, _, Some(_)) if !input.is_empty() &&
+                                           vector.iter().any(|r| r.is::<TcpPacket>()) => Some(true),
+
+                        _ => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(false),
+            }) >>
+
+            packet: alt!(
+                cond_reduce!(stream, apply!(DnsPacket::parse_stream,
+                                            data.as_mut().map(|d| &mut d.dns),
+                                            sender.as_ref())) |
+                cond_reduce!(!stream, complete!(call!(DnsPacket::parse)))
+            ) >>
+
+            (Box::new(packet.correlate(&mut self.tracker, endpoints, stream)))
+        )
+    }
+}
+
+impl fmt::Display for DnsParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DNS")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a Domain Name System message
+pub struct DnsPacket {
+    /// The message header
+    pub header: DnsHeader,
+
+    /// The question section
+    pub questions: Vec<DnsQuestion>,
+
+    /// The answer section
+    pub answers: Vec<DnsResourceRecord>,
+
+    /// The authority section
+    pub authorities: Vec<DnsResourceRecord>,
+
+    /// The additional section, which contains the EDNS0 pseudo record as well
+    pub additionals: Vec<DnsResourceRecord>,
+
+    /// The transactions which are completed by this message, like the query answered by a
+    /// response or queries of any connection which timed out meanwhile
+    pub transactions: Vec<DnsTransaction>,
+}
+
+impl Summary for DnsPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "DNS")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        if self.header.response {
+            write!(w, " response id={:#06x} {:?}", self.header.id, self.header.response_code)?;
+        } else {
+            write!(w, " query id={:#06x}", self.header.id)?;
+        }
+        let questions: Vec<_> = self.questions.iter().map(ToString::to_string).collect();
+        if !questions.is_empty() {
+            write!(w, " {}", questions.join(", "))?;
+        }
+        if self.header.response {
+            write!(w, " answers={}", self.answers.len())?;
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w,
+                   " opcode={:?} authorities={} additionals={}",
+                   self.header.opcode,
+                   self.authorities.len(),
+                   self.additionals.len())?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(DnsPacket);
+
+impl Dissect for DnsPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        let id = [(self.header.id >> 8) as u8, self.header.id as u8];
+        if input.get(..2) != Some(&id[..]) {
+            if input.get(2..4) != Some(&id[..]) {
+                // The message was reassembled from previous segments of the stream
+                return cursor.finish("dns", self.summary(Verbosity::Normal));
+            }
+            // Messages within a stream are prefixed by their length
+            cursor.uint("dns.length", 16);
+        }
+        let message = cursor.remaining();
+        self.header.dissect(&mut cursor, "dns");
+        for question in &self.questions {
+            question.dissect(&mut cursor, "dns.questions", message, None);
+        }
+        for &(prefix, records) in &[("dns.answers", &self.answers),
+                                    ("dns.authorities", &self.authorities),
+                                    ("dns.additionals", &self.additionals)] {
+            for record in records {
+                record.dissect(&mut cursor, prefix, message, None);
+            }
+        }
+        cursor.finish("dns", self.summary(Verbosity::Normal))
+    }
+}
+
+impl DnsPacket {
+    /// Parse a complete message, like the payload of a datagram
+    pub fn parse(input: &[u8]) -> IResult<&[u8], DnsPacket> {
+        do_parse!(input,
+            header: call!(DnsHeader::parse) >>
+            questions: apply!(parse_count, header.question_count, |i| DnsQuestion::parse(i, input)) >>
+            answers: apply!(parse_count, header.answer_count, |i| DnsResourceRecord::parse(i, input)) >>
+            authorities: apply!(parse_count, header.authority_count, |i| DnsResourceRecord::parse(i, input)) >>
+            additionals: apply!(parse_count, header.additional_count, |i| DnsResourceRecord::parse(i, input)) >>
+
+            (DnsPacket {
+                header: header,
+                questions: questions,
+                answers: answers,
+                authorities: authorities,
+                additionals: additionals,
+                transactions: vec![],
+            })
+        )
+    }
+
+    /// Parse a message which is prefixed by its length, like within a TCP stream. The state is
+    /// used to reassemble messages which are split over multiple segments.
+    pub fn parse_stream<'a>(input: &'a [u8],
+                            state: Option<&mut DnsState>,
+                            sender: Option<&Endpoint>)
+                            -> IResult<&'a [u8], DnsPacket> {
+        // Continue an incomplete message of the previous segment
+        let mut state = match sender {
+            Some(_) => state,
+            None => None,
+        };
+        let mut buffer = match (state.as_mut(), sender) {
+            (Some(state), Some(sender)) => state.buffers.remove(sender).unwrap_or_default(),
+            _ => vec![],
+        };
+        buffer.extend_from_slice(input);
+
+        let length = match buffer.get(..2) {
+            Some(prefix) => (prefix[0] as usize) << 8 | prefix[1] as usize,
+            None => 0,
+        };
+        if buffer.len() < 2 || buffer.len() < 2 + length {
+            // Wait for the rest of the message
+            if let (Some(state), Some(sender)) = (state, sender) {
+                state.buffers.insert(*sender, buffer);
+            }
+            return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+        }
+
+        // The message has to fill the announced length completely
+        match DnsPacket::parse(&buffer[2..2 + length]) {
+            IResult::Done(&[], packet) => {
+                let left = buffer.len() - 2 - length;
+                IResult::Done(&input[input.len() - left..], packet)
+            }
+            _ => IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        }
+    }
+
+    /// Match the message against the tracked queries at the capture time, if the endpoints are
+    /// known
+    fn correlate(mut self, tracker: &mut DnsTracker, endpoints: Option<(Endpoint, Endpoint)>, stream: bool)
+                 -> DnsPacket {
+        if let Some((source, destination)) = endpoints {
+            self.transactions = tracker.add_message(&source, &destination, stream, &self, capture_time());
+        }
+        self
+    }
+
+    /// Retrieve the EDNS0 information of the message
+    pub fn edns(&self) -> Option<&DnsEdns> {
+        self.additionals
+            .iter()
+            .filter_map(|r| match r.data {
+                DnsRecordData::Opt(ref edns) => Some(edns),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Retrieve the response code, which is extended by the EDNS0 information
+    pub fn response_code(&self) -> DnsResponseCode {
+        let extended = self.edns().map_or(0, |e| e.extended_response_code as u16);
+        DnsResponseCode::from_u16(extended << 4 | self.header.response_code.to_u16() & 0xf)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The header of a DNS message
+pub struct DnsHeader {
+    /// The identifier to match responses and queries
+    pub id: u16,
+
+    /// The message is a response
+    pub response: bool,
+
+    /// The kind of query
+    pub opcode: DnsOpcode,
+
+    /// The responding server is an authority for the domain name
+    pub authoritative: bool,
+
+    /// The message was truncated due to the length limit of the transport
+    pub truncated: bool,
+
+    /// The server should pursue the query recursively
+    pub recursion_desired: bool,
+
+    /// The server supports recursive queries
+    pub recursion_available: bool,
+
+    /// Reserved bit, which has to be zero
+    pub zero: bool,
+
+    /// All data of the response has been authenticated by DNSSEC
+    pub authentic_data: bool,
+
+    /// DNSSEC validation is disabled
+    pub checking_disabled: bool,
+
+    /// The lower four bits of the response code
+    pub response_code: DnsResponseCode,
+
+    /// The amount of entries in the question section
+    pub question_count: u16,
+
+    /// The amount of resource records in the answer section
+    pub answer_count: u16,
+
+    /// The amount of resource records in the authority section
+    pub authority_count: u16,
+
+    /// The amount of resource records in the additional section
+    pub additional_count: u16,
+}
+
+impl DnsHeader {
+    named!(#[doc = "Parse a message header"],
+           pub parse<&[u8], DnsHeader>,
+        do_parse!(
+            id: be_u16 >>
+            flags: be_u16 >>
+            question_count: be_u16 >>
+            answer_count: be_u16 >>
+            authority_count: be_u16 >>
+            additional_count: be_u16 >>
+
+            (DnsHeader {
+                id: id,
+                response: flags & 0x8000 != 0,
+                opcode: DnsOpcode::from_u8((flags >> 11) as u8 & 0xf),
+                authoritative: flags & 0x400 != 0,
+                truncated: flags & 0x200 != 0,
+                recursion_desired: flags & 0x100 != 0,
+                recursion_available: flags & 0x80 != 0,
+                zero: flags & 0x40 != 0,
+                authentic_data: flags & 0x20 != 0,
+                checking_disabled: flags & 0x10 != 0,
+                response_code: DnsResponseCode::from_u16(flags & 0xf),
+                question_count: question_count,
+                answer_count: answer_count,
+                authority_count: authority_count,
+                additional_count: additional_count,
+            })
+        )
+    );
+
+    /// Add the fields of the header to a cursor, named after the given protocol
+    pub fn dissect(&self, cursor: &mut FieldCursor, protocol: &str) {
+        let name = |field: &str| format!("{}.header.{}", protocol, field);
+        cursor.field(&name("id"), 16, self.id)
+            .field(&name("response"), 1, self.response)
+            .named(&name("opcode"), 4, &self.opcode)
+            .field(&name("authoritative"), 1, self.authoritative)
+            .field(&name("truncated"), 1, self.truncated)
+            .field(&name("recursion_desired"), 1, self.recursion_desired)
+            .field(&name("recursion_available"), 1, self.recursion_available)
+            .field(&name("zero"), 1, self.zero)
+            .field(&name("authentic_data"), 1, self.authentic_data)
+            .field(&name("checking_disabled"), 1, self.checking_disabled)
+            .named(&name("response_code"), 4, &self.response_code)
+            .field(&name("question_count"), 16, self.question_count)
+            .field(&name("answer_count"), 16, self.answer_count)
+            .field(&name("authority_count"), 16, self.authority_count)
+            .field(&name("additional_count"), 16, self.additional_count);
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// DNS operation codes
+pub enum DnsOpcode {
+    /// A standard query
+    Query,
+
+    /// An inverse query (obsolete)
+    InverseQuery,
+
+    /// A server status request
+    Status,
+
+    /// A zone change notification
+    Notify,
+
+    /// A dynamic update
+    Update,
+
+    /// DNS stateful operations
+    StatefulOperations,
+
+    /// An unassigned operation code
+    Unknown(u8),
+}
+
+impl DnsOpcode {
+    /// Convert a u8 to a `DnsOpcode`
+    pub fn from_u8(input: u8) -> DnsOpcode {
+        match input {
+            0 => DnsOpcode::Query,
+            1 => DnsOpcode::InverseQuery,
+            2 => DnsOpcode::Status,
+            4 => DnsOpcode::Notify,
+            5 => DnsOpcode::Update,
+            6 => DnsOpcode::StatefulOperations,
+            other => DnsOpcode::Unknown(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// DNS response codes
+pub enum DnsResponseCode {
+    /// No error condition
+    NoError,
+
+    /// The server was unable to interpret the query
+    FormatError,
+
+    /// The server was unable to process the query due to a problem with the name server
+    ServerFailure,
+
+    /// The domain name referenced in the query does not exist
+    NonExistentDomain,
+
+    /// The server does not support the requested kind of query
+    NotImplemented,
+
+    /// The server refuses to perform the operation for policy reasons
+    Refused,
+
+    /// A name exists when it should not
+    YxDomain,
+
+    /// A resource record set exists when it should not
+    YxRrSet,
+
+    /// A resource record set that should exist does not
+    NxRrSet,
+
+    /// The server is not authoritative for the zone
+    NotAuthoritative,
+
+    /// A name is not contained in the zone
+    NotZone,
+
+    /// The EDNS version is not supported
+    BadVersion,
+
+    /// A bad or missing server cookie
+    BadCookie,
+
+    /// An unassigned response code
+    Unknown(u16),
+}
+
+impl DnsResponseCode {
+    /// Convert a u16 to a `DnsResponseCode`
+    pub fn from_u16(input: u16) -> DnsResponseCode {
+        match input {
+            0 => DnsResponseCode::NoError,
+            1 => DnsResponseCode::FormatError,
+            2 => DnsResponseCode::ServerFailure,
+            3 => DnsResponseCode::NonExistentDomain,
+            4 => DnsResponseCode::NotImplemented,
+            5 => DnsResponseCode::Refused,
+            6 => DnsResponseCode::YxDomain,
+            7 => DnsResponseCode::YxRrSet,
+            8 => DnsResponseCode::NxRrSet,
+            9 => DnsResponseCode::NotAuthoritative,
+            10 => DnsResponseCode::NotZone,
+            16 => DnsResponseCode::BadVersion,
+            23 => DnsResponseCode::BadCookie,
+            other => DnsResponseCode::Unknown(other),
+        }
+    }
+
+    /// Convert a `DnsResponseCode` to its numeric value
+    pub fn to_u16(&self) -> u16 {
+        match *self {
+            DnsResponseCode::NoError => 0,
+            DnsResponseCode::FormatError => 1,
+            DnsResponseCode::ServerFailure => 2,
+            DnsResponseCode::NonExistentDomain => 3,
+            DnsResponseCode::NotImplemented => 4,
+            DnsResponseCode::Refused => 5,
+            DnsResponseCode::YxDomain => 6,
+            DnsResponseCode::YxRrSet => 7,
+            DnsResponseCode::NxRrSet => 8,
+            DnsResponseCode::NotAuthoritative => 9,
+            DnsResponseCode::NotZone => 10,
+            DnsResponseCode::BadVersion => 16,
+            DnsResponseCode::BadCookie => 23,
+            DnsResponseCode::Unknown(other) => other,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// An entry of the question section
+pub struct DnsQuestion {
+    /// The queried domain name
+    pub name: String,
+
+    /// The queried resource record type
+    pub qtype: DnsType,
+
+    /// The queried class
+    pub class: DnsClass,
+}
+
+impl fmt::Display for DnsQuestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}? {}", self.qtype, self.name)
+    }
+}
+
+impl DnsQuestion {
+    /// Parse a question, the message is needed to resolve compressed names
+    pub fn parse<'a>(input: &'a [u8], message: &[u8]) -> IResult<&'a [u8], DnsQuestion> {
+        do_parse!(input,
+            name: apply!(parse_name, message) >>
+            qtype: be_u16 >>
+            class: be_u16 >>
+
+            (DnsQuestion {
+                name: name,
+                qtype: DnsType::from_u16(qtype),
+                class: DnsClass::from_u16(class),
+            })
+        )
+    }
+
+    /// Add the fields of the question to a cursor, the message is needed to measure compressed
+    /// names. The flag is the name and value of the top bit of the class, which is used by mDNS.
+    pub fn dissect(&self, cursor: &mut FieldCursor, prefix: &str, message: &[u8], flag: Option<(&str, bool)>) {
+        cursor.group(prefix, self.to_string(), |cursor| {
+            let name = |field: &str| format!("{}.{}", prefix, field);
+            let length = name_length(cursor, message);
+            cursor.field(&name("name"), length, self.name.as_str())
+                .named(&name("qtype"), 16, &self.qtype);
+            dissect_class(cursor, &name("class"), &self.class, flag);
+        });
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A resource record of the answer, authority or additional section
+pub struct DnsResourceRecord {
+    /// The domain name the record belongs to
+    pub name: String,
+
+    /// The type of the record
+    pub rtype: DnsType,
+
+    /// The class of the record, which is the UDP payload size for the EDNS0 pseudo record
+    pub class: DnsClass,
+
+    /// The time the record may be cached in seconds
+    pub ttl: u32,
+
+    /// The decoded record data
+    pub data: DnsRecordData,
+}
+
+impl DnsResourceRecord {
+    /// Parse a resource record, the message is needed to resolve compressed names
+    pub fn parse<'a>(input: &'a [u8], message: &[u8]) -> IResult<&'a [u8], DnsResourceRecord> {
+        do_parse!(input,
+            name: apply!(parse_name, message) >>
+            rtype: map!(be_u16, DnsType::from_u16) >>
+            class: be_u16 >>
+            ttl: be_u32 >>
+            data: length_bytes!(be_u16) >>
+
+            (DnsResourceRecord {
+                data: DnsRecordData::new(&rtype, class, ttl, data, message),
+                name: name,
+                rtype: rtype,
+                class: DnsClass::from_u16(class),
+                ttl: ttl,
+            })
+        )
+    }
+
+    /// Add the fields of the record to a cursor, the message is needed to measure compressed
+    /// names. The flag is the name and value of the top bit of the class, which is used by mDNS.
+    pub fn dissect(&self, cursor: &mut FieldCursor, prefix: &str, message: &[u8], flag: Option<(&str, bool)>) {
+        cursor.group(prefix, self.name.as_str(), |cursor| {
+            let name = |field: &str| format!("{}.{}", prefix, field);
+            let length = name_length(cursor, message);
+            cursor.field(&name("name"), length, self.name.as_str())
+                .named(&name("rtype"), 16, &self.rtype);
+            dissect_class(cursor, &name("class"), &self.class, flag);
+            let length = cursor.field(&name("ttl"), 32, self.ttl).peek(16) as usize;
+            cursor.field(&name("length"), 16, length);
+            let value = match self.data {
+                DnsRecordData::A(address) => FieldValue::from(address),
+                DnsRecordData::Aaaa(address) => FieldValue::from(address),
+                DnsRecordData::Ns(ref name) |
+                DnsRecordData::Cname(ref name) |
+                DnsRecordData::Ptr(ref name) => FieldValue::from(name.as_str()),
+                _ => {
+                    cursor.bytes(&name("data"), length);
+                    return;
+                }
+            };
+            let length = cmp::min(length, cursor.remaining().len());
+            cursor.field(&name("data"), length * 8, value);
+        });
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// DNS resource record types
+pub enum DnsType {
+    /// IPv4 host address
+    A,
+
+    /// Authoritative name server
+    Ns,
+
+    /// Canonical name of an alias
+    Cname,
+
+    /// Start of a zone of authority
+    Soa,
+
+    /// Domain name pointer
+    Ptr,
+
+    /// Host information
+    Hinfo,
+
+    /// Mail exchange
+    Mx,
+
+    /// Text strings
+    Txt,
+
+    /// IPv6 host address
+    Aaaa,
+
+    /// Service locator
+    Srv,
+
+    /// Naming authority pointer
+    Naptr,
+
+    /// EDNS0 pseudo record
+    Opt,
+
+    /// Delegation signer
+    Ds,
+
+    /// DNSSEC signature
+    Rrsig,
+
+    /// Next secure record
+    Nsec,
+
+    /// DNS public key
+    Dnskey,
+
+    /// Hashed next secure record
+    Nsec3,
+
+    /// Parameters of hashed next secure records
+    Nsec3Param,
+
+    /// TLS certificate association
+    Tlsa,
+
+    /// General purpose service binding
+    Svcb,
+
+    /// Service binding for HTTPS
+    Https,
+
+    /// Incremental zone transfer
+    Ixfr,
+
+    /// Full zone transfer
+    Axfr,
+
+    /// All records
+    Any,
+
+    /// Certification authority authorization
+    Caa,
+
+    /// Any other type
+    Unknown(u16),
+}
+
+impl DnsType {
+    /// Convert a u16 to a `DnsType`
+    pub fn from_u16(input: u16) -> DnsType {
+        match input {
+            1 => DnsType::A,
+            2 => DnsType::Ns,
+            5 => DnsType::Cname,
+            6 => DnsType::Soa,
+            12 => DnsType::Ptr,
+            13 => DnsType::Hinfo,
+            15 => DnsType::Mx,
+            16 => DnsType::Txt,
+            28 => DnsType::Aaaa,
+            33 => DnsType::Srv,
+            35 => DnsType::Naptr,
+            41 => DnsType::Opt,
+            43 => DnsType::Ds,
+            46 => DnsType::Rrsig,
+            47 => DnsType::Nsec,
+            48 => DnsType::Dnskey,
+            50 => DnsType::Nsec3,
+            51 => DnsType::Nsec3Param,
+            52 => DnsType::Tlsa,
+            64 => DnsType::Svcb,
+            65 => DnsType::Https,
+            251 => DnsType::Ixfr,
+            252 => DnsType::Axfr,
+            255 => DnsType::Any,
+            257 => DnsType::Caa,
+            other => DnsType::Unknown(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// DNS classes
+pub enum DnsClass {
+    /// The Internet
+    In,
+
+    /// Chaos
+    Ch,
+
+    /// Hesiod
+    Hs,
+
+    /// No class, used by dynamic updates
+    None,
+
+    /// Any class
+    Any,
+
+    /// Any other class, like the UDP payload size of the EDNS0 pseudo record
+    Unknown(u16),
+}
+
+impl DnsClass {
+    /// Convert a u16 to a `DnsClass`
+    pub fn from_u16(input: u16) -> DnsClass {
+        match input {
+            1 => DnsClass::In,
+            3 => DnsClass::Ch,
+            4 => DnsClass::Hs,
+            254 => DnsClass::None,
+            255 => DnsClass::Any,
+            other => DnsClass::Unknown(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The decoded data of a resource record
+pub enum DnsRecordData {
+    /// An IPv4 address
+    A(Ipv4Addr),
+
+    /// An IPv6 address
+    Aaaa(Ipv6Addr),
+
+    /// The name of an authoritative name server
+    Ns(String),
+
+    /// The canonical name of an alias
+    Cname(String),
+
+    /// A domain name pointer, like for reverse lookups
+    Ptr(String),
+
+    /// A mail exchange with its preference
+    Mx(u16, String),
+
+    /// The start of a zone of authority
+    Soa(DnsSoa),
+
+    /// The character strings of a text record
+    Txt(Vec<Vec<u8>>),
+
+    /// A service location
+    Srv(DnsSrv),
+
+    /// A certification authority authorization
+    Caa(DnsCaa),
+
+    /// A service binding of a SVCB or HTTPS record
+    Svcb(DnsSvcb),
+
+    /// The EDNS0 information of the OPT pseudo record
+    Opt(DnsEdns),
+
+    /// A delegation signer
+    Ds(DnsDs),
+
+    /// A DNSSEC signature
+    Rrsig(DnsRrsig),
+
+    /// The next secure record, which proves the non-existence of names and types
+    Nsec(String, Vec<DnsType>),
+
+    /// A DNS public key
+    Dnskey(DnsDnskey),
+
+    /// The hashed next secure record
+    Nsec3(DnsNsec3),
+
+    /// The raw data of any other record type
+    Unknown(Vec<u8>),
+
+    /// The data does not match the record type
+    Invalid(Vec<u8>),
+}
+
+impl DnsRecordData {
+    /// Decode the record data by the record type. The class and time to live are only needed for
+    /// the EDNS0 pseudo record.
+    pub fn new(rtype: &DnsType, class: u16, ttl: u32, data: &[u8], message: &[u8]) -> DnsRecordData {
+        let result = match *rtype {
+            DnsType::A => complete!(data, map!(be_u32, |a| DnsRecordData::A(Ipv4Addr::from(a)))),
+            DnsType::Aaaa => {
+                complete!(data,
+                          map!(take!(16), |a: &[u8]| {
+                              let mut address = [0; 16];
+                              address.copy_from_slice(a);
+                              DnsRecordData::Aaaa(Ipv6Addr::from(address))
+                          }))
+            }
+            DnsType::Ns => map!(data, apply!(parse_name, message), DnsRecordData::Ns),
+            DnsType::Cname => map!(data, apply!(parse_name, message), DnsRecordData::Cname),
+            DnsType::Ptr => map!(data, apply!(parse_name, message), DnsRecordData::Ptr),
+            DnsType::Mx => {
+                do_parse!(data,
+                    preference: be_u16 >>
+                    exchange: apply!(parse_name, message) >>
+                    (DnsRecordData::Mx(preference, exchange))
+                )
+            }
+            DnsType::Soa => map!(data, apply!(DnsSoa::parse, message), DnsRecordData::Soa),
+            DnsType::Txt => {
+                map!(data,
+                     many1!(map!(length_bytes!(be_u8), Vec::from)),
+                     DnsRecordData::Txt)
+            }
+            DnsType::Srv => map!(data, apply!(DnsSrv::parse, message), DnsRecordData::Srv),
+            DnsType::Caa => map!(data, call!(DnsCaa::parse), DnsRecordData::Caa),
+            DnsType::Svcb | DnsType::Https => map!(data, apply!(DnsSvcb::parse, message), DnsRecordData::Svcb),
+            DnsType::Opt => map!(data, apply!(DnsEdns::parse, class, ttl), DnsRecordData::Opt),
+            DnsType::Ds => map!(data, call!(DnsDs::parse), DnsRecordData::Ds),
+            DnsType::Rrsig => map!(data, apply!(DnsRrsig::parse, message), DnsRecordData::Rrsig),
+            DnsType::Nsec => {
+                do_parse!(data,
+                    next: apply!(parse_name, message) >>
+                    types: call!(parse_type_bitmap) >>
+                    (DnsRecordData::Nsec(next, types))
+                )
+            }
+            DnsType::Dnskey => map!(data, call!(DnsDnskey::parse), DnsRecordData::Dnskey),
+            DnsType::Nsec3 => map!(data, call!(DnsNsec3::parse), DnsRecordData::Nsec3),
+            _ => return DnsRecordData::Unknown(data.to_vec()),
+        };
+
+        // The record data has to be consumed completely
+        match result {
+            IResult::Done(&[], record) => record,
+            _ => DnsRecordData::Invalid(data.to_vec()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The data of a start of authority record
+pub struct DnsSoa {
+    /// The primary name server of the zone
+    pub primary: String,
+
+    /// The mailbox of the person responsible for the zone
+    pub mailbox: String,
+
+    /// The version of the zone
+    pub serial: u32,
+
+    /// The interval before the zone should be refreshed
+    pub refresh: u32,
+
+    /// The interval before a failed refresh should be retried
+    pub retry: u32,
+
+    /// The upper limit before the zone is no longer authoritative
+    pub expire: u32,
+
+    /// The time to live of negative responses
+    pub minimum: u32,
+}
+
+impl DnsSoa {
+    /// Parse the data of a start of authority record
+    pub fn parse<'a>(input: &'a [u8], message: &[u8]) -> IResult<&'a [u8], DnsSoa> {
+        do_parse!(input,
+            primary: apply!(parse_name, message) >>
+            mailbox: apply!(parse_name, message) >>
+            serial: be_u32 >>
+            refresh: be_u32 >>
+            retry: be_u32 >>
+            expire: be_u32 >>
+            minimum: be_u32 >>
+
+            (DnsSoa {
+                primary: primary,
+                mailbox: mailbox,
+                serial: serial,
+                refresh: refresh,
+                retry: retry,
+                expire: expire,
+                minimum: minimum,
+            })
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The data of a service location record
+pub struct DnsSrv {
+    /// The priority of the target, lower values are preferred
+    pub priority: u16,
+
+    /// The relative weight of targets with the same priority
+    pub weight: u16,
+
+    /// The port of the service
+    pub port: u16,
+
+    /// The host which provides the service
+    pub target: String,
+}
+
+impl DnsSrv {
+    /// Parse the data of a service location record
+    pub fn parse<'a>(input: &'a [u8], message: &[u8]) -> IResult<&'a [u8], DnsSrv> {
+        do_parse!(input,
+            priority: be_u16 >>
+            weight: be_u16 >>
+            port: be_u16 >>
+            target: apply!(parse_name, message) >>
+
+            (DnsSrv {
+                priority: priority,
+                weight: weight,
+                port: port,
+                target: target,
+            })
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The data of a certification authority authorization record
+pub struct DnsCaa {
+    /// The flags, where the highest bit marks critical properties
+    pub flags: u8,
+
+    /// The property tag, like "issue"
+    pub tag: String,
+
+    /// The property value
+    pub value: Vec<u8>,
+}
+
+impl DnsCaa {
+    named!(#[doc = "Parse the data of a certification authority authorization record"],
+           pub parse<&[u8], DnsCaa>,
+        do_parse!(
+            flags: be_u8 >>
+            tag: map!(length_bytes!(be_u8), String::from_utf8_lossy) >>
+            value: rest >>
+
+            (DnsCaa {
+                flags: flags,
+                tag: tag.into_owned(),
+                value: value.to_vec(),
+            })
+        )
+    );
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The data of a SVCB or HTTPS service binding record
+pub struct DnsSvcb {
+    /// The priority of the binding, zero marks the alias mode
+    pub priority: u16,
+
+    /// The target name of the binding
+    pub target: String,
+
+    /// The service parameters
+    pub params: Vec<DnsSvcParam>,
+}
+
+impl DnsSvcb {
+    /// Parse the data of a service binding record
+    pub fn parse<'a>(input: &'a [u8], message: &[u8]) -> IResult<&'a [u8], DnsSvcb> {
+        do_parse!(input,
+            priority: be_u16 >>
+            target: apply!(parse_name, message) >>
+            params: many0!(complete!(DnsSvcParam::parse)) >>
+
+            (DnsSvcb {
+                priority: priority,
+                target: target,
+                params: params,
+            })
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A service parameter of a service binding record
+pub enum DnsSvcParam {
+    /// Keys which are mandatory for the binding
+    Mandatory(Vec<u16>),
+
+    /// The supported application protocols
+    Alpn(Vec<String>),
+
+    /// The default application protocol is not supported
+    NoDefaultAlpn,
+
+    /// The alternative port of the service
+    Port(u16),
+
+    /// IPv4 addresses of the target
+    Ipv4Hint(Vec<Ipv4Addr>),
+
+    /// The encrypted client hello configuration
+    Ech(Vec<u8>),
+
+    /// IPv6 addresses of the target
+    Ipv6Hint(Vec<Ipv6Addr>),
+
+    /// Any other parameter with its key
+    Unknown(u16, Vec<u8>),
+}
+
+impl DnsSvcParam {
+    named!(#[doc = "Parse a single service parameter"],
+           pub parse<&[u8], DnsSvcParam>,
+        do_parse!(
+            key: be_u16 >>
+            value: length_bytes!(be_u16) >>
+
+            (match DnsSvcParam::from_value(key, value) {
+                IResult::Done(&[], param) => param,
+                _ => DnsSvcParam::Unknown(key, value.to_vec()),
+            })
+        )
+    );
+
+    /// Decode the value of a known service parameter key
+    fn from_value(key: u16, value: &[u8]) -> IResult<&[u8], DnsSvcParam> {
+        match key {
+            0 => map!(value, many0!(complete!(be_u16)), DnsSvcParam::Mandatory),
+            1 => {
+                map!(value,
+                     many0!(complete!(map!(length_bytes!(be_u8), |a| String::from_utf8_lossy(a).into_owned()))),
+                     DnsSvcParam::Alpn)
+            }
+            2 => IResult::Done(value, DnsSvcParam::NoDefaultAlpn),
+            3 => map!(value, be_u16, DnsSvcParam::Port),
+            4 => map!(value, many0!(complete!(map!(be_u32, Ipv4Addr::from))), DnsSvcParam::Ipv4Hint),
+            5 => IResult::Done(&value[value.len()..], DnsSvcParam::Ech(value.to_vec())),
+            6 => {
+                map!(value,
+                     many0!(complete!(map!(take!(16), |a: &[u8]| {
+                         let mut address = [0; 16];
+                         address.copy_from_slice(a);
+                         Ipv6Addr::from(address)
+                     }))),
+                     DnsSvcParam::Ipv6Hint)
+            }
+            _ => IResult::Done(&value[value.len()..], DnsSvcParam::Unknown(key, value.to_vec())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The EDNS0 information of an OPT pseudo record
+pub struct DnsEdns {
+    /// The maximum UDP payload size of the sender
+    pub udp_payload_size: u16,
+
+    /// The upper eight bits of the response code
+    pub extended_response_code: u8,
+
+    /// The EDNS version
+    pub version: u8,
+
+    /// The sender is able to handle DNSSEC records
+    pub dnssec_ok: bool,
+
+    /// The options, like the client subnet or cookies
+    pub options: Vec<DnsOption>,
+}
+
+impl DnsEdns {
+    /// Parse the options of an OPT pseudo record, the other fields are stored within the class
+    /// and the time to live of the record
+    pub fn parse(input: &[u8], class: u16, ttl: u32) -> IResult<&[u8], DnsEdns> {
+        do_parse!(input,
+            options: many0!(complete!(DnsOption::parse)) >>
+
+            (DnsEdns {
+                udp_payload_size: class,
+                extended_response_code: (ttl >> 24) as u8,
+                version: (ttl >> 16) as u8,
+                dnssec_ok: ttl & 0x8000 != 0,
+                options: options,
+            })
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// An EDNS0 option
+pub struct DnsOption {
+    /// The option code, like 8 for the client subnet or 10 for cookies
+    pub code: u16,
+
+    /// The raw option data
+    pub data: Vec<u8>,
+}
+
+impl DnsOption {
+    named!(#[doc = "Parse a single EDNS0 option"],
+           pub parse<&[u8], DnsOption>,
+        do_parse!(
+            code: be_u16 >>
+            data: length_bytes!(be_u16) >>
+
+            (DnsOption {
+                code: code,
+                data: data.to_vec(),
+            })
+        )
+    );
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The data of a delegation signer record
+pub struct DnsDs {
+    /// The key tag of the referenced key
+    pub key_tag: u16,
+
+    /// The algorithm of the referenced key
+    pub algorithm: u8,
+
+    /// The algorithm of the digest
+    pub digest_type: u8,
+
+    /// The digest of the referenced key
+    pub digest: Vec<u8>,
+}
+
+impl DnsDs {
+    named!(#[doc = "Parse the data of a delegation signer record"],
+           pub parse<&[u8], DnsDs>,
+        do_parse!(
+            key_tag: be_u16 >>
+            algorithm: be_u8 >>
+            digest_type: be_u8 >>
+            digest: rest >>
+
+            (DnsDs {
+                key_tag: key_tag,
+                algorithm: algorithm,
+                digest_type: digest_type,
+                digest: digest.to_vec(),
+            })
+        )
+    );
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The data of a DNSSEC signature record
+pub struct DnsRrsig {
+    /// The type of the signed record set
+    pub type_covered: DnsType,
+
+    /// The algorithm of the signature
+    pub algorithm: u8,
+
+    /// The amount of labels of the original owner name
+    pub labels: u8,
+
+    /// The original time to live of the signed records
+    pub original_ttl: u32,
+
+    /// The end of the validity period as seconds since the epoch
+    pub expiration: u32,
+
+    /// The start of the validity period as seconds since the epoch
+    pub inception: u32,
+
+    /// The key tag of the signing key
+    pub key_tag: u16,
+
+    /// The owner name of the signing key
+    pub signer: String,
+
+    /// The signature
+    pub signature: Vec<u8>,
+}
+
+impl DnsRrsig {
+    /// Parse the data of a DNSSEC signature record
+    pub fn parse<'a>(input: &'a [u8], message: &[u8]) -> IResult<&'a [u8], DnsRrsig> {
+        do_parse!(input,
+            type_covered: be_u16 >>
+            algorithm: be_u8 >>
+            labels: be_u8 >>
+            original_ttl: be_u32 >>
+            expiration: be_u32 >>
+            inception: be_u32 >>
+            key_tag: be_u16 >>
+            signer: apply!(parse_name, message) >>
+            signature: rest >>
+
+            (DnsRrsig {
+                type_covered: DnsType::from_u16(type_covered),
+                algorithm: algorithm,
+                labels: labels,
+                original_ttl: original_ttl,
+                expiration: expiration,
+                inception: inception,
+                key_tag: key_tag,
+                signer: signer,
+                signature: signature.to_vec(),
+            })
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The data of a DNS public key record
+pub struct DnsDnskey {
+    /// The flags, like the zone key and secure entry point bits
+    pub flags: u16,
+
+    /// The protocol, which is always 3
+    pub protocol: u8,
+
+    /// The algorithm of the key
+    pub algorithm: u8,
+
+    /// The public key
+    pub public_key: Vec<u8>,
+}
+
+impl DnsDnskey {
+    named!(#[doc = "Parse the data of a DNS public key record"],
+           pub parse<&[u8], DnsDnskey>,
+        do_parse!(
+            flags: be_u16 >>
+            protocol: be_u8 >>
+            algorithm: be_u8 >>
+            public_key: rest >>
+
+            (DnsDnskey {
+                flags: flags,
+                protocol: protocol,
+                algorithm: algorithm,
+                public_key: public_key.to_vec(),
+            })
+        )
+    );
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The data of a hashed next secure record
+pub struct DnsNsec3 {
+    /// The hash algorithm
+    pub hash_algorithm: u8,
+
+    /// The flags, like the opt-out bit
+    pub flags: u8,
+
+    /// The additional hash iterations
+    pub iterations: u16,
+
+    /// The salt of the hash
+    pub salt: Vec<u8>,
+
+    /// The next hashed owner name
+    pub next_hashed_owner: Vec<u8>,
+
+    /// The types which exist at the original owner name
+    pub types: Vec<DnsType>,
+}
+
+impl DnsNsec3 {
+    named!(#[doc = "Parse the data of a hashed next secure record"],
+           pub parse<&[u8], DnsNsec3>,
+        do_parse!(
+            hash_algorithm: be_u8 >>
+            flags: be_u8 >>
+            iterations: be_u16 >>
+            salt: length_bytes!(be_u8) >>
+            next_hashed_owner: length_bytes!(be_u8) >>
+            types: call!(parse_type_bitmap) >>
+
+            (DnsNsec3 {
+                hash_algorithm: hash_algorithm,
+                flags: flags,
+                iterations: iterations,
+                salt: salt.to_vec(),
+                next_hashed_owner: next_hashed_owner.to_vec(),
+                types: types,
+            })
+        )
+    );
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A DNS query and its outcome
+pub struct DnsTransaction {
+    /// The endpoint which sent the query
+    pub client: Endpoint,
+
+    /// The endpoint which the query has been sent to
+    pub server: Endpoint,
+
+    /// The transaction identifier of the query
+    pub id: u16,
+
+    /// The first question of the query
+    pub question: Option<DnsQuestion>,
+
+    /// The outcome of the query
+    pub status: DnsTransactionStatus,
+
+    /// The response code, including the EDNS0 extension, if the query has been answered
+    pub response_code: Option<DnsResponseCode>,
+
+    /// The time between the query and the response, if the query has been answered
+    pub latency: Option<Duration>,
+
+    /// The statistics of all tracked messages, including this transaction
+    pub statistics: DnsStatistics,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The outcome of a DNS query
+pub enum DnsTransactionStatus {
+    /// The query has been answered by the first matching response
+    Answered,
+
+    /// Another response matched an already answered query
+    Duplicate,
+
+    /// No response arrived within the timeout
+    Unanswered,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The DNS message counters of all connections
+pub struct DnsStatistics {
+    /// The amount of queries, without retransmissions
+    pub queries: u64,
+
+    /// The amount of retransmitted queries, which match a pending query
+    pub retransmissions: u64,
+
+    /// The amount of responses
+    pub responses: u64,
+
+    /// The amount of queries which have been answered
+    pub answered: u64,
+
+    /// The amount of queries without a response within the timeout
+    pub unanswered: u64,
+
+    /// The amount of responses to already answered queries
+    pub duplicates: u64,
+
+    /// The amount of responses without a matching query
+    pub unmatched: u64,
+
+    /// The amount of answered queries with a NXDOMAIN response code
+    pub nxdomain: u64,
+
+    /// The amount of answered queries with a SERVFAIL response code
+    pub servfail: u64,
+}
+
+impl DnsStatistics {
+    /// The share of answered queries with a NXDOMAIN response code
+    pub fn nxdomain_rate(&self) -> f64 {
+        self.rate(self.nxdomain)
+    }
+
+    /// The share of answered queries with a SERVFAIL response code
+    pub fn servfail_rate(&self) -> f64 {
+        self.rate(self.servfail)
+    }
+
+    /// The share of queries which have been answered
+    pub fn answer_rate(&self) -> f64 {
+        match self.answered + self.unanswered {
+            0 => 0.0,
+            total => self.answered as f64 / total as f64,
+        }
+    }
+
+    /// Relate a counter to the amount of answered queries
+    fn rate(&self, count: u64) -> f64 {
+        match self.answered {
+            0 => 0.0,
+            answered => count as f64 / answered as f64,
+        }
+    }
+}
+
+/// The identifier of tracked DNS queries, which consists of the client and server endpoint,
+/// whether the query has been sent within a stream and the transaction identifier
+pub type DnsQueryKey = (Endpoint, Endpoint, bool, u16);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A DNS query which is tracked until it times out, to detect duplicate responses
+pub struct DnsPendingQuery {
+    /// The first question of the query
+    pub question: Option<DnsQuestion>,
+
+    /// The capture time of the query since the Unix epoch
+    pub time: Duration,
+
+    /// The response code and latency of the first matching response
+    pub answer: Option<(DnsResponseCode, Duration)>,
+}
+
+impl DnsPendingQuery {
+    /// Create the transaction record of the query
+    fn transaction(&self,
+                   key: &DnsQueryKey,
+                   status: DnsTransactionStatus,
+                   latency: Option<Duration>)
+                   -> DnsTransaction {
+        DnsTransaction {
+            client: key.0,
+            server: key.1,
+            id: key.3,
+            question: self.question.clone(),
+            status: status,
+            response_code: self.answer.as_ref().map(|a| a.0.clone()),
+            latency: latency,
+            statistics: DnsStatistics::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The DNS related state of a connection
+pub struct DnsState {
+    /// Incomplete messages at the end of the previous segment, identified by their sender
+    pub buffers: HashMap<Endpoint, Vec<u8>>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The DNS queries of all connections, which are tracked until they are answered or time out
+pub struct DnsTracker {
+    /// Queries which did not time out yet, in the order of their transmission per identifier
+    pub queries: HashMap<DnsQueryKey, VecDeque<DnsPendingQuery>>,
+
+    /// The identifiers of the tracked queries in the order of their transmission
+    pub order: VecDeque<DnsQueryKey>,
+
+    /// The message counters of all connections
+    pub statistics: DnsStatistics,
+}
+
+impl DnsTracker {
+    /// Add a message which is sent from `source` to `destination` at the given capture time,
+    /// within a stream like TCP or a datagram. Queries are matched with responses by their
+    /// endpoints, transport, transaction identifier and question. Returns the transactions which
+    /// are completed by the message, including the queries of any connection which timed out
+    /// meanwhile.
+    pub fn add_message(&mut self,
+                       source: &Endpoint,
+                       destination: &Endpoint,
+                       stream: bool,
+                       packet: &DnsPacket,
+                       now: Duration)
+                       -> Vec<DnsTransaction> {
+        let mut transactions = self.expire(now);
+        let question = packet.questions.first();
+
+        if !packet.header.response {
+            // A retransmission keeps the time of the original query
+            let key = (*source, *destination, stream, packet.header.id);
+            if self.queries
+                .get(&key)
+                .is_some_and(|queries| queries.iter().any(|q| q.answer.is_none() && q.question.as_ref() == question)) {
+                self.statistics.retransmissions += 1;
+            } else {
+                self.statistics.queries += 1;
+                if self.order.len() >= MAX_PENDING_QUERIES {
+                    transactions.extend(self.pop_oldest());
+                }
+                self.queries.entry(key).or_default().push_back(DnsPendingQuery {
+                    question: question.cloned(),
+                    time: now,
+                    answer: None,
+                });
+                self.order.push_back(key);
+            }
+        } else {
+            self.statistics.responses += 1;
+            let response_code = packet.response_code();
+            let key = (*destination, *source, stream, packet.header.id);
+            match self.queries
+                .get_mut(&key)
+                .and_then(|queries| queries.iter_mut().find(|q| q.question.as_ref() == question)) {
+                Some(query) => {
+                    let latency = now.saturating_sub(query.time);
+                    let status = match query.answer {
+                        Some(_) => {
+                            self.statistics.duplicates += 1;
+                            DnsTransactionStatus::Duplicate
+                        }
+                        None => {
+                            self.statistics.answered += 1;
+                            match response_code {
+                                DnsResponseCode::NonExistentDomain => self.statistics.nxdomain += 1,
+                                DnsResponseCode::ServerFailure => self.statistics.servfail += 1,
+                                _ => {}
+                            }
+                            query.answer = Some((response_code.clone(), latency));
+                            DnsTransactionStatus::Answered
+                        }
+                    };
+                    let mut transaction = query.transaction(&key, status, Some(latency));
+                    transaction.response_code = Some(response_code);
+                    transactions.push(transaction);
+                }
+                None => self.statistics.unmatched += 1,
+            }
+        }
+
+        for transaction in &mut transactions {
+            transaction.statistics = self.statistics.clone();
+        }
+        transactions
+    }
+
+    /// Remove the queries which timed out and return the unanswered ones
+    fn expire(&mut self, now: Duration) -> Vec<DnsTransaction> {
+        let mut transactions = vec![];
+        while let Some(key) = self.order.front() {
+            match self.queries.get(key).and_then(|queries| queries.front()) {
+                Some(query) if now.saturating_sub(query.time) < DNS_QUERY_TIMEOUT => break,
+                _ => transactions.extend(self.pop_oldest()),
+            }
+        }
+        transactions
+    }
+
+    /// Finish tracking the oldest query, which results in a transaction if it has not been answered
+    fn pop_oldest(&mut self) -> Option<DnsTransaction> {
+        let key = self.order.pop_front()?;
+        let query = match self.queries.get_mut(&key) {
+            Some(queries) => {
+                let query = queries.pop_front();
+                if queries.is_empty() {
+                    self.queries.remove(&key);
+                }
+                query?
+            }
+            None => return None,
+        };
+        match query.answer {
+            Some(_) => None,
+            None => {
+                self.statistics.unanswered += 1;
+                Some(query.transaction(&key, DnsTransactionStatus::Unanswered, None))
+            }
+        }
+    }
+}
+
+/// Parse the given amount of entries. Unlike `count!`, the result is not preallocated by the
+/// untrusted amount of the header, which may exceed the entries the input can hold by far.
+fn parse_count<'a, T, F>(input: &'a [u8], count: u16, parser: F) -> IResult<&'a [u8], Vec<T>>
+    where F: Fn(&'a [u8]) -> IResult<&'a [u8], T>
+{
+    let mut result = vec![];
+    let mut rest = input;
+    for _ in 0..count {
+        match parser(rest) {
+            IResult::Done(remaining, entry) => {
+                result.push(entry);
+                rest = remaining;
+            }
+            IResult::Incomplete(Needed::Size(n)) => {
+                return IResult::Incomplete(Needed::Size(input.len() - rest.len() + n));
+            }
+            IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+            IResult::Error(_) => return IResult::Error(error_position!(ErrorKind::Count, input)),
+        }
+    }
+    IResult::Done(rest, result)
+}
+
+/// The length of the domain name at the position of the cursor in bits
+fn name_length(cursor: &FieldCursor, message: &[u8]) -> usize {
+    let input = cursor.remaining();
+    match parse_name(input, message) {
+        IResult::Done(rest, _) => (input.len() - rest.len()) * 8,
+        _ => 0,
+    }
+}
+
+/// Add the class field, which is preceded by the given flag if the top bit is used as one
+fn dissect_class(cursor: &mut FieldCursor, name: &str, class: &DnsClass, flag: Option<(&str, bool)>) {
+    match flag {
+        Some((flag, value)) => cursor.field(flag, 1, value).named(name, 15, class),
+        None => cursor.named(name, 16, class),
+    };
+}
+
+/// Parse a domain name, which may be compressed by pointers to previous names. The input has to
+/// be a part of the message. Pointers have to refer to strictly decreasing positions, which
+/// prevents loops.
+pub fn parse_name<'a>(input: &'a [u8], message: &[u8]) -> IResult<&'a [u8], String> {
+    let start = (input.as_ptr() as usize).wrapping_sub(message.as_ptr() as usize);
+    if start > message.len() {
+        return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+    }
+
+    let mut name = String::new();
+    let mut position = start;
+    let mut limit = start;
+    let mut end = None;
+    let mut length = 0;
+    loop {
+        let label_length = match message.get(position) {
+            Some(&length) => length as usize,
+            None => return IResult::Incomplete(Needed::Unknown),
+        };
+        match label_length {
+            // The root label terminates the name
+            0 => {
+                end = end.or(Some(position + 1));
+                break;
+            }
+
+            // A regular label
+            1..=0x3f => {
+                let label = match message.get(position + 1..position + 1 + label_length) {
+                    Some(label) => label,
+                    None => return IResult::Incomplete(Needed::Unknown),
+                };
+                length += label_length + 1;
+                if length > MAX_NAME_LENGTH {
+                    return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                }
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                escape_label(&mut name, label);
+                position += label_length + 1;
+            }
+
+            // A compression pointer
+            0xc0..=0xff => {
+                let pointer = match message.get(position + 1) {
+                    Some(&low) => (label_length & 0x3f) << 8 | low as usize,
+                    None => return IResult::Incomplete(Needed::Unknown),
+                };
+                if pointer >= limit {
+                    return IResult::Error(error_position!(ErrorKind::Custom(0), input));
+                }
+                end = end.or(Some(position + 2));
+                limit = pointer;
+                position = pointer;
+            }
+
+            // Extended label types are not supported
+            _ => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        }
+    }
+
+    // The uncompressed part of the name has to be within the input
+    let consumed = end.unwrap_or(position) - start;
+    if consumed > input.len() {
+        return IResult::Incomplete(Needed::Unknown);
+    }
+    if name.is_empty() {
+        name.push('.');
+    }
+    IResult::Done(&input[consumed..], name)
+}
+
+/// Parse the type bitmap of NSEC and NSEC3 records
+fn parse_type_bitmap(input: &[u8]) -> IResult<&[u8], Vec<DnsType>> {
+    map!(input,
+         many0!(complete!(pair!(be_u8, length_bytes!(be_u8)))),
+         |windows: Vec<(u8, &[u8])>| {
+             windows.iter()
+                 .flat_map(|&(window, bitmap)| {
+                     bitmap.iter()
+                         .enumerate()
+                         .flat_map(move |(index, &bits)| {
+                             (0..8).filter(move |bit| bits & (0x80 >> bit) != 0)
+                                 .map(move |bit| (window as u16) << 8 | (index as u16) << 3 | bit)
+                         })
+                 })
+                 .map(DnsType::from_u16)
+                 .collect()
+         })
+}
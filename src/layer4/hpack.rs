@@ -0,0 +1,269 @@
+//! Header compression for HTTP/2 (HPACK) related processing
+use std::collections::VecDeque;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The initial maximum size of the dynamic table
+pub const HPACK_DEFAULT_TABLE_SIZE: usize = 4096;
+
+/// The size overhead of a dynamic table entry
+const ENTRY_OVERHEAD: usize = 32;
+
+/// The static table (RFC 7541, Appendix A)
+static STATIC_TABLE: [(&'static str, &'static str); 61] =
+    [(":authority", ""), (":method", "GET"), (":method", "POST"), (":path", "/"), (":path", "/index.html"),
+     (":scheme", "http"), (":scheme", "https"), (":status", "200"), (":status", "204"), (":status", "206"),
+     (":status", "304"), (":status", "400"), (":status", "404"), (":status", "500"), ("accept-charset", ""),
+     ("accept-encoding", "gzip, deflate"), ("accept-language", ""), ("accept-ranges", ""), ("accept", ""),
+     ("access-control-allow-origin", ""), ("age", ""), ("allow", ""), ("authorization", ""),
+     ("cache-control", ""), ("content-disposition", ""), ("content-encoding", ""), ("content-language", ""),
+     ("content-length", ""), ("content-location", ""), ("content-range", ""), ("content-type", ""),
+     ("cookie", ""), ("date", ""), ("etag", ""), ("expect", ""), ("expires", ""), ("from", ""), ("host", ""),
+     ("if-match", ""), ("if-modified-since", ""), ("if-none-match", ""), ("if-range", ""),
+     ("if-unmodified-since", ""), ("last-modified", ""), ("link", ""), ("location", ""), ("max-forwards", ""),
+     ("proxy-authenticate", ""), ("proxy-authorization", ""), ("range", ""), ("referer", ""), ("refresh", ""),
+     ("retry-after", ""), ("server", ""), ("set-cookie", ""), ("strict-transport-security", ""),
+     ("transfer-encoding", ""), ("user-agent", ""), ("vary", ""), ("via", ""), ("www-authenticate", "")];
+
+/// The symbols of the HPACK Huffman code (RFC 7541, Appendix B), ordered by their code length and code
+static HUFFMAN_SYMBOLS: [u16; 257] =
+    [48, 49, 50, 97, 99, 101, 105, 111, 115, 116, 32, 37, 45, 46, 47, 51, 52, 53, 54, 55, 56, 57, 61, 65, 95,
+     98, 100, 102, 103, 104, 108, 109, 110, 112, 114, 117, 58, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77,
+     78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 89, 106, 107, 113, 118, 119, 120, 121, 122, 38, 42, 44, 59, 88, 90,
+     33, 34, 40, 41, 63, 39, 43, 124, 35, 62, 0, 36, 64, 91, 93, 126, 94, 125, 60, 96, 123, 92, 195, 208, 128,
+     130, 131, 162, 184, 194, 224, 226, 153, 161, 167, 172, 176, 177, 179, 209, 216, 217, 227, 229, 230, 129,
+     132, 133, 134, 136, 146, 154, 156, 160, 163, 164, 169, 170, 173, 178, 181, 185, 186, 187, 189, 190, 196,
+     198, 228, 232, 233, 1, 135, 137, 138, 139, 140, 141, 143, 147, 149, 150, 151, 152, 155, 157, 158, 165, 166,
+     168, 174, 175, 180, 182, 183, 188, 191, 197, 231, 239, 9, 142, 144, 145, 148, 159, 171, 206, 215, 225, 236,
+     237, 199, 207, 234, 235, 192, 193, 200, 201, 202, 205, 210, 213, 218, 219, 238, 240, 242, 243, 255, 203,
+     204, 211, 212, 214, 221, 222, 223, 241, 244, 245, 246, 247, 248, 250, 251, 252, 253, 254, 2, 3, 4, 5, 6, 7,
+     8, 11, 12, 14, 15, 16, 17, 18, 19, 20, 21, 23, 24, 25, 26, 27, 28, 29, 30, 31, 127, 220, 249, 10, 13, 22,
+     256];
+
+/// The canonical HPACK Huffman code per code length: the first code, the amount of codes and the
+/// index of the first symbol within `HUFFMAN_SYMBOLS`
+static HUFFMAN_CODES: [(u32, u32, usize); 31] =
+    [(0x0, 0, 0), (0x0, 0, 0), (0x0, 0, 0), (0x0, 0, 0),
+     (0x0, 0, 0), (0x0, 10, 0), (0x14, 26, 10), (0x5c, 32, 36),
+     (0xf8, 6, 68), (0x0, 0, 0), (0x3f8, 5, 74), (0x7fa, 3, 79),
+     (0xffa, 2, 82), (0x1ff8, 6, 84), (0x3ffc, 2, 90), (0x7ffc, 3, 92),
+     (0x0, 0, 0), (0x0, 0, 0), (0x0, 0, 0), (0x7fff0, 3, 95),
+     (0xfffe6, 8, 98), (0x1fffdc, 13, 106), (0x3fffd2, 26, 119), (0x7fffd8, 29, 145),
+     (0xffffea, 12, 174), (0x1ffffec, 4, 186), (0x3ffffe0, 15, 190), (0x7ffffde, 19, 205),
+     (0xfffffe2, 29, 224), (0x0, 0, 0), (0x3ffffffc, 4, 253)];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A decoded HPACK header field
+pub struct HpackHeader {
+    /// The name of the header field, like ":path"
+    pub name: String,
+
+    /// The value of the header field
+    pub value: String,
+
+    /// The field must never be added to a dynamic table by intermediaries, since it contains
+    /// sensitive data
+    pub never_indexed: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The HPACK decoding context of a sender, which contains the dynamic table
+pub struct HpackDecoder {
+    /// The entries of the dynamic table, the newest entry first
+    pub dynamic_table: VecDeque<(String, String)>,
+
+    /// The current size of the dynamic table
+    pub size: usize,
+
+    /// The maximum size of the dynamic table
+    pub max_size: usize,
+
+    /// Indicates that a header block could not be decoded. The dynamic table may be out of sync
+    /// with the encoder afterwards, so no further header blocks are decoded.
+    pub failed: bool,
+}
+
+impl Default for HpackDecoder {
+    fn default() -> Self {
+        HpackDecoder {
+            dynamic_table: VecDeque::new(),
+            size: 0,
+            max_size: HPACK_DEFAULT_TABLE_SIZE,
+            failed: false,
+        }
+    }
+}
+
+impl HpackDecoder {
+    /// Decode a complete header block. Returns None if the block is invalid or refers to unknown
+    /// dynamic table entries, for example when the start of the connection has not been seen.
+    pub fn decode(&mut self, mut input: &[u8]) -> Option<Vec<HpackHeader>> {
+        if self.failed {
+            return None;
+        }
+        let mut headers = vec![];
+        while !input.is_empty() {
+            match self.decode_field(input) {
+                Some((left, header)) => {
+                    headers.extend(header);
+                    input = left;
+                }
+                None => {
+                    self.failed = true;
+                    return None;
+                }
+            }
+        }
+        Some(headers)
+    }
+
+    /// Decode a single header field representation or dynamic table size update
+    fn decode_field<'a>(&mut self, input: &'a [u8]) -> Option<(&'a [u8], Option<HpackHeader>)> {
+        let first = *input.first()?;
+        if first & 0x80 != 0 {
+            // Indexed header field
+            let (left, index) = decode_integer(input, 7)?;
+            let (name, value) = self.entry(index)?;
+            let header = HpackHeader {
+                name: name,
+                value: value,
+                never_indexed: false,
+            };
+            Some((left, Some(header)))
+        } else if first & 0x40 != 0 {
+            // Literal header field with incremental indexing
+            let (left, header) = self.decode_literal(input, 6, false)?;
+            self.insert(header.name.clone(), header.value.clone());
+            Some((left, Some(header)))
+        } else if first & 0x20 != 0 {
+            // Dynamic table size update
+            let (left, size) = decode_integer(input, 5)?;
+            self.max_size = size;
+            self.evict();
+            Some((left, None))
+        } else {
+            // Literal header field without indexing or never indexed
+            let (left, header) = self.decode_literal(input, 4, first & 0x10 != 0)?;
+            Some((left, Some(header)))
+        }
+    }
+
+    /// Decode a literal header field, whose name is either indexed or a literal as well
+    fn decode_literal<'a>(&self,
+                          input: &'a [u8],
+                          prefix: u8,
+                          never_indexed: bool)
+                          -> Option<(&'a [u8], HpackHeader)> {
+        let (input, index) = decode_integer(input, prefix)?;
+        let (input, name) = match index {
+            0 => {
+                let (input, name) = decode_string(input)?;
+                (input, String::from_utf8_lossy(&name).into_owned())
+            }
+            index => (input, self.entry(index)?.0),
+        };
+        let (input, value) = decode_string(input)?;
+        let header = HpackHeader {
+            name: name,
+            value: String::from_utf8_lossy(&value).into_owned(),
+            never_indexed: never_indexed,
+        };
+        Some((input, header))
+    }
+
+    /// Get an entry of the static or dynamic table by its index
+    fn entry(&self, index: usize) -> Option<(String, String)> {
+        match index {
+            0 => None,
+            1..=61 => STATIC_TABLE.get(index - 1).map(|&(name, value)| (name.to_owned(), value.to_owned())),
+            _ => self.dynamic_table.get(index - 62).cloned(),
+        }
+    }
+
+    /// Insert an entry into the dynamic table
+    fn insert(&mut self, name: String, value: String) {
+        self.size += name.len() + value.len() + ENTRY_OVERHEAD;
+        self.dynamic_table.push_front((name, value));
+        self.evict();
+    }
+
+    /// Evict the oldest entries until the table fits into its maximum size
+    fn evict(&mut self) {
+        while self.size > self.max_size {
+            match self.dynamic_table.pop_back() {
+                Some((name, value)) => self.size -= name.len() + value.len() + ENTRY_OVERHEAD,
+                None => break,
+            }
+        }
+    }
+}
+
+/// Decode an integer with a prefix of the given amount of bits
+pub fn decode_integer(input: &[u8], prefix: u8) -> Option<(&[u8], usize)> {
+    let (first, mut input) = input.split_first()?;
+    let mask = (1 << prefix) - 1;
+    let mut value = *first as usize & mask;
+    if value < mask {
+        return Some((input, value));
+    }
+
+    // The value continues within the following octets, limit it to 32 bits
+    let mut shift = 0;
+    loop {
+        let (byte, left) = input.split_first()?;
+        input = left;
+        value += ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Some((input, value));
+        }
+        if shift > 28 {
+            return None;
+        }
+    }
+}
+
+/// Decode a string literal, which may be Huffman encoded
+pub fn decode_string(input: &[u8]) -> Option<(&[u8], Vec<u8>)> {
+    let huffman = input.first()? & 0x80 != 0;
+    let (input, length) = decode_integer(input, 7)?;
+    if length > input.len() {
+        return None;
+    }
+    let (data, left) = input.split_at(length);
+    let data = if huffman {
+        huffman_decode(data)?
+    } else {
+        data.to_vec()
+    };
+    Some((left, data))
+}
+
+/// Decode Huffman encoded data
+pub fn huffman_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(input.len() * 8 / 5);
+    let (mut code, mut length) = (0u32, 0usize);
+    for byte in input {
+        for bit in (0..8).rev() {
+            code = code << 1 | (byte >> bit & 1) as u32;
+            length += 1;
+            let &(first, count, index) = HUFFMAN_CODES.get(length)?;
+            if code >= first && code - first < count {
+                // The end of string symbol must not be encoded
+                match HUFFMAN_SYMBOLS[index + (code - first) as usize] {
+                    256 => return None,
+                    symbol => result.push(symbol as u8),
+                }
+                code = 0;
+                length = 0;
+            }
+        }
+    }
+
+    // The padding consists of less than eight most significant bits of the end of string symbol
+    if length > 7 || code != (1 << length) - 1 {
+        return None;
+    }
+    Some(result)
+}
@@ -1,5 +1,32 @@
 //! Hypertext Transfer Protocol related packet processing
 use prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+#[cfg(feature = "compression")]
+use std::io::Read;
+#[cfg(feature = "compression")]
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+#[cfg(feature = "compression")]
+use brotli_decompressor::Decompressor;
+
+/// The well known port of plain text HTTP
+pub const HTTP_PORT: u16 = 80;
+
+/// The alternative port of plain text HTTP, which is commonly used by proxies
+pub const HTTP_ALTERNATIVE_PORT: u16 = 8080;
+
+/// The maximum length of a message body which will be buffered for the content decoding
+const MAX_BODY_LENGTH: usize = 0x100_0000;
+
+/// The maximum length of a line within the chunked transfer coding or a start line
+const MAX_LINE_LENGTH: usize = 0x1000;
+
+/// The maximum length of a message head, which consists of the start line and the header fields
+const MAX_HEAD_LENGTH: usize = 0x1_0000;
+
+/// The maximum amount of unanswered requests per connection
+const MAX_PENDING_REQUESTS: usize = 64;
 
 /// The HTTP parser
 pub struct HttpParser;
@@ -9,24 +36,41 @@ impl Parsable<PathIp> for HttpParser {
     fn parse<'a>(&mut self,
                  input: &'a [u8],
                  result: Option<&ParserResultVec>,
-                 _: Option<&mut PathIp>)
+                 path: Option<&mut PathIp>)
                  -> IResult<&'a [u8], ParserResult> {
+        // Retrieve the connection related information
+        let sender = connection_endpoints(result).map(|e| e.0);
+        let mut data = connection_data(path, result);
+
+        // The connection does not transfer HTTP/1 messages after a protocol switch
+        let upgraded = data.as_ref().is_some_and(|d| d.http.upgrade.is_some());
+
+        // Segments without a start line belong to HTTP if the connection already transferred a message
+        let known = data.as_ref().is_some_and(|d| d.http.client.is_some() || !d.http.bodies.is_empty());
+
         do_parse!(input,
 
             // Check the transport protocol from the parent parser (TCP or TLS)
             result: alt!(
                 // TCP based plain text transfer
-                cond_reduce!(match result {
+                cond_reduce!(!upgraded && match result {
                     Some(vector) => match vector.last() {
-                        Some(ref any) => if let Some(_) = any.downcast_ref::<TcpPacket>() {
-                            true
-                        } else {
-                            false
+                        Some(ref any) => match (any.downcast_ref::<TcpPacket>(), any.downcast_ref::<HttpPacket>()) {
+                            (Some(tcp), _) => tcp.dispatches_to(&ApplicationProtocol::Http) || known,
+
+                            // Pipelined messages within a single segment
+                            (_, Some(&HttpPacket::Any)) => false,
+                            (_, Some(_)) => !input.is_empty(),
+
+                            _ => false,
                         },
                         _ => false, // Previous result found, but not correct parent
                     },
                     None => true, // Parse also if no result is given, for testability
-                }, HttpPacket::parse_plain) |
+                }, apply!(HttpPacket::parse_plain,
+                          data.as_mut().map(|d| &mut d.http),
+                          sender.as_ref(),
+                          is_closing(result))) |
 
                 // TLS based encrypted traffic
                 apply!(HttpPacket::parse_encrypted, result)
@@ -44,6 +88,7 @@ impl fmt::Display for HttpParser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of a Hypertext Transfer Protocol packet
 pub enum HttpPacket {
     /// Specifies a HTTP request
@@ -52,15 +97,220 @@ pub enum HttpPacket {
     /// Specifies a HTTP response
     Response(HttpResponse),
 
+    /// The continuation of a message body which started within a previous packet
+    Body(HttpBody),
+
     /// Could be a plain or encrypted HTTP packet, but there is no further data parsable
     Any,
 }
 
+impl Summary for HttpPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "HTTP")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        match *self {
+            HttpPacket::Request(ref request) => {
+                write!(w, " {} {}", request.request_method.token(), request.path)?;
+                if verbosity == Verbosity::Verbose {
+                    write!(w,
+                           " HTTP/{}.{} headers={} body={}",
+                           request.version.major,
+                           request.version.minor,
+                           request.headers.len(),
+                           request.body.data.len())?;
+                }
+            }
+            HttpPacket::Response(ref response) => {
+                write!(w, " {} {}", response.code, response.reason)?;
+                if verbosity == Verbosity::Verbose {
+                    write!(w,
+                           " HTTP/{}.{} headers={} body={}",
+                           response.version.major,
+                           response.version.minor,
+                           response.headers.len(),
+                           response.body.data.len())?;
+                }
+            }
+            HttpPacket::Body(ref body) => {
+                write!(w, " body len={}", body.data.len())?;
+                if body.complete {
+                    write!(w, " complete")?;
+                }
+            }
+            HttpPacket::Any => {}
+        }
+        Ok(())
+    }
+}
+
+summary_display!(HttpPacket);
+
+impl Dissect for HttpPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        let body = match *self {
+            HttpPacket::Request(ref request) => {
+                let version = format!("HTTP/{}.{}", request.version.major, request.version.minor);
+                dissect_head(&mut cursor,
+                             &[("http.request_method", request.request_method.token().into()),
+                               ("http.path", request.path.as_str().into()),
+                               ("http.version", version.into())],
+                             &request.headers);
+                Some(&request.body)
+            }
+            HttpPacket::Response(ref response) => {
+                let version = format!("HTTP/{}.{}", response.version.major, response.version.minor);
+                dissect_head(&mut cursor,
+                             &[("http.version", version.into()),
+                               ("http.code", response.code.into()),
+                               ("http.reason", response.reason.as_str().into())],
+                             &response.headers);
+                Some(&response.body)
+            }
+            HttpPacket::Body(ref body) => Some(body),
+            HttpPacket::Any => None,
+        };
+        if let Some(body) = body {
+            // The chunked transfer coding is removed from the data, so take the whole rest
+            let length = match body.framing {
+                HttpBodyFraming::Chunked => cursor.remaining().len(),
+                _ => body.data.len(),
+            };
+            if length > 0 {
+                cursor.bytes("http.body", length);
+            }
+        }
+        cursor.finish("http", self.summary(Verbosity::Normal))
+    }
+}
+
+/// The length of the first line of the input without and with its line ending
+fn line_lengths(input: &[u8]) -> (usize, usize) {
+    match input.iter().position(|&c| c == b'\n') {
+        Some(end) if end > 0 && input[end - 1] == b'\r' => (end - 1, end + 1),
+        Some(end) => (end, end + 1),
+        None => (input.len(), input.len()),
+    }
+}
+
+/// Add the fields of a message head. The start line is split into the given fields at spaces,
+/// where the last field takes the rest of the line.
+fn dissect_head(cursor: &mut FieldCursor, start_line: &[(&str, FieldValue)], headers: &[HttpHeader]) {
+    // Empty lines in front of the start line are tolerated by the parser
+    let leading = cursor.remaining().iter().take_while(|&&c| c == b'\r' || c == b'\n').count();
+    cursor.skip(leading * 8);
+
+    let start = cursor.position();
+    let (length, line_length) = line_lengths(cursor.remaining());
+    let line = &cursor.remaining()[..length];
+    let mut index = 0;
+    for (i, &(name, ref value)) in start_line.iter().enumerate() {
+        index += line[index..].iter().take_while(|&&c| c == b' ').count();
+        let end = match line[index..].iter().position(|&c| c == b' ') {
+            Some(position) if i + 1 < start_line.len() => index + position,
+            _ => length,
+        };
+        cursor.skip_to(start + index * 8).field(name, (end - index) * 8, value.clone());
+        index = end;
+    }
+    cursor.skip_to(start + line_length * 8);
+
+    for header in headers {
+        let start = cursor.position();
+        let (length, line_length) = line_lengths(cursor.remaining());
+        let line = &cursor.remaining()[..length];
+        if line.is_empty() {
+            break;
+        }
+        let value_start = line.iter().position(|&c| c == b':').map_or(length, |colon| {
+            colon + 1 + line[colon + 1..].iter().take_while(|&&c| c == b' ' || c == b'\t').count()
+        });
+        cursor.group("http.headers", format!("{}: {}", header.key, header.value), |field| {
+            field.field("http.headers.key", header.key.len() * 8, header.key.as_str())
+                .skip_to(start + value_start * 8)
+                .field("http.headers.value", (length - value_start) * 8, header.value.as_str());
+        });
+        cursor.skip_to(start + line_length * 8);
+    }
+
+    // The empty line which terminates the head
+    let (length, line_length) = line_lengths(cursor.remaining());
+    if length == 0 {
+        cursor.skip(line_length * 8);
+    }
+}
+
 impl HttpPacket {
-    named!(parse_plain<&[u8], ParserResult>,
-           alt!(call!(HttpRequest::parse) | call!(HttpResponse::parse))
-    );
+    /// Parse a plain text HTTP message, or the continuation of a message body if the sender has
+    /// a pending one
+    fn parse_plain<'a>(input: &'a [u8],
+                       state: Option<&mut HttpState>,
+                       sender: Option<&Endpoint>,
+                       closing: bool)
+                       -> IResult<&'a [u8], ParserResult> {
+        // The state is only usable if the sender is known
+        let mut state = match sender {
+            Some(_) => state,
+            None => None,
+        };
+
+        // Continue a message body of a previous packet
+        if let (Some(state), Some(sender)) = (state.as_mut(), sender) {
+            if let Some(mut decoder) = state.bodies.remove(sender) {
+                let (left, mut body) = decoder.decode(input, closing);
+                if decoder.is_pending() {
+                    state.bodies.insert(*sender, decoder);
+                }
+                state.add_body(sender, &mut body, (input.len() - left.len()) as u64);
+                return IResult::Done(left, Box::new(HttpPacket::Body(body)));
+            }
+        }
+
+        // Parse the message head
+        let (body_input, mut packet) = match alt!(input,
+                                                  map!(HttpRequest::parse, HttpPacket::Request) |
+                                                  map!(HttpResponse::parse, HttpPacket::Response)) {
+            IResult::Done(left, packet) => (left, packet),
+            IResult::Error(error) => return IResult::Error(error),
+            IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+        };
 
+        // Parse the message body, the framing of a response depends on its request
+        let mut decoder = match packet {
+            HttpPacket::Request(ref request) => HttpBodyDecoder::new(request.body_framing(), &request.headers),
+            HttpPacket::Response(ref response) => {
+                let method = state.as_ref().and_then(|s| s.requests.front()).map(|r| &r.transaction.method);
+                HttpBodyDecoder::new(response.body_framing(method), &response.headers)
+            }
+            _ => return IResult::Done(body_input, Box::new(packet)),
+        };
+        let (left, body) = decoder.decode(body_input, closing);
+        let size = (input.len() - left.len()) as u64;
+
+        match packet {
+            HttpPacket::Request(ref mut request) => request.body = body,
+            HttpPacket::Response(ref mut response) => response.body = body,
+            _ => {}
+        }
+
+        // Pair the requests and responses of the connection
+        if let (Some(state), Some(sender)) = (state, sender) {
+            if decoder.is_pending() {
+                state.bodies.insert(*sender, decoder);
+            }
+            match packet {
+                HttpPacket::Request(ref request) => state.add_request(sender, request, size),
+                HttpPacket::Response(ref mut response) => state.add_response(response, size),
+                _ => {}
+            }
+        }
+
+        IResult::Done(left, Box::new(packet))
+    }
+
+    #[cfg(feature = "tls")]
     fn parse_encrypted<'a>(input: &'a [u8], result: Option<&ParserResultVec>) -> IResult<&'a [u8], ParserResult> {
         expr_opt!(input,
             match result {
@@ -71,7 +321,7 @@ impl HttpPacket {
                                any_tcp.downcast_ref::<TcpPacket>()) {
 
                             /// TLS and TCP combination matches
-                            (Some(_), Some(tcp)) if (tcp.header.source_port == 443 || tcp.header.dest_port == 443) => {
+                            (Some(_), Some(tcp)) if tcp.dispatches_to(&ApplicationProtocol::Http) => {
                                 Some(Box::new(HttpPacket::Any))
                             }
 
@@ -85,9 +335,16 @@ impl HttpPacket {
             }
         )
     }
+
+    /// Without TLS support there is no encrypted traffic
+    #[cfg(not(feature = "tls"))]
+    fn parse_encrypted<'a>(input: &'a [u8], _: Option<&ParserResultVec>) -> IResult<&'a [u8], ParserResult> {
+        IResult::Error(error_position!(ErrorKind::Custom(0), input))
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// A HTTP request representation
 pub struct HttpRequest {
     /// The HTTP request method
@@ -101,40 +358,77 @@ pub struct HttpRequest {
 
     /// Available HTTP headers
     pub headers: Vec<HttpHeader>,
+
+    /// Deviations from the message syntax which have been tolerated while parsing
+    pub anomalies: Vec<HttpAnomaly>,
+
+    /// The message body
+    pub body: HttpBody,
 }
 
 impl HttpRequest {
-    named!(parse<&[u8], ParserResult>,
-        ws!(do_parse!(
-            // HTTP Request parsing
-            method: alt!(
-                map!(tag!("GET"), |_| HttpRequestMethod::Get) |
-                map!(tag!("POST"), |_| HttpRequestMethod::Post) |
-                map!(tag!("HEAD"), |_| HttpRequestMethod::Head) |
-                map!(tag!("PUT"), |_| HttpRequestMethod::Put) |
-                map!(tag!("DELETE"), |_| HttpRequestMethod::Delete) |
-                map!(tag!("TRACE"), |_| HttpRequestMethod::Trace) |
-                map!(tag!("OPTIONS"), |_| HttpRequestMethod::Options) |
-                map!(tag!("CONNECT"), |_| HttpRequestMethod::Connect) |
-                map!(tag!("PATCH"), |_| HttpRequestMethod::Patch)
-            ) >>
+    /// Parse the request line and the header fields
+    fn parse(input: &[u8]) -> IResult<&[u8], HttpRequest> {
+        let mut anomalies = vec![];
+        let (rest, line) = match read_start_line(input, is_request_line_prefix, &mut anomalies) {
+            IResult::Done(rest, line) => (rest, line),
+            IResult::Error(error) => return IResult::Error(error),
+            IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+        };
 
-            path: map_res!(take_until!(" "), str::from_utf8) >>
-            tag!("HTTP/") >>
-            version: call!(HttpVersion::parse) >>
-            headers: call!(HttpHeader::parse) >>
-
-            (Box::new(HttpPacket::Request(HttpRequest {
-                request_method: method,
-                path: path.to_owned(),
-                version: version,
-                headers: headers,
-            })))
-        ))
-    );
+        // The request line consists of the method, the request target and the version
+        let words = split_start_line(line, 3, &mut anomalies);
+        let request = match (words.first(), words.get(1), words.get(2).and_then(|w| HttpVersion::from_word(w))) {
+            (Some(method), Some(path), Some(version)) if words.len() == 3 && method.iter().all(|&c| is_tchar(c)) => {
+                HttpRequest {
+                    request_method: HttpRequestMethod::from_token(&String::from_utf8_lossy(method)),
+                    path: decode_text(path, &mut anomalies),
+                    version: version,
+                    headers: vec![],
+                    anomalies: vec![],
+                    body: HttpBody::default(),
+                }
+            }
+            _ => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        };
+
+        let (rest, headers) = match HttpHeader::parse(rest, &mut anomalies) {
+            IResult::Done(rest, headers) => (rest, headers),
+            IResult::Error(_) => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+            IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+        };
+        HttpAnomaly::check_framing(&headers, &request.version, true, &mut anomalies);
+
+        IResult::Done(rest,
+                      HttpRequest {
+                          headers: headers,
+                          anomalies: anomalies,
+                          ..request
+                      })
+    }
+
+    /// Get the value of the first header field with the given (case-insensitive) key
+    pub fn header(&self, key: &str) -> Option<&str> {
+        HttpHeader::find(&self.headers, key)
+    }
+
+    /// Get the comma separated values of all header fields with the given (case-insensitive) key
+    pub fn header_list(&self, key: &str) -> Vec<&str> {
+        HttpHeader::list(&self.headers, key)
+    }
+
+    /// Determine how the length of the request body is specified
+    pub fn body_framing(&self) -> HttpBodyFraming {
+        match HttpBodyFraming::from_headers(&self.headers) {
+            // A request without a valid length has no body
+            Some(HttpBodyFraming::CloseDelimited) | None => HttpBodyFraming::None,
+            Some(framing) => framing,
+        }
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// List of supported HTTP request methods
 pub enum HttpRequestMethod {
     /// The GET method requests a representation of the specified resource.
@@ -167,9 +461,47 @@ pub enum HttpRequestMethod {
 
     /// The PATCH method applies partial modifications to a resource.
     Patch,
+
+    /// Any other method, like the WebDAV methods PROPFIND or MKCOL.
+    Extension(String),
+}
+
+impl HttpRequestMethod {
+    /// Convert a method token to a `HttpRequestMethod`, the method is case-sensitive
+    pub fn from_token(token: &str) -> HttpRequestMethod {
+        match token {
+            "GET" => HttpRequestMethod::Get,
+            "POST" => HttpRequestMethod::Post,
+            "HEAD" => HttpRequestMethod::Head,
+            "PUT" => HttpRequestMethod::Put,
+            "DELETE" => HttpRequestMethod::Delete,
+            "TRACE" => HttpRequestMethod::Trace,
+            "OPTIONS" => HttpRequestMethod::Options,
+            "CONNECT" => HttpRequestMethod::Connect,
+            "PATCH" => HttpRequestMethod::Patch,
+            other => HttpRequestMethod::Extension(other.to_owned()),
+        }
+    }
+
+    /// Get the method token of a `HttpRequestMethod`
+    pub fn token(&self) -> &str {
+        match *self {
+            HttpRequestMethod::Get => "GET",
+            HttpRequestMethod::Post => "POST",
+            HttpRequestMethod::Head => "HEAD",
+            HttpRequestMethod::Put => "PUT",
+            HttpRequestMethod::Delete => "DELETE",
+            HttpRequestMethod::Trace => "TRACE",
+            HttpRequestMethod::Options => "OPTIONS",
+            HttpRequestMethod::Connect => "CONNECT",
+            HttpRequestMethod::Patch => "PATCH",
+            HttpRequestMethod::Extension(ref token) => token,
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// HTTP protocol version
 pub struct HttpVersion {
     /// Major part of the HTTP version
@@ -191,9 +523,21 @@ impl HttpVersion {
             })
         )
     );
+
+    /// Parse a complete protocol version word, like "HTTP/1.1"
+    fn from_word(word: &[u8]) -> Option<HttpVersion> {
+        if !word.starts_with(b"HTTP/") {
+            return None;
+        }
+        match HttpVersion::parse(&word[5..]) {
+            IResult::Done(&[], version) => Some(version),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// A generic HTTP header field
 pub struct HttpHeader {
     /// A Key, like "Host"
@@ -204,26 +548,176 @@ pub struct HttpHeader {
 }
 
 impl HttpHeader {
-    fn from_tuple(tuple: (&str, &str)) -> Self {
-        HttpHeader {
-            key: tuple.0.to_owned(),
-            value: tuple.1.to_owned(),
+    /// Parse the field lines of a message head including the terminating empty line. Obsolete
+    /// line folding is replaced by a single space.
+    fn parse<'a>(input: &'a [u8], anomalies: &mut Vec<HttpAnomaly>) -> IResult<&'a [u8], Vec<HttpHeader>> {
+        let mut headers: Vec<HttpHeader> = vec![];
+        let mut rest = input;
+        loop {
+            let (left, line) = match read_head_line(rest, anomalies) {
+                Some(result) => result,
+                None if input.len() < MAX_HEAD_LENGTH => return IResult::Incomplete(Needed::Unknown),
+                None => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+            };
+            rest = left;
+
+            match line.first() {
+                None => return IResult::Done(rest, headers),
+                Some(&b' ') | Some(&b'\t') => {
+                    match headers.last_mut() {
+                        Some(header) => {
+                            HttpAnomaly::report(anomalies, HttpAnomaly::ObsoleteLineFolding);
+                            let value = decode_text(trim_whitespace(line), anomalies);
+                            if !header.value.is_empty() && !value.is_empty() {
+                                header.value.push(' ');
+                            }
+                            header.value.push_str(&value);
+                        }
+                        // The line is ignored, since it could be interpreted as part of the start line
+                        None => HttpAnomaly::report(anomalies, HttpAnomaly::WhitespaceAfterStartLine),
+                    }
+                }
+                Some(_) => headers.extend(HttpHeader::from_line(line, anomalies)),
+            }
         }
     }
 
-    named!(parse<&[u8], Vec<HttpHeader> >,
-        do_parse!(
-            result: many_till!(map!(separated_pair!(
-                map_res!(ws!(take_until!(":")), str::from_utf8),
-                tag!(": "),
-                map_res!(take_until!("\r"), str::from_utf8)), HttpHeader::from_tuple),
-                tag!("\r\n\r\n")) >>
-            (result.0)
-        )
-    );
+    /// Parse a single field line without its line ending. Returns None if the line contains no
+    /// colon.
+    pub fn from_line(line: &[u8], anomalies: &mut Vec<HttpAnomaly>) -> Option<HttpHeader> {
+        let index = match line.iter().position(|&c| c == b':') {
+            Some(index) => index,
+            None => {
+                HttpAnomaly::report(anomalies, HttpAnomaly::MissingColon);
+                return None;
+            }
+        };
+
+        // Whitespace between the field name and the colon is a common request smuggling vector
+        let name = trim_whitespace(&line[..index]);
+        if name.len() != index || line.first().is_some_and(|&c| c == b' ' || c == b'\t') {
+            HttpAnomaly::report(anomalies, HttpAnomaly::WhitespaceBeforeColon);
+        }
+        if name.is_empty() || !name.iter().all(|&c| is_tchar(c)) {
+            HttpAnomaly::report(anomalies, HttpAnomaly::InvalidFieldName);
+        }
+
+        // The optional whitespace around the value is not part of it
+        let value = trim_whitespace(&line[index + 1..]);
+        if value.iter().any(|&c| (c < 0x20 && c != b'\t') || c == 0x7f) {
+            HttpAnomaly::report(anomalies, HttpAnomaly::InvalidFieldValue);
+        }
+
+        Some(HttpHeader {
+            key: decode_text(name, anomalies),
+            value: decode_text(value, anomalies),
+        })
+    }
+
+    /// Get the value of the first header field with the given (case-insensitive) key
+    pub fn find<'a>(headers: &'a [HttpHeader], key: &str) -> Option<&'a str> {
+        headers.iter().find(|h| h.key.eq_ignore_ascii_case(key)).map(|h| h.value.as_str())
+    }
+
+    /// Get the values of all header fields with the given (case-insensitive) key
+    pub fn find_all<'a>(headers: &'a [HttpHeader], key: &str) -> Vec<&'a str> {
+        headers.iter().filter(|h| h.key.eq_ignore_ascii_case(key)).map(|h| h.value.as_str()).collect()
+    }
+
+    /// Get the comma separated values of all header fields with the given (case-insensitive) key
+    pub fn list<'a>(headers: &'a [HttpHeader], key: &str) -> Vec<&'a str> {
+        headers.iter()
+            .filter(|h| h.key.eq_ignore_ascii_case(key))
+            .flat_map(|h| h.value.split(','))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A deviation from the HTTP/1 message syntax (RFC 9112). Such messages are parsed anyway, but
+/// different implementations may interpret them differently, which enables request smuggling.
+pub enum HttpAnomaly {
+    /// Empty lines precede the start line
+    LeadingEmptyLine,
+
+    /// The words of the start line are not separated by a single space
+    WhitespaceInStartLine,
+
+    /// A line is terminated by a line feed without a carriage return
+    BareLineFeed,
+
+    /// A field line starts with whitespace, but there is no preceding field line to continue
+    WhitespaceAfterStartLine,
+
+    /// A field value is continued on the next line (obsolete line folding)
+    ObsoleteLineFolding,
+
+    /// A field line does not contain a colon
+    MissingColon,
+
+    /// A field name is followed or preceded by whitespace
+    WhitespaceBeforeColon,
+
+    /// A field name is empty or contains characters which are not allowed within a token
+    InvalidFieldName,
+
+    /// A field value contains control characters
+    InvalidFieldValue,
+
+    /// The text is not valid UTF-8 and has been decoded as ISO-8859-1
+    NonUtf8Text,
+
+    /// The message contains a `Content-Length` and a `Transfer-Encoding` header
+    ContentLengthWithTransferEncoding,
+
+    /// The message contains multiple `Content-Length` values
+    MultipleContentLength,
+
+    /// A `Content-Length` value is not a decimal number
+    InvalidContentLength,
+
+    /// The final transfer coding of a request is not chunked
+    TransferEncodingNotChunked,
+
+    /// A HTTP/1.0 message contains a `Transfer-Encoding` header
+    TransferEncodingInHttp10,
+}
+
+impl HttpAnomaly {
+    /// Add an anomaly to a list, if it is not contained already
+    pub fn report(anomalies: &mut Vec<HttpAnomaly>, anomaly: HttpAnomaly) {
+        if !anomalies.contains(&anomaly) {
+            anomalies.push(anomaly);
+        }
+    }
+
+    /// Check the header fields which determine the length of the message body
+    fn check_framing(headers: &[HttpHeader], version: &HttpVersion, request: bool, anomalies: &mut Vec<HttpAnomaly>) {
+        let codings = HttpHeader::list(headers, "Transfer-Encoding");
+        let lengths = HttpHeader::list(headers, "Content-Length");
+        if !codings.is_empty() && !lengths.is_empty() {
+            HttpAnomaly::report(anomalies, HttpAnomaly::ContentLengthWithTransferEncoding);
+        }
+        if lengths.len() > 1 {
+            HttpAnomaly::report(anomalies, HttpAnomaly::MultipleContentLength);
+        }
+        if lengths.iter().any(|l| !l.bytes().all(|c| c.is_ascii_digit())) {
+            HttpAnomaly::report(anomalies, HttpAnomaly::InvalidContentLength);
+        }
+        if request && codings.last().is_some_and(|c| !c.eq_ignore_ascii_case("chunked")) {
+            HttpAnomaly::report(anomalies, HttpAnomaly::TransferEncodingNotChunked);
+        }
+        if !codings.is_empty() && version.major == 1 && version.minor == 0 {
+            HttpAnomaly::report(anomalies, HttpAnomaly::TransferEncodingInHttp10);
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// A HTTP response representation
 pub struct HttpResponse {
     /// The HTTP version
@@ -237,24 +731,676 @@ pub struct HttpResponse {
 
     /// Available HTTP headers
     pub headers: Vec<HttpHeader>,
+
+    /// Deviations from the message syntax which have been tolerated while parsing
+    pub anomalies: Vec<HttpAnomaly>,
+
+    /// The message body
+    pub body: HttpBody,
 }
 
 impl HttpResponse {
-    named!(parse<&[u8], ParserResult>,
-        ws!(do_parse!(
-            // HTTP response parsing
-            tag!("HTTP/") >>
-            version: call!(HttpVersion::parse) >>
-            code: map_res!(map_res!(take_until!(" "), str::from_utf8), FromStr::from_str) >>
-            reason: map_res!(take_until!("\r"), str::from_utf8) >>
-            headers: call!(HttpHeader::parse) >>
-
-            (Box::new(HttpPacket::Response(HttpResponse {
-                version: version,
-                code: code,
-                reason: reason.to_owned(),
-                headers: headers,
-            })))
-        ))
-    );
+    /// Parse the status line and the header fields
+    fn parse(input: &[u8]) -> IResult<&[u8], HttpResponse> {
+        let mut anomalies = vec![];
+        let (rest, line) = match read_start_line(input, is_status_line_prefix, &mut anomalies) {
+            IResult::Done(rest, line) => (rest, line),
+            IResult::Error(error) => return IResult::Error(error),
+            IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+        };
+
+        // The status line consists of the version, the status code and an optional reason phrase
+        let words = split_start_line(line, 3, &mut anomalies);
+        let response = match (words.first().and_then(|w| HttpVersion::from_word(w)), words.get(1)) {
+            (Some(version), Some(code)) if code.len() == 3 && code.iter().all(|c| c.is_ascii_digit()) => {
+                HttpResponse {
+                    version: version,
+                    code: code.iter().fold(0, |acc, &c| acc * 10 + (c - b'0') as u16),
+                    reason: words.get(2).map(|r| decode_text(r, &mut anomalies)).unwrap_or_default(),
+                    headers: vec![],
+                    anomalies: vec![],
+                    body: HttpBody::default(),
+                }
+            }
+            _ => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        };
+
+        let (rest, headers) = match HttpHeader::parse(rest, &mut anomalies) {
+            IResult::Done(rest, headers) => (rest, headers),
+            IResult::Error(_) => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+            IResult::Incomplete(needed) => return IResult::Incomplete(needed),
+        };
+        HttpAnomaly::check_framing(&headers, &response.version, false, &mut anomalies);
+
+        IResult::Done(rest,
+                      HttpResponse {
+                          headers: headers,
+                          anomalies: anomalies,
+                          ..response
+                      })
+    }
+
+    /// Get the value of the first header field with the given (case-insensitive) key
+    pub fn header(&self, key: &str) -> Option<&str> {
+        HttpHeader::find(&self.headers, key)
+    }
+
+    /// Get the comma separated values of all header fields with the given (case-insensitive) key
+    pub fn header_list(&self, key: &str) -> Vec<&str> {
+        HttpHeader::list(&self.headers, key)
+    }
+
+    /// Determine how the length of the response body is specified. The method of the related
+    /// request is needed to recognize bodiless responses to `HEAD` and `CONNECT` requests.
+    pub fn body_framing(&self, request_method: Option<&HttpRequestMethod>) -> HttpBodyFraming {
+        match (self.code, request_method) {
+            // Informational, no content and not modified responses never have a body
+            (100..=199, _) | (204, _) | (304, _) => HttpBodyFraming::None,
+
+            // The headers of a HEAD response describe the body which would have been sent
+            (_, Some(&HttpRequestMethod::Head)) => HttpBodyFraming::None,
+
+            // A successful CONNECT switches to a tunnel
+            (200..=299, Some(&HttpRequestMethod::Connect)) => HttpBodyFraming::None,
+
+            _ => HttpBodyFraming::from_headers(&self.headers).unwrap_or(HttpBodyFraming::CloseDelimited),
+        }
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A HTTP message body, or the part of it which is contained in a single packet
+pub struct HttpBody {
+    /// The way the length of the body is specified
+    pub framing: HttpBodyFraming,
+
+    /// The body data within the packet, without the chunked transfer coding
+    pub data: Vec<u8>,
+
+    /// Trailer fields of a chunked body
+    pub trailers: Vec<HttpHeader>,
+
+    /// Indicates that the end of the body has been reached
+    pub complete: bool,
+
+    /// The complete body decoded by its content codings, like gzip. Only available if the body is
+    /// complete, all content codings are supported and the body does not exceed the size limit.
+    pub content: Option<Vec<u8>>,
+
+    /// The transaction which is completed by the end of this response body, if the related
+    /// request is known
+    pub transaction: Option<HttpTransaction>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The way the length of a HTTP message body is specified
+pub enum HttpBodyFraming {
+    /// The message has no body
+    #[default]
+    None,
+
+    /// The length of the body is given by the `Content-Length` header
+    ContentLength(u64),
+
+    /// The body uses the chunked transfer coding
+    Chunked,
+
+    /// The body ends when the connection is closed
+    CloseDelimited,
+}
+
+impl HttpBodyFraming {
+    /// Determine the framing from the `Transfer-Encoding` and `Content-Length` headers. Returns
+    /// `CloseDelimited` if the final transfer coding is not chunked and None if no valid length
+    /// is specified.
+    pub fn from_headers(headers: &[HttpHeader]) -> Option<HttpBodyFraming> {
+        // The transfer encoding takes precedence over the content length
+        let codings = HttpHeader::list(headers, "Transfer-Encoding");
+        if let Some(coding) = codings.last() {
+            return Some(if coding.eq_ignore_ascii_case("chunked") {
+                HttpBodyFraming::Chunked
+            } else {
+                HttpBodyFraming::CloseDelimited
+            });
+        }
+
+        // Multiple content lengths are only valid if they are equal
+        let lengths = HttpHeader::list(headers, "Content-Length");
+        match lengths.first().and_then(|l| parse_number(l, 10)) {
+            Some(length) if lengths.iter().all(|l| parse_number(l, 10) == Some(length)) => {
+                Some(HttpBodyFraming::ContentLength(length))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A content coding which is applied to a HTTP message body
+pub enum HttpContentCoding {
+    /// No transformation
+    Identity,
+
+    /// GZIP file format (RFC 1952)
+    Gzip,
+
+    /// ZLIB data format (RFC 1950)
+    Deflate,
+
+    /// Brotli compressed data format (RFC 7932)
+    Brotli,
+
+    /// Any other content coding
+    Other(String),
+}
+
+impl HttpContentCoding {
+    /// Convert a content coding name (case-insensitive) to a `HttpContentCoding`
+    pub fn from_name(name: &str) -> HttpContentCoding {
+        match name.to_lowercase().as_str() {
+            "identity" => HttpContentCoding::Identity,
+            "gzip" | "x-gzip" => HttpContentCoding::Gzip,
+            "deflate" => HttpContentCoding::Deflate,
+            "br" => HttpContentCoding::Brotli,
+            _ => HttpContentCoding::Other(name.to_owned()),
+        }
+    }
+
+    /// Decode data by the content coding. Returns None if the coding is not supported or the
+    /// data is invalid.
+    pub fn decode(&self, data: Vec<u8>) -> Option<Vec<u8>> {
+        match *self {
+            HttpContentCoding::Identity => Some(data),
+            #[cfg(feature = "compression")]
+            HttpContentCoding::Gzip => read_content(GzDecoder::new(&data[..])),
+            #[cfg(feature = "compression")]
+            HttpContentCoding::Deflate => {
+                // Some implementations send raw deflate data without the zlib wrapper
+                read_content(ZlibDecoder::new(&data[..])).or_else(|| read_content(DeflateDecoder::new(&data[..])))
+            }
+            #[cfg(feature = "compression")]
+            HttpContentCoding::Brotli => read_content(Decompressor::new(&data[..], 4096)),
+            _ => None,
+        }
+    }
+}
+
+/// Read the decoded content, which fails if the content exceeds the size limit
+#[cfg(feature = "compression")]
+fn read_content<R: Read>(reader: R) -> Option<Vec<u8>> {
+    let mut content = vec![];
+    match reader.take(MAX_BODY_LENGTH as u64 + 1).read_to_end(&mut content) {
+        Ok(length) if length <= MAX_BODY_LENGTH => Some(content),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The progress of the transfer decoding of a HTTP message body
+pub enum HttpTransferState {
+    /// The amount of body bytes which are still expected
+    Length(u64),
+
+    /// Within a chunk size line, with its already received part
+    ChunkSize(Vec<u8>),
+
+    /// The amount of chunk data bytes which are still expected
+    ChunkData(u64),
+
+    /// Within the line break after chunk data, with its already received part
+    ChunkEnd(Vec<u8>),
+
+    /// Within the trailer section, with the already received part of the current line
+    Trailer(Vec<u8>),
+
+    /// All data until the connection is closed belongs to the body
+    Close,
+
+    /// The body is complete
+    Done,
+
+    /// The transfer coding is malformed, the body cannot be decoded any further
+    Invalid,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A decoder for a HTTP message body which may span multiple packets
+pub struct HttpBodyDecoder {
+    /// The way the length of the body is specified
+    pub framing: HttpBodyFraming,
+
+    /// The content codings applied to the body, in the order of their application
+    pub codings: Vec<HttpContentCoding>,
+
+    /// The current transfer decoding state
+    pub state: HttpTransferState,
+
+    /// The body data received so far, as long as it does not exceed the size limit
+    pub buffer: Option<Vec<u8>>,
+}
+
+impl HttpBodyDecoder {
+    /// Create a new decoder for a body with the given framing and message headers
+    pub fn new(framing: HttpBodyFraming, headers: &[HttpHeader]) -> Self {
+        let state = match framing {
+            HttpBodyFraming::None | HttpBodyFraming::ContentLength(0) => HttpTransferState::Done,
+            HttpBodyFraming::ContentLength(length) => HttpTransferState::Length(length),
+            HttpBodyFraming::Chunked => HttpTransferState::ChunkSize(vec![]),
+            HttpBodyFraming::CloseDelimited => HttpTransferState::Close,
+        };
+        HttpBodyDecoder {
+            framing: framing,
+            codings: HttpHeader::list(headers, "Content-Encoding").iter().map(|c| HttpContentCoding::from_name(c)).collect(),
+            state: state,
+            buffer: Some(vec![]),
+        }
+    }
+
+    /// Check if further body data is expected
+    pub fn is_pending(&self) -> bool {
+        self.state != HttpTransferState::Done && self.state != HttpTransferState::Invalid
+    }
+
+    /// Decode the body data within the input. The end of a close delimited body is reached if the
+    /// sender is closing the connection. Returns the input which does not belong to the body.
+    pub fn decode<'a>(&mut self, mut input: &'a [u8], closing: bool) -> (&'a [u8], HttpBody) {
+        let mut data = vec![];
+        let mut trailers = vec![];
+
+        loop {
+            let state = match self.state {
+                HttpTransferState::Length(remaining) => {
+                    let (chunk, left) = input.split_at(input.len().min(remaining as usize));
+                    data.extend_from_slice(chunk);
+                    input = left;
+                    match remaining - chunk.len() as u64 {
+                        0 => HttpTransferState::Done,
+                        remaining => {
+                            self.state = HttpTransferState::Length(remaining);
+                            break;
+                        }
+                    }
+                }
+                HttpTransferState::ChunkData(remaining) => {
+                    let (chunk, left) = input.split_at(input.len().min(remaining as usize));
+                    data.extend_from_slice(chunk);
+                    input = left;
+                    match remaining - chunk.len() as u64 {
+                        0 => HttpTransferState::ChunkEnd(vec![]),
+                        remaining => {
+                            self.state = HttpTransferState::ChunkData(remaining);
+                            break;
+                        }
+                    }
+                }
+                HttpTransferState::ChunkSize(ref mut line) => {
+                    match read_line(line, &mut input) {
+                        // The chunk size may be followed by chunk extensions
+                        Some(line) => {
+                            let size = line.split(|&c| c == b';').next().unwrap_or_default();
+                            match str::from_utf8(size).ok().and_then(|s| parse_number(s.trim(), 16)) {
+                                Some(0) => HttpTransferState::Trailer(vec![]),
+                                Some(size) => HttpTransferState::ChunkData(size),
+                                None => HttpTransferState::Invalid,
+                            }
+                        }
+                        None if line.len() > MAX_LINE_LENGTH => HttpTransferState::Invalid,
+                        None => break,
+                    }
+                }
+                HttpTransferState::ChunkEnd(ref mut line) => {
+                    match read_line(line, &mut input) {
+                        Some(ref line) if line.is_empty() => HttpTransferState::ChunkSize(vec![]),
+                        Some(_) => HttpTransferState::Invalid,
+                        None if line.len() > MAX_LINE_LENGTH => HttpTransferState::Invalid,
+                        None => break,
+                    }
+                }
+                HttpTransferState::Trailer(ref mut line) => {
+                    match read_line(line, &mut input) {
+                        Some(ref line) if line.is_empty() => HttpTransferState::Done,
+                        Some(line) => {
+                            match HttpHeader::from_line(&line, &mut vec![]) {
+                                Some(trailer) => {
+                                    trailers.push(trailer);
+                                    HttpTransferState::Trailer(vec![])
+                                }
+                                None => HttpTransferState::Invalid,
+                            }
+                        }
+                        None if line.len() > MAX_LINE_LENGTH => HttpTransferState::Invalid,
+                        None => break,
+                    }
+                }
+                HttpTransferState::Close => {
+                    data.extend_from_slice(input);
+                    input = &input[input.len()..];
+                    if closing {
+                        HttpTransferState::Done
+                    } else {
+                        break;
+                    }
+                }
+                HttpTransferState::Done | HttpTransferState::Invalid => break,
+            };
+            self.state = state;
+        }
+
+        // Buffer the body for the content decoding
+        if let Some(length) = self.buffer.as_ref().map(|b| b.len() + data.len()) {
+            if length > MAX_BODY_LENGTH {
+                self.buffer = None;
+            } else if let Some(ref mut buffer) = self.buffer {
+                buffer.extend_from_slice(&data);
+            }
+        }
+
+        // Decode the content codings in the reverse order of their application
+        let complete = self.state == HttpTransferState::Done;
+        let content = match self.buffer.take() {
+            Some(buffer) if complete => {
+                self.codings.iter().rev().try_fold(buffer, |content, coding| coding.decode(content))
+            }
+            buffer => {
+                self.buffer = buffer;
+                None
+            }
+        };
+
+        (input,
+         HttpBody {
+            framing: self.framing.clone(),
+            data: data,
+            trailers: trailers,
+            complete: complete,
+            content: content,
+            transaction: None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A HTTP request and its response
+pub struct HttpTransaction {
+    /// The request method
+    pub method: HttpRequestMethod,
+
+    /// The requested URI
+    pub uri: String,
+
+    /// The status code of the final response
+    pub status: u16,
+
+    /// The status codes of informational (1xx) responses preceding the final response
+    pub interim_statuses: Vec<u16>,
+
+    /// The size of the request message, including its head
+    pub request_size: u64,
+
+    /// The size of the request body, without the chunked transfer coding
+    pub request_body_size: u64,
+
+    /// The size of the response message, including its head
+    pub response_size: u64,
+
+    /// The size of the response body, without the chunked transfer coding
+    pub response_body_size: u64,
+
+    /// The time between the capture of the request and the first response
+    pub latency: Duration,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// A HTTP request which waits for its response
+pub struct HttpPendingRequest {
+    /// The transaction, which is completed by the response
+    pub transaction: HttpTransaction,
+
+    /// The capture time of the request since the Unix epoch
+    pub time: Duration,
+
+    /// Indicates that a final response has been started
+    pub answered: bool,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The HTTP related state of a connection
+pub struct HttpState {
+    /// Message bodies which are not complete yet, identified by their sender
+    pub bodies: HashMap<Endpoint, HttpBodyDecoder>,
+
+    /// The client of the connection, which is the sender of the requests
+    pub client: Option<Endpoint>,
+
+    /// Requests which are not answered completely, in the order of their transmission
+    pub requests: VecDeque<HttpPendingRequest>,
+
+    /// The protocol switch after a `101 Switching Protocols` response
+    pub upgrade: Option<HttpUpgrade>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A protocol switch of a connection
+pub struct HttpUpgrade {
+    /// The lowercase name of the protocol the connection switched to, like "websocket"
+    pub protocol: String,
+
+    /// The header fields of the `101 Switching Protocols` response, which may contain
+    /// negotiated parameters of the new protocol
+    pub headers: Vec<HttpHeader>,
+}
+
+impl HttpState {
+    /// Add a request, which will be answered after all pending requests (pipelining)
+    fn add_request(&mut self, sender: &Endpoint, request: &HttpRequest, size: u64) {
+        if self.requests.len() >= MAX_PENDING_REQUESTS {
+            self.requests.pop_front();
+        }
+        self.client = Some(*sender);
+        self.requests.push_back(HttpPendingRequest {
+            transaction: HttpTransaction {
+                method: request.request_method.clone(),
+                uri: request.path.clone(),
+                status: 0,
+                interim_statuses: vec![],
+                request_size: size,
+                request_body_size: request.body.data.len() as u64,
+                response_size: 0,
+                response_body_size: 0,
+                latency: Duration::default(),
+            },
+            time: capture_time(),
+            answered: false,
+        });
+    }
+
+    /// Add a response to the oldest pending request
+    fn add_response(&mut self, response: &mut HttpResponse, size: u64) {
+        if let Some(request) = self.requests.front_mut() {
+            let transaction = &mut request.transaction;
+            if transaction.response_size == 0 {
+                transaction.latency = capture_time().saturating_sub(request.time);
+            }
+            transaction.response_size += size;
+            if response.code == 101 {
+                self.upgrade = response.header_list("Upgrade").first().map(|protocol| {
+                    HttpUpgrade {
+                        protocol: protocol.to_lowercase(),
+                        headers: response.headers.clone(),
+                    }
+                });
+            }
+            match response.code {
+                // Informational responses precede the final response, except a protocol switch
+                100..=199 if response.code != 101 => transaction.interim_statuses.push(response.code),
+                code => {
+                    transaction.status = code;
+                    transaction.response_body_size += response.body.data.len() as u64;
+                    request.answered = true;
+                }
+            }
+        }
+        self.complete_transaction(&mut response.body);
+    }
+
+    /// Add the continuation of a message body
+    fn add_body(&mut self, sender: &Endpoint, body: &mut HttpBody, size: u64) {
+        if self.client == Some(*sender) {
+            if let Some(request) = self.requests.back_mut() {
+                request.transaction.request_size += size;
+                request.transaction.request_body_size += body.data.len() as u64;
+            }
+        } else {
+            if let Some(request) = self.requests.front_mut() {
+                request.transaction.response_size += size;
+                request.transaction.response_body_size += body.data.len() as u64;
+            }
+            self.complete_transaction(body);
+        }
+    }
+
+    /// Complete the transaction of the oldest pending request if the response body is complete
+    fn complete_transaction(&mut self, body: &mut HttpBody) {
+        let answered = match self.requests.front() {
+            Some(request) => request.answered,
+            None => false,
+        };
+        if answered && body.complete {
+            body.transaction = self.requests.pop_front().map(|r| r.transaction);
+        }
+    }
+}
+
+/// Read a line until a line feed. The line is collected within `line`, since it may span
+/// multiple packets. Returns the complete line without its line break if available.
+fn read_line(line: &mut Vec<u8>, input: &mut &[u8]) -> Option<Vec<u8>> {
+    match input.iter().position(|&c| c == b'\n') {
+        Some(index) => {
+            line.extend_from_slice(&input[..index]);
+            *input = &input[index + 1..];
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            Some(line.split_off(0))
+        }
+        None => {
+            line.extend_from_slice(input);
+            *input = &input[input.len()..];
+            None
+        }
+    }
+}
+
+/// Read a line of the message head, which may be terminated by a bare line feed
+fn read_head_line<'a>(input: &'a [u8], anomalies: &mut Vec<HttpAnomaly>) -> Option<(&'a [u8], &'a [u8])> {
+    let index = input.iter().position(|&c| c == b'\n')?;
+    let line = match input[..index].split_last() {
+        Some((&b'\r', line)) => line,
+        _ => {
+            HttpAnomaly::report(anomalies, HttpAnomaly::BareLineFeed);
+            &input[..index]
+        }
+    };
+    Some((&input[index + 1..], line))
+}
+
+/// Read the start line of a message, empty lines before it are skipped. More data is only
+/// requested if the incomplete start line is plausible.
+fn read_start_line<'a>(input: &'a [u8],
+                       is_prefix: fn(&[u8]) -> bool,
+                       anomalies: &mut Vec<HttpAnomaly>)
+                       -> IResult<&'a [u8], &'a [u8]> {
+    let mut rest = input;
+    loop {
+        match read_head_line(rest, anomalies) {
+            Some((left, &[])) => {
+                HttpAnomaly::report(anomalies, HttpAnomaly::LeadingEmptyLine);
+                rest = left;
+            }
+            Some((left, line)) if is_prefix(line) => return IResult::Done(left, line),
+            None if !rest.is_empty() && rest.len() < MAX_LINE_LENGTH && is_prefix(rest) => {
+                return IResult::Incomplete(Needed::Unknown)
+            }
+            _ => return IResult::Error(error_position!(ErrorKind::Custom(0), input)),
+        }
+    }
+}
+
+/// Check if the data could be the start of a request line, which starts with a method token
+fn is_request_line_prefix(input: &[u8]) -> bool {
+    input.first().is_some_and(|&c| is_tchar(c)) && input.iter().all(|&c| c >= 0x20 && c != 0x7f || c == b'\t') &&
+    input.iter().take_while(|&&c| c != b' ' && c != b'\t').all(|&c| is_tchar(c))
+}
+
+/// Check if the data could be the start of a status line, which starts with the protocol version
+fn is_status_line_prefix(input: &[u8]) -> bool {
+    (input.starts_with(b"HTTP/") || b"HTTP/".starts_with(input)) &&
+    input.iter().all(|&c| c >= 0x20 && c != 0x7f || c == b'\t')
+}
+
+/// Split a start line into at most `count` words. The last word contains the rest of the line.
+fn split_start_line<'a>(line: &'a [u8], count: usize, anomalies: &mut Vec<HttpAnomaly>) -> Vec<&'a [u8]> {
+    let mut words = vec![];
+    let mut rest = line;
+    while !rest.is_empty() {
+        if words.len() + 1 == count {
+            words.push(rest);
+            break;
+        }
+        let end = rest.iter().position(|&c| c == b' ' || c == b'\t').unwrap_or(rest.len());
+        words.push(&rest[..end]);
+        let separator = rest[end..].iter().take_while(|&&c| c == b' ' || c == b'\t').count();
+        if separator > 1 || rest.get(end) == Some(&b'\t') {
+            HttpAnomaly::report(anomalies, HttpAnomaly::WhitespaceInStartLine);
+        }
+        rest = &rest[end + separator..];
+    }
+    words
+}
+
+/// Remove leading and trailing spaces and horizontal tabs
+fn trim_whitespace(input: &[u8]) -> &[u8] {
+    let start = input.iter().position(|&c| c != b' ' && c != b'\t').unwrap_or(input.len());
+    let end = input.iter().rposition(|&c| c != b' ' && c != b'\t').map_or(start, |i| i + 1);
+    &input[start..end]
+}
+
+/// Decode text as UTF-8, or as ISO-8859-1 if it is not valid UTF-8
+fn decode_text(input: &[u8], anomalies: &mut Vec<HttpAnomaly>) -> String {
+    match str::from_utf8(input) {
+        Ok(text) => text.to_owned(),
+        Err(_) => {
+            HttpAnomaly::report(anomalies, HttpAnomaly::NonUtf8Text);
+            input.iter().map(|&c| c as char).collect()
+        }
+    }
+}
+
+/// Parse a number which consists of digits only. Unlike `str::parse`, a sign is not accepted,
+/// since other implementations would frame the message differently.
+fn parse_number(input: &str, radix: u32) -> Option<u64> {
+    if input.chars().all(|c| c.is_digit(radix)) {
+        u64::from_str_radix(input, radix).ok()
+    } else {
+        None
+    }
+}
+
+/// Check if a character is allowed within a token, like a method or a field name
+fn is_tchar(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c)
+}
+
+/// Check if the sender is closing the TCP connection with the current packet
+fn is_closing(result: Option<&ParserResultVec>) -> bool {
+    match result.and_then(|vector| vector.iter().rev().filter_map(|any| any.downcast_ref::<TcpPacket>()).next()) {
+        Some(tcp) => tcp.header.flag_fin || tcp.header.flag_rst,
+        None => false,
+    }
 }
@@ -0,0 +1,783 @@
+//! Hypertext Transfer Protocol version 2 related packet processing
+use prelude::*;
+use std::collections::HashMap;
+
+/// The connection preface which is sent by the client
+pub const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// The maximum amount of buffered data per sender, like incomplete frames or header blocks
+const MAX_BUFFER_LENGTH: usize = 0x10_0000;
+
+/// The HTTP/2 parser
+pub struct Http2Parser;
+
+impl Parsable<PathIp> for Http2Parser {
+    /// Parse a `Http2Packet` from an `&[u8]`
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 path: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        // Retrieve the connection related information
+        let sender = connection_endpoints(result).map(|e| e.0);
+        let mut data = connection_data(path, result);
+
+        // HTTP/2 is used with prior knowledge, after an upgrade from HTTP/1.1 or by ALPN
+        let preface = input.starts_with(HTTP2_PREFACE);
+        let established = match data {
+            Some(ref data) => {
+                data.http2.established || data.http.upgrade.as_ref().map(|u| u.protocol.as_str()) == Some("h2c")
+            }
+            None => false,
+        };
+        #[cfg(feature = "tls")]
+        let alpn = match data {
+            Some(ref data) => data.application_protocol.as_deref() == Some("h2"),
+            None => false,
+        };
+        #[cfg(not(feature = "tls"))]
+        let alpn = false;
+
+        do_parse!(input,
+            // Check the transport protocol from the parent parser (TCP, TLS or a HTTP/1.1 upgrade)
+            encrypted: expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    Some(ref any) => match (any.downcast_ref::<TcpPacket>(),
+                                            is_application_data(any),
+                                            any.downcast_ref::<HttpPacket>()) {
+                        // Plain text HTTP/2 (h2c)
+                        (Some(_), _, _) if preface || (established && !input.is_empty()) => Some(false),
+
+                        // HTTP/2 over TLS (h2), where the frames are encrypted
+                        (_, true, _) if alpn => Some(true),
+
+                        // Frames following the response of an upgrade
+                        (_, _, Some(_)) if established && !input.is_empty() => Some(false),
+
+                        _ => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(false),
+            }) >>
+
+            packet: apply!(Http2Packet::parse,
+                           encrypted,
+                           data.as_mut().map(|d| &mut d.http2),
+                           sender.as_ref()) >>
+
+            (Box::new(packet))
+        )
+    }
+}
+
+impl fmt::Display for Http2Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HTTP/2")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a Hypertext Transfer Protocol version 2 packet
+pub struct Http2Packet {
+    /// Indicates that the packet starts with the client connection preface
+    pub preface: bool,
+
+    /// The frames which are complete within the packet. Incomplete frames at the end of the packet
+    /// are reported with the next packet of the sender.
+    pub frames: Vec<Http2Frame>,
+
+    /// The frames are transferred within encrypted TLS records and therefore not available
+    pub encrypted: bool,
+}
+
+impl Summary for Http2Packet {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "HTTP/2")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        if self.encrypted {
+            return write!(w, " encrypted");
+        }
+        if self.preface {
+            write!(w, " preface")?;
+        }
+        let frames: Vec<_> = self.frames
+            .iter()
+            .map(|frame| if verbosity == Verbosity::Verbose {
+                format!("{:?}[stream={} len={} flags={:#x}]",
+                        frame.frame_type,
+                        frame.stream_id,
+                        frame.length,
+                        frame.flags)
+            } else {
+                format!("{:?}[stream={}]", frame.frame_type, frame.stream_id)
+            })
+            .collect();
+        if !frames.is_empty() {
+            write!(w, " {}", frames.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(Http2Packet);
+
+impl Dissect for Http2Packet {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        if !self.encrypted {
+            if self.preface {
+                cursor.bytes("http2.preface", 24);
+            }
+            // Frames which started within a previous packet are not located within this one
+            for frame in &self.frames {
+                if cursor.remaining().len() < 9 + frame.length as usize || cursor.peek(24) != u64::from(frame.length) {
+                    break;
+                }
+                cursor.group("http2.frames", format!("{:?}", frame.frame_type), |field| {
+                    field.field("http2.frames.length", 24, frame.length)
+                        .named("http2.frames.frame_type", 8, &frame.frame_type)
+                        .field("http2.frames.flags", 8, frame.flags)
+                        .skip(1)
+                        .field("http2.frames.stream_id", 31, frame.stream_id)
+                        .bytes("http2.frames.payload", frame.length as usize);
+                });
+            }
+        }
+        cursor.finish("http2", self.summary(Verbosity::Normal))
+    }
+}
+
+impl Http2Packet {
+    /// Parse all frames of a packet. The state is used to reassemble frames and header blocks
+    /// over multiple packets, as well as for the stateful HPACK decoding.
+    pub fn parse<'a>(input: &'a [u8],
+                     encrypted: bool,
+                     state: Option<&mut Http2State>,
+                     sender: Option<&Endpoint>)
+                     -> IResult<&'a [u8], Http2Packet> {
+        if encrypted {
+            return IResult::Done(input,
+                                 Http2Packet {
+                                     preface: false,
+                                     frames: vec![],
+                                     encrypted: true,
+                                 });
+        }
+
+        let preface = input.starts_with(HTTP2_PREFACE);
+        let input = if preface {
+            &input[HTTP2_PREFACE.len()..]
+        } else {
+            input
+        };
+
+        // The state is only usable if the sender is known
+        let mut state = match sender {
+            Some(_) => state,
+            None => None,
+        };
+        if let Some(ref mut state) = state {
+            state.established |= preface;
+        }
+
+        // The frame boundaries of a sender are unknown after an oversized frame has been dropped
+        if let (Some(state), Some(sender)) = (state.as_ref(), sender) {
+            if state.desynchronized.contains(sender) {
+                return IResult::Done(&input[input.len()..],
+                                     Http2Packet {
+                                         preface: preface,
+                                         frames: vec![],
+                                         encrypted: false,
+                                     });
+            }
+        }
+
+        // Continue an incomplete frame of the previous packet
+        let buffered = match (state.as_mut(), sender) {
+            (Some(state), Some(sender)) => state.buffers.remove(sender),
+            _ => None,
+        };
+        let mut data = match buffered {
+            Some(ref buffer) if !buffer.is_empty() => {
+                let mut data = buffer.clone();
+                data.extend_from_slice(input);
+                data
+            }
+            _ => input.to_vec(),
+        };
+
+        let mut frames = vec![];
+        let mut offset = 0;
+        while let IResult::Done(payload, header) = Http2Frame::parse_header(&data[offset..]) {
+            let (length, frame_type, flags, stream_id) = header;
+            if payload.len() < length as usize {
+                break;
+            }
+            frames.push(Http2Frame::new(length,
+                                        frame_type,
+                                        flags,
+                                        stream_id,
+                                        &payload[..length as usize],
+                                        state.as_deref_mut(),
+                                        sender));
+            offset += 9 + length as usize;
+        }
+
+        // Buffer an incomplete frame for the next packet of the sender
+        let left = data.split_off(offset);
+        let left = match (state, sender) {
+            (Some(state), Some(sender)) => {
+                if left.len() > MAX_BUFFER_LENGTH {
+                    state.desynchronized.push(*sender);
+                } else if !left.is_empty() {
+                    state.buffers.insert(*sender, left);
+                }
+                &input[input.len()..]
+            }
+            _ => &input[input.len() - left.len().min(input.len())..],
+        };
+
+        IResult::Done(left,
+                      Http2Packet {
+                          preface: preface,
+                          frames: frames,
+                          encrypted: false,
+                      })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A HTTP/2 frame
+pub struct Http2Frame {
+    /// The length of the frame payload
+    pub length: u32,
+
+    /// The type of the frame
+    pub frame_type: Http2FrameType,
+
+    /// Boolean flags specific to the frame type
+    pub flags: u8,
+
+    /// The stream identifier, zero for frames related to the whole connection
+    pub stream_id: u32,
+
+    /// The decoded payload of the frame
+    pub payload: Http2FramePayload,
+}
+
+impl Http2Frame {
+    /// The `END_STREAM` flag of DATA and HEADERS frames
+    pub const END_STREAM: u8 = 0x1;
+
+    /// The `ACK` flag of SETTINGS and PING frames
+    pub const ACK: u8 = 0x1;
+
+    /// The `END_HEADERS` flag of HEADERS, PUSH_PROMISE and CONTINUATION frames
+    pub const END_HEADERS: u8 = 0x4;
+
+    /// The `PADDED` flag of DATA, HEADERS and PUSH_PROMISE frames
+    pub const PADDED: u8 = 0x8;
+
+    /// The `PRIORITY` flag of HEADERS frames
+    pub const PRIORITY: u8 = 0x20;
+
+    named!(#[doc = "Parse the frame header, which consists of the length, type, flags and stream identifier"],
+           pub parse_header<&[u8], (u32, u8, u8, u32)>,
+        do_parse!(
+            length: bits!(take_bits!(u32, 24)) >>
+            frame_type: be_u8 >>
+            flags: be_u8 >>
+            stream_id: map!(be_u32, |s| s & 0x7fff_ffff) >>
+            (length, frame_type, flags, stream_id)
+        )
+    );
+
+    /// Create a new frame by decoding its payload
+    pub fn new(length: u32,
+               frame_type: u8,
+               flags: u8,
+               stream_id: u32,
+               payload: &[u8],
+               state: Option<&mut Http2State>,
+               sender: Option<&Endpoint>)
+               -> Self {
+        let frame_type = Http2FrameType::from_u8(frame_type);
+        Http2Frame {
+            length: length,
+            payload: Http2FramePayload::from_payload(&frame_type, flags, payload, state, sender)
+                .unwrap_or(Http2FramePayload::Invalid),
+            frame_type: frame_type,
+            flags: flags,
+            stream_id: stream_id,
+        }
+    }
+
+    /// Check if a flag is set
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// HTTP/2 frame types
+pub enum Http2FrameType {
+    /// Application data of a stream
+    Data,
+
+    /// Opens a stream and carries a header block fragment
+    Headers,
+
+    /// The sender-advised priority of a stream
+    Priority,
+
+    /// Immediate termination of a stream
+    RstStream,
+
+    /// Configuration parameters of the sender
+    Settings,
+
+    /// Notifies the peer of a stream the sender intends to initiate
+    PushPromise,
+
+    /// Round-trip time measurement and liveness check
+    Ping,
+
+    /// Initiates the shutdown of a connection
+    GoAway,
+
+    /// Flow control window increment
+    WindowUpdate,
+
+    /// Continues a header block fragment
+    Continuation,
+
+    /// An unknown frame type, which has to be ignored
+    Unknown(u8),
+}
+
+impl Http2FrameType {
+    /// Convert a u8 to a `Http2FrameType`
+    pub fn from_u8(input: u8) -> Http2FrameType {
+        match input {
+            0 => Http2FrameType::Data,
+            1 => Http2FrameType::Headers,
+            2 => Http2FrameType::Priority,
+            3 => Http2FrameType::RstStream,
+            4 => Http2FrameType::Settings,
+            5 => Http2FrameType::PushPromise,
+            6 => Http2FrameType::Ping,
+            7 => Http2FrameType::GoAway,
+            8 => Http2FrameType::WindowUpdate,
+            9 => Http2FrameType::Continuation,
+            other => Http2FrameType::Unknown(other),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The decoded payload of a HTTP/2 frame
+pub enum Http2FramePayload {
+    /// Application data, without padding
+    Data(Vec<u8>),
+
+    /// A header block fragment and the optional stream priority
+    Headers(Http2HeaderBlock, Option<Http2Priority>),
+
+    /// The priority of a stream
+    Priority(Http2Priority),
+
+    /// The reason for the termination of a stream
+    RstStream(Http2ErrorCode),
+
+    /// Configuration parameters, empty for acknowledgements
+    Settings(Vec<Http2Setting>),
+
+    /// The identifier of the promised stream and the header block fragment of its request
+    PushPromise(u32, Http2HeaderBlock),
+
+    /// Opaque data which is returned within the acknowledgement
+    Ping(Vec<u8>),
+
+    /// The connection shutdown information
+    GoAway(Http2GoAway),
+
+    /// The flow control window size increment
+    WindowUpdate(u32),
+
+    /// A continued header block fragment
+    Continuation(Http2HeaderBlock),
+
+    /// The payload of an unknown frame type
+    Unknown(Vec<u8>),
+
+    /// The payload does not match the frame type
+    Invalid,
+}
+
+impl Http2FramePayload {
+    /// Decode a frame payload. Returns None if the payload is malformed.
+    pub fn from_payload(frame_type: &Http2FrameType,
+                        flags: u8,
+                        payload: &[u8],
+                        state: Option<&mut Http2State>,
+                        sender: Option<&Endpoint>)
+                        -> Option<Http2FramePayload> {
+        // Remove the padding
+        let payload = match *frame_type {
+            Http2FrameType::Data |
+            Http2FrameType::Headers |
+            Http2FrameType::PushPromise if flags & Http2Frame::PADDED != 0 => {
+                let (padding, payload) = payload.split_first()?;
+                if *padding as usize > payload.len() {
+                    return None;
+                }
+                &payload[..payload.len() - *padding as usize]
+            }
+            _ => payload,
+        };
+        let end_headers = flags & Http2Frame::END_HEADERS != 0;
+
+        Some(match *frame_type {
+            Http2FrameType::Data => Http2FramePayload::Data(payload.to_vec()),
+            Http2FrameType::Headers => {
+                let (priority, fragment) = if flags & Http2Frame::PRIORITY != 0 {
+                    match Http2Priority::parse(payload) {
+                        IResult::Done(fragment, priority) => (Some(priority), fragment),
+                        _ => return None,
+                    }
+                } else {
+                    (None, payload)
+                };
+                Http2FramePayload::Headers(Http2HeaderBlock::new(fragment, true, end_headers, state, sender),
+                                           priority)
+            }
+            Http2FrameType::Priority if payload.len() == 5 => {
+                match Http2Priority::parse(payload) {
+                    IResult::Done(_, priority) => Http2FramePayload::Priority(priority),
+                    _ => return None,
+                }
+            }
+            Http2FrameType::RstStream if payload.len() == 4 => {
+                Http2FramePayload::RstStream(Http2ErrorCode::from_u32(read_u32(payload)))
+            }
+            Http2FrameType::Settings if payload.len() % 6 == 0 => {
+                Http2FramePayload::Settings(payload.chunks(6)
+                    .map(|s| {
+                        Http2Setting {
+                            parameter: Http2SettingsParameter::from_u16((s[0] as u16) << 8 | s[1] as u16),
+                            value: read_u32(&s[2..]),
+                        }
+                    })
+                    .collect())
+            }
+            Http2FrameType::PushPromise if payload.len() >= 4 => {
+                Http2FramePayload::PushPromise(read_u32(payload) & 0x7fff_ffff,
+                                               Http2HeaderBlock::new(&payload[4..], true, end_headers, state, sender))
+            }
+            Http2FrameType::Ping if payload.len() == 8 => Http2FramePayload::Ping(payload.to_vec()),
+            Http2FrameType::GoAway if payload.len() >= 8 => {
+                Http2FramePayload::GoAway(Http2GoAway {
+                    last_stream_id: read_u32(payload) & 0x7fff_ffff,
+                    error_code: Http2ErrorCode::from_u32(read_u32(&payload[4..])),
+                    debug_data: payload[8..].to_vec(),
+                })
+            }
+            Http2FrameType::WindowUpdate if payload.len() == 4 => {
+                Http2FramePayload::WindowUpdate(read_u32(payload) & 0x7fff_ffff)
+            }
+            Http2FrameType::Continuation => {
+                Http2FramePayload::Continuation(Http2HeaderBlock::new(payload, false, end_headers, state, sender))
+            }
+            Http2FrameType::Unknown(_) => Http2FramePayload::Unknown(payload.to_vec()),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A header block fragment of a HEADERS, PUSH_PROMISE or CONTINUATION frame
+pub struct Http2HeaderBlock {
+    /// The encoded header block fragment
+    pub fragment: Vec<u8>,
+
+    /// The decoded header fields, which are available within the frame that completes the
+    /// header block
+    pub headers: Option<Vec<HpackHeader>>,
+}
+
+impl Http2HeaderBlock {
+    /// Create a new header block from a fragment. The header block is decoded if it is complete
+    /// and either starts with the fragment or is continued by it.
+    pub fn new(fragment: &[u8],
+               start: bool,
+               end: bool,
+               state: Option<&mut Http2State>,
+               sender: Option<&Endpoint>)
+               -> Self {
+        let headers = match (state, sender) {
+            (Some(state), Some(sender)) => state.add_header_block_fragment(sender, fragment, start, end),
+            // A single fragment can be decoded without the dynamic table of the connection
+            _ if start && end => HpackDecoder::default().decode(fragment),
+            _ => None,
+        };
+        Http2HeaderBlock {
+            fragment: fragment.to_vec(),
+            headers: headers,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The priority of a stream
+pub struct Http2Priority {
+    /// The stream becomes the sole dependency of its parent stream
+    pub exclusive: bool,
+
+    /// The identifier of the stream this stream depends on
+    pub dependency: u32,
+
+    /// The priority weight of the stream (1 to 256)
+    pub weight: u16,
+}
+
+impl Http2Priority {
+    named!(#[doc = "Parse the priority information"],
+           pub parse<&[u8], Http2Priority>,
+        do_parse!(
+            dependency: be_u32 >>
+            weight: be_u8 >>
+
+            (Http2Priority {
+                exclusive: dependency & 0x8000_0000 != 0,
+                dependency: dependency & 0x7fff_ffff,
+                weight: weight as u16 + 1,
+            })
+        )
+    );
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A configuration parameter of a SETTINGS frame
+pub struct Http2Setting {
+    /// The parameter which is configured
+    pub parameter: Http2SettingsParameter,
+
+    /// The value of the parameter
+    pub value: u32,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// HTTP/2 settings parameters
+pub enum Http2SettingsParameter {
+    /// The maximum size of the header compression table used to decode header blocks
+    HeaderTableSize,
+
+    /// Server push is permitted
+    EnablePush,
+
+    /// The maximum number of concurrent streams the sender permits
+    MaxConcurrentStreams,
+
+    /// The initial flow control window size of streams
+    InitialWindowSize,
+
+    /// The largest frame payload the sender is willing to receive
+    MaxFrameSize,
+
+    /// The maximum size of header lists the sender is prepared to accept
+    MaxHeaderListSize,
+
+    /// The extended CONNECT method is supported (RFC 8441)
+    EnableConnectProtocol,
+
+    /// An unknown parameter, which has to be ignored
+    Unknown(u16),
+}
+
+impl Http2SettingsParameter {
+    /// Convert a u16 to a `Http2SettingsParameter`
+    pub fn from_u16(input: u16) -> Http2SettingsParameter {
+        match input {
+            1 => Http2SettingsParameter::HeaderTableSize,
+            2 => Http2SettingsParameter::EnablePush,
+            3 => Http2SettingsParameter::MaxConcurrentStreams,
+            4 => Http2SettingsParameter::InitialWindowSize,
+            5 => Http2SettingsParameter::MaxFrameSize,
+            6 => Http2SettingsParameter::MaxHeaderListSize,
+            8 => Http2SettingsParameter::EnableConnectProtocol,
+            other => Http2SettingsParameter::Unknown(other),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The content of a GOAWAY frame
+pub struct Http2GoAway {
+    /// The highest stream identifier which might have been processed
+    pub last_stream_id: u32,
+
+    /// The reason for closing the connection
+    pub error_code: Http2ErrorCode,
+
+    /// Additional diagnostic data
+    pub debug_data: Vec<u8>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// HTTP/2 error codes
+pub enum Http2ErrorCode {
+    /// Graceful shutdown
+    NoError,
+
+    /// Protocol error detected
+    ProtocolError,
+
+    /// Implementation fault
+    InternalError,
+
+    /// Flow control limits exceeded
+    FlowControlError,
+
+    /// Settings not acknowledged
+    SettingsTimeout,
+
+    /// Frame received for closed stream
+    StreamClosed,
+
+    /// Frame size incorrect
+    FrameSizeError,
+
+    /// Stream not processed
+    RefusedStream,
+
+    /// Stream cancelled
+    Cancel,
+
+    /// Compression state not updated
+    CompressionError,
+
+    /// TCP connection error for CONNECT method
+    ConnectError,
+
+    /// Processing capacity exceeded
+    EnhanceYourCalm,
+
+    /// Negotiated TLS parameters not acceptable
+    InadequateSecurity,
+
+    /// Use HTTP/1.1 for the request
+    Http11Required,
+
+    /// An unknown error code
+    Unknown(u32),
+}
+
+impl Http2ErrorCode {
+    /// Convert a u32 to a `Http2ErrorCode`
+    pub fn from_u32(input: u32) -> Http2ErrorCode {
+        match input {
+            0x0 => Http2ErrorCode::NoError,
+            0x1 => Http2ErrorCode::ProtocolError,
+            0x2 => Http2ErrorCode::InternalError,
+            0x3 => Http2ErrorCode::FlowControlError,
+            0x4 => Http2ErrorCode::SettingsTimeout,
+            0x5 => Http2ErrorCode::StreamClosed,
+            0x6 => Http2ErrorCode::FrameSizeError,
+            0x7 => Http2ErrorCode::RefusedStream,
+            0x8 => Http2ErrorCode::Cancel,
+            0x9 => Http2ErrorCode::CompressionError,
+            0xa => Http2ErrorCode::ConnectError,
+            0xb => Http2ErrorCode::EnhanceYourCalm,
+            0xc => Http2ErrorCode::InadequateSecurity,
+            0xd => Http2ErrorCode::Http11Required,
+            other => Http2ErrorCode::Unknown(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The HTTP/2 related state of a connection
+pub struct Http2State {
+    /// The connection uses HTTP/2, since the connection preface has been seen
+    pub established: bool,
+
+    /// Incomplete frames at the end of the previous packet, identified by their sender
+    pub buffers: HashMap<Endpoint, Vec<u8>>,
+
+    /// Senders whose frames are not parsed anymore, since an incomplete frame exceeded the
+    /// buffer limit and the start of the following frame is unknown
+    pub desynchronized: Vec<Endpoint>,
+
+    /// Header blocks which wait for CONTINUATION frames, identified by their sender
+    pub header_blocks: HashMap<Endpoint, Vec<u8>>,
+
+    /// The HPACK decoding context of each sender
+    pub decoders: HashMap<Endpoint, HpackDecoder>,
+}
+
+impl Http2State {
+    /// Add a header block fragment of a sender. Returns the decoded header fields if the
+    /// header block is complete.
+    fn add_header_block_fragment(&mut self,
+                                 sender: &Endpoint,
+                                 fragment: &[u8],
+                                 start: bool,
+                                 end: bool)
+                                 -> Option<Vec<HpackHeader>> {
+        let mut block = if start {
+            vec![]
+        } else {
+            // The start of the header block is unknown, which desynchronizes the decoder as well
+            match self.header_blocks.remove(sender) {
+                Some(block) => block,
+                None => {
+                    self.decoders.entry(*sender).or_default().failed = true;
+                    return None;
+                }
+            }
+        };
+        block.extend_from_slice(fragment);
+
+        if end {
+            self.decoders.entry(*sender).or_default().decode(&block)
+        } else {
+            if block.len() <= MAX_BUFFER_LENGTH {
+                self.header_blocks.insert(*sender, block);
+            }
+            None
+        }
+    }
+}
+
+/// Read a big endian u32 from the first four bytes of a slice
+fn read_u32(input: &[u8]) -> u32 {
+    input.iter().take(4).fold(0, |acc, &x| acc << 8 | x as u32)
+}
+
+/// Check if the parsing result is a TLS record of application data
+#[cfg(feature = "tls")]
+fn is_application_data(result: &ParserResult) -> bool {
+    result.downcast_ref::<TlsPacket>().is_some_and(|tls| tls.content_type == TlsRecordContentType::ApplicationData)
+}
+
+/// Without TLS support there is no encrypted traffic
+#[cfg(not(feature = "tls"))]
+fn is_application_data(_: &ParserResult) -> bool {
+    false
+}
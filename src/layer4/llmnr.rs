@@ -0,0 +1,201 @@
+//! Link-Local Multicast Name Resolution related packet processing
+use prelude::*;
+
+/// The well known port of LLMNR
+pub const LLMNR_PORT: u16 = 5355;
+
+/// The LLMNR parser
+pub struct LlmnrParser;
+
+impl Parsable<PathIp> for LlmnrParser {
+    /// Parse a `LlmnrPacket` from an `&[u8]`
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 _: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        do_parse!(input,
+            // Check the transport protocol from the parent parser (UDP)
+            expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    Some(ref any) => match any.downcast_ref::<UdpPacket>() {
+                        Some(udp) if udp.dispatches_to(&ApplicationProtocol::Llmnr) => Some(()),
+                        _ => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(()),
+            }) >>
+
+            packet: call!(LlmnrPacket::parse) >>
+
+            (Box::new(packet))
+        )
+    }
+}
+
+impl fmt::Display for LlmnrParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "LLMNR")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a Link-Local Multicast Name Resolution message
+pub struct LlmnrPacket {
+    /// The message header
+    pub header: LlmnrHeader,
+
+    /// The question section
+    pub questions: Vec<DnsQuestion>,
+
+    /// The answer section
+    pub answers: Vec<DnsResourceRecord>,
+
+    /// The authority section
+    pub authorities: Vec<DnsResourceRecord>,
+
+    /// The additional section
+    pub additionals: Vec<DnsResourceRecord>,
+}
+
+impl Summary for LlmnrPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "LLMNR")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        if self.header.response {
+            write!(w, " response id={:#06x} {:?}", self.header.id, self.header.response_code)?;
+        } else {
+            write!(w, " query id={:#06x}", self.header.id)?;
+        }
+        let questions: Vec<_> = self.questions.iter().map(ToString::to_string).collect();
+        if !questions.is_empty() {
+            write!(w, " {}", questions.join(", "))?;
+        }
+        if self.header.response {
+            write!(w, " answers={}", self.answers.len())?;
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w, " opcode={:?}", self.header.opcode)?;
+            if self.header.conflict {
+                write!(w, " [C]")?;
+            }
+            if self.header.tentative {
+                write!(w, " [T]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(LlmnrPacket);
+
+impl Dissect for LlmnrPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.field("llmnr.header.id", 16, self.header.id)
+            .field("llmnr.header.response", 1, self.header.response)
+            .named("llmnr.header.opcode", 4, &self.header.opcode)
+            .field("llmnr.header.conflict", 1, self.header.conflict)
+            .field("llmnr.header.truncated", 1, self.header.truncated)
+            .field("llmnr.header.tentative", 1, self.header.tentative)
+            .skip(4)
+            .named("llmnr.header.response_code", 4, &self.header.response_code)
+            .field("llmnr.header.question_count", 16, self.header.question_count)
+            .field("llmnr.header.answer_count", 16, self.header.answer_count)
+            .field("llmnr.header.authority_count", 16, self.header.authority_count)
+            .field("llmnr.header.additional_count", 16, self.header.additional_count);
+        for question in &self.questions {
+            question.dissect(&mut cursor, "llmnr.questions", input, None);
+        }
+        for &(prefix, records) in &[("llmnr.answers", &self.answers),
+                                    ("llmnr.authorities", &self.authorities),
+                                    ("llmnr.additionals", &self.additionals)] {
+            for record in records {
+                record.dissect(&mut cursor, prefix, input, None);
+            }
+        }
+        cursor.finish("llmnr", self.summary(Verbosity::Normal))
+    }
+}
+
+impl LlmnrPacket {
+    named!(#[doc = "Parse a LLMNR message, which uses the DNS wire format"],
+           pub parse<&[u8], LlmnrPacket>,
+        map!(complete!(DnsPacket::parse), LlmnrPacket::new)
+    );
+
+    /// Create a LLMNR message from a DNS message by interpreting the header flags
+    pub fn new(packet: DnsPacket) -> LlmnrPacket {
+        LlmnrPacket {
+            header: LlmnrHeader::new(packet.header),
+            questions: packet.questions,
+            answers: packet.answers,
+            authorities: packet.authorities,
+            additionals: packet.additionals,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The header of a LLMNR message, which differs from DNS within the flags
+pub struct LlmnrHeader {
+    /// The identifier to match responses and queries
+    pub id: u16,
+
+    /// The message is a response
+    pub response: bool,
+
+    /// The kind of query
+    pub opcode: DnsOpcode,
+
+    /// The name is not considered unique by the responder or multiple responses were received
+    pub conflict: bool,
+
+    /// The message was truncated due to the length limit of the transport
+    pub truncated: bool,
+
+    /// The responder has not verified the uniqueness of the name yet
+    pub tentative: bool,
+
+    /// The response code
+    pub response_code: DnsResponseCode,
+
+    /// The amount of entries in the question section
+    pub question_count: u16,
+
+    /// The amount of resource records in the answer section
+    pub answer_count: u16,
+
+    /// The amount of resource records in the authority section
+    pub authority_count: u16,
+
+    /// The amount of resource records in the additional section
+    pub additional_count: u16,
+}
+
+impl LlmnrHeader {
+    /// Create a LLMNR header from a DNS header, the flag bits are shared by both protocols
+    pub fn new(header: DnsHeader) -> LlmnrHeader {
+        LlmnrHeader {
+            id: header.id,
+            response: header.response,
+            opcode: header.opcode,
+            conflict: header.authoritative,
+            truncated: header.truncated,
+            tentative: header.recursion_desired,
+            response_code: header.response_code,
+            question_count: header.question_count,
+            answer_count: header.answer_count,
+            authority_count: header.authority_count,
+            additional_count: header.additional_count,
+        }
+    }
+}
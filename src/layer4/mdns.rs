@@ -0,0 +1,389 @@
+//! Multicast DNS and DNS-based Service Discovery related packet processing
+use prelude::*;
+
+/// The well known port of Multicast DNS
+pub const MDNS_PORT: u16 = 5353;
+
+/// The mDNS parser
+pub struct MdnsParser;
+
+impl Parsable<PathIp> for MdnsParser {
+    /// Parse a `MdnsPacket` from an `&[u8]`
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 _: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        do_parse!(input,
+            // Check the transport protocol from the parent parser (UDP)
+            expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    // Queries are sent from the mDNS port, legacy unicast queries to it
+                    Some(ref any) => match any.downcast_ref::<UdpPacket>() {
+                        Some(udp) if udp.dispatches_to(&ApplicationProtocol::Mdns) => Some(()),
+                        _ => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(()),
+            }) >>
+
+            packet: call!(MdnsPacket::parse) >>
+
+            (Box::new(packet))
+        )
+    }
+}
+
+impl fmt::Display for MdnsParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "mDNS")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a Multicast DNS message
+pub struct MdnsPacket {
+    /// The message header, which uses the DNS semantics
+    pub header: DnsHeader,
+
+    /// The question section
+    pub questions: Vec<MdnsQuestion>,
+
+    /// The answer section
+    pub answers: Vec<MdnsRecord>,
+
+    /// The authority section, which contains the proposed records of probing queries
+    pub authorities: Vec<MdnsRecord>,
+
+    /// The additional section
+    pub additionals: Vec<MdnsRecord>,
+
+    /// The DNS-SD service instances which are described by the records of the message
+    pub services: Vec<DnsSdService>,
+}
+
+impl Summary for MdnsPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "mDNS")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        write!(w, " {}", if self.header.response { "response" } else { "query" })?;
+        let questions: Vec<_> = self.questions.iter().map(|q| q.question.to_string()).collect();
+        if !questions.is_empty() {
+            write!(w, " {}", questions.join(", "))?;
+        }
+        if self.header.response {
+            write!(w, " answers={}", self.answers.len())?;
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w,
+                   " id={:#06x} authorities={} additionals={} services={}",
+                   self.header.id,
+                   self.authorities.len(),
+                   self.additionals.len(),
+                   self.services.len())?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(MdnsPacket);
+
+impl Dissect for MdnsPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        self.header.dissect(&mut cursor, "mdns");
+        for question in &self.questions {
+            let flag = ("mdns.questions.unicast_response", question.unicast_response);
+            question.question.dissect(&mut cursor, "mdns.questions", input, Some(flag));
+        }
+        for &(prefix, records) in &[("mdns.answers", &self.answers),
+                                    ("mdns.authorities", &self.authorities),
+                                    ("mdns.additionals", &self.additionals)] {
+            for record in records {
+                // The OPT pseudo record keeps the top bit within its class
+                let flag = match record.record.rtype {
+                    DnsType::Opt => None,
+                    _ => Some((format!("{}.cache_flush", prefix), record.cache_flush)),
+                };
+                let flag = flag.as_ref().map(|&(ref name, value)| (name.as_str(), value));
+                record.record.dissect(&mut cursor, prefix, input, flag);
+            }
+        }
+        cursor.finish("mdns", self.summary(Verbosity::Normal))
+    }
+}
+
+impl MdnsPacket {
+    named!(#[doc = "Parse a mDNS message, which uses the DNS wire format"],
+           pub parse<&[u8], MdnsPacket>,
+        map!(complete!(DnsPacket::parse), MdnsPacket::new)
+    );
+
+    /// Create a mDNS message from a DNS message by interpreting the top bit of the classes
+    pub fn new(packet: DnsPacket) -> MdnsPacket {
+        let questions = packet.questions
+            .into_iter()
+            .map(|mut question| {
+                let (class, unicast_response) = split_class(question.class);
+                question.class = class;
+                MdnsQuestion {
+                    question: question,
+                    unicast_response: unicast_response,
+                }
+            })
+            .collect();
+        let answers = packet.answers.into_iter().map(MdnsRecord::new).collect::<Vec<_>>();
+        let authorities = packet.authorities.into_iter().map(MdnsRecord::new).collect();
+        let additionals = packet.additionals.into_iter().map(MdnsRecord::new).collect::<Vec<_>>();
+        let services = DnsSdService::collect(answers.iter().chain(additionals.iter()).map(|r| &r.record));
+
+        MdnsPacket {
+            header: packet.header,
+            questions: questions,
+            answers: answers,
+            authorities: authorities,
+            additionals: additionals,
+            services: services,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A mDNS question
+pub struct MdnsQuestion {
+    /// The question, without the unicast-response bit within the class
+    pub question: DnsQuestion,
+
+    /// The querier accepts unicast responses (QU question)
+    pub unicast_response: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A mDNS resource record
+pub struct MdnsRecord {
+    /// The record, without the cache-flush bit within the class
+    pub record: DnsResourceRecord,
+
+    /// The record replaces all cached records of the same name, type and class
+    pub cache_flush: bool,
+}
+
+impl MdnsRecord {
+    /// Create a mDNS record from a DNS record, the class of the OPT pseudo record is kept
+    pub fn new(mut record: DnsResourceRecord) -> MdnsRecord {
+        let cache_flush = match record.rtype {
+            DnsType::Opt => false,
+            _ => {
+                let (class, cache_flush) = split_class(record.class);
+                record.class = class;
+                cache_flush
+            }
+        };
+        MdnsRecord {
+            record: record,
+            cache_flush: cache_flush,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A DNS-SD service instance, like "Printer._ipp._tcp.local"
+pub struct DnsSdService {
+    /// The user friendly name of the instance
+    pub instance: String,
+
+    /// The service type, like "_ipp._tcp"
+    pub service: String,
+
+    /// The domain of the service, like "local"
+    pub domain: String,
+
+    /// The host which provides the instance, from the SRV record
+    pub target: Option<String>,
+
+    /// The port of the instance, from the SRV record
+    pub port: Option<u16>,
+
+    /// The attributes of the instance, from the TXT record
+    pub attributes: Vec<DnsSdAttribute>,
+
+    /// The addresses of the target host, from the A and AAAA records
+    pub addresses: Vec<IpAddr>,
+}
+
+impl DnsSdService {
+    /// Collect the service instances which are referenced by PTR records or described by SRV
+    /// and TXT records
+    pub fn collect<'a, I>(records: I) -> Vec<DnsSdService>
+        where I: Iterator<Item = &'a DnsResourceRecord> + Clone
+    {
+        let mut services: Vec<DnsSdService> = vec![];
+        for record in records.clone() {
+            let name = match record.data {
+                // The targets of the service type enumeration are rejected by the name check
+                DnsRecordData::Ptr(ref target) => target,
+                DnsRecordData::Srv(_) | DnsRecordData::Txt(_) => &record.name,
+                _ => continue,
+            };
+            if let Some(service) = DnsSdService::from_name(name) {
+                if !services.iter().any(|s| s.name().eq_ignore_ascii_case(&service.name())) {
+                    services.push(service);
+                }
+            }
+        }
+
+        // Fill in the details of the instances
+        for service in &mut services {
+            let name = service.name();
+            for record in records.clone().filter(|r| r.name.eq_ignore_ascii_case(&name)) {
+                match record.data {
+                    DnsRecordData::Srv(ref srv) => {
+                        service.target = Some(srv.target.clone());
+                        service.port = Some(srv.port);
+                    }
+                    DnsRecordData::Txt(ref strings) => {
+                        service.attributes = strings.iter().filter_map(|s| DnsSdAttribute::parse(s)).collect();
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(ref target) = service.target {
+                service.addresses = records.clone()
+                    .filter(|r| r.name.eq_ignore_ascii_case(target))
+                    .filter_map(|r| match r.data {
+                        DnsRecordData::A(address) => Some(IpAddr::V4(address)),
+                        DnsRecordData::Aaaa(address) => Some(IpAddr::V6(address)),
+                        _ => None,
+                    })
+                    .collect();
+            }
+        }
+        services
+    }
+
+    /// Split an instance name into its instance label, service type and domain
+    pub fn from_name(name: &str) -> Option<DnsSdService> {
+        let labels = split_labels(name);
+        match labels.get(1..3) {
+            Some(service) if service[0].starts_with('_') && (service[1] == "_tcp" || service[1] == "_udp") => {
+                Some(DnsSdService {
+                    instance: unescape_label(labels[0]),
+                    service: service.join("."),
+                    domain: labels[3..].join("."),
+                    target: None,
+                    port: None,
+                    attributes: vec![],
+                    addresses: vec![],
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The full instance name, as it is used within records
+    pub fn name(&self) -> String {
+        let mut name = String::new();
+        escape_label(&mut name, self.instance.as_bytes());
+        name.push('.');
+        name.push_str(&self.service);
+        if !self.domain.is_empty() {
+            name.push('.');
+            name.push_str(&self.domain);
+        }
+        name
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A key/value attribute of a DNS-SD TXT record
+pub struct DnsSdAttribute {
+    /// The key of the attribute
+    pub key: String,
+
+    /// The value, which is missing for boolean attributes
+    pub value: Option<Vec<u8>>,
+}
+
+impl DnsSdAttribute {
+    /// Parse an attribute from a character string, empty strings and keys are ignored
+    pub fn parse(input: &[u8]) -> Option<DnsSdAttribute> {
+        let (key, value) = match input.iter().position(|&c| c == b'=') {
+            Some(index) => (&input[..index], Some(input[index + 1..].to_vec())),
+            None => (input, None),
+        };
+        if key.is_empty() {
+            return None;
+        }
+        Some(DnsSdAttribute {
+            key: String::from_utf8_lossy(key).into_owned(),
+            value: value,
+        })
+    }
+}
+
+/// Split the top bit from a class, which is the unicast-response bit of questions and the
+/// cache-flush bit of records
+fn split_class(class: DnsClass) -> (DnsClass, bool) {
+    match class {
+        DnsClass::Unknown(value) if value & 0x8000 != 0 => (DnsClass::from_u16(value & 0x7fff), true),
+        class => (class, false),
+    }
+}
+
+/// Split an escaped name into its labels
+fn split_labels(name: &str) -> Vec<&str> {
+    let mut labels = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in name.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '.' => {
+                labels.push(&name[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < name.len() {
+        labels.push(&name[start..]);
+    }
+    labels
+}
+
+/// Reverse the escaping of a label, the result is interpreted as UTF-8
+fn unescape_label(label: &str) -> String {
+    let bytes = label.as_bytes();
+    let mut result = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        match (bytes[index], bytes.get(index + 1..index + 4)) {
+            (b'\\', Some(digits)) if digits.iter().all(|c| c.is_ascii_digit()) => {
+                let value = digits.iter().fold(0u16, |v, &c| v * 10 + (c - b'0') as u16);
+                result.push(value as u8);
+                index += 4;
+            }
+            (b'\\', _) if index + 1 < bytes.len() => {
+                result.push(bytes[index + 1]);
+                index += 2;
+            }
+            (c, _) => {
+                result.push(c);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
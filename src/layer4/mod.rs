@@ -1,3 +1,35 @@
 //! Application Layer packets
+#[cfg(feature = "http")]
 pub mod http;
+#[cfg(feature = "http2")]
+pub mod http2;
+#[cfg(feature = "http2")]
+pub mod hpack;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+#[cfg(feature = "ntp")]
 pub mod ntp;
+#[cfg(feature = "dns")]
+pub mod dns;
+#[cfg(feature = "mdns")]
+pub mod mdns;
+#[cfg(feature = "llmnr")]
+pub mod llmnr;
+#[cfg(feature = "dhcp")]
+pub mod dhcp;
+#[cfg(feature = "dhcpv6")]
+pub mod dhcpv6;
+
+/// Append a label to a name, special and non-printable characters are escaped
+pub fn escape_label(name: &mut String, label: &[u8]) {
+    for &c in label {
+        match c {
+            b'.' | b'\\' => {
+                name.push('\\');
+                name.push(c as char);
+            }
+            0x21..=0x7e => name.push(c as char),
+            _ => name.push_str(&format!("\\{:03}", c)),
+        }
+    }
+}
@@ -1,7 +1,11 @@
 //! Network Time Protocol related packet processing
 use prelude::*;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-/// The UDP parser
+/// The well known port of NTP
+pub const NTP_PORT: u16 = 123;
+
+/// The NTP parser
 pub struct NtpParser;
 
 impl Parsable<PathIp> for NtpParser {
@@ -18,10 +22,9 @@ impl Parsable<PathIp> for NtpParser {
                 Some(vector) => {
                     match vector.last() {
                         // Check the parent node for the correct transport protocol
-                        Some(ref any) => if let Some(_) = any.downcast_ref::<UdpPacket>() {
-                            Some(())
-                        } else {
-                            None
+                        Some(ref any) => match any.downcast_ref::<UdpPacket>() {
+                            Some(udp) if udp.dispatches_to(&ApplicationProtocol::Ntp) => Some(()),
+                            _ => None,
                         },
 
                         // Previous result found, but not correct parent
@@ -32,38 +35,14 @@ impl Parsable<PathIp> for NtpParser {
                 None => Some(()),
             }) >>
 
-            b0: bits!(tuple!(take_bits!(u8, 2),
-                             take_bits!(u8, 3),
-                             take_bits!(u8, 3))) >>
-            st: be_u8 >>
-            pl: be_i8 >>
-            pr: be_i8 >>
-            rde: be_u32 >>
-            rdi: be_u32 >>
-            rid: be_u32 >>
-            tsr: be_u64 >>
-            tso: be_u64 >>
-            tsv: be_u64 >>
-            tsx: be_u64 >>
-            auth: opt!(complete!(pair!(be_u32,
-                                 map!(take!(16), Vec::from)))) >>
+            // The mode determines the message format
+            result: switch!(peek!(map!(be_u8, |b| b & 0x7)),
+                6 => map!(NtpControlPacket::parse, |p| Box::new(p) as ParserResult) |
+                7 => map!(NtpPrivatePacket::parse, |p| Box::new(p) as ParserResult) |
+                _ => map!(NtpPacket::parse, |p| Box::new(p) as ParserResult)
+            ) >>
 
-            (Box::new(NtpPacket {
-                li: b0.0,
-                version: b0.1,
-                mode: b0.2,
-                stratum: st,
-                poll: pl,
-                precision: pr,
-                root_delay: rde,
-                root_dispersion: rdi,
-                ref_id: rid,
-                ts_ref: tsr,
-                ts_orig: tso,
-                ts_recv: tsv,
-                ts_xmit: tsx,
-                auth: auth,
-            }))
+            (result)
         )
     }
 }
@@ -75,17 +54,18 @@ impl fmt::Display for NtpParser {
 }
 
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Representation of a Network Time Protocol packet
 pub struct NtpPacket {
     /// Leap Indicator (2 Bit)
     /// This field is used to warn of an impending leap second
-    pub li: u8,
+    pub li: LeapIndicator,
 
     /// NTP version number (3 Bit)
     pub version: u8,
 
     /// Mode (3 Bit)
-    pub mode: u8,
+    pub mode: NtpMode,
 
     /// Stratum level of the local clock
     pub stratum: u8,
@@ -119,6 +99,1141 @@ pub struct NtpPacket {
     /// Transmit timestamp. The local time when the reply was sent from the server.
     pub ts_xmit: u64,
 
-    /// Authenticator. (0 or 96 Bit) See section 7.5 of [RFC5905] and [RFC7822]
+    /// Extension fields, which are only present in version 4 packets (RFC 7822)
+    pub extensions: Vec<NtpExtensionField>,
+
+    /// Authenticator, the key identifier and the message digest. See section 7.5 of [RFC5905] and
+    /// [RFC7822]
+    pub auth: Option<(u32, Vec<u8>)>,
+}
+
+impl Summary for NtpPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "NTP")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        write!(w, " v{} {:?} stratum={}", self.version, self.mode, self.stratum)?;
+        if verbosity == Verbosity::Verbose {
+            write!(w,
+                   " leap={:?} poll={} precision={} ref_id={:#x}",
+                   self.li,
+                   self.poll,
+                   self.precision,
+                   self.ref_id)?;
+            if !self.extensions.is_empty() {
+                write!(w, " extensions={}", self.extensions.len())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(NtpPacket);
+
+impl Dissect for NtpPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.named("ntp.li", 2, &self.li)
+            .field("ntp.version", 3, self.version)
+            .named("ntp.mode", 3, &self.mode)
+            .field("ntp.stratum", 8, self.stratum)
+            .field("ntp.poll", 8, self.poll)
+            .field("ntp.precision", 8, self.precision)
+            .field("ntp.root_delay", 32, self.root_delay)
+            .field("ntp.root_dispersion", 32, self.root_dispersion)
+            .field("ntp.ref_id", 32, self.ref_id)
+            .field("ntp.ts_ref", 64, self.ts_ref)
+            .field("ntp.ts_orig", 64, self.ts_orig)
+            .field("ntp.ts_recv", 64, self.ts_recv)
+            .field("ntp.ts_xmit", 64, self.ts_xmit);
+        for _ in &self.extensions {
+            let length = cursor.remaining().get(2..4).map_or(0, |l| u16::from_be_bytes([l[0], l[1]]));
+            let field_type = cursor.peek(16);
+            cursor.group("ntp.extensions", field_type, |field| {
+                field.uint("ntp.extensions.field_type", 16)
+                    .uint("ntp.extensions.length", 16)
+                    .bytes("ntp.extensions.value", length.saturating_sub(4) as usize);
+            });
+        }
+        dissect_auth(&mut cursor, &self.auth);
+        cursor.finish("ntp", self.summary(Verbosity::Normal))
+    }
+}
+
+/// Add the fields of the MAC, which is located at the end of the packet
+fn dissect_auth(cursor: &mut FieldCursor, auth: &Option<(u32, Vec<u8>)>) {
+    if let Some((key_id, ref digest)) = *auth {
+        let end = cursor.position() + cursor.remaining().len() * 8;
+        cursor.skip_to(end - (4 + digest.len()) * 8)
+            .field("ntp.auth.key_id", 32, key_id)
+            .bytes("ntp.auth.digest", digest.len());
+    }
+}
+
+impl NtpPacket {
+    named!(#[doc = "Parse a NTP packet in client, server, symmetric or broadcast mode"],
+           pub parse<&[u8], NtpPacket>,
+        do_parse!(
+            b0: bits!(tuple!(map!(take_bits!(u8, 2), LeapIndicator::from_u8),
+                             take_bits!(u8, 3),
+                             map!(take_bits!(u8, 3), NtpMode::from_u8))) >>
+            st: be_u8 >>
+            pl: be_i8 >>
+            pr: be_i8 >>
+            rde: be_u32 >>
+            rdi: be_u32 >>
+            rid: be_u32 >>
+            tsr: be_u64 >>
+            tso: be_u64 >>
+            tsv: be_u64 >>
+            tsx: be_u64 >>
+            ext: apply!(NtpExtensionField::parse_all, b0.1) >>
+            auth: call!(parse_auth) >>
+
+            (NtpPacket {
+                li: b0.0,
+                version: b0.1,
+                mode: b0.2,
+                stratum: st,
+                poll: pl,
+                precision: pr,
+                root_delay: rde,
+                root_dispersion: rdi,
+                ref_id: rid,
+                ts_ref: tsr,
+                ts_orig: tso,
+                ts_recv: tsv,
+                ts_xmit: tsx,
+                extensions: ext,
+                auth: auth,
+            })
+        )
+    );
+
+    /// The time at which the clock of the sender was last set or corrected
+    pub fn reference_time(&self) -> Option<SystemTime> {
+        NtpTimestamp(self.ts_ref).system_time()
+    }
+
+    /// The time at which the request departed the client, copied by the server
+    pub fn origin_time(&self) -> Option<SystemTime> {
+        NtpTimestamp(self.ts_orig).system_time()
+    }
+
+    /// The time at which the request arrived at the server
+    pub fn receive_time(&self) -> Option<SystemTime> {
+        NtpTimestamp(self.ts_recv).system_time()
+    }
+
+    /// The time at which the packet departed the sender
+    pub fn transmit_time(&self) -> Option<SystemTime> {
+        NtpTimestamp(self.ts_xmit).system_time()
+    }
+
+    /// The total round trip delay to the primary reference source
+    pub fn root_delay_duration(&self) -> Duration {
+        short_to_duration(self.root_delay)
+    }
+
+    /// The total dispersion to the primary reference source
+    pub fn root_dispersion_duration(&self) -> Duration {
+        short_to_duration(self.root_dispersion)
+    }
+
+    /// Interpret the reference identifier by the stratum. The identifier of a secondary server
+    /// is an IPv4 address, or the first four octets of the MD5 hash of an IPv6 address.
+    pub fn reference_id(&self, ipv6: bool) -> NtpReferenceId {
+        let ascii = || {
+            let bytes = [(self.ref_id >> 24) as u8, (self.ref_id >> 16) as u8, (self.ref_id >> 8) as u8,
+                         self.ref_id as u8];
+            String::from_utf8_lossy(&bytes).trim_end_matches('\0').to_owned()
+        };
+        match self.stratum {
+            0 => NtpReferenceId::KissOfDeath(NtpKissCode::new(&ascii())),
+            1 => NtpReferenceId::ReferenceClock(ascii()),
+            2..=15 if ipv6 => NtpReferenceId::Ipv6Hash(self.ref_id),
+            2..=15 => NtpReferenceId::Ipv4(Ipv4Addr::from(self.ref_id)),
+            _ => NtpReferenceId::Unknown(self.ref_id),
+        }
+    }
+
+    /// The Kiss-o'-Death code of a server response with stratum zero
+    pub fn kiss_code(&self) -> Option<NtpKissCode> {
+        match self.reference_id(false) {
+            NtpReferenceId::KissOfDeath(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// The algorithm of the message authentication code, derived from the digest length
+    pub fn mac_algorithm(&self) -> Option<NtpMacAlgorithm> {
+        self.auth.as_ref().map(|(_, digest)| NtpMacAlgorithm::from_length(digest.len()))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Warning of an impending leap second to be inserted or deleted in the last minute of the day
+pub enum LeapIndicator {
+    /// No warning (0)
+    NoWarning,
+
+    /// The last minute of the day has 61 seconds (1)
+    LastMinute61,
+
+    /// The last minute of the day has 59 seconds (2)
+    LastMinute59,
+
+    /// The clock is unsynchronized (3)
+    Unsynchronized,
+}
+
+impl LeapIndicator {
+    /// Convert a u8 to a `LeapIndicator`, only the lower two bits are considered
+    pub fn from_u8(input: u8) -> LeapIndicator {
+        match input & 0x3 {
+            0 => LeapIndicator::NoWarning,
+            1 => LeapIndicator::LastMinute61,
+            2 => LeapIndicator::LastMinute59,
+            _ => LeapIndicator::Unsynchronized,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The association mode of a packet
+pub enum NtpMode {
+    /// Reserved (0)
+    Reserved,
+
+    /// Symmetric active (1)
+    SymmetricActive,
+
+    /// Symmetric passive (2)
+    SymmetricPassive,
+
+    /// Client (3)
+    Client,
+
+    /// Server (4)
+    Server,
+
+    /// Broadcast (5)
+    Broadcast,
+
+    /// NTP control message (6)
+    Control,
+
+    /// Reserved for private use (7)
+    Private,
+}
+
+impl NtpMode {
+    /// Convert a u8 to a `NtpMode`, only the lower three bits are considered
+    pub fn from_u8(input: u8) -> NtpMode {
+        match input & 0x7 {
+            0 => NtpMode::Reserved,
+            1 => NtpMode::SymmetricActive,
+            2 => NtpMode::SymmetricPassive,
+            3 => NtpMode::Client,
+            4 => NtpMode::Server,
+            5 => NtpMode::Broadcast,
+            6 => NtpMode::Control,
+            _ => NtpMode::Private,
+        }
+    }
+}
+
+/// The seconds between the NTP epoch (1900) and the Unix epoch (1970)
+const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+
+/// The seconds of a NTP era
+const NTP_ERA: i64 = 1 << 32;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A 64 bit NTP timestamp with 32 bit seconds and 32 bit fraction
+pub struct NtpTimestamp(pub u64);
+
+impl NtpTimestamp {
+    /// Create a timestamp from a system time, the era is not part of the timestamp
+    pub fn from_system_time(time: SystemTime) -> NtpTimestamp {
+        let (seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => (duration.as_secs() as i64, duration.subsec_nanos()),
+            Err(error) => {
+                let duration = error.duration();
+                match duration.subsec_nanos() {
+                    0 => (-(duration.as_secs() as i64), 0),
+                    nanos => (-(duration.as_secs() as i64) - 1, 1_000_000_000 - nanos),
+                }
+            }
+        };
+        let seconds = (seconds + NTP_UNIX_OFFSET).rem_euclid(NTP_ERA) as u64;
+        let fraction = ((nanos as u64) << 32) / 1_000_000_000;
+        NtpTimestamp(seconds << 32 | fraction)
+    }
+
+    /// The seconds relative to the Unix epoch. Timestamps with the most significant bit set
+    /// belong to era 0 (1968-2036), all others to era 1 (2036-2104).
+    pub fn unix_seconds(&self) -> i64 {
+        let seconds = (self.0 >> 32) as i64;
+        let era = if seconds & 0x8000_0000 != 0 { 0 } else { NTP_ERA };
+        seconds + era - NTP_UNIX_OFFSET
+    }
+
+    /// The nanoseconds of the fraction
+    pub fn subsec_nanos(&self) -> u32 {
+        (((self.0 & 0xffff_ffff) * 1_000_000_000) >> 32) as u32
+    }
+
+    /// Convert the timestamp to a system time, which is not possible if the timestamp is unset
+    pub fn system_time(&self) -> Option<SystemTime> {
+        if self.0 == 0 {
+            return None;
+        }
+        let seconds = self.unix_seconds();
+        let nanos = Duration::new(0, self.subsec_nanos());
+        Some(if seconds >= 0 {
+            UNIX_EPOCH + Duration::from_secs(seconds as u64) + nanos
+        } else {
+            UNIX_EPOCH - Duration::from_secs(-seconds as u64) + nanos
+        })
+    }
+
+    /// The signed difference to another timestamp in seconds, which is correct as long as both
+    /// timestamps are less than 68 years apart
+    pub fn seconds_since(&self, earlier: NtpTimestamp) -> f64 {
+        self.0.wrapping_sub(earlier.0) as i64 as f64 / NTP_ERA as f64
+    }
+}
+
+/// Convert a 32 bit NTP short format with 16 bit seconds and 16 bit fraction to a duration
+fn short_to_duration(value: u32) -> Duration {
+    Duration::new(u64::from(value >> 16),
+                  ((u64::from(value & 0xffff) * 1_000_000_000) >> 16) as u32)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The interpretation of a reference identifier
+pub enum NtpReferenceId {
+    /// A Kiss-o'-Death message of a server with stratum zero
+    KissOfDeath(NtpKissCode),
+
+    /// The ASCII identifier of the reference clock of a primary server, like `GPS`
+    ReferenceClock(String),
+
+    /// The IPv4 address of the upstream server
+    Ipv4(Ipv4Addr),
+
+    /// The first four octets of the MD5 hash of the IPv6 address of the upstream server
+    Ipv6Hash(u32),
+
+    /// The identifier of an unsynchronized or reserved stratum
+    Unknown(u32),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A Kiss-o'-Death code, which tells the client to change its behavior
+pub enum NtpKissCode {
+    /// The association belongs to an anycast server (ACST)
+    Anycast,
+
+    /// Server authentication failed (AUTH)
+    AuthenticationFailed,
+
+    /// Autokey sequence failed (AUTO)
+    Autokey,
+
+    /// The association belongs to a broadcast server (BCST)
+    Broadcast,
+
+    /// Cryptographic authentication or identification failed (CRYP)
+    Cryptographic,
+
+    /// Access denied by the remote server (DENY)
+    Deny,
+
+    /// Lost peer in symmetric mode (DROP)
+    Drop,
+
+    /// Access denied due to local policy (RSTR)
+    Restricted,
+
+    /// The association has not yet synchronized for the first time (INIT)
+    Initializing,
+
+    /// The association belongs to a dynamically discovered server (MCST)
+    Multicast,
+
+    /// No key found (NKEY)
+    NoKey,
+
+    /// The NTS cookie could not be authenticated (NTSN, RFC 8915)
+    NtsNak,
+
+    /// Rate exceeded, the client should reduce its polling (RATE)
+    Rate,
+
+    /// Alteration of the association from a remote host running ntpdc (RMOT)
+    Remote,
+
+    /// A step change in system time has occurred (STEP)
+    Step,
+
+    /// Any other code
+    Unknown(String),
+}
+
+impl NtpKissCode {
+    /// Create a `NtpKissCode` from its ASCII representation
+    pub fn new(code: &str) -> NtpKissCode {
+        match code {
+            "ACST" => NtpKissCode::Anycast,
+            "AUTH" => NtpKissCode::AuthenticationFailed,
+            "AUTO" => NtpKissCode::Autokey,
+            "BCST" => NtpKissCode::Broadcast,
+            "CRYP" => NtpKissCode::Cryptographic,
+            "DENY" => NtpKissCode::Deny,
+            "DROP" => NtpKissCode::Drop,
+            "RSTR" => NtpKissCode::Restricted,
+            "INIT" => NtpKissCode::Initializing,
+            "MCST" => NtpKissCode::Multicast,
+            "NKEY" => NtpKissCode::NoKey,
+            "NTSN" => NtpKissCode::NtsNak,
+            "RATE" => NtpKissCode::Rate,
+            "RMOT" => NtpKissCode::Remote,
+            "STEP" => NtpKissCode::Step,
+            other => NtpKissCode::Unknown(other.to_owned()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The clock offset and round trip delay of a paired client request and server response
+pub struct NtpMeasurement {
+    /// The offset of the server clock relative to the client clock in seconds
+    pub offset: f64,
+
+    /// The round trip delay between client and server in seconds
+    pub delay: f64,
+}
+
+impl NtpMeasurement {
+    /// Compute the offset and delay from a client request, the server response to it and the
+    /// time the response was received by the client. Returns `None` if the packets do not belong
+    /// together.
+    pub fn new(request: &NtpPacket, response: &NtpPacket, received: SystemTime) -> Option<NtpMeasurement> {
+        if request.mode != NtpMode::Client || response.mode != NtpMode::Server || request.ts_xmit == 0 ||
+           response.ts_orig != request.ts_xmit {
+            return None;
+        }
+        let t1 = NtpTimestamp(request.ts_xmit);
+        let t2 = NtpTimestamp(response.ts_recv);
+        let t3 = NtpTimestamp(response.ts_xmit);
+        let t4 = NtpTimestamp::from_system_time(received);
+        Some(NtpMeasurement {
+            offset: (t2.seconds_since(t1) + t3.seconds_since(t4)) / 2.0,
+            delay: t4.seconds_since(t1) - t3.seconds_since(t2),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The algorithm of a message authentication code
+pub enum NtpMacAlgorithm {
+    /// A key identifier without digest, which signals an authentication failure
+    CryptoNak,
+
+    /// A 128 bit digest of MD5 or AES-128-CMAC (RFC 8573)
+    Md5OrCmac,
+
+    /// A 160 bit digest of SHA1
+    Sha1,
+
+    /// Any other digest with its length in bytes
+    Unknown(usize),
+}
+
+impl NtpMacAlgorithm {
+    /// Convert a digest length in bytes to a `NtpMacAlgorithm`
+    pub fn from_length(length: usize) -> NtpMacAlgorithm {
+        match length {
+            0 => NtpMacAlgorithm::CryptoNak,
+            16 => NtpMacAlgorithm::Md5OrCmac,
+            20 => NtpMacAlgorithm::Sha1,
+            _ => NtpMacAlgorithm::Unknown(length),
+        }
+    }
+}
+
+/// Parse the trailing message authentication code. A packet where the extension fields could not
+/// be parsed falls back to a 128 bit digest and the remaining data is left over.
+fn parse_auth(input: &[u8]) -> IResult<&[u8], Option<(u32, Vec<u8>)>> {
+    let digest_length = match input.len() {
+        4 => 0,
+        20 => 16,
+        24 => 20,
+        n if n > 24 => 16,
+        _ => return IResult::Done(input, None),
+    };
+    map!(input,
+         pair!(be_u32, map!(take!(digest_length), Vec::from)),
+         Some)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// An extension field of a version 4 packet (RFC 7822)
+pub enum NtpExtensionField {
+    /// A random value to match a response to its request (0x0104, RFC 8915)
+    UniqueIdentifier(Vec<u8>),
+
+    /// A NTS cookie which is opaque to the client (0x0204, RFC 8915)
+    NtsCookie(Vec<u8>),
+
+    /// A placeholder to request an additional cookie, padded to the cookie length (0x0304, RFC 8915)
+    NtsCookiePlaceholder(Vec<u8>),
+
+    /// The authenticator and the encrypted extension fields (0x0404, RFC 8915)
+    NtsAuthenticator {
+        /// The nonce of the authenticated encryption
+        nonce: Vec<u8>,
+
+        /// The encrypted extension fields and the authentication tag
+        ciphertext: Vec<u8>,
+    },
+
+    /// Any other extension field with its type
+    Unknown(u16, Vec<u8>),
+
+    /// A known extension field with a value which does not match its format
+    Invalid(u16, Vec<u8>),
+}
+
+/// The smallest valid length of an extension field
+const MIN_EXTENSION_LENGTH: u16 = 16;
+
+/// Extension fields are only parsed as long as more data remains than the largest MAC and the
+/// smallest extension field occupy together
+const MAX_TRAILER_LENGTH: usize = 28;
+
+impl NtpExtensionField {
+    named!(#[doc = "Parse a single extension field, which is padded to a multiple of four bytes"],
+           pub parse<&[u8], NtpExtensionField>,
+        do_parse!(
+            field_type: be_u16 >>
+            length: verify!(be_u16, |l| l >= MIN_EXTENSION_LENGTH && l % 4 == 0) >>
+            value: take!(length - 4) >>
+
+            (NtpExtensionField::new(field_type, value))
+        )
+    );
+
+    /// Parse the extension fields in front of the MAC of a packet with the given version
+    pub fn parse_all(input: &[u8], version: u8) -> IResult<&[u8], Vec<NtpExtensionField>> {
+        let mut fields = vec![];
+        let mut input = input;
+        while version == 4 && input.len() > MAX_TRAILER_LENGTH {
+            match NtpExtensionField::parse(input) {
+                IResult::Done(rest, field) => {
+                    fields.push(field);
+                    input = rest;
+                }
+                _ => break,
+            }
+        }
+        IResult::Done(input, fields)
+    }
+
+    /// Decode the value of an extension field by its type
+    pub fn new(field_type: u16, value: &[u8]) -> NtpExtensionField {
+        match field_type {
+            0x0104 => NtpExtensionField::UniqueIdentifier(value.to_vec()),
+            0x0204 => NtpExtensionField::NtsCookie(value.to_vec()),
+            0x0304 => NtpExtensionField::NtsCookiePlaceholder(value.to_vec()),
+            0x0404 => {
+                match do_parse!(value,
+                                nonce_length: be_u16 >>
+                                ciphertext_length: be_u16 >>
+                                nonce: take!(nonce_length) >>
+                                take!(padding(nonce_length as usize)) >>
+                                ciphertext: take!(ciphertext_length) >>
+                                take!(padding(ciphertext_length as usize)) >>
+                                (nonce, ciphertext)) {
+                    IResult::Done(_, (nonce, ciphertext)) => {
+                        NtpExtensionField::NtsAuthenticator {
+                            nonce: nonce.to_vec(),
+                            ciphertext: ciphertext.to_vec(),
+                        }
+                    }
+                    _ => NtpExtensionField::Invalid(field_type, value.to_vec()),
+                }
+            }
+            _ => NtpExtensionField::Unknown(field_type, value.to_vec()),
+        }
+    }
+}
+
+/// The amount of bytes to pad the given length to a multiple of four
+fn padding(length: usize) -> usize {
+    (4 - length % 4) % 4
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a NTP control message (mode 6)
+pub struct NtpControlPacket {
+    /// Leap Indicator (2 Bit)
+    pub li: LeapIndicator,
+
+    /// NTP version number (3 Bit)
+    pub version: u8,
+
+    /// The message is a response
+    pub response: bool,
+
+    /// The response contains an error status
+    pub error: bool,
+
+    /// More fragments of the response follow
+    pub more: bool,
+
+    /// The command of the message
+    pub opcode: NtpControlOpcode,
+
+    /// The sequence number to match responses and requests
+    pub sequence: u16,
+
+    /// The system, peer or clock status, or the error code
+    pub status: u16,
+
+    /// The association the message refers to, zero for the system
+    pub association_id: u16,
+
+    /// The offset of the data within the whole response
+    pub offset: u16,
+
+    /// The data of the message
+    pub data: Vec<u8>,
+
+    /// Authenticator, the key identifier and the message digest
     pub auth: Option<(u32, Vec<u8>)>,
 }
+
+impl Summary for NtpControlPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "NTP")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        write!(w, " control {:?} seq={}", self.opcode, self.sequence)?;
+        if self.response {
+            write!(w, " response")?;
+        }
+        if self.error {
+            write!(w, " error")?;
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w,
+                   " v{} status={:#x} association={} len={}",
+                   self.version,
+                   self.status,
+                   self.association_id,
+                   self.data.len())?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(NtpControlPacket);
+
+impl Dissect for NtpControlPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.named("ntp.li", 2, &self.li)
+            .field("ntp.version", 3, self.version)
+            .uint("ntp.mode", 3)
+            .field("ntp.response", 1, self.response)
+            .field("ntp.error", 1, self.error)
+            .field("ntp.more", 1, self.more)
+            .named("ntp.opcode", 5, &self.opcode)
+            .field("ntp.sequence", 16, self.sequence)
+            .field("ntp.status", 16, self.status)
+            .field("ntp.association_id", 16, self.association_id)
+            .field("ntp.offset", 16, self.offset)
+            .field("ntp.count", 16, self.data.len())
+            .bytes("ntp.data", self.data.len());
+        dissect_auth(&mut cursor, &self.auth);
+        cursor.finish("ntp", self.summary(Verbosity::Normal))
+    }
+}
+
+impl NtpControlPacket {
+    named!(#[doc = "Parse a NTP control message"],
+           pub parse<&[u8], NtpControlPacket>,
+        do_parse!(
+            b0: bits!(tuple!(map!(take_bits!(u8, 2), LeapIndicator::from_u8),
+                             take_bits!(u8, 3),
+                             tag_bits!(u8, 3, 6))) >>
+            b1: bits!(tuple!(map!(take_bits!(u8, 1), |r: u8| r == 1),
+                             map!(take_bits!(u8, 1), |e: u8| e == 1),
+                             map!(take_bits!(u8, 1), |m: u8| m == 1),
+                             map!(take_bits!(u8, 5), NtpControlOpcode::from_u8))) >>
+            sequence: be_u16 >>
+            status: be_u16 >>
+            association_id: be_u16 >>
+            offset: be_u16 >>
+            count: be_u16 >>
+            data: take!(count) >>
+            opt!(complete!(take!(padding(count as usize)))) >>
+            auth: call!(parse_auth) >>
+
+            (NtpControlPacket {
+                li: b0.0,
+                version: b0.1,
+                response: b1.0,
+                error: b1.1,
+                more: b1.2,
+                opcode: b1.3,
+                sequence: sequence,
+                status: status,
+                association_id: association_id,
+                offset: offset,
+                data: data.to_vec(),
+                auth: auth,
+            })
+        )
+    );
+
+    /// Split the data into variables of the form `name=value`, separated by commas. Values can be
+    /// quoted to contain commas.
+    pub fn variables(&self) -> Vec<NtpVariable> {
+        let text = String::from_utf8_lossy(&self.data);
+        let mut variables = vec![];
+        let mut chars = text.trim_end_matches('\0').chars().peekable();
+        while chars.peek().is_some() {
+            let mut name = String::new();
+            let mut value = None;
+            while let Some(c) = chars.next() {
+                match c {
+                    ',' => break,
+                    '=' => {
+                        let mut quoted = false;
+                        let mut content = String::new();
+                        while let Some(&c) = chars.peek() {
+                            if c == ',' && !quoted {
+                                break;
+                            }
+                            if c == '"' {
+                                quoted = !quoted;
+                            } else {
+                                content.push(c);
+                            }
+                            chars.next();
+                        }
+                        value = Some(content.trim().to_owned());
+                    }
+                    _ => name.push(c),
+                }
+            }
+            let name = name.trim();
+            if !name.is_empty() {
+                variables.push(NtpVariable {
+                    name: name.to_owned(),
+                    value: value,
+                });
+            }
+        }
+        variables
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A system or peer variable of a control message
+pub struct NtpVariable {
+    /// The name of the variable
+    pub name: String,
+
+    /// The value of the variable, without quotes
+    pub value: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The command of a control message
+pub enum NtpControlOpcode {
+    /// Read the status of the system or the associations (1)
+    ReadStatus,
+
+    /// Read system or peer variables (2)
+    ReadVariables,
+
+    /// Write system or peer variables (3)
+    WriteVariables,
+
+    /// Read clock variables (4)
+    ReadClock,
+
+    /// Write clock variables (5)
+    WriteClock,
+
+    /// Set a trap address (6)
+    SetTrap,
+
+    /// An asynchronous message of a trap (7)
+    AsyncMessage,
+
+    /// Apply a configuration command (8)
+    Configure,
+
+    /// Save the configuration to a file (9)
+    SaveConfig,
+
+    /// Read the most recently used list (10)
+    ReadMru,
+
+    /// Request a nonce for subsequent commands (12)
+    RequestNonce,
+
+    /// Remove a trap address (31)
+    UnsetTrap,
+
+    /// Any other opcode
+    Unknown(u8),
+}
+
+impl NtpControlOpcode {
+    /// Convert a u8 to a `NtpControlOpcode`
+    pub fn from_u8(input: u8) -> NtpControlOpcode {
+        match input {
+            1 => NtpControlOpcode::ReadStatus,
+            2 => NtpControlOpcode::ReadVariables,
+            3 => NtpControlOpcode::WriteVariables,
+            4 => NtpControlOpcode::ReadClock,
+            5 => NtpControlOpcode::WriteClock,
+            6 => NtpControlOpcode::SetTrap,
+            7 => NtpControlOpcode::AsyncMessage,
+            8 => NtpControlOpcode::Configure,
+            9 => NtpControlOpcode::SaveConfig,
+            10 => NtpControlOpcode::ReadMru,
+            12 => NtpControlOpcode::RequestNonce,
+            31 => NtpControlOpcode::UnsetTrap,
+            other => NtpControlOpcode::Unknown(other),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of a NTP private message (mode 7), which is specific to the reference
+/// implementation
+pub struct NtpPrivatePacket {
+    /// The message is a response
+    pub response: bool,
+
+    /// More fragments of the response follow
+    pub more: bool,
+
+    /// NTP version number (3 Bit)
+    pub version: u8,
+
+    /// The request is authenticated
+    pub authenticated: bool,
+
+    /// The sequence number of a response fragment (7 Bit)
+    pub sequence: u8,
+
+    /// The implementation the request code belongs to
+    pub implementation: u8,
+
+    /// The command of the message
+    pub request_code: NtpRequestCode,
+
+    /// The error code of a response (4 Bit)
+    pub error: u8,
+
+    /// The amount of data items (12 Bit)
+    pub item_count: u16,
+
+    /// The size of a single data item in bytes (12 Bit)
+    pub item_size: u16,
+
+    /// The data items
+    pub items: Vec<Vec<u8>>,
+
+    /// The remaining data, like the padding and authenticator of a request
+    pub data: Vec<u8>,
+}
+
+impl Summary for NtpPrivatePacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "NTP")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        write!(w, " private {:?} seq={}", self.request_code, self.sequence)?;
+        if self.response {
+            write!(w, " response")?;
+        }
+        if verbosity == Verbosity::Verbose {
+            write!(w,
+                   " v{} implementation={} error={} items={}",
+                   self.version,
+                   self.implementation,
+                   self.error,
+                   self.item_count)?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(NtpPrivatePacket);
+
+impl Dissect for NtpPrivatePacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        cursor.field("ntp.response", 1, self.response)
+            .field("ntp.more", 1, self.more)
+            .field("ntp.version", 3, self.version)
+            .uint("ntp.mode", 3)
+            .field("ntp.authenticated", 1, self.authenticated)
+            .field("ntp.sequence", 7, self.sequence)
+            .field("ntp.implementation", 8, self.implementation)
+            .named("ntp.request_code", 8, &self.request_code)
+            .field("ntp.error", 4, self.error)
+            .field("ntp.item_count", 12, self.item_count)
+            .skip(4)
+            .field("ntp.item_size", 12, self.item_size);
+        for item in &self.items {
+            cursor.bytes("ntp.items", item.len());
+        }
+        if !self.data.is_empty() {
+            cursor.bytes("ntp.data", self.data.len());
+        }
+        cursor.finish("ntp", self.summary(Verbosity::Normal))
+    }
+}
+
+/// The size of a monitor list entry with IPv6 support
+const MONITOR_ENTRY_SIZE: u16 = 72;
+
+impl NtpPrivatePacket {
+    named!(#[doc = "Parse a NTP private message"],
+           pub parse<&[u8], NtpPrivatePacket>,
+        do_parse!(
+            b0: bits!(tuple!(map!(take_bits!(u8, 1), |r: u8| r == 1),
+                             map!(take_bits!(u8, 1), |m: u8| m == 1),
+                             take_bits!(u8, 3),
+                             tag_bits!(u8, 3, 7))) >>
+            b1: bits!(pair!(map!(take_bits!(u8, 1), |a: u8| a == 1),
+                            take_bits!(u8, 7))) >>
+            implementation: be_u8 >>
+            request_code: map!(be_u8, NtpRequestCode::from_u8) >>
+            b4: bits!(pair!(take_bits!(u8, 4), take_bits!(u16, 12))) >>
+            item_size: bits!(preceded!(take_bits!(u8, 4), take_bits!(u16, 12))) >>
+            items: take!(b4.1 as usize * item_size as usize) >>
+            data: call!(rest) >>
+
+            (NtpPrivatePacket {
+                response: b0.0,
+                more: b0.1,
+                version: b0.2,
+                authenticated: b1.0,
+                sequence: b1.1,
+                implementation: implementation,
+                request_code: request_code,
+                error: b4.0,
+                item_count: b4.1,
+                item_size: item_size,
+                items: (0..b4.1 as usize)
+                    .map(|i| items[i * item_size as usize..(i + 1) * item_size as usize].to_vec())
+                    .collect(),
+                data: data.to_vec(),
+            })
+        )
+    );
+
+    /// The message is a request for the monitor list, which can be abused for traffic
+    /// amplification
+    pub fn is_monlist_request(&self) -> bool {
+        !self.response && self.request_code.is_monlist()
+    }
+
+    /// Decode the entries of a monitor list response
+    pub fn monlist(&self) -> Vec<NtpMonitorEntry> {
+        if !self.response || !self.request_code.is_monlist() || self.item_size != MONITOR_ENTRY_SIZE {
+            return vec![];
+        }
+        self.items
+            .iter()
+            .filter_map(|item| match NtpMonitorEntry::parse(item) {
+                IResult::Done(_, entry) => Some(entry),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The command of a private message
+pub enum NtpRequestCode {
+    /// List the peers (0)
+    PeerList,
+
+    /// Summarize the peers (1)
+    PeerListSummary,
+
+    /// Information about a peer (2)
+    PeerInfo,
+
+    /// Statistics of a peer (3)
+    PeerStats,
+
+    /// Information about the system (4)
+    SystemInfo,
+
+    /// Statistics of the system (5)
+    SystemStats,
+
+    /// Statistics of the I/O (6)
+    IoStats,
+
+    /// Statistics of the memory (7)
+    MemoryStats,
+
+    /// Information about the loop filter (8)
+    LoopInfo,
+
+    /// Statistics of the timer (9)
+    TimerStats,
+
+    /// Configure a new peer (10)
+    Config,
+
+    /// Remove a peer (11)
+    Unconfig,
+
+    /// Get the monitor list (20)
+    MonGetList,
+
+    /// Get the monitor list with IPv6 support (42)
+    MonGetList1,
+
+    /// Any other request code
+    Unknown(u8),
+}
+
+impl NtpRequestCode {
+    /// Convert a u8 to a `NtpRequestCode`
+    pub fn from_u8(input: u8) -> NtpRequestCode {
+        match input {
+            0 => NtpRequestCode::PeerList,
+            1 => NtpRequestCode::PeerListSummary,
+            2 => NtpRequestCode::PeerInfo,
+            3 => NtpRequestCode::PeerStats,
+            4 => NtpRequestCode::SystemInfo,
+            5 => NtpRequestCode::SystemStats,
+            6 => NtpRequestCode::IoStats,
+            7 => NtpRequestCode::MemoryStats,
+            8 => NtpRequestCode::LoopInfo,
+            9 => NtpRequestCode::TimerStats,
+            10 => NtpRequestCode::Config,
+            11 => NtpRequestCode::Unconfig,
+            20 => NtpRequestCode::MonGetList,
+            42 => NtpRequestCode::MonGetList1,
+            other => NtpRequestCode::Unknown(other),
+        }
+    }
+
+    /// The request code retrieves the monitor list
+    pub fn is_monlist(&self) -> bool {
+        *self == NtpRequestCode::MonGetList || *self == NtpRequestCode::MonGetList1
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// An entry of the monitor list, which describes a recently seen remote host
+pub struct NtpMonitorEntry {
+    /// Seconds since the last packet of the host
+    pub last_time: u32,
+
+    /// Seconds since the first packet of the host
+    pub first_time: u32,
+
+    /// The restriction flags
+    pub restrict: u32,
+
+    /// The amount of received packets
+    pub count: u32,
+
+    /// The address of the host
+    pub address: IpAddr,
+
+    /// The local address the packets were received on
+    pub destination: IpAddr,
+
+    /// Flags about the destination
+    pub flags: u32,
+
+    /// The port of the last received packet
+    pub port: u16,
+
+    /// The mode of the last received packet
+    pub mode: NtpMode,
+
+    /// The version of the last received packet
+    pub version: u8,
+}
+
+impl NtpMonitorEntry {
+    named!(#[doc = "Parse a monitor list entry with IPv6 support"],
+           pub parse<&[u8], NtpMonitorEntry>,
+        do_parse!(
+            last_time: be_u32 >>
+            first_time: be_u32 >>
+            restrict: be_u32 >>
+            count: be_u32 >>
+            address: map!(be_u32, Ipv4Addr::from) >>
+            destination: map!(be_u32, Ipv4Addr::from) >>
+            flags: be_u32 >>
+            port: be_u16 >>
+            mode: map!(be_u8, NtpMode::from_u8) >>
+            version: be_u8 >>
+            v6: map!(be_u32, |f: u32| f != 0) >>
+            be_u32 >>
+            address6: ipv6_address >>
+            destination6: ipv6_address >>
+
+            (NtpMonitorEntry {
+                last_time: last_time,
+                first_time: first_time,
+                restrict: restrict,
+                count: count,
+                address: if v6 { IpAddr::V6(address6) } else { IpAddr::V4(address) },
+                destination: if v6 { IpAddr::V6(destination6) } else { IpAddr::V4(destination) },
+                flags: flags,
+                port: port,
+                mode: mode,
+                version: version,
+            })
+        )
+    );
+}
+
+named!(#[doc = "Parse an IPv6 address"],
+       ipv6_address<&[u8], Ipv6Addr>,
+    map!(take!(16), |a: &[u8]| {
+        let mut address = [0; 16];
+        address.copy_from_slice(a);
+        Ipv6Addr::from(address)
+    })
+);
@@ -0,0 +1,520 @@
+//! WebSocket related packet processing
+use prelude::*;
+use std::collections::HashMap;
+
+#[cfg(feature = "compression")]
+use flate2::{Decompress, FlushDecompress, Status};
+
+/// The maximum amount of buffered data per sender, like incomplete frames or messages
+const MAX_BUFFER_LENGTH: usize = 0x10_0000;
+
+/// The size of the sliding window of the permessage-deflate extension
+const DEFLATE_WINDOW_SIZE: usize = 0x8000;
+
+/// The WebSocket parser
+pub struct WebSocketParser;
+
+impl Parsable<PathIp> for WebSocketParser {
+    /// Parse a `WebSocketPacket` from an `&[u8]`
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 result: Option<&ParserResultVec>,
+                 path: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        // Retrieve the connection related information
+        let sender = connection_endpoints(result).map(|e| e.0);
+        let mut data = connection_data(path, result);
+
+        // The connection switched to WebSocket after a HTTP/1.1 upgrade, the negotiated
+        // permessage-deflate parameters determine whether the compression context is kept
+        let (upgraded, takeover) = match data {
+            Some(ref data) => {
+                match data.http.upgrade {
+                    Some(ref upgrade) if upgrade.protocol == "websocket" => {
+                        let client = data.http.client.is_some() && data.http.client == sender;
+                        (true,
+                         WebSocketDeflate::from_headers(&upgrade.headers).is_some_and(|d| d.context_takeover(client)))
+                    }
+                    _ => (false, false),
+                }
+            }
+            None => (false, false),
+        };
+
+        do_parse!(input,
+            // Check the transport protocol from the parent parser (TCP or the HTTP upgrade)
+            expr_opt!(match result {
+                Some(vector) => match vector.last() {
+                    Some(ref any) => match (any.downcast_ref::<TcpPacket>(), any.downcast_ref::<HttpPacket>()) {
+                        (Some(_), _) | (_, Some(_)) if upgraded && !input.is_empty() => Some(()),
+                        _ => None,
+                    },
+
+                    // Previous result found, but not correct parent
+                    _ => None,
+                },
+                // Parse also if no result is given, for testability
+                None => Some(()),
+            }) >>
+
+            packet: apply!(WebSocketPacket::parse,
+                           data.as_mut().map(|d| &mut d.websocket),
+                           sender.as_ref(),
+                           takeover) >>
+
+            (Box::new(packet))
+        )
+    }
+}
+
+impl fmt::Display for WebSocketParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WebSocket")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Representation of the WebSocket frames within a packet
+pub struct WebSocketPacket {
+    /// The frames which are complete within the packet. Incomplete frames at the end of the packet
+    /// are reported with the next packet of the sender.
+    pub frames: Vec<WebSocketFrame>,
+
+    /// The data messages which are completed by the frames of this packet
+    pub messages: Vec<WebSocketMessage>,
+}
+
+impl Summary for WebSocketPacket {
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        write!(w, "WebSocket")?;
+        if verbosity == Verbosity::Brief {
+            return Ok(());
+        }
+        let frames: Vec<_> = self.frames
+            .iter()
+            .map(|frame| if verbosity == Verbosity::Verbose {
+                format!("{:?}[len={}{}{}]",
+                        frame.opcode,
+                        frame.length,
+                        if frame.fin { " fin" } else { "" },
+                        if frame.masking_key.is_some() { " masked" } else { "" })
+            } else {
+                format!("{:?}", frame.opcode)
+            })
+            .collect();
+        if !frames.is_empty() {
+            write!(w, " {}", frames.join(", "))?;
+        }
+        if !self.messages.is_empty() {
+            write!(w, " messages={}", self.messages.len())?;
+        }
+        Ok(())
+    }
+}
+
+summary_display!(WebSocketPacket);
+
+impl Dissect for WebSocketPacket {
+    fn dissect(&self, input: &[u8], offset: usize) -> Field {
+        let mut cursor = FieldCursor::new(input, offset);
+        // Frames which started within a previous packet are not located within this one
+        for frame in &self.frames {
+            let extended_length = match cursor.peek(16) & 0x7f {
+                126 => 16,
+                127 => 64,
+                _ => 0,
+            };
+            let header_length = 2 + extended_length / 8 + frame.masking_key.map_or(0, |_| 4);
+            if cursor.remaining().len() < header_length + frame.length as usize ||
+               (cursor.peek(1) == 1) != frame.fin {
+                break;
+            }
+            cursor.group("websocket.frames", format!("{:?}", frame.opcode), |field| {
+                field.field("websocket.frames.fin", 1, frame.fin)
+                    .field("websocket.frames.reserved", 3, frame.reserved)
+                    .named("websocket.frames.opcode", 4, &frame.opcode)
+                    .field("websocket.frames.masked", 1, frame.masking_key.is_some())
+                    .field("websocket.frames.length", 7 + extended_length, frame.length);
+                if let Some(key) = frame.masking_key {
+                    field.bytes("websocket.frames.masking_key", key.len());
+                }
+                field.bytes("websocket.frames.payload", frame.length as usize);
+            });
+        }
+        cursor.finish("websocket", self.summary(Verbosity::Normal))
+    }
+}
+
+impl WebSocketPacket {
+    /// Parse all frames of a packet. The state is used to reassemble frames and fragmented
+    /// messages over multiple packets, as well as for the decompression context.
+    pub fn parse<'a>(input: &'a [u8],
+                     mut state: Option<&mut WebSocketState>,
+                     sender: Option<&Endpoint>,
+                     takeover: bool)
+                     -> IResult<&'a [u8], WebSocketPacket> {
+        // The frame boundaries of a sender are unknown after an oversized frame
+        if let (Some(state), Some(sender)) = (state.as_ref(), sender) {
+            if state.desynchronized.contains(sender) {
+                return IResult::Done(&input[input.len()..],
+                                     WebSocketPacket {
+                                         frames: vec![],
+                                         messages: vec![],
+                                     });
+            }
+        }
+
+        // The state is only usable if the sender is known, its pending data is taken over and
+        // stored again afterwards, which avoids copying it for every packet
+        let (mut buffer, mut message, mut window) = match (state.as_mut(), sender) {
+            (Some(state), Some(sender)) => {
+                (state.buffers.remove(sender).unwrap_or_default(),
+                 state.messages.remove(sender),
+                 state.windows.remove(sender).unwrap_or_default())
+            }
+            _ => (vec![], None, vec![]),
+        };
+        let buffered = buffer.len();
+        buffer.extend_from_slice(input);
+
+        let mut frames = vec![];
+        let mut messages = vec![];
+        let mut offset = 0;
+        let mut incomplete = false;
+        loop {
+            match WebSocketFrame::parse(&buffer[offset..]) {
+                IResult::Done(rest, frame) => {
+                    offset = buffer.len() - rest.len();
+                    if let Some(complete) = WebSocketMessage::add_frame(&mut message, &frame) {
+                        messages.push(complete.decode(&mut window, takeover));
+                    }
+                    frames.push(frame);
+                }
+                IResult::Incomplete(_) => {
+                    incomplete = true;
+                    break;
+                }
+                IResult::Error(_) => break,
+            }
+        }
+
+        // Keep the incomplete frame and message for the next packet of the sender. Frames which
+        // are too large to be parsed at all stop the parsing of the sender, since the start of
+        // the following frame is unknown.
+        let left = match (state, sender) {
+            (Some(state), Some(sender)) => {
+                if offset < buffer.len() && (!incomplete || buffer.len() - offset > MAX_BUFFER_LENGTH) {
+                    state.desynchronized.push(*sender);
+                    return IResult::Done(&input[input.len()..],
+                                         WebSocketPacket {
+                                             frames: frames,
+                                             messages: messages,
+                                         });
+                }
+                if incomplete && offset < buffer.len() {
+                    buffer.drain(..offset);
+                    state.buffers.insert(*sender, buffer);
+                }
+                if let Some(message) = message {
+                    state.messages.insert(*sender, message);
+                }
+                state.windows.insert(*sender, window);
+                &input[input.len()..]
+            }
+            _ => &input[offset.saturating_sub(buffered)..],
+        };
+
+        IResult::Done(left,
+                      WebSocketPacket {
+                          frames: frames,
+                          messages: messages,
+                      })
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A WebSocket frame
+pub struct WebSocketFrame {
+    /// Indicates the final fragment of a message
+    pub fin: bool,
+
+    /// The three reserved bits, where the first one marks compressed messages
+    pub reserved: u8,
+
+    /// The interpretation of the payload
+    pub opcode: WebSocketOpcode,
+
+    /// The key which is used to mask frames from the client to the server
+    pub masking_key: Option<[u8; 4]>,
+
+    /// The length of the payload
+    pub length: u64,
+
+    /// The unmasked payload
+    pub payload: Vec<u8>,
+}
+
+impl WebSocketFrame {
+    named!(#[doc = "Parse a single frame"],
+           pub parse<&[u8], WebSocketFrame>,
+        do_parse!(
+            first: be_u8 >>
+            second: be_u8 >>
+            length: switch!(value!(second & 0x7f),
+                126 => map!(be_u16, u64::from) |
+                127 => verify!(be_u64, |l| l <= MAX_BUFFER_LENGTH as u64) |
+                length => value!(length as u64)
+            ) >>
+            masking_key: cond_with_error!(second & 0x80 != 0, map!(take!(4), |k: &[u8]| [k[0], k[1], k[2], k[3]])) >>
+            payload: take!(length as usize) >>
+
+            (WebSocketFrame {
+                fin: first & 0x80 != 0,
+                reserved: (first >> 4) & 0x7,
+                opcode: WebSocketOpcode::from_u8(first & 0xf),
+                masking_key: masking_key,
+                length: length,
+                payload: match masking_key {
+                    Some(key) => payload.iter().zip(key.iter().cycle()).map(|(c, k)| c ^ k).collect(),
+                    None => payload.to_vec(),
+                },
+            })
+        )
+    );
+
+    /// Indicates that the frame starts a compressed message (permessage-deflate)
+    pub fn is_compressed(&self) -> bool {
+        self.reserved & 0x4 != 0
+    }
+
+    /// Retrieve the status code and reason of a close frame
+    pub fn close_status(&self) -> Option<(u16, String)> {
+        match (&self.opcode, self.payload.len()) {
+            (&WebSocketOpcode::Close, 2..) => {
+                Some(((self.payload[0] as u16) << 8 | self.payload[1] as u16,
+                      String::from_utf8_lossy(&self.payload[2..]).into_owned()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// WebSocket frame opcodes
+pub enum WebSocketOpcode {
+    /// The continuation of a fragmented message
+    Continuation,
+
+    /// A UTF-8 text message
+    Text,
+
+    /// A binary message
+    Binary,
+
+    /// Closes the connection
+    Close,
+
+    /// A keepalive request
+    Ping,
+
+    /// The response to a ping
+    Pong,
+
+    /// A reserved opcode
+    Unknown(u8),
+}
+
+impl WebSocketOpcode {
+    /// Convert a u8 to a `WebSocketOpcode`
+    pub fn from_u8(input: u8) -> WebSocketOpcode {
+        match input {
+            0x0 => WebSocketOpcode::Continuation,
+            0x1 => WebSocketOpcode::Text,
+            0x2 => WebSocketOpcode::Binary,
+            0x8 => WebSocketOpcode::Close,
+            0x9 => WebSocketOpcode::Ping,
+            0xa => WebSocketOpcode::Pong,
+            other => WebSocketOpcode::Unknown(other),
+        }
+    }
+
+    /// Control frames may be injected in the middle of a fragmented message
+    pub fn is_control(&self) -> bool {
+        match *self {
+            WebSocketOpcode::Close | WebSocketOpcode::Ping | WebSocketOpcode::Pong => true,
+            WebSocketOpcode::Unknown(opcode) => opcode >= 0x8,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// A text or binary message, which may consist of multiple frames
+pub struct WebSocketMessage {
+    /// The opcode of the first frame
+    pub opcode: WebSocketOpcode,
+
+    /// The reassembled payload of all frames
+    pub data: Vec<u8>,
+
+    /// Indicates that the payload is compressed by the permessage-deflate extension
+    pub compressed: bool,
+
+    /// The decompressed payload. Only available if the message is complete and could be
+    /// decompressed.
+    pub content: Option<Vec<u8>>,
+}
+
+impl WebSocketMessage {
+    /// Add a frame to the pending message. Returns the message if it is completed by the frame.
+    fn add_frame(pending: &mut Option<WebSocketMessage>, frame: &WebSocketFrame) -> Option<WebSocketMessage> {
+        match frame.opcode {
+            WebSocketOpcode::Text | WebSocketOpcode::Binary => {
+                *pending = Some(WebSocketMessage {
+                    opcode: frame.opcode.clone(),
+                    data: frame.payload.clone(),
+                    compressed: frame.is_compressed(),
+                    content: None,
+                })
+            }
+            WebSocketOpcode::Continuation => {
+                // The start of the message is unknown, so the fragment is not usable
+                match *pending {
+                    Some(ref mut message) if message.data.len() + frame.payload.len() <= MAX_BUFFER_LENGTH => {
+                        message.data.extend_from_slice(&frame.payload)
+                    }
+                    _ => *pending = None,
+                }
+            }
+            _ => return None,
+        }
+        if frame.fin { pending.take() } else { None }
+    }
+
+    /// Decode a complete message by the sliding window of the sender, which is updated afterwards
+    fn decode(mut self, window: &mut Vec<u8>, takeover: bool) -> WebSocketMessage {
+        self.content = if self.compressed {
+            inflate(window, &self.data)
+        } else {
+            Some(self.data.clone())
+        };
+
+        // The window is only needed if the compression context is kept between messages
+        match self.content {
+            Some(ref content) if self.compressed && takeover => {
+                window.extend_from_slice(content);
+                let excess = window.len().saturating_sub(DEFLATE_WINDOW_SIZE);
+                window.drain(..excess);
+            }
+            _ => window.clear(),
+        }
+        self
+    }
+
+    /// Retrieve the content of a text message
+    pub fn text(&self) -> Option<&str> {
+        match (&self.opcode, &self.content) {
+            (WebSocketOpcode::Text, Some(content)) => str::from_utf8(content).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// The negotiated parameters of the permessage-deflate extension (RFC 7692)
+pub struct WebSocketDeflate {
+    /// The server resets its compression context for every message
+    pub server_no_context_takeover: bool,
+
+    /// The client resets its compression context for every message
+    pub client_no_context_takeover: bool,
+}
+
+impl WebSocketDeflate {
+    /// Retrieve the parameters from the `Sec-WebSocket-Extensions` headers of the handshake
+    /// response. Returns None if the extension has not been accepted.
+    pub fn from_headers(headers: &[HttpHeader]) -> Option<WebSocketDeflate> {
+        HttpHeader::list(headers, "Sec-WebSocket-Extensions")
+            .into_iter()
+            .map(|e| e.split(';').map(str::trim).collect::<Vec<_>>())
+            .find(|e| e[0].eq_ignore_ascii_case("permessage-deflate"))
+            .map(|parameters| {
+                WebSocketDeflate {
+                    server_no_context_takeover: parameters.iter()
+                        .any(|p| p.eq_ignore_ascii_case("server_no_context_takeover")),
+                    client_no_context_takeover: parameters.iter()
+                        .any(|p| p.eq_ignore_ascii_case("client_no_context_takeover")),
+                }
+            })
+    }
+
+    /// Check if the client or server keeps its compression context between messages
+    pub fn context_takeover(&self, client: bool) -> bool {
+        if client {
+            !self.client_no_context_takeover
+        } else {
+            !self.server_no_context_takeover
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+/// The WebSocket related state of a connection
+pub struct WebSocketState {
+    /// Incomplete frames at the end of the previous packet, identified by their sender
+    pub buffers: HashMap<Endpoint, Vec<u8>>,
+
+    /// Fragmented messages which are not complete yet, identified by their sender
+    pub messages: HashMap<Endpoint, WebSocketMessage>,
+
+    /// The recently decompressed data of each sender, which may be referenced by its next message
+    pub windows: HashMap<Endpoint, Vec<u8>>,
+
+    /// Senders whose frames are not parsed anymore, since a frame exceeded the buffer limit and
+    /// the start of the following frame is unknown
+    pub desynchronized: Vec<Endpoint>,
+}
+
+#[cfg(feature = "compression")]
+/// Decompress a message of the permessage-deflate extension. The window is prepended as a stored
+/// block, which allows the compressed data to refer to previous messages.
+fn inflate(window: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+    let mut input = vec![0x00];
+    input.extend_from_slice(&[window.len() as u8, (window.len() >> 8) as u8]);
+    input.extend_from_slice(&[!window.len() as u8, (!window.len() >> 8) as u8]);
+    input.extend_from_slice(window);
+    input.extend_from_slice(data);
+    input.extend_from_slice(&[0x00, 0x00, 0xff, 0xff]);
+
+    let mut decompress = Decompress::new(false);
+    let mut output = Vec::with_capacity(input.len() * 4);
+    loop {
+        let (total_in, total_out) = (decompress.total_in(), decompress.total_out());
+        let status = decompress.decompress_vec(&input[total_in as usize..], &mut output, FlushDecompress::Sync)
+            .ok()?;
+        if status == Status::StreamEnd || decompress.total_in() as usize == input.len() {
+            break;
+        }
+        if output.len() > MAX_BUFFER_LENGTH + window.len() {
+            return None;
+        }
+        if output.len() == output.capacity() {
+            output.reserve(output.len());
+        } else if decompress.total_in() == total_in && decompress.total_out() == total_out {
+            return None;
+        }
+    }
+    Some(output.split_off(window.len().min(output.len())))
+}
+
+#[cfg(not(feature = "compression"))]
+/// Decompress a message of the permessage-deflate extension, not supported without the
+/// `compression` feature
+fn inflate(_: &[u8], _: &[u8]) -> Option<Vec<u8>> {
+    None
+}
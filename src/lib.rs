@@ -9,6 +9,14 @@
 //! let result = peel.traverse(&input, vec![]).result;
 //! assert_eq!(result.len(), 1);
 //! ```
+//!
+//! ## Cargo features
+//! Every parser above the TCP and UDP layer, as well as ARP, ICMP and ICMPv6, can be compiled
+//! out by its own feature, like `tls`, `http` or `ntp`. All of them are enabled by default. The
+//! `prelude`, the dispatch registries and `NewPeelIp::default` only contain the enabled parsers.
+//!
+//! The optional `serde` feature implements `Serialize` and `Deserialize` for all packets, and adds
+//! `TaggedResults` to serialize a complete traversal as a layered JSON document.
 #![deny(missing_docs)]
 
 #[macro_use]
@@ -19,12 +27,33 @@ extern crate nom;
 extern crate peel;
 extern crate path;
 
+#[cfg(feature = "compression")]
+extern crate flate2;
+#[cfg(feature = "compression")]
+extern crate brotli_decompressor;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
+pub mod bpf;
+pub mod builder;
+pub mod capture;
+pub mod dissection;
+pub mod filter;
+#[macro_use]
+pub mod summary;
 pub mod layer1;
 pub mod layer2;
 pub mod layer3;
 pub mod layer4;
+pub mod packet;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod statistics;
 
 use prelude::*;
+use std::time::Duration;
 
 /// Provides sensible imports for packet parsers
 pub mod prelude {
@@ -39,69 +68,94 @@ pub mod prelude {
     pub use path::error::ErrorType as PathErrorType;
     pub use peel::prelude::*;
     pub use super::NewPeelIp;
+    pub use bpf::*;
+    pub use builder::*;
+    pub use capture::*;
+    pub use dissection::*;
+    pub use filter::*;
+    pub use packet::*;
+    pub use statistics::*;
+    pub use summary::*;
+    #[cfg(feature = "serde")]
+    pub use serde::{Deserialize, Serialize};
+    #[cfg(feature = "serde")]
+    pub use serialization::*;
 
     /// A shorthand for the TCP/IP based `Peel`
     pub type PeelIp = Peel<PathIp>;
 
     /// A shorthand for the `IpProtocol` based `Path`
-    pub type PathIp = Path<IpProtocol, ()>;
+    pub type PathIp = Path<IpProtocol, ConnectionData>;
 
     /// Link
     pub use layer1::*;
     pub use layer1::ethernet::*;
+    #[cfg(feature = "arp")]
     pub use layer1::arp::*;
 
     /// Internet
     pub use layer2::*;
     pub use layer2::ipv4::*;
     pub use layer2::ipv6::*;
+    #[cfg(feature = "icmp")]
     pub use layer2::icmp::*;
+    #[cfg(feature = "icmpv6")]
     pub use layer2::icmpv6::*;
 
     // Transport
     pub use layer3::*;
+    pub use layer3::dispatch::*;
     pub use layer3::tcp::*;
+    #[cfg(feature = "tls")]
     pub use layer3::tls::*;
+    #[cfg(feature = "dtls")]
+    pub use layer3::dtls::*;
     pub use layer3::udp::*;
 
     // Application
+    pub use layer4::escape_label;
+    #[cfg(feature = "http")]
     pub use layer4::http::*;
+    #[cfg(feature = "http2")]
+    pub use layer4::http2::*;
+    #[cfg(feature = "http2")]
+    pub use layer4::hpack::*;
+    #[cfg(feature = "websocket")]
+    pub use layer4::websocket::*;
+    #[cfg(feature = "ntp")]
     pub use layer4::ntp::*;
+    #[cfg(feature = "dns")]
+    pub use layer4::dns::*;
+    #[cfg(feature = "mdns")]
+    pub use layer4::mdns::*;
+    #[cfg(feature = "llmnr")]
+    pub use layer4::llmnr::*;
+    #[cfg(feature = "dhcp")]
+    pub use layer4::dhcp::*;
+    #[cfg(feature = "dhcpv6")]
+    pub use layer4::dhcpv6::*;
 }
 
 /// Trait for default parser tree generation
 pub trait NewPeelIp {
     /// Get the default parser tree
     fn default() -> PeelIp {
-        // Create a tree
-        let mut p = Peel::new();
-
-        // Create the parsers
-        let eth = p.new_parser(EthernetParser);
-        let arp = p.new_parser(ArpParser);
-        let ipv4 = p.new_parser(Ipv4Parser);
-        let ipv6 = p.new_parser(Ipv6Parser);
-        let icmp = p.new_parser(IcmpParser);
-        let icmpv6 = p.new_parser(Icmpv6Parser);
-        let tcp = p.new_parser(TcpParser);
-        let udp = p.new_parser(UdpParser);
-        let tls = p.new_parser(TlsParser);
-        let http = p.new_parser(HttpParser);
-        let ntp = p.new_parser(NtpParser);
-
-        // Link the parsers
-        p.link_nodes(&[(eth, arp), (eth, ipv4), (eth, ipv6),
-                       (ipv4, ipv4), (ipv4, ipv6), (ipv6, ipv6),
-                       (ipv4, icmp), (ipv6, icmpv6), (ipv4, tcp),
-                       (ipv6, tcp), (ipv4, udp), (ipv6, udp),
-                       (tcp, tls), (tcp, http), (tls, http),
-                       (udp, ntp)]);
-
-        // Create a path instance
-        p.data = Some(Path::new());
-
-        p
+        Self::with_registries(DispatchRegistry::tcp(), DispatchRegistry::udp())
+    }
+
+    /// Get the default parser tree, where the TCP and UDP payload is dispatched to the application
+    /// protocols by the given registries
+    fn with_registries(tcp_registry: DispatchRegistry, udp_registry: DispatchRegistry) -> PeelIp {
+        PeelIpBuilder::new().tcp_registry(tcp_registry).udp_registry(udp_registry).build()
     }
+
+    /// Traverse the parser tree with a frame which has been captured at the given time since the
+    /// Unix epoch. Latencies and timeouts are based on this time instead of the system time.
+    fn traverse_at<'a>(&mut self, input: &'a [u8], result: ParserResultVec, time: Duration) -> PeelResult<'a>;
 }
 
-impl NewPeelIp for PeelIp {}
+impl NewPeelIp for PeelIp {
+    fn traverse_at<'a>(&mut self, input: &'a [u8], result: ParserResultVec, time: Duration) -> PeelResult<'a> {
+        with_capture_time(time, || self.traverse(input, result))
+    }
+}
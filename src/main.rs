@@ -0,0 +1,379 @@
+//! Command line dissector for pcap and pcapng files
+extern crate peel_ip;
+
+use peel_ip::prelude::*;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::SocketAddr;
+use std::time::Duration;
+use std::{env, process};
+
+const USAGE: &str = "Usage: peel-ip [COMMAND] [OPTIONS] <FILE>
+       peel-ip bpf [-v] -f <EXPRESSION>
+
+Dissects the frames of a pcap or pcapng file, where the file '-' is the standard input.
+
+Commands:
+    summary          Print one line per packet (default)
+    tree             Print the field tree of every packet
+    json             Print one JSON document per packet (requires the serde feature)
+    hierarchy        Print the packets and bytes per protocol
+    conversations    Print the packets and bytes per conversation
+    endpoints        Print the packets and bytes per IP address
+    bpf              Print the classic BPF program of the capture filter for Ethernet frames, or
+                     with '--verbose' as C array
+
+Options:
+    -b, --brief                 Print only the highest protocol of every packet
+    -v, --verbose               Print all layers of every packet with all details
+    -p, --protocol <NAME>       Only packets containing the protocol, like 'tcp' or 'dns'
+        --host <ADDRESS>        Only packets from or to the address
+        --port <PORT>           Only packets from or to the port
+        --connection <A>,<B>    Only packets between two endpoints, like '10.0.0.1:80,10.0.0.2:4321'
+    -f, --capture-filter <EXPRESSION>
+                                Only frames matching the pcap-filter expression, like 'tcp port 80'
+    -Y, --filter <EXPRESSION>   Only packets matching the display filter, like 'tcp.dest_port == 443'
+    -c, --count <COUNT>         Stop after the given amount of matching packets
+    -h, --help                  Print this help
+
+All filters have to match, where the addresses and ports are taken from the innermost IP layer.";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The output of the dissector
+enum Command {
+    /// One line per packet
+    Summary,
+
+    /// The field tree of every packet
+    Tree,
+
+    /// One JSON document per packet
+    Json,
+
+    /// The protocol hierarchy statistics
+    Hierarchy,
+
+    /// The conversation statistics
+    Conversations,
+
+    /// The endpoint statistics
+    Endpoints,
+
+    /// The BPF program of the capture filter
+    Bpf,
+}
+
+/// The command line options
+struct Options {
+    /// The output of the dissector
+    command: Command,
+
+    /// The verbosity of the summaries
+    verbosity: Verbosity,
+
+    /// The capture filter, which is compiled for the link type of every frame
+    capture_filter: Option<String>,
+
+    /// The packet filters
+    filter: Filter,
+
+    /// The maximum amount of printed or counted packets
+    count: Option<usize>,
+
+    /// The capture file
+    file: String,
+}
+
+impl Options {
+    /// Parse the command line arguments without the program name, which is `None` if the help
+    /// was requested
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+        let mut options = Options {
+            command: Command::Summary,
+            verbosity: Verbosity::Normal,
+            capture_filter: None,
+            filter: Filter::default(),
+            count: None,
+            file: String::new(),
+        };
+        let mut positional = vec![];
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("Missing value for '{}'", name));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-b" | "--brief" => options.verbosity = Verbosity::Brief,
+                "-v" | "--verbose" => options.verbosity = Verbosity::Verbose,
+                "-p" | "--protocol" => options.filter.protocols.push(value(&arg)?.parse()?),
+                "--host" => options.filter.hosts.push(parse_value(&value(&arg)?, "address")?),
+                "--port" => options.filter.ports.push(parse_value(&value(&arg)?, "port")?),
+                "-f" | "--capture-filter" => {
+                    let expression = value(&arg)?;
+                    CaptureFilter::new(&expression)
+                        .map_err(|e| format!("Invalid capture filter '{}': {}", expression, e))?;
+                    options.capture_filter = Some(expression);
+                }
+                "-Y" | "--filter" => {
+                    let expression = value(&arg)?;
+                    let filter = expression.parse().map_err(|e| format!("Invalid filter '{}': {}", expression, e))?;
+                    options.filter.display = Some(filter);
+                }
+                "--connection" => {
+                    let connection = value(&arg)?;
+                    let mut endpoints = connection.splitn(2, ',');
+                    let a = parse_value(endpoints.next().unwrap_or_default(), "endpoint")?;
+                    let b = parse_value(endpoints.next().unwrap_or_default(), "endpoint")?;
+                    options.filter.connection = Some((a, b));
+                }
+                "-c" | "--count" => options.count = Some(parse_value(&value(&arg)?, "count")?),
+                "-" => positional.push(arg),
+                _ if arg.starts_with('-') => return Err(format!("Unknown option '{}'", arg)),
+                _ => positional.push(arg),
+            }
+        }
+
+        // An optional command followed by the file
+        let mut positional = positional.into_iter();
+        let first = positional.next().ok_or_else(|| "Missing capture file".to_owned())?;
+        options.command = match first.as_str() {
+            "summary" => Command::Summary,
+            "tree" => Command::Tree,
+            "json" => Command::Json,
+            "hierarchy" => Command::Hierarchy,
+            "conversations" => Command::Conversations,
+            "endpoints" => Command::Endpoints,
+            "bpf" => Command::Bpf,
+            _ => {
+                options.file = first;
+                Command::Summary
+            }
+        };
+        if options.command == Command::Bpf && options.capture_filter.is_none() {
+            return Err("The bpf command requires a capture filter".to_owned());
+        }
+        if options.file.is_empty() && options.command != Command::Bpf {
+            options.file = positional.next().ok_or_else(|| "Missing capture file".to_owned())?;
+        }
+        if let Some(arg) = positional.next() {
+            return Err(format!("Unexpected argument '{}'", arg));
+        }
+        if cfg!(not(feature = "serde")) && options.command == Command::Json {
+            return Err("The JSON output requires the serde feature".to_owned());
+        }
+        Ok(Some(options))
+    }
+}
+
+/// Parse the value of an option
+fn parse_value<T: FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid {} '{}'", name, value))
+}
+
+#[derive(Default)]
+/// Filters on the protocols and endpoints of a packet, which all have to match
+struct Filter {
+    /// Protocols which have to be contained
+    protocols: Vec<Protocol>,
+
+    /// Addresses which have to be the source or destination
+    hosts: Vec<IpAddr>,
+
+    /// Ports which have to be the source or destination
+    ports: Vec<u16>,
+
+    /// Endpoints which have to be the source and destination in any direction
+    connection: Option<(SocketAddr, SocketAddr)>,
+
+    /// A display filter on the fields of the packet
+    display: Option<DisplayFilter>,
+}
+
+impl Filter {
+    /// Check if the packet matches all filters, the frame is needed for the display filter
+    fn matches(&self, packet: &Packet, frame: &[u8]) -> bool {
+        if self.display.as_ref().is_some_and(|filter| !filter.matches_packet(packet, frame)) {
+            return false;
+        }
+        let contains = |protocol: &Protocol| packet.iter().any(|layer| layer.protocol() == Some(*protocol));
+        if !self.protocols.iter().all(contains) {
+            return false;
+        }
+        if self.hosts.is_empty() && self.ports.is_empty() && self.connection.is_none() {
+            return true;
+        }
+        let (protocol, src, dst) = match packet.flow() {
+            Some(flow) => flow,
+            None => return false,
+        };
+        let has_ports = protocol == Protocol::Tcp || protocol == Protocol::Udp;
+        self.hosts.iter().all(|host| *host == src.0 || *host == dst.0) &&
+        self.ports.iter().all(|port| has_ports && (*port == src.1 || *port == dst.1)) &&
+        self.connection.is_none_or(|(a, b)| {
+            let (a, b) = ((a.ip(), a.port()), (b.ip(), b.port()));
+            has_ports && ((a, b) == (src, dst) || (b, a) == (src, dst))
+        })
+    }
+}
+
+/// The parser trees for the different link types of a capture file
+#[derive(Default)]
+struct Dissector {
+    /// The parser trees by their root protocol
+    trees: HashMap<Protocol, PeelIp>,
+}
+
+impl Dissector {
+    /// Parse a captured frame, which results in no layers for unknown link types
+    fn traverse(&mut self, record: &CaptureRecord) -> ParserResultVec {
+        let root = match record.link_type.root(&record.data) {
+            Some(root) => root,
+            None => return vec![],
+        };
+        let peel = self.trees.entry(root).or_insert_with(|| PeelIpBuilder::new().root(root).build());
+        peel.traverse_at(&record.data, vec![], record.timestamp).result
+    }
+}
+
+/// The statistics to collect over all packets
+enum Statistics {
+    /// The protocol hierarchy
+    Hierarchy(ProtocolHierarchy),
+
+    /// The conversations
+    Conversations(Conversations),
+
+    /// The endpoints
+    Endpoints(Endpoints),
+}
+
+/// Dissect the capture file and write the output
+fn run(options: &Options, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    if let (Command::Bpf, Some(expression)) = (options.command, options.capture_filter.as_ref()) {
+        let program = CaptureFilter::new(expression)?.to_bpf()?;
+        match options.verbosity {
+            Verbosity::Verbose => write!(out, "{:#}", program)?,
+            _ => write!(out, "{}", program)?,
+        }
+        out.flush()?;
+        return Ok(());
+    }
+
+    let input: Box<dyn Read> = match options.file.as_str() {
+        "-" => Box::new(io::stdin()),
+        file => Box::new(File::open(file).map_err(|e| format!("Unable to open '{}': {}", file, e))?),
+    };
+    let reader = CaptureReader::new(BufReader::new(input))?;
+    let mut statistics = match options.command {
+        Command::Hierarchy => Some(Statistics::Hierarchy(ProtocolHierarchy::default())),
+        Command::Conversations => Some(Statistics::Conversations(Conversations::default())),
+        Command::Endpoints => Some(Statistics::Endpoints(Endpoints::default())),
+        _ => None,
+    };
+
+    let mut dissector = Dissector::default();
+    let mut capture_filters = HashMap::new();
+    let mut start = None;
+    let mut matched = 0;
+    for (index, record) in reader.enumerate() {
+        if options.count.is_some_and(|count| matched >= count) {
+            break;
+        }
+        let record = record?;
+        let start = *start.get_or_insert(record.timestamp);
+        if let Some(ref expression) = options.capture_filter {
+            let filter = match capture_filters.entry(record.link_type) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(CaptureFilter::with_link_type(expression, record.link_type)?),
+            };
+            if !filter.matches(&record.data) {
+                continue;
+            }
+        }
+        let packet = Packet::new(dissector.traverse(&record));
+        if !options.filter.matches(&packet, &record.data) {
+            continue;
+        }
+        matched += 1;
+
+        // The frames are numbered starting with one, the time is relative to the first frame
+        let number = index + 1;
+        let time = record.timestamp.checked_sub(start).unwrap_or_default();
+        match statistics {
+            Some(Statistics::Hierarchy(ref mut hierarchy)) => hierarchy.add(&packet, record.original_length),
+            Some(Statistics::Conversations(ref mut conversations)) => {
+                conversations.add(&packet, record.original_length)
+            }
+            Some(Statistics::Endpoints(ref mut endpoints)) => endpoints.add(&packet, record.original_length),
+            None => {}
+        }
+        match options.command {
+            Command::Summary if packet.is_empty() => {
+                writeln!(out, "{} {} Unknown {} bytes", number, Seconds(time), record.original_length)?
+            }
+            Command::Summary => {
+                writeln!(out, "{} {} {}", number, Seconds(time), packet.summary(options.verbosity))?
+            }
+            Command::Tree => {
+                writeln!(out, "Frame {} at {}", number, Seconds(time))?;
+                writeln!(out, "{}", packet.dissect(&record.data))?;
+            }
+            #[cfg(feature = "serde")]
+            Command::Json => {
+                let mut json = TaggedResults::new(&packet).to_json_value()?;
+                if let Some(object) = json.as_object_mut() {
+                    object.insert("frame".to_owned(), number.into());
+                    object.insert("time".to_owned(), time.as_secs_f64().into());
+                    object.insert("length".to_owned(), record.original_length.into());
+                }
+                writeln!(out, "{}", json)?;
+            }
+            _ => {}
+        }
+    }
+
+    match statistics {
+        Some(Statistics::Hierarchy(hierarchy)) => write!(out, "{}", hierarchy)?,
+        Some(Statistics::Conversations(conversations)) => write!(out, "{}", conversations)?,
+        Some(Statistics::Endpoints(endpoints)) => write!(out, "{}", endpoints)?,
+        None => {}
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// A duration written as seconds with microsecond precision
+struct Seconds(Duration);
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:06}", self.0.as_secs(), self.0.subsec_micros())
+    }
+}
+
+fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("peel-ip: {}\n\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if let Err(error) = run(&options, &mut out) {
+        // A closed pipe, like from `head`, is no failure
+        match error.downcast_ref::<io::Error>() {
+            Some(error) if error.kind() == io::ErrorKind::BrokenPipe => {}
+            _ => {
+                eprintln!("peel-ip: {}", error);
+                process::exit(1);
+            }
+        }
+    }
+}
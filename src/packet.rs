@@ -0,0 +1,412 @@
+//! Typed access to the parsing results of a traversal
+use prelude::*;
+use std::{cmp, slice, vec};
+
+/// Create the layer type and the packet accessors from the list of built-in protocols. Every entry
+/// consists of the documentation and the variant of the layer, the packet type, the protocol and
+/// the name and documentation of the accessor to the first layer of the variant.
+macro_rules! layers {
+    ($(#[doc = $doc:expr] $(#[cfg($cfg:meta)])*
+       $variant:ident($packet:ty) => $protocol:ident, $name:ident($accessor_doc:expr);)*) => {
+        #[derive(Debug)]
+        /// A single parsing result of a built-in or custom parser
+        pub enum Layer {
+            $(
+                #[doc = $doc]
+                $(#[cfg($cfg)])*
+                $variant($packet),
+            )*
+
+            /// The result of a custom parser, which has to be downcasted
+            Custom(ParserResult),
+        }
+
+        impl Layer {
+            /// Create a layer from a parsing result
+            pub fn new(result: ParserResult) -> Self {
+                $(
+                    $(#[cfg($cfg)])*
+                    let result = match result.downcast::<$packet>() {
+                        Ok(packet) => return Layer::$variant(*packet),
+                        Err(result) => result,
+                    };
+                )*
+                Layer::Custom(result)
+            }
+
+            /// The built-in protocol of the layer, which is `None` for custom parsing results
+            pub fn protocol(&self) -> Option<Protocol> {
+                Some(match *self {
+                    $(
+                        $(#[cfg($cfg)])*
+                        Layer::$variant(_) => Protocol::$protocol,
+                    )*
+                    Layer::Custom(_) => return None,
+                })
+            }
+
+            /// The summary of the layer, which is `None` for custom parsing results
+            pub fn as_summary(&self) -> Option<&dyn Summary> {
+                Some(match *self {
+                    $(
+                        $(#[cfg($cfg)])*
+                        Layer::$variant(ref p) => p,
+                    )*
+                    Layer::Custom(_) => return None,
+                })
+            }
+
+            /// The dissector of the layer, which is `None` for custom parsing results
+            pub fn as_dissect(&self) -> Option<&dyn Dissect> {
+                Some(match *self {
+                    $(
+                        $(#[cfg($cfg)])*
+                        Layer::$variant(ref p) => p,
+                    )*
+                    Layer::Custom(_) => return None,
+                })
+            }
+        }
+
+        /// Serialization of the packet of the layer, which fails for custom parsing results
+        #[cfg(feature = "serde")]
+        impl Serialize for Layer {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::Error;
+                match *self {
+                    $(
+                        $(#[cfg($cfg)])*
+                        Layer::$variant(ref p) => p.serialize(serializer),
+                    )*
+                    Layer::Custom(_) => Err(S::Error::custom("custom parsing results are not serializable")),
+                }
+            }
+        }
+
+        impl Packet {
+            $(
+                #[doc = $accessor_doc]
+                $(#[cfg($cfg)])*
+                pub fn $name(&self) -> Option<&$packet> {
+                    self.layers.iter().filter_map(|layer| match *layer {
+                        Layer::$variant(ref packet) => Some(packet),
+                        _ => None,
+                    }).next()
+                }
+            )*
+        }
+    }
+}
+
+layers! {
+    /// Ethernet
+    Ethernet(EthernetPacket) => Ethernet, ethernet("The first Ethernet layer");
+
+    /// Address Resolution Protocol
+    #[cfg(feature = "arp")]
+    Arp(ArpPacket) => Arp, arp("The first ARP layer");
+
+    /// Internet Protocol version 4
+    Ipv4(Ipv4Packet) => Ipv4, ipv4("The first, outer IPv4 layer");
+
+    /// Internet Protocol version 6
+    Ipv6(Ipv6Packet) => Ipv6, ipv6("The first, outer IPv6 layer");
+
+    /// Internet Control Message Protocol
+    #[cfg(feature = "icmp")]
+    Icmp(IcmpPacket) => Icmp, icmp("The first ICMP layer");
+
+    /// Internet Control Message Protocol for IPv6
+    #[cfg(feature = "icmpv6")]
+    Icmpv6(Icmpv6Packet) => Icmpv6, icmpv6("The first ICMPv6 layer");
+
+    /// Transmission Control Protocol
+    Tcp(TcpPacket) => Tcp, tcp("The first TCP layer");
+
+    /// User Datagram Protocol
+    Udp(UdpPacket) => Udp, udp("The first UDP layer");
+
+    /// Transport Layer Security
+    #[cfg(feature = "tls")]
+    Tls(TlsPacket) => Tls, tls("The first TLS record");
+
+    /// Datagram Transport Layer Security
+    #[cfg(feature = "dtls")]
+    Dtls(DtlsPacket) => Dtls, dtls("The first DTLS record");
+
+    /// Hypertext Transfer Protocol
+    #[cfg(feature = "http")]
+    Http(HttpPacket) => Http, http("The first HTTP message or body");
+
+    /// Hypertext Transfer Protocol version 2
+    #[cfg(feature = "http2")]
+    Http2(Http2Packet) => Http2, http2("The first HTTP/2 layer");
+
+    /// WebSocket
+    #[cfg(feature = "websocket")]
+    WebSocket(WebSocketPacket) => WebSocket, websocket("The first WebSocket layer");
+
+    /// Network Time Protocol
+    #[cfg(feature = "ntp")]
+    Ntp(NtpPacket) => Ntp, ntp("The first NTP packet");
+
+    /// Network Time Protocol control message (mode 6)
+    #[cfg(feature = "ntp")]
+    NtpControl(NtpControlPacket) => Ntp, ntp_control("The first NTP control message");
+
+    /// Network Time Protocol private message (mode 7)
+    #[cfg(feature = "ntp")]
+    NtpPrivate(NtpPrivatePacket) => Ntp, ntp_private("The first NTP private message");
+
+    /// Domain Name System
+    #[cfg(feature = "dns")]
+    Dns(DnsPacket) => Dns, dns("The first DNS message");
+
+    /// Multicast DNS
+    #[cfg(feature = "mdns")]
+    Mdns(MdnsPacket) => Mdns, mdns("The first mDNS message");
+
+    /// Link-Local Multicast Name Resolution
+    #[cfg(feature = "llmnr")]
+    Llmnr(LlmnrPacket) => Llmnr, llmnr("The first LLMNR message");
+
+    /// Dynamic Host Configuration Protocol
+    #[cfg(feature = "dhcp")]
+    Dhcp(DhcpPacket) => Dhcp, dhcp("The first DHCP message");
+
+    /// Dynamic Host Configuration Protocol for IPv6
+    #[cfg(feature = "dhcpv6")]
+    Dhcpv6(Dhcpv6Packet) => Dhcpv6, dhcpv6("The first DHCPv6 message");
+}
+
+impl Layer {
+    /// The kind of the layer, which is `None` for custom parsing results
+    pub fn kind(&self) -> Option<LayerKind> {
+        self.protocol().map(|p| p.layer())
+    }
+}
+
+impl From<ParserResult> for Layer {
+    fn from(result: ParserResult) -> Self {
+        Layer::new(result)
+    }
+}
+
+impl Summary for Layer {
+    fn endpoints(&self) -> Option<(String, String)> {
+        self.as_summary().and_then(Summary::endpoints)
+    }
+
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        match self.as_summary() {
+            Some(summary) => summary.write_summary(w, verbosity),
+            None => write!(w, "Custom"),
+        }
+    }
+}
+
+summary_display!(Layer);
+
+#[derive(Debug, Default)]
+/// The typed parsing results of a traversal, starting with the lowest layer
+pub struct Packet {
+    /// All layers of the packet
+    layers: Vec<Layer>,
+}
+
+impl Packet {
+    /// Create a packet from the parsing results of a traversal
+    pub fn new(results: ParserResultVec) -> Self {
+        Packet { layers: results.into_iter().map(Layer::new).collect() }
+    }
+
+    /// The amount of layers
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Check if the packet has no layers at all
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Get a layer by its index
+    pub fn get(&self, index: usize) -> Option<&Layer> {
+        self.layers.get(index)
+    }
+
+    /// The highest layer of the packet
+    pub fn last(&self) -> Option<&Layer> {
+        self.layers.last()
+    }
+
+    /// Iterate over all layers, starting with the lowest one
+    pub fn iter(&self) -> slice::Iter<'_, Layer> {
+        self.layers.iter()
+    }
+
+    /// Iterate over all layers of the given kind, like all tunneled IP layers
+    pub fn iter_kind(&self, kind: LayerKind) -> impl Iterator<Item = &Layer> {
+        self.layers.iter().filter(move |layer| layer.kind() == Some(kind))
+    }
+
+    /// Iterate over the results of custom parsers
+    pub fn custom(&self) -> impl Iterator<Item = &ParserResult> {
+        self.layers.iter().filter_map(|layer| match *layer {
+            Layer::Custom(ref result) => Some(result),
+            _ => None,
+        })
+    }
+
+    /// The source and destination address of the innermost IP layer
+    pub fn ip_addresses(&self) -> Option<(IpAddr, IpAddr)> {
+        self.layers.iter().rev().filter_map(|layer| match *layer {
+            Layer::Ipv4(ref p) => Some((IpAddr::V4(p.src), IpAddr::V4(p.dst))),
+            Layer::Ipv6(ref p) => Some((IpAddr::V6(p.src), IpAddr::V6(p.dst))),
+            _ => None,
+        }).next()
+    }
+
+    /// The source and destination port of the first transport layer
+    pub fn ports(&self) -> Option<(u16, u16)> {
+        self.layers.iter().filter_map(|layer| match *layer {
+            Layer::Tcp(ref p) => Some((p.header.source_port, p.header.dest_port)),
+            Layer::Udp(ref p) => Some((p.header.source_port, p.header.dest_port)),
+            _ => None,
+        }).next()
+    }
+
+    /// The transport protocol and the endpoints of the innermost IP layer, where the ports are
+    /// taken from the following TCP or UDP layer. Without such a layer the protocol is the IP
+    /// version and both ports are zero.
+    pub fn flow(&self) -> Option<(Protocol, Endpoint, Endpoint)> {
+        let (ip, transport) = self.endpoint_layers();
+        let (protocol, src, dst) = match self.layers[ip?] {
+            Layer::Ipv4(ref p) => (Protocol::Ipv4, IpAddr::V4(p.src), IpAddr::V4(p.dst)),
+            Layer::Ipv6(ref p) => (Protocol::Ipv6, IpAddr::V6(p.src), IpAddr::V6(p.dst)),
+            _ => return None,
+        };
+        let transport = match transport {
+            Some(transport) => &self.layers[transport],
+            None => return Some((protocol, (src, 0), (dst, 0))),
+        };
+        Some(match *transport {
+            Layer::Tcp(ref p) => (Protocol::Tcp, (src, p.header.source_port), (dst, p.header.dest_port)),
+            Layer::Udp(ref p) => (Protocol::Udp, (src, p.header.source_port), (dst, p.header.dest_port)),
+            _ => (protocol, (src, 0), (dst, 0)),
+        })
+    }
+
+    /// Dissect the layers into a field tree. The frame has to be the input of the traversal which
+    /// created the packet. Layers after a custom parsing result are omitted, since the amount of
+    /// bytes consumed by the custom parser is unknown.
+    pub fn dissect(&self, frame: &[u8]) -> Field {
+        let mut root = Field::new("frame", frame.len(), 0, frame.len() * 8);
+        for layer in &self.layers {
+            let dissect = match layer.as_dissect() {
+                Some(dissect) => dissect,
+                None => break,
+            };
+            let offset = root.children.last().map_or(0, Field::bit_end);
+            let input = &frame[cmp::min(offset / 8, frame.len())..];
+            root.children.push(dissect.dissect(input, offset));
+        }
+        root
+    }
+
+    /// The index of the innermost IP layer and of the transport layer on top of it
+    fn endpoint_layers(&self) -> (Option<usize>, Option<usize>) {
+        let ip = self.layers.iter().rposition(|layer| matches!(*layer, Layer::Ipv4(_) | Layer::Ipv6(_)));
+        let transport = ip.and_then(|ip| {
+            self.layers
+                .iter()
+                .skip(ip + 1)
+                .position(|layer| matches!(*layer, Layer::Tcp(_) | Layer::Udp(_)))
+                .map(|position| ip + 1 + position)
+        });
+        (ip, transport)
+    }
+}
+
+impl From<ParserResultVec> for Packet {
+    fn from(results: ParserResultVec) -> Self {
+        Packet::new(results)
+    }
+}
+
+/// The summary of a complete traversal. The endpoints are the addresses of the innermost IP layer
+/// together with the ports of the following transport layer. The brief summary contains only the
+/// highest protocol, the normal one all layers above the innermost IP layer and the verbose one all
+/// layers, where layers which do not contribute to the endpoints are written with their own.
+impl Summary for Packet {
+    fn endpoints(&self) -> Option<(String, String)> {
+        let (ip, transport) = self.endpoint_layers();
+        let ip = &self.layers[ip?];
+        let (src, dst) = ip.endpoints()?;
+        Some(match (ip, transport.and_then(|transport| self.layers[transport].endpoints())) {
+            (&Layer::Ipv6(_), Some((src_port, dst_port))) => {
+                (format!("[{}]:{}", src, src_port), format!("[{}]:{}", dst, dst_port))
+            }
+            (_, Some((src_port, dst_port))) => (format!("{}:{}", src, src_port), format!("{}:{}", dst, dst_port)),
+            (_, None) => (src, dst),
+        })
+    }
+
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result {
+        let (ip, transport) = self.endpoint_layers();
+        let known: Vec<_> = self.layers
+            .iter()
+            .enumerate()
+            .filter(|&(_, layer)| layer.as_summary().is_some())
+            .collect();
+        let selected: Vec<_> = match verbosity {
+            Verbosity::Brief => known.last().cloned().into_iter().collect(),
+            Verbosity::Normal => {
+                let layers: Vec<_> = known.iter()
+                    .cloned()
+                    .filter(|&(index, layer)| match ip {
+                        Some(ip) => index > ip,
+                        None => !matches!(*layer, Layer::Ethernet(_)),
+                    })
+                    .collect();
+                if layers.is_empty() {
+                    known.last().cloned().into_iter().collect()
+                } else {
+                    layers
+                }
+            }
+            Verbosity::Verbose => known,
+        };
+        for (position, &(index, layer)) in selected.iter().enumerate() {
+            if position > 0 {
+                write!(w, ", ")?;
+            }
+            if verbosity == Verbosity::Verbose && Some(index) != ip && Some(index) != transport {
+                write_summary_line(layer, w, verbosity)?;
+            } else {
+                layer.write_summary(w, verbosity)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+summary_display!(Packet);
+
+impl IntoIterator for Packet {
+    type Item = Layer;
+    type IntoIter = vec::IntoIter<Layer>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.layers.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Packet {
+    type Item = &'a Layer;
+    type IntoIter = slice::Iter<'a, Layer>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.layers.iter()
+    }
+}
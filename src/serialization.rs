@@ -0,0 +1,84 @@
+//! Tagged and layered serialization of the parsing results
+use prelude::*;
+use serde_json::{self, Value};
+
+#[derive(Debug, Serialize)]
+/// A serializable document of a complete traversal, which contains one entry per parsing result
+pub struct TaggedResults<'a> {
+    /// The parsing results, starting with the lowest layer
+    pub layers: Vec<TaggedResult<'a>>,
+}
+
+impl<'a> TaggedResults<'a> {
+    /// Tag all layers of a packet. Results of custom parsers are omitted, since their type is
+    /// unknown.
+    pub fn new(packet: &'a Packet) -> Self {
+        TaggedResults { layers: packet.iter().filter_map(TaggedResult::new).collect() }
+    }
+
+    /// Convert the document into a JSON value
+    pub fn to_json_value(&self) -> serde_json::Result<Value> {
+        serde_json::to_value(self)
+    }
+
+    /// Convert the document into a JSON string
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+#[derive(Debug, Serialize)]
+/// A layer tagged with its kind and protocol
+pub struct TaggedResult<'a> {
+    /// The layer of the protocol
+    pub layer: LayerKind,
+
+    /// The protocol of the packet
+    pub protocol: Protocol,
+
+    /// The layer itself, which is serialized as its packet
+    pub packet: &'a Layer,
+}
+
+impl<'a> TaggedResult<'a> {
+    /// Tag a single layer, which returns `None` for custom parsing results
+    pub fn new(layer: &'a Layer) -> Option<Self> {
+        layer.protocol().map(|protocol| {
+            TaggedResult {
+                layer: protocol.layer(),
+                protocol: protocol,
+                packet: layer,
+            }
+        })
+    }
+}
+
+/// Serialization of the connection tracking errors by their name like `"Timeout"`, since the
+/// error type of the `path` crate does not implement serde
+pub mod path_error {
+    use prelude::*;
+    use serde::{de, Deserializer, Serializer};
+
+    /// The names of all connection tracking errors
+    const NAMES: &[&str] = &["PacketCounterOverflow", "Timeout", "Internal"];
+
+    /// Serialize an optional error by its name
+    pub fn serialize<S>(error: &Option<PathErrorType>, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        error.as_ref().map(|e| format!("{:?}", e)).serialize(serializer)
+    }
+
+    /// Deserialize an optional error from its name
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<PathErrorType>, D::Error>
+        where D: Deserializer<'de>
+    {
+        Ok(match Option::<String>::deserialize(deserializer)?.as_deref() {
+            None => None,
+            Some("PacketCounterOverflow") => Some(PathErrorType::PacketCounterOverflow),
+            Some("Timeout") => Some(PathErrorType::Timeout),
+            Some("Internal") => Some(PathErrorType::Internal),
+            Some(name) => return Err(de::Error::unknown_variant(name, NAMES)),
+        })
+    }
+}
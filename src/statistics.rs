@@ -0,0 +1,262 @@
+//! Statistics over multiple packets, like the protocol hierarchy or the conversations
+use prelude::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+/// A packet and byte counter
+pub struct Counter {
+    /// The amount of packets
+    pub packets: u64,
+
+    /// The amount of bytes
+    pub bytes: u64,
+}
+
+impl Counter {
+    /// Count a packet of the given length
+    pub fn add(&mut self, length: usize) {
+        self.packets += 1;
+        self.bytes += length as u64;
+    }
+}
+
+impl fmt::Display for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "packets={} bytes={}", self.packets, self.bytes)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The packets and bytes per protocol, where every protocol is counted below the protocols it
+/// was encapsulated in
+pub struct ProtocolHierarchy {
+    /// The name of the protocol, which is `frame` for the root and `custom` for custom parsers
+    pub name: String,
+
+    /// The packets containing the protocol at this position
+    pub counter: Counter,
+
+    /// The protocols which were encapsulated in this one
+    pub children: Vec<ProtocolHierarchy>,
+}
+
+impl Default for ProtocolHierarchy {
+    fn default() -> Self {
+        ProtocolHierarchy::new("frame")
+    }
+}
+
+impl ProtocolHierarchy {
+    /// Create an empty hierarchy with the given protocol name
+    pub fn new(name: &str) -> Self {
+        ProtocolHierarchy {
+            name: name.to_owned(),
+            counter: Counter::default(),
+            children: vec![],
+        }
+    }
+
+    /// Count a packet, the length is the length of the whole frame
+    pub fn add(&mut self, packet: &Packet, length: usize) {
+        self.counter.add(length);
+        let mut node = self;
+        for layer in packet {
+            let name = layer.protocol().map_or("custom", |p| p.name());
+            let index = match node.children.iter().position(|child| child.name == name) {
+                Some(index) => index,
+                None => {
+                    node.children.push(ProtocolHierarchy::new(name));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index];
+            node.counter.add(length);
+        }
+    }
+
+    /// Write the hierarchy and its children indented by the given depth
+    fn write_tree(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        writeln!(f, "{:width$}{} {}", "", self.name, self.counter, width = depth * 2)?;
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the hierarchy as an indented tree, one line per protocol
+impl fmt::Display for ProtocolHierarchy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The traffic between two endpoints
+pub struct Conversation {
+    /// The transport protocol, or the IP version if the ports are unknown
+    pub protocol: Protocol,
+
+    /// The endpoint which sent the first packet
+    pub a: Endpoint,
+
+    /// The other endpoint
+    pub b: Endpoint,
+
+    /// The packets sent from `a` to `b`
+    pub a_to_b: Counter,
+
+    /// The packets sent from `b` to `a`
+    pub b_to_a: Counter,
+}
+
+impl fmt::Display for Conversation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ", self.protocol)?;
+        match self.protocol {
+            Protocol::Tcp | Protocol::Udp => {
+                write_endpoint(f, &self.a)?;
+                write!(f, " <-> ")?;
+                write_endpoint(f, &self.b)?;
+            }
+            _ => write!(f, "{} <-> {}", self.a.0, self.b.0)?,
+        }
+        write!(f,
+               " packets={}/{} bytes={}/{}",
+               self.a_to_b.packets,
+               self.b_to_a.packets,
+               self.a_to_b.bytes,
+               self.b_to_a.bytes)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// The conversations of the innermost IP layers, in the order of their first packet
+pub struct Conversations {
+    /// The conversations
+    pub list: Vec<Conversation>,
+
+    /// The index of every conversation by its protocol and both directions of its endpoints
+    index: HashMap<(Protocol, Endpoint, Endpoint), usize>,
+}
+
+impl Conversations {
+    /// Count a packet, the length is the length of the whole frame. Packets without IP layer
+    /// are ignored.
+    pub fn add(&mut self, packet: &Packet, length: usize) {
+        let (protocol, src, dst) = match packet.flow() {
+            Some(flow) => flow,
+            None => return,
+        };
+        let index = match self.index.get(&(protocol, src, dst)) {
+            Some(&index) => index,
+            None => {
+                self.list.push(Conversation {
+                    protocol: protocol,
+                    a: src,
+                    b: dst,
+                    a_to_b: Counter::default(),
+                    b_to_a: Counter::default(),
+                });
+                let index = self.list.len() - 1;
+                self.index.insert((protocol, src, dst), index);
+                self.index.insert((protocol, dst, src), index);
+                index
+            }
+        };
+        let conversation = &mut self.list[index];
+        if conversation.a == src && conversation.b == dst {
+            conversation.a_to_b.add(length);
+        } else {
+            conversation.b_to_a.add(length);
+        }
+    }
+}
+
+/// Write one conversation per line
+impl fmt::Display for Conversations {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for conversation in &self.list {
+            writeln!(f, "{}", conversation)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// The traffic of a single address
+pub struct EndpointStatistics {
+    /// The address of the endpoint
+    pub address: IpAddr,
+
+    /// The packets sent by the endpoint
+    pub sent: Counter,
+
+    /// The packets received by the endpoint
+    pub received: Counter,
+}
+
+impl fmt::Display for EndpointStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{} packets={}/{} bytes={}/{}",
+               self.address,
+               self.sent.packets,
+               self.received.packets,
+               self.sent.bytes,
+               self.received.bytes)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// The addresses of the innermost IP layers, in the order of their first packet
+pub struct Endpoints {
+    /// The endpoints
+    pub list: Vec<EndpointStatistics>,
+
+    /// The index of every endpoint by its address
+    index: HashMap<IpAddr, usize>,
+}
+
+impl Endpoints {
+    /// Count a packet, the length is the length of the whole frame. Packets without IP layer
+    /// are ignored.
+    pub fn add(&mut self, packet: &Packet, length: usize) {
+        if let Some((src, dst)) = packet.ip_addresses() {
+            self.endpoint(src).sent.add(length);
+            self.endpoint(dst).received.add(length);
+        }
+    }
+
+    /// Get the statistics of an address, which are created if needed
+    fn endpoint(&mut self, address: IpAddr) -> &mut EndpointStatistics {
+        let list = &mut self.list;
+        let index = *self.index.entry(address).or_insert_with(|| {
+            list.push(EndpointStatistics {
+                address: address,
+                sent: Counter::default(),
+                received: Counter::default(),
+            });
+            list.len() - 1
+        });
+        &mut list[index]
+    }
+}
+
+/// Write one endpoint per line
+impl fmt::Display for Endpoints {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for endpoint in &self.list {
+            writeln!(f, "{}", endpoint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write an endpoint with its port, where IPv6 addresses are enclosed in brackets
+fn write_endpoint(f: &mut fmt::Formatter, endpoint: &Endpoint) -> fmt::Result {
+    match endpoint.0 {
+        IpAddr::V4(address) => write!(f, "{}:{}", address, endpoint.1),
+        IpAddr::V6(address) => write!(f, "[{}]:{}", address, endpoint.1),
+    }
+}
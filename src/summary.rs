@@ -0,0 +1,58 @@
+//! One-line summaries of the packets in the style of tcpdump
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+/// The amount of details of a summary
+pub enum Verbosity {
+    /// Only the endpoints and the protocol names
+    Brief,
+
+    /// The most important fields of every protocol above the internet layer
+    Normal,
+
+    /// Additional fields of every protocol, including the link and internet layer
+    Verbose,
+}
+
+/// A packet which can be summarized in a single line
+pub trait Summary {
+    /// The source and destination of the packet, like its addresses or ports
+    fn endpoints(&self) -> Option<(String, String)> {
+        None
+    }
+
+    /// Write the summary of the packet without its endpoints
+    fn write_summary(&self, w: &mut dyn fmt::Write, verbosity: Verbosity) -> fmt::Result;
+
+    /// Get the complete summary of the packet including its endpoints
+    fn summary(&self, verbosity: Verbosity) -> String {
+        let mut line = String::new();
+        // Writing into a string does not fail
+        let _ = write_summary_line(self, &mut line, verbosity);
+        line
+    }
+}
+
+/// Write the endpoints, if available, followed by the summary
+pub fn write_summary_line<T: Summary + ?Sized>(packet: &T,
+                                               w: &mut dyn fmt::Write,
+                                               verbosity: Verbosity)
+                                               -> fmt::Result {
+    if let Some((src, dst)) = packet.endpoints() {
+        write!(w, "{} > {} ", src, dst)?;
+    }
+    packet.write_summary(w, verbosity)
+}
+
+/// Implement `Display` by the `Summary` of a type, where the alternate flag (`{:#}`) selects the
+/// verbose summary
+macro_rules! summary_display {
+    ($t:ty) => {
+        impl fmt::Display for $t {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let verbosity = if f.alternate() { Verbosity::Verbose } else { Verbosity::Normal };
+                write_summary_line(self, f, verbosity)
+            }
+        }
+    }
+}
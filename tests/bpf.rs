@@ -0,0 +1,148 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+static PACKET_ETH_IPV6_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
+      0x00, 0x24, 0x11, 0x40, 0x3f, 0xfe, 0x05, 0x07, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x86, 0xff, 0xfe, 0x05,
+      0x80, 0xda, 0x3f, 0xfe, 0x05, 0x01, 0x48, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
+      0x09, 0x5c, 0x00, 0x35, 0x00, 0x24, 0xf0, 0x09];
+
+static PACKET_ETH_ARP: &'static [u8] =
+    &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x07, 0x0d, 0xaf, 0xf4, 0x54, 0x08, 0x06, 0x00, 0x01, 0x08, 0x00,
+      0x06, 0x04, 0x00, 0x01, 0x00, 0x07, 0x0d, 0xaf, 0xf4, 0x54, 0x18, 0xa6, 0xac, 0x01, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x18, 0xa6, 0xad, 0x9f];
+
+/// The test frames: IPv4 TCP, IPv6 UDP, ARP, IPv4 TCP within VLAN 100 and IPv6 ICMPv6
+fn frames() -> Vec<Vec<u8>> {
+    let mut vlan = Vec::from(PACKET_ETH_IPV4_TCP);
+    vlan.splice(12..12, vec![0x81, 0x00, 0x00, 0x64]);
+    let mut icmpv6 = Vec::from(PACKET_ETH_IPV6_UDP);
+    icmpv6[20] = 58;
+    vec![PACKET_ETH_IPV4_TCP.to_vec(), PACKET_ETH_IPV6_UDP.to_vec(), PACKET_ETH_ARP.to_vec(), vlan, icmpv6]
+}
+
+/// Check the filter and its BPF program against the expected results for all test frames
+fn check(filter: &str, expected: &[bool]) {
+    let capture_filter = CaptureFilter::new(filter).unwrap();
+    let program = capture_filter.to_bpf().unwrap();
+    let frames = frames();
+    let results: Vec<_> = frames.iter().map(|frame| capture_filter.matches(frame)).collect();
+    assert_eq!(results, expected, "{}", filter);
+    let results: Vec<_> = frames.iter().map(|frame| program.matches(frame)).collect();
+    assert_eq!(results, expected, "{} compiled to\n{}", filter, program);
+}
+
+#[test]
+fn bpf_success_protocols() {
+    check("tcp", &[true, false, false, false, false]);
+    check("udp || arp", &[false, true, true, false, false]);
+    check("ip6", &[false, true, false, false, true]);
+    check("ip6 proto 58", &[false, false, false, false, true]);
+    check("icmp6 or icmp", &[false, false, false, false, true]);
+    check("ether proto \\arp", &[false, false, true, false, false]);
+    check("proto \\udp", &[false, true, false, false, false]);
+    check("not tcp", &[false, true, true, true, true]);
+    check("", &[true; 5]);
+}
+
+#[test]
+fn bpf_success_hosts() {
+    check("tcp port 443 and host 10.0.0.101", &[true, false, false, false, false]);
+    check("src host 66.196.65.112", &[false; 5]);
+    check("dst net 66.196.0.0/16 and src net 10", &[true, false, false, false, false]);
+    check("net 10.0.0.0 mask 255.255.255.0", &[true, false, false, false, false]);
+    check("host 24.166.173.159", &[false, false, true, false, false]);
+    check("ip host 24.166.173.159", &[false; 5]);
+    check("ip6 src net 3ffe:507::/32", &[false, true, false, false, true]);
+    check("src and dst host 10.0.0.101", &[false; 5]);
+    check("ether src 00:07:0d:af:f4:54 and ether broadcast", &[false, false, true, false, false]);
+    check("ether multicast or ip multicast", &[false, false, true, false, false]);
+}
+
+#[test]
+fn bpf_success_ports() {
+    check("port 53", &[false, true, false, false, false]);
+    check("tcp port 80 or 443", &[true, false, false, false, false]);
+    check("udp dst port domain", &[false, true, false, false, false]);
+    check("src port 443", &[false; 5]);
+    check("portrange 440-450 or ip6 portrange 2000-3000", &[true, true, false, false, false]);
+    check("vlan 100 and tcp port 443", &[false, false, false, true, false]);
+    check("vlan 101 or vlan", &[false; 5]);
+}
+
+#[test]
+fn bpf_success_relations() {
+    check("tcp[tcpflags] & tcp-ack != 0", &[true, false, false, false, false]);
+    check("tcp[tcpflags] & (tcp-syn|tcp-fin) != 0", &[false; 5]);
+    check("ip[2:2] - ((ip[0] & 0xf) << 2) > 20 && ip[6] & 0x40 != 0", &[true, false, false, false, false]);
+    check("tcp[(ip[0] & 0x1) * 12:1] >> 4 == 8", &[true, false, false, false, false]);
+    check("len >= 62 and greater 64", &[true, false, false, true, false]);
+    check("less 50", &[false, false, true, false, false]);
+    check("ether[12:2] = 0x8100 and ether[len - 1] == 0x5b", &[false, false, false, true, false]);
+    check("icmp6[icmp6type] != icmp6-echo", &[false, false, false, false, true]);
+
+    // A load beyond the frame rejects it, even if negated
+    check("not tcp[100] == 1", &[false, true, true, true, true]);
+
+    // Alternation and concatenation have the same precedence
+    check("udp or arp and not ip6", &[false, false, true, false, false]);
+}
+
+#[test]
+fn bpf_success_link_types() {
+    let filter = CaptureFilter::with_link_type("ip and tcp port 443", LinkType::Raw).unwrap();
+    assert!(filter.matches(&PACKET_ETH_IPV4_TCP[14..]));
+    assert!(!filter.matches(&PACKET_ETH_IPV6_UDP[14..]));
+    assert!(filter.to_bpf().unwrap().matches(&PACKET_ETH_IPV4_TCP[14..]));
+    let filter = CaptureFilter::with_link_type("udp port 53", LinkType::Ipv6).unwrap();
+    assert!(filter.matches(&PACKET_ETH_IPV6_UDP[14..]));
+    let filter = CaptureFilter::with_link_type("ip or arp", LinkType::Ipv6).unwrap();
+    assert_eq!(filter.expression, CaptureExpression::False);
+    assert!(CaptureFilter::with_link_type("ether broadcast", LinkType::Raw).is_err());
+    assert!(CaptureFilter::with_link_type("vlan", LinkType::Ipv4).is_err());
+    assert!(CaptureFilter::with_link_type("ip", LinkType::Unknown(147)).is_err());
+}
+
+#[test]
+fn bpf_success_program() {
+    let program = CaptureFilter::new("ip").unwrap().to_bpf().unwrap();
+    assert_eq!(program.to_string(),
+               "(000) ldh      [12]\n\
+                (001) jeq      #0x800           jt 2\tjf 3\n\
+                (002) ret      #262144\n\
+                (003) ret      #0\n");
+    assert_eq!(format!("{:#}", program),
+               "{ 0x28, 0, 0, 0x0000000c },\n\
+                { 0x15, 0, 1, 0x00000800 },\n\
+                { 0x06, 0, 0, 0x00040000 },\n\
+                { 0x06, 0, 0, 0x00000000 },\n");
+    assert_eq!(program.run(PACKET_ETH_IPV4_TCP), BPF_ACCEPT);
+
+    let program = BpfProgram {
+        instructions: vec![BpfInstruction::new(0x00, 5), BpfInstruction::new(0x04, 3), BpfInstruction::new(0x16, 0)],
+    };
+    assert_eq!(program.run(&[]), 8);
+    assert_eq!(BpfProgram::default().run(PACKET_ETH_IPV4_TCP), 0);
+}
+
+#[test]
+fn bpf_failure() {
+    let error = |filter: &str| CaptureFilter::new(filter).unwrap_err();
+    assert_eq!(error("tcp port").to_string(), "Missing identifier at position 8");
+    assert_eq!(error("host 10.0.0.256").message, "Invalid host '10.0.0.256'");
+    assert_eq!(error("ip[2:3] > 1").message, "Invalid load size '3'");
+    assert_eq!(error("net 10.0.0.1/8").message, "Non-network bits set in '10.0.0.1/8'");
+    assert_eq!(error("tcp host 10.0.0.1").message, "'tcp' modifier applied to 'host' 10.0.0.1");
+    assert_eq!(error("ether host 00:11").message, "Invalid MAC address '00:11'");
+    assert_eq!(error("port 80 or foo").position, 11);
+    assert_eq!(error("(tcp or udp").message, "Missing ')'");
+    assert_eq!(error("tcp[0] / 0 == 1").message, "Division by zero");
+    assert_eq!(error("ip broadcast").position, 0);
+    assert!("vlan 4096".parse::<CaptureFilter>().is_err());
+}
@@ -0,0 +1,133 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+use std::time::Duration;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+/// Encode a little endian pcap file with microsecond timestamps
+fn pcap(link_type: u32, frames: &[(u32, u32, &[u8])]) -> Vec<u8> {
+    let mut file = vec![0xd4, 0xc3, 0xb2, 0xa1, 0x02, 0x00, 0x04, 0x00];
+    file.extend_from_slice(&[0; 8]);
+    file.extend_from_slice(&65535u32.to_le_bytes());
+    file.extend_from_slice(&link_type.to_le_bytes());
+    for &(seconds, micros, data) in frames {
+        for value in &[seconds, micros, data.len() as u32, data.len() as u32] {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        file.extend_from_slice(data);
+    }
+    file
+}
+
+/// Encode a big endian pcapng block
+fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let length = 12 + body.len().div_ceil(4) * 4;
+    let mut block = block_type.to_be_bytes().to_vec();
+    block.extend_from_slice(&(length as u32).to_be_bytes());
+    block.extend_from_slice(body);
+    block.resize(length - 4, 0);
+    block.extend_from_slice(&(length as u32).to_be_bytes());
+    block
+}
+
+/// Encode a big endian pcapng section with one interface of the given timestamp resolution
+fn pcapng(link_type: u16, resolution: u8) -> Vec<u8> {
+    let mut file = block(0x0a0d0d0a, &[0x1a, 0x2b, 0x3c, 0x4d, 0x00, 0x01, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff,
+                                       0xff, 0xff, 0xff]);
+    let mut interface = link_type.to_be_bytes().to_vec();
+    interface.extend_from_slice(&[0, 0, 0, 0, 0xff, 0xff, 0x00, 0x09, 0x00, 0x01, resolution, 0, 0, 0, 0, 0, 0, 0]);
+    file.extend_from_slice(&block(1, &interface));
+    file
+}
+
+/// Encode an enhanced packet block of the first interface
+fn enhanced_packet(timestamp: u64, data: &[u8]) -> Vec<u8> {
+    let mut body = vec![0; 4];
+    body.extend_from_slice(&((timestamp >> 32) as u32).to_be_bytes());
+    body.extend_from_slice(&(timestamp as u32).to_be_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    body.extend_from_slice(&(data.len() as u32 + 4).to_be_bytes());
+    body.extend_from_slice(data);
+    block(6, &body)
+}
+
+#[test]
+fn capture_success_pcap() {
+    let file = pcap(1, &[(10, 500, PACKET_ETH_IPV4_TCP), (11, 0, &PACKET_ETH_IPV4_TCP[..14])]);
+    let records: Vec<_> = CaptureReader::new(file.as_slice()).unwrap().map(Result::unwrap).collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].timestamp, Duration::new(10, 500_000));
+    assert_eq!(records[0].link_type, LinkType::Ethernet);
+    assert_eq!(records[0].original_length, 66);
+    assert_eq!(records[0].data, PACKET_ETH_IPV4_TCP);
+    assert_eq!(records[1].data.len(), 14);
+    assert_eq!(records[0].link_type.root(&records[0].data), Some(Protocol::Ethernet));
+}
+
+#[test]
+fn capture_success_pcap_nanoseconds() {
+    let mut file = vec![0xa1, 0xb2, 0x3c, 0x4d, 0x00, 0x02, 0x00, 0x04];
+    file.extend_from_slice(&[0; 12]);
+    file.extend_from_slice(&101u32.to_be_bytes());
+    for value in &[1u32, 5, 20, 20] {
+        file.extend_from_slice(&value.to_be_bytes());
+    }
+    file.extend_from_slice(&PACKET_ETH_IPV4_TCP[14..34]);
+    let mut reader = CaptureReader::new(file.as_slice()).unwrap();
+    let record = reader.read_record().unwrap().unwrap();
+    assert_eq!(record.timestamp, Duration::new(1, 5));
+    assert_eq!(record.link_type, LinkType::Raw);
+    assert_eq!(record.link_type.root(&record.data), Some(Protocol::Ipv4));
+    assert!(reader.read_record().unwrap().is_none());
+}
+
+#[test]
+fn capture_success_pcapng() {
+    let mut file = pcapng(1, 9);
+    file.extend_from_slice(&block(4, &[0; 8]));
+    file.extend_from_slice(&enhanced_packet(1_500_000_000, PACKET_ETH_IPV4_TCP));
+    let mut simple = (PACKET_ETH_IPV4_TCP.len() as u32).to_be_bytes().to_vec();
+    simple.extend_from_slice(PACKET_ETH_IPV4_TCP);
+    file.extend_from_slice(&block(3, &simple));
+
+    let records: Vec<_> = CaptureReader::new(file.as_slice()).unwrap().map(Result::unwrap).collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].timestamp, Duration::new(1, 500_000_000));
+    assert_eq!(records[0].original_length, 70);
+    assert_eq!(records[0].data, PACKET_ETH_IPV4_TCP);
+    assert_eq!(records[1].link_type, LinkType::Ethernet);
+    assert_eq!(records[1].data, PACKET_ETH_IPV4_TCP);
+
+    // A binary timestamp resolution of 2^-10 seconds
+    let mut file = pcapng(229, 0x8a);
+    file.extend_from_slice(&enhanced_packet(1536, &[0x60]));
+    let record = CaptureReader::new(file.as_slice()).unwrap().next().unwrap().unwrap();
+    assert_eq!(record.timestamp, Duration::from_millis(1500));
+    assert_eq!(record.link_type, LinkType::Ipv6);
+}
+
+#[test]
+fn capture_failure() {
+    match CaptureReader::new(&[0x12, 0x34, 0x56, 0x78][..]) {
+        Err(CaptureError::UnknownFormat(0x12345678)) => {}
+        _ => panic!("Unknown format expected"),
+    }
+    let file = pcap(1, &[(0, 0, PACKET_ETH_IPV4_TCP)]);
+    let mut reader = CaptureReader::new(&file[..file.len() - 1]).unwrap();
+    match reader.next() {
+        Some(Err(CaptureError::Truncated)) => {}
+        _ => panic!("Truncated file expected"),
+    }
+
+    // Packets have to refer to an existing interface
+    let mut file = pcapng(1, 6);
+    let mut packet = enhanced_packet(0, PACKET_ETH_IPV4_TCP);
+    packet[11] = 1;
+    file.extend_from_slice(&packet);
+    assert_eq!(CaptureReader::new(file.as_slice()).unwrap().next().unwrap().unwrap_err().to_string(),
+               "Invalid capture file: interface 1");
+}
@@ -0,0 +1,166 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+static PACKET_ETH_IPV6_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
+      0x00, 0x24, 0x11, 0x40, 0x3f, 0xfe, 0x05, 0x07, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x86, 0xff, 0xfe, 0x05,
+      0x80, 0xda, 0x3f, 0xfe, 0x05, 0x01, 0x48, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
+      0x09, 0x5c, 0x00, 0x35, 0x00, 0x24, 0xf0, 0x09];
+
+/// Encode a little endian pcap file of Ethernet frames, which are captured a quarter second apart
+fn pcap(frames: &[&[u8]]) -> Vec<u8> {
+    let mut file = vec![0xd4, 0xc3, 0xb2, 0xa1, 0x02, 0x00, 0x04, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0, 1,
+                        0, 0, 0];
+    for (index, data) in frames.iter().enumerate() {
+        let micros = 250_000 * index as u32;
+        for value in &[100 + micros / 1_000_000, micros % 1_000_000, data.len() as u32, data.len() as u32] {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        file.extend_from_slice(data);
+    }
+    file
+}
+
+/// Write a capture file with the TCP, the UDP and a truncated frame
+fn capture_file(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("peel-ip-cli-{}-{}.pcap", name, std::process::id()));
+    fs::write(&path, pcap(&[PACKET_ETH_IPV4_TCP, PACKET_ETH_IPV6_UDP, &PACKET_ETH_IPV4_TCP[..10]])).unwrap();
+    path
+}
+
+/// Run the binary with the given arguments on a capture file
+fn run(name: &str, args: &[&str]) -> (String, Output) {
+    let path = capture_file(name);
+    let output = Command::new(env!("CARGO_BIN_EXE_peel-ip")).args(args).arg(&path).output().unwrap();
+    fs::remove_file(path).unwrap();
+    (String::from_utf8_lossy(&output.stdout).into_owned(), output)
+}
+
+#[test]
+fn cli_success_summary() {
+    let (stdout, output) = run("summary", &[]);
+    assert!(output.status.success());
+    assert_eq!(stdout,
+               "1 0.000000 10.0.0.101:51781 > 66.196.65.112:443 TCP [ACK] seq=2556845834 ack=1151172357 win=8192\n\
+                2 0.250000 [3ffe:507:0:1:200:86ff:fe05:80da]:2396 > [3ffe:501:4819::42]:53 UDP len=36\n\
+                3 0.500000 Unknown 10 bytes\n");
+    let (stdout, _) = run("brief", &["summary", "--brief", "-c", "1"]);
+    assert_eq!(stdout, "1 0.000000 10.0.0.101:51781 > 66.196.65.112:443 TCP\n");
+}
+
+#[test]
+fn cli_success_filters() {
+    let (stdout, _) = run("protocol", &["-p", "UDP"]);
+    assert!(stdout.starts_with("2 0.250000 [3ffe:507:0:1:200:86ff:fe05:80da]:2396"));
+    assert_eq!(stdout.lines().count(), 1);
+    let (stdout, _) = run("host", &["--host", "66.196.65.112", "--port", "443"]);
+    assert!(stdout.starts_with("1 0.000000"));
+    assert_eq!(stdout.lines().count(), 1);
+    let connection = "[3ffe:501:4819::42]:53,[3ffe:507:0:1:200:86ff:fe05:80da]:2396";
+    let (stdout, _) = run("connection", &["--connection", connection]);
+    assert!(stdout.starts_with("2 "));
+    assert_eq!(stdout.lines().count(), 1);
+    let (stdout, _) = run("port", &["--port", "80"]);
+    assert!(stdout.is_empty());
+    let (stdout, _) = run("display", &["-Y", "ipv6.dst == 3ffe:501::/32 || tcp.dest_port in {80 8080}"]);
+    assert!(stdout.starts_with("2 "));
+    assert_eq!(stdout.lines().count(), 1);
+    let (stdout, _) = run("display_protocol", &["--filter", "tcp.window >= 8192", "-p", "udp"]);
+    assert!(stdout.is_empty());
+    let (stdout, _) = run("capture", &["-f", "ip6 and udp port 53 or tcp[tcpflags] & tcp-syn != 0"]);
+    assert!(stdout.starts_with("2 0.250000 "));
+    assert_eq!(stdout.lines().count(), 1);
+}
+
+#[test]
+fn cli_success_bpf() {
+    let output = Command::new(env!("CARGO_BIN_EXE_peel-ip")).args(["bpf", "-f", "arp"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout),
+               "(000) ldh      [12]\n\
+                (001) jeq      #0x806           jt 2\tjf 3\n\
+                (002) ret      #262144\n\
+                (003) ret      #0\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_peel-ip")).args(["bpf", "-v", "-f", "arp"]).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("{ 0x28, 0, 0, 0x0000000c },\n"));
+}
+
+#[test]
+fn cli_success_tree() {
+    let (stdout, _) = run("tree", &["tree", "--count", "1"]);
+    let lines: Vec<_> = stdout.lines().take(3).collect();
+    assert_eq!(lines,
+               vec!["Frame 1 at 0.000000",
+                    "frame: 66 [bytes 0..66]",
+                    "    ethernet: 00:00:00:00:00:00 > 00:00:00:00:00:00 Ethernet Ipv4 [bytes 0..14]"]);
+    assert!(stdout.contains("        tcp.dest_port: 443 [bytes 36..38]\n"));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn cli_success_json() {
+    let (stdout, _) = run("json", &["json", "-p", "tcp"]);
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.starts_with("{\"frame\":1,\"layers\":[{\"layer\":\"link\",\"packet\":"));
+    assert!(stdout.ends_with("\"length\":66,\"time\":0.0}\n"));
+}
+
+#[test]
+fn cli_success_statistics() {
+    let (stdout, _) = run("hierarchy", &["hierarchy"]);
+    assert_eq!(stdout,
+               "frame packets=3 bytes=138\n  ethernet packets=2 bytes=128\n    ipv4 packets=1 bytes=66\n      \
+                tcp packets=1 bytes=66\n    ipv6 packets=1 bytes=62\n      udp packets=1 bytes=62\n");
+    let (stdout, _) = run("conversations", &["conversations", "--protocol", "tcp"]);
+    assert_eq!(stdout, "tcp 10.0.0.101:51781 <-> 66.196.65.112:443 packets=1/0 bytes=66/0\n");
+    let (stdout, _) = run("endpoints", &["endpoints"]);
+    assert_eq!(stdout.lines().collect::<Vec<_>>(),
+               vec!["10.0.0.101 packets=1/0 bytes=66/0",
+                    "66.196.65.112 packets=0/1 bytes=0/66",
+                    "3ffe:507:0:1:200:86ff:fe05:80da packets=1/0 bytes=62/0",
+                    "3ffe:501:4819::42 packets=0/1 bytes=0/62"]);
+}
+
+#[test]
+fn cli_success_stdin() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_peel-ip"))
+        .args(["-b", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&pcap(&[PACKET_ETH_IPV6_UDP])).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout),
+               "1 0.000000 [3ffe:507:0:1:200:86ff:fe05:80da]:2396 > [3ffe:501:4819::42]:53 UDP\n");
+}
+
+#[test]
+fn cli_failure() {
+    let (_, output) = run("unknown", &["--unknown"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("peel-ip: Unknown option '--unknown'\n\nUsage:"));
+    let (_, output) = run("invalid_protocol", &["-p", "ipx"]);
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("peel-ip: Unknown protocol 'ipx'"));
+    let (_, output) = run("invalid_capture_filter", &["-f", "tcp port"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("peel-ip: Invalid capture filter 'tcp port': Missing identifier at position 8"));
+    let (_, output) = run("invalid_filter", &["-Y", "tcp =="]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .starts_with("peel-ip: Invalid filter 'tcp ==': Missing value at position 6"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_peel-ip")).arg("/nonexistent.pcap").output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("peel-ip: Unable to open '/nonexistent.pcap'"));
+}
@@ -0,0 +1,10 @@
+//! Helpers which are shared by the integration tests
+use peel_ip::prelude::*;
+
+/// Parse the input without any parent results and take the expected packet from the result
+pub fn parse_packet<T: 'static>(mut parser: impl Parsable<PathIp>, input: &[u8]) -> T {
+    match parser.parse(input, None, None).unwrap().1.downcast::<T>() {
+        Ok(packet) => *packet,
+        _ => panic!("{} packet expected", parser),
+    }
+}
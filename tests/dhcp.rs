@@ -0,0 +1,113 @@
+extern crate nom;
+extern crate peel_ip;
+mod common;
+use common::parse_packet;
+use peel_ip::prelude::*;
+use std::net::Ipv4Addr;
+
+static DISCOVER_OPTIONS: &'static [u8] = &[0x35, 0x01, 0x01, 0x3d, 0x07, 0x01, 0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42,
+                                           0x32, 0x04, 0xc0, 0xa8, 0x00, 0x64, 0x37, 0x04, 0x01, 0x03, 0x06, 0x2a,
+                                           0x0c, 0x04, 0x68, 0x6f, 0x73, 0x74, 0x00, 0xff];
+
+static ACK_OPTIONS: &'static [u8] = &[0x35, 0x01, 0x05, 0x36, 0x04, 0xc0, 0xa8, 0x00, 0x01, 0x33, 0x04, 0x00, 0x00,
+                                      0x0e, 0x10, 0x3a, 0x04, 0x00, 0x00, 0x07, 0x08, 0x3b, 0x04, 0x00, 0x00, 0x0c,
+                                      0x4e, 0x01, 0x04, 0xff, 0xff, 0xff, 0x00, 0x03, 0x04, 0xc0, 0xa8, 0x00, 0x01,
+                                      0x06, 0x08, 0xc0, 0xa8, 0x00, 0x01, 0x08, 0x08, 0x08, 0x08, 0x0f, 0x05, 0x6c,
+                                      0x6f, 0x63, 0x61, 0x6c, 0x52, 0x09, 0x01, 0x03, 0x65, 0x74, 0x68, 0x02, 0x02,
+                                      0xab, 0xcd, 0xff];
+
+/// Create a message with the given operation and options, which follow the magic cookie
+fn message(op: u8, options: &[u8]) -> Vec<u8> {
+    let mut input = vec![op, 0x01, 0x06, 0x00, 0x39, 0x03, 0xf3, 0x26, 0x00, 0x00, 0x80, 0x00];
+    input.extend_from_slice(&[0x00; 16]);
+    input.extend_from_slice(&[0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42]);
+    input.extend_from_slice(&[0x00; 202]);
+    if !options.is_empty() {
+        input.extend_from_slice(&[0x63, 0x82, 0x53, 0x63]);
+        input.extend_from_slice(options);
+    }
+    input
+}
+
+#[test]
+fn parse_dhcp_success_discover() {
+    let mut parser = DhcpParser;
+    println!("{}", parser);
+    let res = parser.parse(&message(1, DISCOVER_OPTIONS), None, None).unwrap().1;
+    assert_eq!(Some(&DhcpPacket {
+                   op: DhcpOperation::BootRequest,
+                   hardware_type: 1,
+                   hardware_length: 6,
+                   hops: 0,
+                   xid: 0x3903f326,
+                   secs: 0,
+                   broadcast: true,
+                   ciaddr: Ipv4Addr::new(0, 0, 0, 0),
+                   yiaddr: Ipv4Addr::new(0, 0, 0, 0),
+                   siaddr: Ipv4Addr::new(0, 0, 0, 0),
+                   giaddr: Ipv4Addr::new(0, 0, 0, 0),
+                   chaddr: MacAddress(0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42),
+                   sname: String::new(),
+                   file: String::new(),
+                   options: vec![DhcpOption::MessageType(DhcpMessageType::Discover),
+                                 DhcpOption::ClientIdentifier(1, vec![0x00, 0x0b, 0x82, 0x01, 0xfc, 0x42]),
+                                 DhcpOption::RequestedAddress(Ipv4Addr::new(192, 168, 0, 100)),
+                                 DhcpOption::ParameterRequestList(vec![1, 3, 6, 42]),
+                                 DhcpOption::HostName("host".to_owned())],
+               }),
+               res.downcast_ref());
+}
+
+#[test]
+fn parse_dhcp_success_ack() {
+    let packet = parse_packet::<DhcpPacket>(DhcpParser, &message(2, ACK_OPTIONS));
+    assert_eq!(packet.op, DhcpOperation::BootReply);
+    assert_eq!(packet.message_type(), Some(&DhcpMessageType::Ack));
+    assert_eq!(packet.options[1..],
+               [DhcpOption::ServerIdentifier(Ipv4Addr::new(192, 168, 0, 1)),
+                DhcpOption::LeaseTime(3600),
+                DhcpOption::RenewalTime(1800),
+                DhcpOption::RebindingTime(3150),
+                DhcpOption::SubnetMask(Ipv4Addr::new(255, 255, 255, 0)),
+                DhcpOption::Router(vec![Ipv4Addr::new(192, 168, 0, 1)]),
+                DhcpOption::DomainNameServer(vec![Ipv4Addr::new(192, 168, 0, 1), Ipv4Addr::new(8, 8, 8, 8)]),
+                DhcpOption::DomainName("local".to_owned()),
+                DhcpOption::RelayAgentInformation(vec![DhcpRelayAgentOption::CircuitId(b"eth".to_vec()),
+                                                       DhcpRelayAgentOption::RemoteId(vec![0xab, 0xcd])])]);
+}
+
+#[test]
+fn parse_dhcp_success_split_options() {
+    // The parts of a long option are concatenated, unknown options are kept
+    let packet = parse_packet::<DhcpPacket>(DhcpParser, &message(2,
+                                       &[0x06, 0x04, 0x0a, 0x00, 0x00, 0x01, 0x00, 0xe0, 0x01, 0xaa, 0x06, 0x04,
+                                         0x0a, 0x00, 0x00, 0x02, 0xff]));
+    assert_eq!(packet.options,
+               vec![DhcpOption::DomainNameServer(vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)]),
+                    DhcpOption::Unknown(0xe0, vec![0xaa])]);
+}
+
+#[test]
+fn parse_dhcp_success_bootp() {
+    let mut input = message(1, &[]);
+    input[44] = 0x73;
+    input[45] = 0x72;
+    input[46] = 0x76;
+    let packet = parse_packet::<DhcpPacket>(DhcpParser, &input);
+    assert_eq!(packet.sname, "srv");
+    assert!(packet.options.is_empty());
+    assert_eq!(packet.message_type(), None);
+}
+
+#[test]
+fn parse_dhcp_failure_invalid_options() {
+    let mut parser = DhcpParser;
+
+    // Options with a wrong length are kept raw
+    let packet = parse_packet::<DhcpPacket>(DhcpParser, &message(1, &[0x33, 0x02, 0x00, 0x01, 0xff]));
+    assert_eq!(packet.options, vec![DhcpOption::Invalid(51, vec![0x00, 0x01])]);
+
+    // Truncated options and messages
+    assert!(parser.parse(&message(1, &[0x35, 0x03, 0x01]), None, None).is_err());
+    assert!(parser.parse(&message(1, &[])[..200], None, None).is_err());
+}
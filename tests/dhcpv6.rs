@@ -0,0 +1,204 @@
+extern crate nom;
+extern crate peel_ip;
+mod common;
+use common::parse_packet;
+use peel_ip::prelude::*;
+use std::net::Ipv6Addr;
+
+static SOLICIT: &'static [u8] = &[0x01, 0x12, 0x34, 0x56, 0x00, 0x01, 0x00, 0x0e, 0x00, 0x01, 0x00, 0x01, 0x1c, 0x39,
+                                  0xcf, 0x88, 0x08, 0x00, 0x27, 0xfe, 0x8f, 0x95, 0x00, 0x08, 0x00, 0x02, 0x00, 0x00,
+                                  0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18, 0x00, 0x0e, 0x00, 0x00, 0x00, 0x03,
+                                  0x00, 0x0c, 0x27, 0xfe, 0x8f, 0x95, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x15, 0x18];
+
+/// Encode an option with the given code and data
+fn option(code: u16, data: &[u8]) -> Vec<u8> {
+    let mut option = vec![(code >> 8) as u8, code as u8, (data.len() >> 8) as u8, data.len() as u8];
+    option.extend_from_slice(data);
+    option
+}
+
+/// Encapsulate a message into a Relay-Forward message
+fn relay(message: &[u8]) -> Vec<u8> {
+    let mut relay = vec![0x0c, 0x00];
+    relay.extend_from_slice(&Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).octets());
+    relay.extend_from_slice(&Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1).octets());
+    relay.extend_from_slice(&option(18, b"eth0"));
+    relay.extend_from_slice(&option(9, message));
+    relay
+}
+
+#[test]
+fn parse_dhcpv6_success_solicit() {
+    let mut parser = Dhcpv6Parser;
+    println!("{}", parser);
+    let res = parser.parse(SOLICIT, None, None).unwrap().1;
+    assert_eq!(Some(&Dhcpv6Packet::Message(Dhcpv6Message {
+                   message_type: Dhcpv6MessageType::Solicit,
+                   transaction_id: 0x123456,
+                   options: vec![Dhcpv6Option::ClientId(Dhcpv6Duid::LinkLayerTime(1,
+                                                                                  0x1c39cf88,
+                                                                                  vec![0x08, 0x00, 0x27, 0xfe,
+                                                                                       0x8f, 0x95])),
+                                 Dhcpv6Option::ElapsedTime(0),
+                                 Dhcpv6Option::OptionRequest(vec![23, 24]),
+                                 Dhcpv6Option::RapidCommit,
+                                 Dhcpv6Option::IaNa(Dhcpv6IdentityAssociation {
+                                     iaid: 0x27fe8f95,
+                                     t1: 3600,
+                                     t2: 5400,
+                                     options: vec![],
+                                 })],
+               })),
+               res.downcast_ref());
+}
+
+#[test]
+fn parse_dhcpv6_success_reply() {
+    let mut address = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x10).octets().to_vec();
+    address.extend_from_slice(&[0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x1c, 0x20]);
+    let mut ia_na = vec![0x27, 0xfe, 0x8f, 0x95, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x15, 0x18];
+    ia_na.extend_from_slice(&option(5, &address));
+    let mut prefix = vec![0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x1c, 0x20, 0x30];
+    prefix.extend_from_slice(&Ipv6Addr::new(0x2001, 0xdb8, 1, 0, 0, 0, 0, 0).octets());
+    let mut ia_pd = vec![0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    ia_pd.extend_from_slice(&option(26, &prefix));
+
+    let mut input = vec![0x07, 0x12, 0x34, 0x56];
+    input.extend(option(2, &[0x00, 0x03, 0x00, 0x01, 0x08, 0x00, 0x27, 0x00, 0x00, 0x01]));
+    input.extend(option(3, &ia_na));
+    input.extend(option(25, &ia_pd));
+    input.extend(option(23, &Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53).octets()));
+    input.extend(option(24, b"\x07example\x03com\x00\x04test\x00"));
+    input.extend(option(13, b"\x00\x00ok"));
+    input.extend(option(0x1234, &[0xaa]));
+
+    let packet = parse_packet::<Dhcpv6Packet>(Dhcpv6Parser, &input);
+    assert_eq!(packet.message_type(), &Dhcpv6MessageType::Reply);
+    assert_eq!(packet.options(),
+               &[Dhcpv6Option::ServerId(Dhcpv6Duid::LinkLayer(1, vec![0x08, 0x00, 0x27, 0x00, 0x00, 0x01])),
+                 Dhcpv6Option::IaNa(Dhcpv6IdentityAssociation {
+                     iaid: 0x27fe8f95,
+                     t1: 3600,
+                     t2: 5400,
+                     options: vec![Dhcpv6Option::IaAddress(Dhcpv6IaAddress {
+                                       address: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x10),
+                                       preferred_lifetime: 3600,
+                                       valid_lifetime: 7200,
+                                       options: vec![],
+                                   })],
+                 }),
+                 Dhcpv6Option::IaPd(Dhcpv6IdentityAssociation {
+                     iaid: 2,
+                     t1: 0,
+                     t2: 0,
+                     options: vec![Dhcpv6Option::IaPrefix(Dhcpv6IaPrefix {
+                                       preferred_lifetime: 3600,
+                                       valid_lifetime: 7200,
+                                       prefix_length: 48,
+                                       prefix: Ipv6Addr::new(0x2001, 0xdb8, 1, 0, 0, 0, 0, 0),
+                                       options: vec![],
+                                   })],
+                 }),
+                 Dhcpv6Option::DnsServers(vec![Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53)]),
+                 Dhcpv6Option::DomainList(vec!["example.com".to_owned(), "test".to_owned()]),
+                 Dhcpv6Option::StatusCode(0, "ok".to_owned()),
+                 Dhcpv6Option::Unknown(0x1234, vec![0xaa])][..]);
+}
+
+#[test]
+fn parse_dhcpv6_success_duid_types() {
+    assert_eq!(Dhcpv6Duid::parse(&[0x00, 0x02, 0x00, 0x00, 0x00, 0x09, 0xaa]).unwrap().1,
+               Dhcpv6Duid::Enterprise(9, vec![0xaa]));
+    assert_eq!(Dhcpv6Duid::parse(&[0x00, 0x04, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
+                                   0x0c, 0x0d, 0x0e, 0x0f, 0x10])
+                   .unwrap()
+                   .1,
+               Dhcpv6Duid::Uuid((1..17).collect()));
+    assert_eq!(Dhcpv6Duid::parse(&[0x00, 0x05, 0xbb]).unwrap().1,
+               Dhcpv6Duid::Unknown(5, vec![0xbb]));
+}
+
+#[test]
+fn parse_dhcpv6_success_nested_relay() {
+    let packet = parse_packet::<Dhcpv6Packet>(Dhcpv6Parser, &relay(&relay(SOLICIT)));
+    match packet {
+        Dhcpv6Packet::Relay(ref relay) => {
+            assert_eq!(relay.message_type, Dhcpv6MessageType::RelayForward);
+            assert_eq!(relay.link_address, Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+            assert_eq!(relay.peer_address, Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1));
+            assert_eq!(relay.options[0], Dhcpv6Option::InterfaceId(b"eth0".to_vec()));
+            match relay.options[1] {
+                Dhcpv6Option::RelayMessage(ref inner) => {
+                    assert_eq!(inner.message_type(), &Dhcpv6MessageType::RelayForward)
+                }
+                _ => panic!("Relay message expected"),
+            }
+        }
+        _ => panic!("Relay expected"),
+    }
+    assert_eq!(packet.innermost(), &parse_packet::<Dhcpv6Packet>(Dhcpv6Parser, SOLICIT));
+}
+
+#[test]
+fn parse_dhcpv6_failure_relay_depth() {
+    let mut input = Vec::from(SOLICIT);
+    for _ in 0..34 {
+        input = relay(&input);
+    }
+    match parse_packet::<Dhcpv6Packet>(Dhcpv6Parser, &input).innermost().options()[1] {
+        Dhcpv6Option::Invalid(9, _) => {}
+        _ => panic!("Invalid relay message expected"),
+    }
+}
+
+#[test]
+fn parse_dhcpv6_failure_option_depth() {
+    let mut ia = option(3, &[0; 12]);
+    for _ in 0..2000 {
+        let mut data = vec![0; 12];
+        data.extend_from_slice(&ia);
+        ia = option(3, &data);
+    }
+    let mut input = vec![0x07, 0x12, 0x34, 0x56];
+    input.extend_from_slice(&ia);
+    let packet = parse_packet::<Dhcpv6Packet>(Dhcpv6Parser, &input);
+    let mut options = packet.options();
+    let mut depth = 0;
+    while let Dhcpv6Option::IaNa(ref ia) = options[0] {
+        options = &ia.options;
+        depth += 1;
+    }
+    assert_eq!(depth, 34);
+    match options[0] {
+        Dhcpv6Option::Invalid(3, _) => {}
+        _ => panic!("Invalid identity association expected"),
+    }
+}
+
+#[test]
+fn parse_dhcpv6_failure_invalid() {
+    let mut parser = Dhcpv6Parser;
+    assert!(parser.parse(&[0x01, 0x12], None, None).is_err());
+    assert!(parser.parse(&relay(SOLICIT)[..20], None, None).is_err());
+    let packet = parse_packet::<Dhcpv6Packet>(Dhcpv6Parser, &[0x07, 0x00, 0x00, 0x01, 0x00, 0x08, 0x00, 0x01, 0x00]);
+    assert_eq!(packet.options(), &[Dhcpv6Option::Invalid(8, vec![0x00])][..]);
+
+    // The domain search list must not be compressed
+    let input = [0x07, 0x00, 0x00, 0x01, 0x00, 0x18, 0x00, 0x06, 0x01, 0x61, 0x00, 0x01, 0x62, 0xc0];
+    let packet = parse_packet::<Dhcpv6Packet>(Dhcpv6Parser, &input);
+    assert_eq!(packet.options(), &[Dhcpv6Option::Invalid(24, vec![0x01, 0x61, 0x00, 0x01, 0x62, 0xc0])][..]);
+}
+
+#[test]
+fn dissect_dhcpv6_success_nested_relay() {
+    let input = relay(SOLICIT);
+    let field = parse_packet::<Dhcpv6Packet>(Dhcpv6Parser, &input).dissect(&input, 0);
+    assert_eq!(field.byte_range(), 0..input.len());
+    assert_eq!(field.find("dhcpv6.peer_address").map(Field::byte_range), Some(18..34));
+
+    // The relayed message is dissected at its position within the relay message
+    let inner = field.children[5].children.iter().find(|f| f.name == "dhcpv6").unwrap();
+    assert_eq!(inner.byte_range(), 46..input.len());
+    assert_eq!(inner.find("dhcpv6.transaction_id").map(|f| (f.byte_range(), &f.value)),
+               Some((47..50, &FieldValue::Unsigned(0x123456))));
+}
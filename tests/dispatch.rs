@@ -0,0 +1,88 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+/// A heuristic for a custom protocol
+fn is_custom(input: &[u8]) -> bool {
+    input.starts_with(b"CUSTOM")
+}
+
+#[test]
+#[cfg(all(feature = "http", feature = "dns", feature = "mdns", feature = "llmnr", feature = "dhcp",
+          feature = "dhcpv6", feature = "ntp"))]
+fn dispatch_success_well_known_ports() {
+    let tcp = DispatchRegistry::tcp();
+    assert_eq!(tcp.dispatch(51781, 443, &[]),
+               vec![ApplicationProtocol::Tls, ApplicationProtocol::Http]);
+    assert_eq!(tcp.dispatch(53, 51781, &[]), vec![ApplicationProtocol::Dns]);
+    assert_eq!(tcp.dispatch(51781, 80, &[]), vec![ApplicationProtocol::Http]);
+    assert_eq!(tcp.dispatch(8080, 51781, &[]), vec![ApplicationProtocol::Http]);
+
+    let udp = DispatchRegistry::udp();
+    assert_eq!(udp.dispatch(68, 67, &[]), vec![ApplicationProtocol::Dhcp]);
+    assert_eq!(udp.dispatch(546, 547, &[]), vec![ApplicationProtocol::Dhcpv6]);
+    assert_eq!(udp.dispatch(5353, 5353, &[]), vec![ApplicationProtocol::Mdns]);
+    assert_eq!(udp.dispatch(50000, 5355, &[]), vec![ApplicationProtocol::Llmnr]);
+    assert_eq!(udp.dispatch(123, 123, &[]), vec![ApplicationProtocol::Ntp]);
+}
+
+#[test]
+#[cfg(all(feature = "dtls", feature = "ntp"))]
+fn dispatch_success_heuristics() {
+    let tcp = DispatchRegistry::tcp();
+    assert_eq!(tcp.dispatch(50000, 8443, &[0x16, 0x03, 0x01, 0x00, 0x10]),
+               vec![ApplicationProtocol::Tls]);
+    assert!(tcp.dispatch(50000, 8443, &[0x16, 0x04, 0x01]).is_empty());
+
+    let udp = DispatchRegistry::udp();
+    assert_eq!(udp.dispatch(50000, 4433, &[0x16, 0xfe, 0xfd, 0x00, 0x00]),
+               vec![ApplicationProtocol::Dtls]);
+    let mut ntp = vec![0x23];
+    ntp.extend_from_slice(&[0x00; 47]);
+    assert_eq!(udp.dispatch(50000, 50001, &ntp), vec![ApplicationProtocol::Ntp]);
+    assert!(udp.dispatch(50000, 50001, &ntp[..47]).is_empty());
+    ntp[1] = 200;
+    assert!(udp.dispatch(50000, 50001, &ntp).is_empty());
+    ntp[1] = 0;
+    ntp.extend_from_slice(&[0x00; 20]);
+    assert_eq!(udp.dispatch(50000, 50001, &ntp), vec![ApplicationProtocol::Ntp]);
+    ntp.push(0x00);
+    assert!(udp.dispatch(50000, 50001, &ntp).is_empty());
+
+    let mut control = vec![0x16, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63];
+    assert!(udp.dispatch(50000, 50001, &control).is_empty());
+    control.push(0x00);
+    assert_eq!(udp.dispatch(50000, 50001, &control), vec![ApplicationProtocol::Ntp]);
+    assert!(udp.dispatch(50000, 50001, &[0x16, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]).is_empty());
+    assert_eq!(udp.dispatch(50000, 50001, &[0x17, 0x00, 0x03, 0x2a, 0x00, 0x00, 0x00, 0x00]),
+               vec![ApplicationProtocol::Ntp]);
+    assert!(udp.dispatch(50000, 50001, &[0x17, 0x00, 0x03, 0x2a, 0x00, 0x01, 0x00, 0x48]).is_empty());
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn dispatch_success_http_heuristic() {
+    let tcp = DispatchRegistry::tcp();
+    assert_eq!(tcp.dispatch(50000, 8000, b"GET / HTTP/1.1\r\n\r\n"), vec![ApplicationProtocol::Http]);
+    assert_eq!(tcp.dispatch(8000, 50000, b"HTTP/1.1 200 OK\r\n\r\n"), vec![ApplicationProtocol::Http]);
+    assert!(tcp.dispatch(50000, 8000, b"GETTER").is_empty());
+    assert!(tcp.dispatch(50000, 8000, b"SSH-2.0-OpenSSH_9.6\r\n").is_empty());
+}
+
+#[test]
+fn dispatch_success_custom() {
+    let mut registry = DispatchRegistry::new();
+    assert!(registry.dispatch(1, 2, b"CUSTOM").is_empty());
+
+    registry.add_port(8080, ApplicationProtocol::Custom("proxy".into()))
+        .add_port(8080, ApplicationProtocol::Custom("proxy".into()))
+        .add_port(9000, ApplicationProtocol::Dns)
+        .add_heuristic(ApplicationProtocol::Custom("custom".into()), is_custom);
+    assert_eq!(registry.dispatch(9000, 8080, b"CUSTOM"),
+               vec![ApplicationProtocol::Custom("proxy".into()),
+                    ApplicationProtocol::Dns,
+                    ApplicationProtocol::Custom("custom".into())]);
+
+    registry.remove_port(8080, &ApplicationProtocol::Custom("proxy".into()))
+        .remove_heuristics(&ApplicationProtocol::Custom("custom".into()));
+    assert_eq!(registry.dispatch(9000, 8080, b"CUSTOM"), vec![ApplicationProtocol::Dns]);
+}
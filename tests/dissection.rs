@@ -0,0 +1,100 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+static PACKET_ETH_IPV6_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
+      0x00, 0x24, 0x11, 0x40, 0x3f, 0xfe, 0x05, 0x07, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x86, 0xff, 0xfe, 0x05,
+      0x80, 0xda, 0x3f, 0xfe, 0x05, 0x01, 0x48, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
+      0x09, 0x5c, 0x00, 0x35, 0x00, 0x24, 0xf0, 0x09];
+
+fn dissect(input: &[u8]) -> Field {
+    Packet::new(PeelIp::default().traverse(input, vec![]).result).dissect(input)
+}
+
+#[test]
+fn dissect_success_tcp() {
+    let frame = dissect(PACKET_ETH_IPV4_TCP);
+    assert_eq!(frame.byte_range(), 0..PACKET_ETH_IPV4_TCP.len());
+    let layers: Vec<_> = frame.children.iter().map(|f| (f.name.as_str(), f.byte_range())).collect();
+    assert_eq!(layers, vec![("ethernet", 0..14), ("ipv4", 14..34), ("tcp", 34..66)]);
+
+    let ethertype = frame.find("ethernet.ethertype").unwrap();
+    assert_eq!(ethertype.value, FieldValue::Named(0x800, "Ipv4".to_owned()));
+    assert_eq!(frame.find("tcp.dest_port").map(|f| (f.byte_range(), &f.value)),
+               Some((36..38, &FieldValue::Unsigned(443))));
+    assert_eq!(frame.find("ipv4.src").map(|f| f.value.to_string()), Some("10.0.0.101".to_owned()));
+}
+
+#[test]
+fn dissect_success_bit_fields() {
+    let frame = dissect(PACKET_ETH_IPV4_TCP);
+    let flags = frame.find("ipv4.flags").unwrap();
+    assert_eq!((flags.bit_offset, flags.bit_length), (160, 3));
+    assert!(flags.is_bit_field());
+    let names: Vec<_> = flags.children.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["ipv4.flags.reserved", "ipv4.flags.df", "ipv4.flags.mf"]);
+
+    let df = frame.find("ipv4.flags.df").unwrap();
+    assert_eq!((df.bit_offset, df.bit_length, &df.value), (161, 1, &FieldValue::Bool(true)));
+    assert_eq!(df.byte_range(), 20..21);
+    let fragment_offset = frame.find("ipv4.fragment_offset").unwrap();
+    assert_eq!((fragment_offset.bit_offset, fragment_offset.bit_end()), (163, 176));
+
+    let ack = frame.find("tcp.flag_ack").unwrap();
+    assert_eq!((ack.bit_offset, &ack.value), (379, &FieldValue::Bool(true)));
+    assert_eq!(frame.find("tcp.flag_syn").map(|f| &f.value), Some(&FieldValue::Bool(false)));
+    assert!(!frame.find("tcp.window").unwrap().is_bit_field());
+}
+
+#[test]
+fn dissect_success_ipv6_udp() {
+    let frame = dissect(PACKET_ETH_IPV6_UDP);
+    let flow_label = frame.find("ipv6.flow_label").unwrap();
+    assert_eq!((flow_label.bit_offset, flow_label.bit_length), (124, 20));
+    assert_eq!(frame.find("ipv6.dst").map(Field::byte_range), Some(38..54));
+    assert_eq!(frame.find("udp.dest_port").map(|f| (f.byte_range(), &f.value)),
+               Some((56..58, &FieldValue::Unsigned(53))));
+}
+
+#[test]
+fn dissect_success_iter() {
+    let frame = dissect(PACKET_ETH_IPV4_TCP);
+    let names: Vec<_> = frame.iter().take(6).map(|f| f.name.as_str()).collect();
+    assert_eq!(names,
+               vec!["frame", "ethernet", "ethernet.dst", "ethernet.src", "ethernet.ethertype", "ipv4"]);
+    assert_eq!(frame.iter().filter(|f| f.name.starts_with("tcp.flag_")).count(), 6);
+    assert!(frame.find("udp").is_none());
+}
+
+#[test]
+fn dissect_success_tree() {
+    let frame = dissect(PACKET_ETH_IPV4_TCP);
+    let tree = frame.find("ipv4.flags").unwrap().to_string();
+    assert_eq!(tree,
+               "ipv4.flags: 2 [bits 160..163]\n    ipv4.flags.reserved: false [bits 160..161]\n    \
+                ipv4.flags.df: true [bits 161..162]\n    ipv4.flags.mf: false [bits 162..163]\n");
+    let lines: Vec<_> = frame.to_string().lines().take(3).map(str::to_owned).collect();
+    assert_eq!(lines,
+               vec!["frame: 66 [bytes 0..66]",
+                    "    ethernet: 00:00:00:00:00:00 > 00:00:00:00:00:00 Ethernet Ipv4 [bytes 0..14]",
+                    "        ethernet.dst: 00:00:00:00:00:00 [bytes 0..6]"]);
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn dissect_success_http() {
+    let mut input = Vec::from(PACKET_ETH_IPV4_TCP);
+    input.extend_from_slice(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n");
+    let frame = dissect(&input);
+    assert_eq!(frame.find("http").map(Field::byte_range), Some(66..input.len()));
+    assert_eq!(frame.find("http.path").map(|f| (f.byte_range(), f.value.to_string())),
+               Some((70..71, "/".to_owned())));
+    assert_eq!(frame.find("http.headers.value").map(|f| (f.byte_range(), f.value.to_string())),
+               Some((88..99, "example.com".to_owned())));
+}
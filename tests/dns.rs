@@ -0,0 +1,441 @@
+extern crate nom;
+extern crate peel_ip;
+mod common;
+use common::parse_packet;
+use peel_ip::prelude::*;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+static QUERY: &'static [u8] = &[0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x07, 0x65,
+                                0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00, 0x01, 0x00,
+                                0x01, 0x00, 0x00, 0x29, 0x10, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00];
+
+static RESPONSE: &'static [u8] = &[0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x07,
+                                   0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x03, 0x63, 0x6f, 0x6d, 0x00, 0x00,
+                                   0x01, 0x00, 0x01, 0xc0, 0x0c, 0x00, 0x05, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c,
+                                   0x00, 0x06, 0x03, 0x77, 0x77, 0x77, 0xc0, 0x0c, 0xc0, 0x29, 0x00, 0x01, 0x00,
+                                   0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x04, 0x5d, 0xb8, 0xd8, 0x22];
+
+fn record_data(rtype: DnsType, data: &[u8]) -> DnsRecordData {
+    DnsRecordData::new(&rtype, 1, 0, data, data)
+}
+
+#[test]
+fn parse_dns_success_query() {
+    let mut parser = DnsParser::default();
+    println!("{}", parser);
+    let res = parser.parse(QUERY, None, None).unwrap().1;
+    assert_eq!(Some(&DnsPacket {
+                   header: DnsHeader {
+                       id: 0x1234,
+                       response: false,
+                       opcode: DnsOpcode::Query,
+                       authoritative: false,
+                       truncated: false,
+                       recursion_desired: true,
+                       recursion_available: false,
+                       zero: false,
+                       authentic_data: false,
+                       checking_disabled: false,
+                       response_code: DnsResponseCode::NoError,
+                       question_count: 1,
+                       answer_count: 0,
+                       authority_count: 0,
+                       additional_count: 1,
+                   },
+                   questions: vec![DnsQuestion {
+                                       name: "example.com".to_owned(),
+                                       qtype: DnsType::A,
+                                       class: DnsClass::In,
+                                   }],
+                   answers: vec![],
+                   authorities: vec![],
+                   additionals: vec![DnsResourceRecord {
+                                         name: ".".to_owned(),
+                                         rtype: DnsType::Opt,
+                                         class: DnsClass::Unknown(4096),
+                                         ttl: 0x8000,
+                                         data: DnsRecordData::Opt(DnsEdns {
+                                             udp_payload_size: 4096,
+                                             extended_response_code: 0,
+                                             version: 0,
+                                             dnssec_ok: true,
+                                             options: vec![],
+                                         }),
+                                     }],
+                   transactions: vec![],
+               }),
+               res.downcast_ref());
+}
+
+#[test]
+fn parse_dns_success_compressed_response() {
+    let packet = parse_packet::<DnsPacket>(DnsParser::default(), RESPONSE);
+    assert!(packet.header.response);
+    assert!(packet.header.recursion_available);
+    assert_eq!(packet.answers[0].name, "example.com");
+    assert_eq!(packet.answers[0].ttl, 300);
+    assert_eq!(packet.answers[0].data,
+               DnsRecordData::Cname("www.example.com".to_owned()));
+    assert_eq!(packet.answers[1].name, "www.example.com");
+    assert_eq!(packet.answers[1].data,
+               DnsRecordData::A(Ipv4Addr::new(93, 184, 216, 34)));
+    assert_eq!(packet.response_code(), DnsResponseCode::NoError);
+    assert_eq!(packet.edns(), None);
+}
+
+#[test]
+fn parse_dns_failure_compression_loop() {
+    let mut parser = DnsParser::default();
+
+    // The name points to itself
+    let mut input = Vec::from(&QUERY[..12]);
+    input.extend_from_slice(&[0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01]);
+    assert!(parser.parse(&input, None, None).is_err());
+
+    // Pointers to the following data are not allowed
+    let mut input = Vec::from(&QUERY[..12]);
+    input.extend_from_slice(&[0xc0, 0x12, 0x00, 0x01, 0x00, 0x01, 0x00]);
+    assert!(parser.parse(&input, None, None).is_err());
+}
+
+#[test]
+fn parse_dns_failure_name_too_long() {
+    let mut input = Vec::from(&QUERY[..12]);
+    for _ in 0..5 {
+        input.push(63);
+        input.extend_from_slice(&[0x61; 63]);
+    }
+    input.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x01]);
+    assert!(DnsPacket::parse(&input).is_err());
+}
+
+#[test]
+fn parse_dns_failure_counts_exceed_input() {
+    let input = [0x12, 0x34, 0x01, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+    assert!(DnsPacket::parse(&input).is_incomplete());
+
+    let mut input = Vec::from(&QUERY[..12]);
+    input[4] = 0xff;
+    input.extend_from_slice(&QUERY[12..29]);
+    assert!(DnsParser::default().parse(&input, None, None).is_err());
+}
+
+#[test]
+fn parse_dns_success_escaped_labels() {
+    let data = [0x03, 0x61, 0x2e, 0x62, 0x02, 0x5c, 0x20, 0x00];
+    assert_eq!(parse_name(&data, &data).unwrap().1, "a\\.b.\\\\\\032");
+    assert_eq!(parse_name(&[0x00], &[0x00]).unwrap().1, ".");
+}
+
+#[test]
+fn parse_dns_success_record_types() {
+    assert_eq!(record_data(DnsType::Aaaa, &[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01]),
+               DnsRecordData::Aaaa(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)));
+    assert_eq!(record_data(DnsType::Mx, &[0x00, 0x0a, 0x02, 0x6d, 0x78, 0x00]),
+               DnsRecordData::Mx(10, "mx".to_owned()));
+    assert_eq!(record_data(DnsType::Ns, &[0x02, 0x6e, 0x73, 0x00]),
+               DnsRecordData::Ns("ns".to_owned()));
+    assert_eq!(record_data(DnsType::Ptr, &[0x01, 0x61, 0x00]),
+               DnsRecordData::Ptr("a".to_owned()));
+    assert_eq!(record_data(DnsType::Txt, &[0x02, 0x68, 0x69, 0x00]),
+               DnsRecordData::Txt(vec![b"hi".to_vec(), vec![]]));
+    assert_eq!(record_data(DnsType::Soa,
+                           &[0x02, 0x6e, 0x73, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x0e, 0x10, 0x00,
+                             0x00, 0x02, 0x58, 0x00, 0x09, 0x3a, 0x80, 0x00, 0x00, 0x01, 0x2c]),
+               DnsRecordData::Soa(DnsSoa {
+                   primary: "ns".to_owned(),
+                   mailbox: ".".to_owned(),
+                   serial: 1,
+                   refresh: 3600,
+                   retry: 600,
+                   expire: 604800,
+                   minimum: 300,
+               }));
+    assert_eq!(record_data(DnsType::Srv,
+                           &[0x00, 0x01, 0x00, 0x02, 0x14, 0x95, 0x03, 0x73, 0x69, 0x70, 0x00]),
+               DnsRecordData::Srv(DnsSrv {
+                   priority: 1,
+                   weight: 2,
+                   port: 5269,
+                   target: "sip".to_owned(),
+               }));
+    assert_eq!(record_data(DnsType::Caa, &[0x80, 0x05, 0x69, 0x73, 0x73, 0x75, 0x65, 0x63, 0x61]),
+               DnsRecordData::Caa(DnsCaa {
+                   flags: 0x80,
+                   tag: "issue".to_owned(),
+                   value: b"ca".to_vec(),
+               }));
+    assert_eq!(record_data(DnsType::Unknown(99), &[0x01, 0x02]),
+               DnsRecordData::Unknown(vec![0x01, 0x02]));
+}
+
+#[test]
+fn parse_dns_success_service_binding() {
+    let data = [0x00, 0x01, 0x00, 0x00, 0x01, 0x00, 0x03, 0x02, 0x68, 0x32, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03,
+                0x00, 0x02, 0x01, 0xbb, 0x00, 0x04, 0x00, 0x04, 0x0a, 0x00, 0x00, 0x01, 0x00, 0x06, 0x00, 0x10,
+                0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00, 0x05, 0x00, 0x01, 0xaa, 0xff,
+                0xff, 0x00, 0x01, 0xbb];
+    assert_eq!(record_data(DnsType::Https, &data),
+               DnsRecordData::Svcb(DnsSvcb {
+                   priority: 1,
+                   target: ".".to_owned(),
+                   params: vec![DnsSvcParam::Alpn(vec!["h2".to_owned()]),
+                                DnsSvcParam::NoDefaultAlpn,
+                                DnsSvcParam::Port(443),
+                                DnsSvcParam::Ipv4Hint(vec![Ipv4Addr::new(10, 0, 0, 1)]),
+                                DnsSvcParam::Ipv6Hint(vec![Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)]),
+                                DnsSvcParam::Ech(vec![0xaa]),
+                                DnsSvcParam::Unknown(0xffff, vec![0xbb])],
+               }));
+}
+
+#[test]
+fn parse_dns_success_dnssec_records() {
+    assert_eq!(record_data(DnsType::Ds, &[0x30, 0x39, 0x08, 0x02, 0xaa, 0xbb]),
+               DnsRecordData::Ds(DnsDs {
+                   key_tag: 12345,
+                   algorithm: 8,
+                   digest_type: 2,
+                   digest: vec![0xaa, 0xbb],
+               }));
+    assert_eq!(record_data(DnsType::Dnskey, &[0x01, 0x01, 0x03, 0x0d, 0xcc]),
+               DnsRecordData::Dnskey(DnsDnskey {
+                   flags: 257,
+                   protocol: 3,
+                   algorithm: 13,
+                   public_key: vec![0xcc],
+               }));
+    assert_eq!(record_data(DnsType::Rrsig,
+                           &[0x00, 0x01, 0x0d, 0x02, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00,
+                             0x00, 0x01, 0x30, 0x39, 0x00, 0xdd]),
+               DnsRecordData::Rrsig(DnsRrsig {
+                   type_covered: DnsType::A,
+                   algorithm: 13,
+                   labels: 2,
+                   original_ttl: 3600,
+                   expiration: 2,
+                   inception: 1,
+                   key_tag: 12345,
+                   signer: ".".to_owned(),
+                   signature: vec![0xdd],
+               }));
+    assert_eq!(record_data(DnsType::Nsec, &[0x01, 0x62, 0x00, 0x00, 0x06, 0x40, 0x01, 0x00, 0x00, 0x00, 0x03]),
+               DnsRecordData::Nsec("b".to_owned(),
+                                   vec![DnsType::A, DnsType::Mx, DnsType::Rrsig, DnsType::Nsec]));
+    assert_eq!(record_data(DnsType::Nsec3, &[0x01, 0x01, 0x00, 0x0a, 0x01, 0xab, 0x02, 0x11, 0x22, 0x00, 0x01, 0x40]),
+               DnsRecordData::Nsec3(DnsNsec3 {
+                   hash_algorithm: 1,
+                   flags: 1,
+                   iterations: 10,
+                   salt: vec![0xab],
+                   next_hashed_owner: vec![0x11, 0x22],
+                   types: vec![DnsType::A],
+               }));
+}
+
+#[test]
+fn parse_dns_failure_invalid_record_data() {
+    assert_eq!(record_data(DnsType::A, &[0x0a, 0x00, 0x00]),
+               DnsRecordData::Invalid(vec![0x0a, 0x00, 0x00]));
+    assert_eq!(record_data(DnsType::Cname, &[0x01, 0x61, 0x00, 0xff]),
+               DnsRecordData::Invalid(vec![0x01, 0x61, 0x00, 0xff]));
+
+    // Names must not exceed the record data
+    let mut input = Vec::from(&RESPONSE[..47]);
+    input[7] = 1;
+    input[40] = 2;
+    let packet = DnsPacket::parse(&input).unwrap().1;
+    assert_eq!(packet.answers[0].data, DnsRecordData::Invalid(vec![0x03, 0x77]));
+}
+
+#[test]
+fn parse_dns_success_extended_response_code() {
+    let mut input = Vec::from(QUERY);
+    input[2] = 0x80;
+    input[34] = 0x01;
+    let packet = parse_packet::<DnsPacket>(DnsParser::default(), &input);
+    assert_eq!(packet.header.response_code, DnsResponseCode::NoError);
+    assert_eq!(packet.response_code(), DnsResponseCode::BadVersion);
+    assert_eq!(packet.edns().map(|e| e.extended_response_code), Some(1));
+}
+
+#[test]
+fn parse_dns_success_stream() {
+    let mut state = DnsState::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let mut input = vec![0x00, QUERY.len() as u8];
+    input.extend_from_slice(QUERY);
+    input.extend_from_slice(&[0x00, RESPONSE.len() as u8]);
+    input.extend_from_slice(RESPONSE);
+
+    // The first message is split over two segments
+    assert!(DnsPacket::parse_stream(&input[..10], Some(&mut state), Some(&sender)).is_err());
+    assert_eq!(state.buffers.get(&sender).map(|b| b.len()), Some(10));
+    let (left, packet) = DnsPacket::parse_stream(&input[10..], Some(&mut state), Some(&sender)).unwrap();
+    assert_eq!(packet.header.id, 0x1234);
+    assert!(!packet.header.response);
+    assert!(state.buffers.is_empty());
+
+    // The second message follows within the same segment
+    let (left, packet) = DnsPacket::parse_stream(left, Some(&mut state), Some(&sender)).unwrap();
+    assert!(left.is_empty());
+    assert_eq!(packet.answers.len(), 2);
+
+    // The announced length has to match the message
+    let mut input = vec![0x00, QUERY.len() as u8 + 1];
+    input.extend_from_slice(QUERY);
+    input.push(0x00);
+    assert!(DnsPacket::parse_stream(&input, None, None).is_err());
+}
+
+#[test]
+fn dns_success_correlation() {
+    let mut tracker = DnsTracker::default();
+    let client = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let server = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 53);
+    let time = Duration::from_secs(1_500_000_000);
+    let query = parse_packet::<DnsPacket>(DnsParser::default(), QUERY);
+    let response = parse_packet::<DnsPacket>(DnsParser::default(), RESPONSE);
+
+    // The retransmission of the query keeps the original time
+    assert!(tracker.add_message(&client, &server, false, &query, time).is_empty());
+    assert!(tracker.add_message(&client, &server, false, &query, time + Duration::from_millis(10)).is_empty());
+
+    // The response is matched to the query
+    let transactions = tracker.add_message(&server, &client, false, &response, time + Duration::from_millis(20));
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].client, client);
+    assert_eq!(transactions[0].id, 0x1234);
+    assert_eq!(transactions[0].question.as_ref().map(|q| q.name.as_str()), Some("example.com"));
+    assert_eq!(transactions[0].status, DnsTransactionStatus::Answered);
+    assert_eq!(transactions[0].response_code, Some(DnsResponseCode::NoError));
+    assert_eq!(transactions[0].latency, Some(Duration::from_millis(20)));
+    assert_eq!(transactions[0].statistics.queries, 1);
+    assert_eq!(transactions[0].statistics.retransmissions, 1);
+    assert_eq!(transactions[0].statistics.answered, 1);
+
+    // A second response is a duplicate
+    let transactions = tracker.add_message(&server, &client, false, &response, time + Duration::from_millis(30));
+    assert_eq!(transactions[0].status, DnsTransactionStatus::Duplicate);
+    assert_eq!(transactions[0].latency, Some(Duration::from_millis(30)));
+    assert_eq!(transactions[0].statistics.duplicates, 1);
+
+    // Responses to other clients do not match
+    assert!(tracker.add_message(&server, &server, false, &response, time).is_empty());
+    assert_eq!(tracker.statistics.unmatched, 1);
+    assert_eq!(tracker.statistics.responses, 3);
+}
+
+#[test]
+fn dns_success_correlation_servers() {
+    let mut tracker = DnsTracker::default();
+    let client = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let first = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 53);
+    let second = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3)), 53);
+    let time = Duration::from_secs(1_500_000_000);
+    let query = parse_packet::<DnsPacket>(DnsParser::default(), QUERY);
+    let response = parse_packet::<DnsPacket>(DnsParser::default(), RESPONSE);
+
+    // The same query to two resolvers is tracked twice
+    tracker.add_message(&client, &first, false, &query, time);
+    tracker.add_message(&client, &second, false, &query, time);
+    assert_eq!(tracker.statistics.queries, 2);
+    assert_eq!(tracker.statistics.retransmissions, 0);
+
+    // Every response is matched to the query of its server and transport only
+    let transactions = tracker.add_message(&second, &client, false, &response, time + Duration::from_millis(5));
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].server, second);
+    assert_eq!(transactions[0].status, DnsTransactionStatus::Answered);
+    assert!(tracker.add_message(&first, &client, true, &response, time).is_empty());
+    assert_eq!(tracker.statistics.unmatched, 1);
+    let transactions = tracker.add_message(&first, &client, false, &response, time + Duration::from_millis(10));
+    assert_eq!(transactions[0].server, first);
+    assert_eq!(transactions[0].status, DnsTransactionStatus::Answered);
+    assert_eq!(transactions[0].latency, Some(Duration::from_millis(10)));
+    assert_eq!(transactions[0].statistics.duplicates, 0);
+}
+
+#[test]
+fn dns_success_unanswered_queries() {
+    let mut tracker = DnsTracker::default();
+    let client = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let server = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 53);
+    let time = Duration::from_secs(1_500_000_000);
+    let mut query = parse_packet::<DnsPacket>(DnsParser::default(), QUERY);
+    tracker.add_message(&client, &server, false, &query, time);
+
+    // The second query times out the first one, which is reported by the following message
+    query.header.id = 1;
+    assert!(tracker.add_message(&client, &server, false, &query, time + Duration::from_secs(1)).is_empty());
+    let transactions = tracker.add_message(&client, &server, false, &query, time + DNS_QUERY_TIMEOUT);
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].id, 0x1234);
+    assert_eq!(transactions[0].status, DnsTransactionStatus::Unanswered);
+    assert_eq!(transactions[0].response_code, None);
+    assert_eq!(transactions[0].latency, None);
+    assert_eq!(transactions[0].statistics.unanswered, 1);
+    assert_eq!(tracker.queries.len(), 1);
+}
+
+#[test]
+fn dns_success_response_code_rates() {
+    let mut tracker = DnsTracker::default();
+    let client = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let server = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 53);
+    let time = Duration::from_secs(1_500_000_000);
+    let mut query = parse_packet::<DnsPacket>(DnsParser::default(), QUERY);
+    let mut response = parse_packet::<DnsPacket>(DnsParser::default(), RESPONSE);
+    response.questions = query.questions.clone();
+    for (id, response_code) in vec![DnsResponseCode::NoError,
+                                    DnsResponseCode::NonExistentDomain,
+                                    DnsResponseCode::NonExistentDomain,
+                                    DnsResponseCode::ServerFailure]
+        .into_iter()
+        .enumerate() {
+        query.header.id = id as u16;
+        response.header.id = id as u16;
+        response.header.response_code = response_code;
+        tracker.add_message(&client, &server, false, &query, time);
+        tracker.add_message(&server, &client, false, &response, time);
+    }
+    assert_eq!(tracker.statistics.nxdomain, 2);
+    assert_eq!(tracker.statistics.servfail, 1);
+    assert_eq!(tracker.statistics.nxdomain_rate(), 0.5);
+    assert_eq!(tracker.statistics.servfail_rate(), 0.25);
+    assert_eq!(tracker.statistics.answer_rate(), 1.0);
+    assert_eq!(DnsStatistics::default().nxdomain_rate(), 0.0);
+}
+
+#[test]
+fn summary_dns_success() {
+    assert_eq!(parse_packet::<DnsPacket>(DnsParser::default(), QUERY).summary(Verbosity::Normal),
+               "DNS query id=0x1234 A? example.com");
+    assert_eq!(parse_packet::<DnsPacket>(DnsParser::default(), RESPONSE).to_string(),
+               "DNS response id=0x1234 NoError A? example.com answers=2");
+    assert_eq!(format!("{:#}", parse_packet::<DnsPacket>(DnsParser::default(), QUERY)),
+               "DNS query id=0x1234 A? example.com opcode=Query authorities=0 additionals=1");
+    assert_eq!(parse_packet::<DnsPacket>(DnsParser::default(), RESPONSE).summary(Verbosity::Brief), "DNS");
+}
+
+#[test]
+fn dissect_dns_success() {
+    let field = parse_packet::<DnsPacket>(DnsParser::default(), RESPONSE).dissect(RESPONSE, 0);
+    assert_eq!(field.byte_range(), 0..RESPONSE.len());
+    let opcode = field.find("dns.header.opcode").unwrap();
+    assert_eq!((opcode.bit_offset, opcode.bit_length), (17, 4));
+    let answers: Vec<_> = field.children.iter().filter(|f| f.name == "dns.answers").collect();
+    assert_eq!(answers.len(), 2);
+    assert_eq!(answers[1].find("dns.answers.name").map(Field::byte_range), Some(47..49));
+    assert_eq!(answers[1].find("dns.answers.data").map(|f| (f.byte_range(), &f.value)),
+               Some((59..63, &FieldValue::Ipv4(Ipv4Addr::new(93, 184, 216, 34)))));
+
+    // Messages within a stream are prefixed by their length
+    let mut input = vec![0x00, QUERY.len() as u8];
+    input.extend_from_slice(QUERY);
+    let field = parse_packet::<DnsPacket>(DnsParser::default(), QUERY).dissect(&input, 0);
+    assert_eq!(field.find("dns.length").map(|f| &f.value), Some(&FieldValue::Unsigned(40)));
+    assert_eq!(field.find("dns.header.id").map(Field::byte_range), Some(2..4));
+    assert_eq!(field.byte_range(), 0..input.len());
+}
@@ -0,0 +1,261 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV6_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
+      0x00, 0x24, 0x11, 0x40, 0x3f, 0xfe, 0x05, 0x07, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x86, 0xff, 0xfe, 0x05,
+      0x80, 0xda, 0x3f, 0xfe, 0x05, 0x01, 0x48, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
+      0x09, 0x5c, 0x11, 0x51, 0x00, 0x24, 0xf0, 0x09];
+
+static DTLS_CLIENT_HELLO: &'static [u8] =
+    &[0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3f, 0x01, 0x00, 0x00, 0x33, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x33, 0xfe, 0xfd, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+      0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+      0x22, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00, 0x00, 0x02, 0xc0, 0x2b, 0x01, 0x00, 0x00, 0x07, 0x00, 0x36, 0x00,
+      0x03, 0x02, 0xaa, 0xbb];
+
+static DTLS_SERVER_HELLO: &'static [u8] =
+    &[0x16, 0xfe, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x02, 0x00, 0x00, 0x34, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0xfe, 0xfd, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33,
+      0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33,
+      0x33, 0x33, 0x33, 0x33, 0x33, 0x00, 0x13, 0x01, 0x00, 0x00, 0x0c, 0x00, 0x2b, 0x00, 0x02, 0xfe, 0xfc, 0x00,
+      0x36, 0x00, 0x02, 0x01, 0xcc];
+
+static DTLS_CLIENT_HELLO_FRAGMENT_1: &'static [u8] =
+    &[0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x20, 0x01, 0x00, 0x00, 0x33, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x14, 0xfe, 0xfd, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+      0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22];
+
+static DTLS_CLIENT_HELLO_FRAGMENT_2: &'static [u8] =
+    &[0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x2b, 0x01, 0x00, 0x00, 0x33, 0x00,
+      0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x1f, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+      0x22, 0x22, 0x22, 0x00, 0x00, 0x00, 0x02, 0xc0, 0x2b, 0x01, 0x00, 0x00, 0x07, 0x00, 0x36, 0x00, 0x03, 0x02,
+      0xaa, 0xbb];
+
+static DTLS_HELLO_VERIFY_REQUEST: &'static [u8] =
+    &[0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x13, 0x03, 0x00, 0x00, 0x07, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0xfe, 0xfd, 0x04, 0x01, 0x02, 0x03, 0x04];
+
+/// Create a datagram from the client, or from the server if `reply` is set
+fn datagram(reply: bool, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+    if reply {
+        let (src, dst) = (packet[22..38].to_vec(), packet[38..54].to_vec());
+        packet[22..38].copy_from_slice(&dst);
+        packet[38..54].copy_from_slice(&src);
+        packet.swap(54, 56);
+        packet.swap(55, 57);
+    }
+    packet.extend_from_slice(payload);
+    packet
+}
+
+#[test]
+fn parse_dtls_success_client_hello() {
+    let mut parser = DtlsParser;
+    println!("{}", parser);
+    let parsing_result = parser.parse(DTLS_CLIENT_HELLO, None, None).unwrap().1;
+    let res: &DtlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.header,
+               DtlsRecordHeader::Full(DtlsFullHeader {
+                   content_type: TlsRecordContentType::Handshake,
+                   version: TlsRecordVersion {
+                       major: 0xfe,
+                       minor: 0xff,
+                   },
+                   epoch: 0,
+                   sequence_number: 0,
+                   connection_id: None,
+                   length: 63,
+               }));
+    assert_eq!(res.negotiated_version, None);
+    match res.content {
+        DtlsRecordContent::Handshake(ref messages) => {
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].handshake_type, TlsHandshakeType::ClientHello);
+            assert_eq!(messages[0].length, 51);
+            assert_eq!(messages[0].fragment_length, 51);
+            assert_eq!(messages[0].message,
+                       TlsHandshakeMessage::ClientHello(TlsClientHello {
+                           legacy_version: 0xfefd,
+                           random: vec![0x22; 32],
+                           session_id: vec![],
+                           cookie: Some(vec![]),
+                           cipher_suites: vec![0xc02b],
+                           compression_methods: vec![0],
+                           extensions: vec![TlsExtension {
+                                                extension_type: TlsExtension::CONNECTION_ID,
+                                                data: vec![0x02, 0xaa, 0xbb],
+                                            }],
+                       }));
+        }
+        _ => panic!("Handshake expected"),
+    }
+}
+
+#[test]
+fn parse_dtls_success_server_hello() {
+    let mut parser = DtlsParser;
+    let parsing_result = parser.parse(DTLS_SERVER_HELLO, None, None).unwrap().1;
+    let res: &DtlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.negotiated_version, Some(TlsVersion::Dtls13));
+}
+
+#[test]
+fn parse_dtls_success_hello_verify_request() {
+    let mut parser = DtlsParser;
+    let parsing_result = parser.parse(DTLS_HELLO_VERIFY_REQUEST, None, None).unwrap().1;
+    let res: &DtlsPacket = parsing_result.downcast_ref().unwrap();
+    match res.content {
+        DtlsRecordContent::Handshake(ref messages) => {
+            assert_eq!(messages[0].message,
+                       TlsHandshakeMessage::HelloVerifyRequest(TlsHelloVerifyRequest {
+                           server_version: 0xfefd,
+                           cookie: vec![1, 2, 3, 4],
+                       }));
+        }
+        _ => panic!("Handshake expected"),
+    }
+}
+
+#[test]
+fn parse_dtls_success_fragment_without_state() {
+    let mut parser = DtlsParser;
+    let parsing_result = parser.parse(DTLS_CLIENT_HELLO_FRAGMENT_1, None, None).unwrap().1;
+    let res: &DtlsPacket = parsing_result.downcast_ref().unwrap();
+    match res.content {
+        DtlsRecordContent::Handshake(ref messages) => {
+            assert_eq!(messages[0].fragment_offset, 0);
+            assert_eq!(messages[0].fragment_length, 20);
+            assert_eq!(messages[0].message, TlsHandshakeMessage::Incomplete);
+        }
+        _ => panic!("Handshake expected"),
+    }
+}
+
+#[test]
+fn parse_dtls_success_encrypted() {
+    let mut parser = DtlsParser;
+    let input = [0x17, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x03, 0x01, 0x02, 0x03];
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res: &DtlsPacket = parsing_result.downcast_ref().unwrap();
+    match res.header {
+        DtlsRecordHeader::Full(ref header) => {
+            assert_eq!(header.epoch, 1);
+            assert_eq!(header.sequence_number, 7);
+        }
+        _ => panic!("Full header expected"),
+    }
+    assert_eq!(res.content, DtlsRecordContent::Opaque);
+}
+
+#[test]
+fn parse_dtls_success_unified_header() {
+    let mut parser = DtlsParser;
+    let input = [0x2d, 0x12, 0x34, 0x00, 0x03, 0x01, 0x02, 0x03];
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res: &DtlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.header,
+               DtlsRecordHeader::Unified(DtlsUnifiedHeader {
+                   epoch: 1,
+                   sequence_number: 0x1234,
+                   connection_id: None,
+                   length: Some(3),
+               }));
+    assert_eq!(res.content, DtlsRecordContent::Opaque);
+}
+
+#[test]
+fn parse_dtls_failure_version() {
+    let mut parser = DtlsParser;
+    let mut input = Vec::from(DTLS_CLIENT_HELLO);
+    input[1] = 0x03;
+    input[2] = 0x03;
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_dtls_failure_unknown_connection_id() {
+    let mut parser = DtlsParser;
+    let input = [0x19, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0xaa, 0xbb, 0x00, 0x01, 0x00];
+    assert!(parser.parse(&input, None, None).to_full_result().is_err());
+}
+
+#[test]
+fn parse_dtls_failure_too_small() {
+    let mut parser = DtlsParser;
+    assert!(parser.parse(&DTLS_CLIENT_HELLO[..12], None, None).to_full_result().is_err());
+}
+
+#[test]
+fn peel_dtls_success_reassembly() {
+    let mut peel = PeelIp::default();
+    let result = peel.traverse(&datagram(false, DTLS_CLIENT_HELLO_FRAGMENT_1), vec![]).result;
+    assert_eq!(result.len(), 4);
+    let dtls: &DtlsPacket = result[3].downcast_ref().unwrap();
+    match dtls.content {
+        DtlsRecordContent::Handshake(ref messages) => assert_eq!(messages[0].message, TlsHandshakeMessage::Incomplete),
+        _ => panic!("Handshake expected"),
+    }
+
+    let result = peel.traverse(&datagram(false, DTLS_CLIENT_HELLO_FRAGMENT_2), vec![]).result;
+    let dtls: &DtlsPacket = result[3].downcast_ref().unwrap();
+    match dtls.content {
+        DtlsRecordContent::Handshake(ref messages) => {
+            match messages[0].message {
+                TlsHandshakeMessage::ClientHello(ref hello) => assert_eq!(hello.cipher_suites, vec![0xc02b]),
+                _ => panic!("ClientHello expected"),
+            }
+        }
+        _ => panic!("Handshake expected"),
+    }
+}
+
+#[test]
+fn peel_dtls_success_multiple_records() {
+    let mut peel = PeelIp::default();
+    let mut payload = Vec::from(DTLS_CLIENT_HELLO_FRAGMENT_1);
+    payload.extend_from_slice(DTLS_CLIENT_HELLO_FRAGMENT_2);
+    let result = peel.traverse(&datagram(false, &payload), vec![]).result;
+    assert_eq!(result.len(), 5);
+    let dtls: &DtlsPacket = result[4].downcast_ref().unwrap();
+    match dtls.content {
+        DtlsRecordContent::Handshake(ref messages) => {
+            match messages[0].message {
+                TlsHandshakeMessage::ClientHello(_) => {}
+                _ => panic!("ClientHello expected"),
+            }
+        }
+        _ => panic!("Handshake expected"),
+    }
+}
+
+#[test]
+fn peel_dtls_success_connection_id() {
+    let mut peel = PeelIp::default();
+    peel.traverse(&datagram(false, DTLS_CLIENT_HELLO), vec![]);
+    peel.traverse(&datagram(true, DTLS_SERVER_HELLO), vec![]);
+
+    // The server sends records with the connection ID requested by the client
+    let record = [0x19, 0xfe, 0xfd, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xaa, 0xbb, 0x00, 0x01, 0x00];
+    let result = peel.traverse(&datagram(true, &record), vec![]).result;
+    let dtls: &DtlsPacket = result[3].downcast_ref().unwrap();
+    assert_eq!(dtls.negotiated_version, Some(TlsVersion::Dtls13));
+    match dtls.header {
+        DtlsRecordHeader::Full(ref header) => assert_eq!(header.connection_id, Some(vec![0xaa, 0xbb])),
+        _ => panic!("Full header expected"),
+    }
+
+    // The client uses the unified header with the connection ID of the server
+    let record = [0x3c, 0xcc, 0x00, 0x02, 0x00, 0x02, 0x01, 0x02];
+    let result = peel.traverse(&datagram(false, &record), vec![]).result;
+    let dtls: &DtlsPacket = result[3].downcast_ref().unwrap();
+    assert_eq!(dtls.header,
+               DtlsRecordHeader::Unified(DtlsUnifiedHeader {
+                   epoch: 0,
+                   sequence_number: 2,
+                   connection_id: Some(vec![0xcc]),
+                   length: Some(2),
+               }));
+}
@@ -0,0 +1,125 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+static PACKET_ETH_IPV6_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
+      0x00, 0x24, 0x11, 0x40, 0x3f, 0xfe, 0x05, 0x07, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x86, 0xff, 0xfe, 0x05,
+      0x80, 0xda, 0x3f, 0xfe, 0x05, 0x01, 0x48, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
+      0x09, 0x5c, 0x00, 0x35, 0x00, 0x24, 0xf0, 0x09];
+
+#[cfg(feature = "tls")]
+static TLS_SERVER_HELLO: &'static [u8] =
+    &[0x16, 0x03, 0x03, 0x00, 0x32, 0x02, 0x00, 0x00, 0x2e, 0x03, 0x03, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+      0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+      0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x00, 0x13, 0x01, 0x00, 0x00, 0x06, 0x00, 0x2b, 0x00, 0x02, 0x03,
+      0x04];
+
+fn matches(filter: &str, input: &[u8]) -> bool {
+    let packet = Packet::new(PeelIp::default().traverse(input, vec![]).result);
+    DisplayFilter::new(filter).unwrap().matches_packet(&packet, input)
+}
+
+#[test]
+fn filter_success_comparisons() {
+    assert!(matches("tcp", PACKET_ETH_IPV4_TCP));
+    assert!(!matches("udp", PACKET_ETH_IPV4_TCP));
+    assert!(matches("tcp.dest_port == 443 && tcp.source_port > 1024", PACKET_ETH_IPV4_TCP));
+    assert!(matches("tcp.dest_port eq 0x1bb and tcp.window ge 8192", PACKET_ETH_IPV4_TCP));
+    assert!(matches("tcp.dest_port != 80", PACKET_ETH_IPV4_TCP));
+    assert!(!matches("udp.dest_port != 80", PACKET_ETH_IPV4_TCP));
+    assert!(matches("ipv4.flags.df == true && ipv4.flags.mf == 0 && !udp", PACKET_ETH_IPV4_TCP));
+    assert!(matches("ethernet.ethertype == ipv4 && ipv4.protocol == Tcp", PACKET_ETH_IPV4_TCP));
+    assert!(matches("ethernet.dst == 00:00:00:00:00:00", PACKET_ETH_IPV4_TCP));
+    assert!(matches("udp.dest_port < 54 || tcp", PACKET_ETH_IPV6_UDP));
+    assert!(!matches("not (udp and ipv6)", PACKET_ETH_IPV6_UDP));
+}
+
+#[test]
+fn filter_success_addresses() {
+    assert!(matches("ipv4.src == 10.0.0.101", PACKET_ETH_IPV4_TCP));
+    assert!(matches("ipv4.src == 10.0.0.0/8 && ipv4.dst != 10.0.0.0/8", PACKET_ETH_IPV4_TCP));
+    assert!(!matches("ipv4.dst == 10.0.0.0/8", PACKET_ETH_IPV4_TCP));
+    assert!(matches("ipv4.dst in {10.0.0.0/8 66.196.65.112}", PACKET_ETH_IPV4_TCP));
+    assert!(matches("ipv6.src == 3ffe:507::/32", PACKET_ETH_IPV6_UDP));
+    assert!(!matches("ipv6.src == 10.0.0.0/8", PACKET_ETH_IPV6_UDP));
+}
+
+#[test]
+fn filter_success_sets() {
+    assert!(matches("tcp.dest_port in {80, 443}", PACKET_ETH_IPV4_TCP));
+    assert!(matches("tcp.source_port in {80 50000..60000}", PACKET_ETH_IPV4_TCP));
+    assert!(!matches("tcp.dest_port in {80 8000..8080}", PACKET_ETH_IPV4_TCP));
+}
+
+#[test]
+fn filter_success_strings() {
+    assert!(matches("ipv4.src contains \"10.0\"", PACKET_ETH_IPV4_TCP));
+    assert!(matches("ipv4.dst matches \"^66\\\\.\"", PACKET_ETH_IPV4_TCP));
+    assert!(matches("ethernet.ethertype ~ \"(?i)IPV[46]\"", PACKET_ETH_IPV4_TCP));
+    assert!(!matches("ethernet.ethertype ~ \"^ipv6$\"", PACKET_ETH_IPV4_TCP));
+}
+
+#[test]
+#[cfg(feature = "tls")]
+fn filter_success_tls() {
+    let mut input = Vec::from(PACKET_ETH_IPV4_TCP);
+    input.extend_from_slice(TLS_SERVER_HELLO);
+    assert!(matches("ipv4.src == 10.0.0.0/8 && tcp.dest_port == 443 && tls.content_type == handshake",
+                    &input));
+    assert!(matches("tls.content_type == 22", &input));
+    assert!(!matches("tls.content_type == application_data", &input));
+    assert!(!matches("tls", PACKET_ETH_IPV4_TCP));
+}
+
+#[test]
+fn filter_failure() {
+    let error = |filter: &str| DisplayFilter::new(filter).unwrap_err();
+    assert_eq!(error("tcp.port ==").to_string(), "Missing value at position 11");
+    assert_eq!(error("tcp &&& udp").position, 6);
+    assert_eq!(error("(tcp").message, "Missing ')'");
+    assert_eq!(error("tcp udp").position, 4);
+    assert_eq!(error("tcp.port in {1 5..2}").message, "Invalid range '5..2'");
+    assert_eq!(error("10.0.0.1 == tcp").message, "Invalid field name '10.0.0.1'");
+    assert_eq!(error("http.path ~ \"(a\"").message, "Missing ')'");
+    assert_eq!(error("http.path == \"abc").message, "Unterminated string");
+    assert!("tcp.window >= 1".parse::<DisplayFilter>().is_ok());
+}
+
+#[test]
+fn filter_success_regex() {
+    let regex = |pattern: &str| Regex::new(pattern).unwrap();
+    assert!(regex("a[b-d]+e?$").is_match("xabcd"));
+    assert!(regex("^(get|post) /\\w*").is_match("post /index"));
+    assert!(!regex("^(get|post) /\\w*").is_match("put /index"));
+    assert!(regex("^\\d{2,3}$").is_match("123"));
+    assert!(!regex("^\\d{2,3}$").is_match("1234"));
+    assert!(regex("(?i)^[A-Z]+$").is_match("Host"));
+    assert!(regex("(a*)*b").is_match("aaab"));
+    assert!(regex("^[\\D]+$").is_match("abc"));
+    assert!(!regex("[\\D]").is_match("123"));
+    assert!(!regex("[\\W]").is_match("W"));
+    assert!(regex("^[\\S\\d]+$").is_match("a1-"));
+    assert!(!regex("[\\S]").is_match(" \t"));
+    assert!(Regex::new("a{2").is_err());
+    assert!(Regex::new("*a").is_err());
+}
+
+#[test]
+fn filter_success_regex_linear() {
+    let long = format!("x{}", "a".repeat(1_000_000));
+    assert!(!Regex::new("x.*y").unwrap().is_match(&long));
+    assert!(Regex::new("x.*a$").unwrap().is_match(&long));
+    let repeated = "a".repeat(10_000);
+    assert!(!Regex::new("(a|aa)*c").unwrap().is_match(&repeated));
+    assert!(!Regex::new("^(a*)*$").unwrap().is_match(&format!("{}b", repeated)));
+    assert!(Regex::new("a{0,3}b{2}$").unwrap().is_match("aabb"));
+    assert!(Regex::new("a{3,2}").is_err());
+    assert!(Regex::new("a{1000000}").is_err());
+    assert!(Regex::new(&"(".repeat(100_000)).is_err());
+}
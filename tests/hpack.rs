@@ -0,0 +1,177 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+fn header(name: &str, value: &str) -> HpackHeader {
+    HpackHeader {
+        name: name.to_owned(),
+        value: value.to_owned(),
+        never_indexed: false,
+    }
+}
+
+#[test]
+fn hpack_success_integer() {
+    assert_eq!(decode_integer(&[0x0a], 5), Some((&[][..], 10)));
+    assert_eq!(decode_integer(&[0x1f, 0x9a, 0x0a, 0xff], 5), Some((&[0xff][..], 1337)));
+    assert_eq!(decode_integer(&[0x2a], 8), Some((&[][..], 42)));
+}
+
+#[test]
+fn hpack_failure_integer() {
+    assert_eq!(decode_integer(&[], 5), None);
+    assert_eq!(decode_integer(&[0x1f, 0x9a], 5), None);
+    assert_eq!(decode_integer(&[0x1f, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01], 5), None);
+}
+
+#[test]
+fn hpack_success_huffman() {
+    assert_eq!(huffman_decode(&[0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab, 0x90, 0xf4, 0xff]),
+               Some(b"www.example.com".to_vec()));
+    assert_eq!(huffman_decode(&[0xa8, 0xeb, 0x10, 0x64, 0x9c, 0xbf]),
+               Some(b"no-cache".to_vec()));
+    assert_eq!(huffman_decode(&[]), Some(vec![]));
+}
+
+#[test]
+fn hpack_failure_huffman() {
+    // Padding longer than seven bits
+    assert_eq!(huffman_decode(&[0xff, 0xff]), None);
+
+    // Padding which is not the most significant bits of the end of string symbol
+    assert_eq!(huffman_decode(&[0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab, 0x90, 0xf4, 0xfe]),
+               None);
+
+    // Encoded end of string symbol
+    assert_eq!(huffman_decode(&[0xff, 0xff, 0xff, 0xfc]), None);
+}
+
+#[test]
+fn hpack_success_literals() {
+    let mut decoder = HpackDecoder::default();
+
+    // Literal header field with indexing
+    assert_eq!(decoder.decode(&[0x40, 0x0a, 0x63, 0x75, 0x73, 0x74, 0x6f, 0x6d, 0x2d, 0x6b, 0x65, 0x79, 0x0d, 0x63,
+                                0x75, 0x73, 0x74, 0x6f, 0x6d, 0x2d, 0x68, 0x65, 0x61, 0x64, 0x65, 0x72]),
+               Some(vec![header("custom-key", "custom-header")]));
+    assert_eq!(decoder.size, 55);
+
+    // Literal header field without indexing
+    assert_eq!(decoder.decode(&[0x04, 0x0c, 0x2f, 0x73, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2f, 0x70, 0x61, 0x74, 0x68]),
+               Some(vec![header(":path", "/sample/path")]));
+    assert_eq!(decoder.size, 55);
+
+    // Literal header field never indexed
+    assert_eq!(decoder.decode(&[0x10, 0x08, 0x70, 0x61, 0x73, 0x73, 0x77, 0x6f, 0x72, 0x64, 0x06, 0x73, 0x65, 0x63,
+                                0x72, 0x65, 0x74]),
+               Some(vec![HpackHeader {
+                             name: "password".to_owned(),
+                             value: "secret".to_owned(),
+                             never_indexed: true,
+                         }]));
+
+    // Indexed header field
+    assert_eq!(decoder.decode(&[0x82]), Some(vec![header(":method", "GET")]));
+    assert_eq!(decoder.dynamic_table.len(), 1);
+}
+
+#[test]
+fn hpack_success_requests() {
+    let mut decoder = HpackDecoder::default();
+    assert_eq!(decoder.decode(&[0x82, 0x86, 0x84, 0x41, 0x0f, 0x77, 0x77, 0x77, 0x2e, 0x65, 0x78, 0x61, 0x6d, 0x70,
+                                0x6c, 0x65, 0x2e, 0x63, 0x6f, 0x6d]),
+               Some(vec![header(":method", "GET"),
+                         header(":scheme", "http"),
+                         header(":path", "/"),
+                         header(":authority", "www.example.com")]));
+    assert_eq!(decoder.size, 57);
+
+    assert_eq!(decoder.decode(&[0x82, 0x86, 0x84, 0xbe, 0x58, 0x08, 0x6e, 0x6f, 0x2d, 0x63, 0x61, 0x63, 0x68, 0x65]),
+               Some(vec![header(":method", "GET"),
+                         header(":scheme", "http"),
+                         header(":path", "/"),
+                         header(":authority", "www.example.com"),
+                         header("cache-control", "no-cache")]));
+    assert_eq!(decoder.size, 110);
+
+    assert_eq!(decoder.decode(&[0x82, 0x87, 0x85, 0xbf, 0x40, 0x0a, 0x63, 0x75, 0x73, 0x74, 0x6f, 0x6d, 0x2d, 0x6b,
+                                0x65, 0x79, 0x0c, 0x63, 0x75, 0x73, 0x74, 0x6f, 0x6d, 0x2d, 0x76, 0x61, 0x6c, 0x75,
+                                0x65]),
+               Some(vec![header(":method", "GET"),
+                         header(":scheme", "https"),
+                         header(":path", "/index.html"),
+                         header(":authority", "www.example.com"),
+                         header("custom-key", "custom-value")]));
+    assert_eq!(decoder.size, 164);
+}
+
+#[test]
+fn hpack_success_requests_huffman() {
+    let mut decoder = HpackDecoder::default();
+    assert_eq!(decoder.decode(&[0x82, 0x86, 0x84, 0x41, 0x8c, 0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab,
+                                0x90, 0xf4, 0xff]),
+               Some(vec![header(":method", "GET"),
+                         header(":scheme", "http"),
+                         header(":path", "/"),
+                         header(":authority", "www.example.com")]));
+
+    assert_eq!(decoder.decode(&[0x82, 0x86, 0x84, 0xbe, 0x58, 0x86, 0xa8, 0xeb, 0x10, 0x64, 0x9c, 0xbf]),
+               Some(vec![header(":method", "GET"),
+                         header(":scheme", "http"),
+                         header(":path", "/"),
+                         header(":authority", "www.example.com"),
+                         header("cache-control", "no-cache")]));
+
+    assert_eq!(decoder.decode(&[0x82, 0x87, 0x85, 0xbf, 0x40, 0x88, 0x25, 0xa8, 0x49, 0xe9, 0x5b, 0xa9, 0x7d, 0x7f,
+                                0x89, 0x25, 0xa8, 0x49, 0xe9, 0x5b, 0xb8, 0xe8, 0xb4, 0xbf]),
+               Some(vec![header(":method", "GET"),
+                         header(":scheme", "https"),
+                         header(":path", "/index.html"),
+                         header(":authority", "www.example.com"),
+                         header("custom-key", "custom-value")]));
+    assert_eq!(decoder.size, 164);
+}
+
+#[test]
+fn hpack_success_responses_eviction() {
+    let mut decoder = HpackDecoder::default();
+
+    // Limit the dynamic table size
+    assert_eq!(decoder.decode(&[0x3f, 0xe1, 0x01]), Some(vec![]));
+    assert_eq!(decoder.max_size, 256);
+
+    assert_eq!(decoder.decode(&[0x48, 0x03, 0x33, 0x30, 0x32, 0x58, 0x07, 0x70, 0x72, 0x69, 0x76, 0x61, 0x74, 0x65,
+                                0x61, 0x1d, 0x4d, 0x6f, 0x6e, 0x2c, 0x20, 0x32, 0x31, 0x20, 0x4f, 0x63, 0x74, 0x20,
+                                0x32, 0x30, 0x31, 0x33, 0x20, 0x32, 0x30, 0x3a, 0x31, 0x33, 0x3a, 0x32, 0x31, 0x20,
+                                0x47, 0x4d, 0x54, 0x6e, 0x17, 0x68, 0x74, 0x74, 0x70, 0x73, 0x3a, 0x2f, 0x2f, 0x77,
+                                0x77, 0x77, 0x2e, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x63, 0x6f, 0x6d]),
+               Some(vec![header(":status", "302"),
+                         header("cache-control", "private"),
+                         header("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+                         header("location", "https://www.example.com")]));
+    assert_eq!(decoder.size, 222);
+
+    // The status 302 gets evicted by the status 307
+    assert_eq!(decoder.decode(&[0x48, 0x03, 0x33, 0x30, 0x37, 0xc1, 0xc0, 0xbf]),
+               Some(vec![header(":status", "307"),
+                         header("cache-control", "private"),
+                         header("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+                         header("location", "https://www.example.com")]));
+    assert_eq!(decoder.size, 222);
+    assert_eq!(decoder.dynamic_table.len(), 4);
+}
+
+#[test]
+fn hpack_failure_unknown_index() {
+    let mut decoder = HpackDecoder::default();
+    assert_eq!(decoder.decode(&[0xbe]), None);
+    assert!(decoder.failed);
+
+    // The dynamic table is out of sync, so valid blocks are not decoded anymore
+    assert_eq!(decoder.decode(&[0x82]), None);
+}
+
+#[test]
+fn hpack_failure_truncated() {
+    assert_eq!(HpackDecoder::default().decode(&[0x40, 0x0a, 0x63, 0x75]), None);
+    assert_eq!(HpackDecoder::default().decode(&[0x80]), None);
+}
@@ -29,6 +29,15 @@ fn parse_http_request_success_get() {
                                      key: "User-agent".to_owned(),
                                      value: "Myagent/0.1".to_owned(),
                                  }],
+                   anomalies: vec![],
+                   body: HttpBody {
+                       framing: HttpBodyFraming::None,
+                       data: vec![],
+                       trailers: vec![],
+                       complete: true,
+                       content: Some(vec![]),
+                       transaction: None,
+                   },
                })));
 }
 
@@ -51,6 +60,15 @@ fn parse_http_request_success_post() {
                                      key: "Host".to_owned(),
                                      value: "abc.com".to_owned(),
                                  }],
+                   anomalies: vec![],
+                   body: HttpBody {
+                       framing: HttpBodyFraming::None,
+                       data: vec![],
+                       trailers: vec![],
+                       complete: true,
+                       content: Some(vec![]),
+                       transaction: None,
+                   },
                })));
 }
 
@@ -118,6 +136,15 @@ fn parse_http_response_success_moved() {
                                      key: "Location".to_owned(),
                                      value: "https://facebook.com".to_owned(),
                                  }],
+                   anomalies: vec![],
+                   body: HttpBody {
+                       framing: HttpBodyFraming::CloseDelimited,
+                       data: vec![],
+                       trailers: vec![],
+                       complete: false,
+                       content: None,
+                       transaction: None,
+                   },
                })));
 }
 
@@ -140,6 +167,15 @@ fn parse_http_response_success_ok() {
                                      key: "Host".to_owned(),
                                      value: "abc.com".to_owned(),
                                  }],
+                   anomalies: vec![],
+                   body: HttpBody {
+                       framing: HttpBodyFraming::CloseDelimited,
+                       data: vec![],
+                       trailers: vec![],
+                       complete: false,
+                       content: None,
+                       transaction: None,
+                   },
                })));
 }
 
@@ -163,3 +199,307 @@ fn parse_http_response_failure_wrong_status_code() {
     let input = b"HTTP/1.1 20A OK\r\n";
     assert!(parser.parse(input, None, None).to_full_result().is_err());
 }
+
+#[test]
+fn parse_http_request_success_content_length() {
+    let mut parser = HttpParser;
+    let (left, parsing_result) =
+        parser.parse(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\n hellGET", None, None).unwrap();
+    assert_eq!(left, b"GET");
+    match parsing_result.downcast_ref() {
+        Some(HttpPacket::Request(request)) => {
+            assert_eq!(request.body,
+                       HttpBody {
+                           framing: HttpBodyFraming::ContentLength(5),
+                           data: b" hell".to_vec(),
+                           trailers: vec![],
+                           complete: true,
+                           content: Some(b" hell".to_vec()),
+                           transaction: None,
+                       });
+        }
+        _ => panic!("Request expected"),
+    }
+}
+
+#[test]
+fn parse_http_request_success_partial_body() {
+    let mut parser = HttpParser;
+    let parsing_result = parser.parse(b"PUT / HTTP/1.1\r\nContent-Length: 10\r\n\r\nhello", None, None)
+        .unwrap()
+        .1;
+    match parsing_result.downcast_ref() {
+        Some(HttpPacket::Request(request)) => {
+            assert_eq!(request.body.data, b"hello");
+            assert!(!request.body.complete);
+            assert_eq!(request.body.content, None);
+        }
+        _ => panic!("Request expected"),
+    }
+}
+
+#[test]
+fn parse_http_request_success_conflicting_content_length() {
+    let mut parser = HttpParser;
+    let input = b"POST / HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello";
+    let (left, parsing_result) = parser.parse(input, None, None).unwrap();
+    assert_eq!(left, b"hello");
+    match parsing_result.downcast_ref() {
+        Some(HttpPacket::Request(request)) => assert_eq!(request.body.framing, HttpBodyFraming::None),
+        _ => panic!("Request expected"),
+    }
+}
+
+#[test]
+fn parse_http_response_success_chunked() {
+    let mut parser = HttpParser;
+    let input = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\nContent-Length: 3\r\n\r\n\
+                  5;name=value\r\nhello\r\n6\r\n world\r\n0\r\nExpires: never\r\n\r\n";
+    let (left, parsing_result) = parser.parse(input, None, None).unwrap();
+    assert!(left.is_empty());
+    match parsing_result.downcast_ref() {
+        Some(HttpPacket::Response(response)) => {
+            assert_eq!(response.body.framing, HttpBodyFraming::Chunked);
+            assert_eq!(response.body.data, b"hello world");
+            assert_eq!(response.body.trailers,
+                       vec![HttpHeader {
+                                key: "Expires".to_owned(),
+                                value: "never".to_owned(),
+                            }]);
+            assert!(response.body.complete);
+        }
+        _ => panic!("Response expected"),
+    }
+}
+
+#[test]
+fn parse_http_request_success_signed_content_length() {
+    let mut parser = HttpParser;
+    let input = b"POST / HTTP/1.1\r\nContent-Length: +5\r\n\r\nhello";
+    let (left, parsing_result) = parser.parse(input, None, None).unwrap();
+    assert_eq!(left, b"hello");
+    match parsing_result.downcast_ref() {
+        Some(HttpPacket::Request(request)) => {
+            assert_eq!(request.body.framing, HttpBodyFraming::None);
+            assert_eq!(request.anomalies, vec![HttpAnomaly::InvalidContentLength]);
+        }
+        _ => panic!("Request expected"),
+    }
+}
+
+#[test]
+fn parse_http_response_success_invalid_chunk_size() {
+    let mut parser = HttpParser;
+    for size in &["xyz", "+3", "-0"] {
+        let input = format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n{}\r\nabc\r\n0\r\n\r\n",
+                            size);
+        let parsing_result = parser.parse(input.as_bytes(), None, None).unwrap().1;
+        match parsing_result.downcast_ref() {
+            Some(HttpPacket::Response(response)) => {
+                assert_eq!(response.body.data, b"hello");
+                assert!(!response.body.complete);
+            }
+            _ => panic!("Response expected"),
+        }
+    }
+}
+
+#[test]
+fn parse_http_response_success_bodiless() {
+    let mut parser = HttpParser;
+    for code in &["100 Continue", "204 No Content", "304 Not Modified"] {
+        let input = format!("HTTP/1.1 {}\r\nContent-Length: 5\r\n\r\nhello", code);
+        let (left, parsing_result) = parser.parse(input.as_bytes(), None, None).unwrap();
+        assert_eq!(left, b"hello");
+        match parsing_result.downcast_ref() {
+            Some(HttpPacket::Response(response)) => {
+                assert_eq!(response.body.framing, HttpBodyFraming::None);
+                assert!(response.body.complete);
+            }
+            _ => panic!("Response expected"),
+        }
+    }
+}
+
+#[test]
+fn parse_http_response_success_unknown_coding() {
+    let mut parser = HttpParser;
+    let input = b"HTTP/1.1 200 OK\r\nContent-Encoding: unknown\r\nContent-Length: 5\r\n\r\nhello";
+    let parsing_result = parser.parse(input, None, None).unwrap().1;
+    match parsing_result.downcast_ref() {
+        Some(HttpPacket::Response(response)) => {
+            assert!(response.body.complete);
+            assert_eq!(response.body.content, None);
+        }
+        _ => panic!("Response expected"),
+    }
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn parse_http_response_success_content_codings() {
+    let mut parser = HttpParser;
+    let bodies: &[(&str, &[u8])] =
+        &[("gzip",
+           &[0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x08,
+             0xcf, 0x2f, 0xca, 0x49, 0x51, 0x04, 0x00, 0xa3, 0x1c, 0x29, 0x1c, 0x0c, 0x00, 0x00, 0x00]),
+          ("deflate",
+           &[0x78, 0x9c, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x08, 0xcf, 0x2f, 0xca, 0x49, 0x51, 0x04, 0x00, 0x1c,
+             0x49, 0x04, 0x3e]),
+          ("deflate",
+           &[0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x08, 0xcf, 0x2f, 0xca, 0x49, 0x51, 0x04, 0x00]),
+          ("br",
+           &[0xb0, 0x00, 0x10, 0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x57, 0x6f, 0x72, 0x6c, 0x64, 0x21, 0x03]),
+          ("identity, GZIP",
+           &[0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xf3, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x08,
+             0xcf, 0x2f, 0xca, 0x49, 0x51, 0x04, 0x00, 0xa3, 0x1c, 0x29, 0x1c, 0x0c, 0x00, 0x00, 0x00])];
+
+    for &(coding, body) in bodies {
+        let mut input = Vec::from(format!("HTTP/1.1 200 OK\r\nContent-Encoding: {}\r\nContent-Length: {}\r\n\r\n",
+                                          coding,
+                                          body.len()));
+        input.extend_from_slice(body);
+        let parsing_result = parser.parse(&input, None, None).unwrap().1;
+        match parsing_result.downcast_ref() {
+            Some(HttpPacket::Response(response)) => {
+                assert_eq!(response.body.data, body);
+                assert_eq!(response.body.content, Some(b"Hello World!".to_vec()));
+            }
+            _ => panic!("Response expected"),
+        }
+    }
+}
+
+#[test]
+fn parse_http_response_success_framing() {
+    let mut parser = HttpParser;
+    let input = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+    let parsing_result = parser.parse(input, None, None).unwrap().1;
+    match parsing_result.downcast_ref() {
+        Some(HttpPacket::Response(response)) => {
+            assert_eq!(response.body_framing(None), HttpBodyFraming::ContentLength(5));
+            assert_eq!(response.body_framing(Some(&HttpRequestMethod::Get)),
+                       HttpBodyFraming::ContentLength(5));
+            assert_eq!(response.body_framing(Some(&HttpRequestMethod::Head)), HttpBodyFraming::None);
+            assert_eq!(response.body_framing(Some(&HttpRequestMethod::Connect)), HttpBodyFraming::None);
+        }
+        _ => panic!("Response expected"),
+    }
+}
+
+fn parse_request(input: &[u8]) -> HttpRequest {
+    let mut parser = HttpParser;
+    match parser.parse(input, None, None).unwrap().1.downcast::<HttpPacket>().map(|p| *p) {
+        Ok(HttpPacket::Request(request)) => request,
+        _ => panic!("Request expected"),
+    }
+}
+
+fn parse_response(input: &[u8]) -> HttpResponse {
+    let mut parser = HttpParser;
+    match parser.parse(input, None, None).unwrap().1.downcast::<HttpPacket>().map(|p| *p) {
+        Ok(HttpPacket::Response(response)) => response,
+        _ => panic!("Response expected"),
+    }
+}
+
+#[test]
+fn parse_http_request_success_extension_method() {
+    let request = parse_request(b"PROPFIND /dav HTTP/1.1\r\nDepth: 1\r\n\r\n");
+    assert_eq!(request.request_method, HttpRequestMethod::Extension("PROPFIND".to_owned()));
+    assert!(request.anomalies.is_empty());
+
+    // Methods are case-sensitive
+    let request = parse_request(b"get / HTTP/1.1\r\n\r\n");
+    assert_eq!(request.request_method, HttpRequestMethod::Extension("get".to_owned()));
+}
+
+#[test]
+fn parse_http_request_success_header_lookup() {
+    let request = parse_request(b"GET / HTTP/1.1\r\nHost:abc.com\r\nAccept: text/html\r\naccept: text/plain, */*\r\n\r\n");
+    assert_eq!(request.headers[0].value, "abc.com");
+    assert_eq!(request.header("HOST"), Some("abc.com"));
+    assert_eq!(request.header("accept"), Some("text/html"));
+    assert_eq!(request.header("Cookie"), None);
+    assert_eq!(request.header_list("Accept"), vec!["text/html", "text/plain", "*/*"]);
+    assert_eq!(HttpHeader::find_all(&request.headers, "ACCEPT"),
+               vec!["text/html", "text/plain, */*"]);
+    assert!(request.anomalies.is_empty());
+}
+
+#[test]
+fn parse_http_request_success_bare_line_feed() {
+    let request = parse_request(b"GET / HTTP/1.1\nHost: abc.com\n\nabc");
+    assert_eq!(request.header("Host"), Some("abc.com"));
+    assert_eq!(request.anomalies, vec![HttpAnomaly::BareLineFeed]);
+}
+
+#[test]
+fn parse_http_request_success_obsolete_line_folding() {
+    let request = parse_request(b"GET / HTTP/1.1\r\nX-Folded: first\r\n  second\r\n\tthird\r\nHost: abc.com\r\n\r\n");
+    assert_eq!(request.header("X-Folded"), Some("first second third"));
+    assert_eq!(request.header("Host"), Some("abc.com"));
+    assert_eq!(request.anomalies, vec![HttpAnomaly::ObsoleteLineFolding]);
+}
+
+#[test]
+fn parse_http_request_success_non_utf8_value() {
+    let request = parse_request(b"GET /caf\xe9 HTTP/1.1\r\nX-Name: Jos\xe9\r\n\r\n");
+    assert_eq!(request.path, "/caf\u{e9}");
+    assert_eq!(request.header("X-Name"), Some("Jos\u{e9}"));
+    assert_eq!(request.anomalies, vec![HttpAnomaly::NonUtf8Text]);
+}
+
+#[test]
+fn parse_http_request_success_smuggling_anomalies() {
+    let request = parse_request(b"\r\nPOST  / HTTP/1.1\r\n Leading: space\r\nContent-Length : 5\r\n\
+                                  Transfer-Encoding: chunked\r\nContent-Length: 6\r\nBad\x01: \x00\r\nno colon\r\n\r\n");
+    assert_eq!(request.request_method, HttpRequestMethod::Post);
+    assert_eq!(request.path, "/");
+    assert_eq!(request.headers.len(), 4);
+    assert_eq!(request.header("Content-Length"), Some("5"));
+    assert_eq!(request.anomalies,
+               vec![HttpAnomaly::LeadingEmptyLine,
+                    HttpAnomaly::WhitespaceInStartLine,
+                    HttpAnomaly::WhitespaceAfterStartLine,
+                    HttpAnomaly::WhitespaceBeforeColon,
+                    HttpAnomaly::InvalidFieldName,
+                    HttpAnomaly::InvalidFieldValue,
+                    HttpAnomaly::MissingColon,
+                    HttpAnomaly::ContentLengthWithTransferEncoding,
+                    HttpAnomaly::MultipleContentLength]);
+}
+
+#[test]
+fn parse_http_request_success_transfer_encoding_anomalies() {
+    let request = parse_request(b"POST / HTTP/1.0\r\nTransfer-Encoding: chunked, gzip\r\nContent-Length: 1x\r\n\r\n");
+    assert_eq!(request.anomalies,
+               vec![HttpAnomaly::ContentLengthWithTransferEncoding,
+                    HttpAnomaly::InvalidContentLength,
+                    HttpAnomaly::TransferEncodingNotChunked,
+                    HttpAnomaly::TransferEncodingInHttp10]);
+}
+
+#[test]
+fn parse_http_request_failure_malformed_request_line() {
+    let mut parser = HttpParser;
+    assert!(parser.parse(b"GET /\r\n\r\n", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"GET / HTTP/1.1 extra\r\n\r\n", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"GE(T / HTTP/1.1\r\n\r\n", None, None).to_full_result().is_err());
+    assert!(parser.parse(b"\r\n\r\n", None, None).is_err());
+    assert!(parser.parse(b"\x16\x03\x01", None, None).is_err());
+}
+
+#[test]
+fn parse_http_response_success_tolerant_status_line() {
+    let response = parse_response(b"HTTP/1.1 200\r\nServer:test\r\n\r\n");
+    assert_eq!(response.code, 200);
+    assert_eq!(response.reason, "");
+    assert_eq!(response.header("server"), Some("test"));
+    assert!(response.anomalies.is_empty());
+
+    let response = parse_response(b"HTTP/1.1 404 Not  Found\nContent-Length: 0\n\n");
+    assert_eq!(response.code, 404);
+    assert_eq!(response.reason, "Not  Found");
+    assert_eq!(response.anomalies, vec![HttpAnomaly::BareLineFeed]);
+}
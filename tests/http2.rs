@@ -0,0 +1,266 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+use std::net::{IpAddr, Ipv4Addr};
+
+static SETTINGS: &'static [u8] = &[0x00, 0x00, 0x0c, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00,
+                                   0x00, 0x64, 0x00, 0x04, 0x00, 0x00, 0xff, 0xff];
+
+static HEADERS: &'static [u8] = &[0x00, 0x00, 0x11, 0x01, 0x05, 0x00, 0x00, 0x00, 0x01, 0x82, 0x86, 0x84, 0x41, 0x8c,
+                                  0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab, 0x90, 0xf4, 0xff];
+
+fn header(name: &str, value: &str) -> HpackHeader {
+    HpackHeader {
+        name: name.to_owned(),
+        value: value.to_owned(),
+        never_indexed: false,
+    }
+}
+
+fn request_headers() -> Vec<HpackHeader> {
+    vec![header(":method", "GET"),
+         header(":scheme", "http"),
+         header(":path", "/"),
+         header(":authority", "www.example.com")]
+}
+
+fn parse_frame(input: &[u8]) -> Http2Frame {
+    let mut parser = Http2Parser;
+    let res = parser.parse(input, None, None).unwrap().1;
+    let mut packet = res.downcast::<Http2Packet>().unwrap();
+    assert_eq!(packet.frames.len(), 1);
+    packet.frames.remove(0)
+}
+
+#[test]
+fn http2_success_preface_settings() {
+    let mut parser = Http2Parser;
+    let mut input = Vec::from(HTTP2_PREFACE);
+    input.extend_from_slice(SETTINGS);
+    let res = parser.parse(&input, None, None).unwrap().1;
+    assert_eq!(Some(&Http2Packet {
+                   preface: true,
+                   frames: vec![Http2Frame {
+                                    length: 12,
+                                    frame_type: Http2FrameType::Settings,
+                                    flags: 0,
+                                    stream_id: 0,
+                                    payload: Http2FramePayload::Settings(vec![
+                            Http2Setting {
+                                parameter: Http2SettingsParameter::MaxConcurrentStreams,
+                                value: 100,
+                            },
+                            Http2Setting {
+                                parameter: Http2SettingsParameter::InitialWindowSize,
+                                value: 65535,
+                            },
+                        ]),
+                                }],
+                   encrypted: false,
+               }),
+               res.downcast_ref());
+    assert_eq!(format!("{}", parser), "HTTP/2");
+}
+
+#[test]
+fn http2_success_headers() {
+    let frame = parse_frame(HEADERS);
+    assert_eq!(frame.frame_type, Http2FrameType::Headers);
+    assert_eq!(frame.stream_id, 1);
+    assert!(frame.has_flag(Http2Frame::END_STREAM));
+    assert!(frame.has_flag(Http2Frame::END_HEADERS));
+    assert_eq!(frame.payload,
+               Http2FramePayload::Headers(Http2HeaderBlock {
+                                              fragment: HEADERS[9..].to_vec(),
+                                              headers: Some(request_headers()),
+                                          },
+                                          None));
+}
+
+#[test]
+fn http2_success_headers_padded_priority() {
+    let frame = parse_frame(&[0x00, 0x00, 0x09, 0x01, 0x2c, 0x00, 0x00, 0x00, 0x05, 0x02, 0x80, 0x00, 0x00, 0x03,
+                              0x0f, 0x82, 0x00, 0x00]);
+    assert_eq!(frame.payload,
+               Http2FramePayload::Headers(Http2HeaderBlock {
+                                              fragment: vec![0x82],
+                                              headers: Some(vec![header(":method", "GET")]),
+                                          },
+                                          Some(Http2Priority {
+                                              exclusive: true,
+                                              dependency: 3,
+                                              weight: 16,
+                                          })));
+}
+
+#[test]
+fn http2_success_data_padded() {
+    let frame = parse_frame(&[0x00, 0x00, 0x06, 0x00, 0x09, 0x00, 0x00, 0x00, 0x01, 0x03, 0x68, 0x69, 0x00, 0x00,
+                              0x00]);
+    assert_eq!(frame.frame_type, Http2FrameType::Data);
+    assert_eq!(frame.payload, Http2FramePayload::Data(b"hi".to_vec()));
+}
+
+#[test]
+fn http2_success_control_frames() {
+    assert_eq!(parse_frame(&[0x00, 0x00, 0x05, 0x02, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0xff])
+                   .payload,
+               Http2FramePayload::Priority(Http2Priority {
+                   exclusive: false,
+                   dependency: 1,
+                   weight: 256,
+               }));
+    assert_eq!(parse_frame(&[0x00, 0x00, 0x04, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x08]).payload,
+               Http2FramePayload::RstStream(Http2ErrorCode::Cancel));
+    assert_eq!(parse_frame(&[0x00, 0x00, 0x05, 0x05, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x82])
+                   .payload,
+               Http2FramePayload::PushPromise(2,
+                                              Http2HeaderBlock {
+                                                  fragment: vec![0x82],
+                                                  headers: Some(vec![header(":method", "GET")]),
+                                              }));
+    let ping = parse_frame(&[0x00, 0x00, 0x08, 0x06, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05,
+                             0x06, 0x07, 0x08]);
+    assert!(ping.has_flag(Http2Frame::ACK));
+    assert_eq!(ping.payload, Http2FramePayload::Ping(vec![1, 2, 3, 4, 5, 6, 7, 8]));
+    assert_eq!(parse_frame(&[0x00, 0x00, 0x0a, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+                             0x00, 0x00, 0x0b, 0x68, 0x69])
+                   .payload,
+               Http2FramePayload::GoAway(Http2GoAway {
+                   last_stream_id: 1,
+                   error_code: Http2ErrorCode::EnhanceYourCalm,
+                   debug_data: b"hi".to_vec(),
+               }));
+    assert_eq!(parse_frame(&[0x00, 0x00, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x10, 0x00]).payload,
+               Http2FramePayload::WindowUpdate(0x1000));
+}
+
+#[test]
+fn http2_success_unknown_frame() {
+    let frame = parse_frame(&[0x00, 0x00, 0x02, 0xfa, 0x00, 0x00, 0x00, 0x00, 0x00, 0xaa, 0xbb]);
+    assert_eq!(frame.frame_type, Http2FrameType::Unknown(0xfa));
+    assert_eq!(frame.payload, Http2FramePayload::Unknown(vec![0xaa, 0xbb]));
+}
+
+#[test]
+fn http2_failure_invalid_frames() {
+    // Wrong window update length
+    assert_eq!(parse_frame(&[0x00, 0x00, 0x03, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00]).payload,
+               Http2FramePayload::Invalid);
+
+    // Padding exceeds the payload
+    assert_eq!(parse_frame(&[0x00, 0x00, 0x02, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x05, 0x68]).payload,
+               Http2FramePayload::Invalid);
+
+    // Settings which are not a multiple of six octets
+    assert_eq!(parse_frame(&[0x00, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]).payload,
+               Http2FramePayload::Invalid);
+}
+
+#[test]
+fn http2_success_incomplete_frame_stateless() {
+    let mut parser = Http2Parser;
+    let mut input = Vec::from(SETTINGS);
+    input.extend_from_slice(&HEADERS[..12]);
+    let (left, res) = parser.parse(&input, None, None).unwrap();
+    assert_eq!(res.downcast_ref::<Http2Packet>().map(|p| p.frames.len()), Some(1));
+    assert_eq!(left, &HEADERS[..12]);
+}
+
+#[test]
+fn http2_success_reassembly() {
+    let mut state = Http2State::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+
+    // The frame is split over two packets
+    let (left, packet) = Http2Packet::parse(&HEADERS[..5], false, Some(&mut state), Some(&sender)).unwrap();
+    assert!(left.is_empty());
+    assert!(packet.frames.is_empty());
+    assert_eq!(state.buffers.get(&sender).map(|b| b.len()), Some(5));
+
+    let (left, packet) = Http2Packet::parse(&HEADERS[5..], false, Some(&mut state), Some(&sender)).unwrap();
+    assert!(left.is_empty());
+    assert_eq!(packet.frames.len(), 1);
+    assert!(state.buffers.is_empty());
+    match packet.frames[0].payload {
+        Http2FramePayload::Headers(ref block, None) => assert_eq!(block.headers, Some(request_headers())),
+        _ => panic!("Headers expected"),
+    }
+}
+
+#[test]
+fn http2_success_oversized_frame() {
+    let mut state = Http2State::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let receiver = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 443);
+
+    // A DATA frame of the maximum length exceeds the buffer limit
+    let mut input = vec![0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+    input.extend_from_slice(&[0x00; 0x10_0000]);
+    let (left, packet) = Http2Packet::parse(&input, false, Some(&mut state), Some(&sender)).unwrap();
+    assert!(left.is_empty());
+    assert!(packet.frames.is_empty());
+    assert!(state.buffers.is_empty());
+    assert_eq!(state.desynchronized, vec![sender]);
+
+    // The following packets of the sender are not parsed from within the frame payload
+    let (_, packet) = Http2Packet::parse(SETTINGS, false, Some(&mut state), Some(&sender)).unwrap();
+    assert!(packet.frames.is_empty());
+
+    // The other direction is not affected
+    let (_, packet) = Http2Packet::parse(SETTINGS, false, Some(&mut state), Some(&receiver)).unwrap();
+    assert_eq!(packet.frames.len(), 1);
+}
+
+#[test]
+fn http2_success_continuation() {
+    let mut state = Http2State::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+
+    // The header block of a HEADERS frame without END_HEADERS is continued
+    let input = [0x00, 0x00, 0x02, 0x01, 0x01, 0x00, 0x00, 0x00, 0x01, 0x82, 0x86, 0x00, 0x00, 0x0f, 0x09, 0x04,
+                 0x00, 0x00, 0x00, 0x01, 0x84, 0x41, 0x8c, 0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a, 0x6b, 0xa0, 0xab,
+                 0x90, 0xf4, 0xff];
+    let (_, packet) = Http2Packet::parse(&input, false, Some(&mut state), Some(&sender)).unwrap();
+    assert_eq!(packet.frames.len(), 2);
+    assert_eq!(packet.frames[0].payload,
+               Http2FramePayload::Headers(Http2HeaderBlock {
+                                              fragment: vec![0x82, 0x86],
+                                              headers: None,
+                                          },
+                                          None));
+    match packet.frames[1].payload {
+        Http2FramePayload::Continuation(ref block) => assert_eq!(block.headers, Some(request_headers())),
+        _ => panic!("Continuation expected"),
+    }
+    assert!(state.header_blocks.is_empty());
+
+    // The dynamic table of the sender is used by subsequent header blocks
+    let (_, packet) = Http2Packet::parse(&[0x00, 0x00, 0x01, 0x01, 0x05, 0x00, 0x00, 0x00, 0x03, 0xbe],
+                                         false,
+                                         Some(&mut state),
+                                         Some(&sender))
+        .unwrap();
+    match packet.frames[0].payload {
+        Http2FramePayload::Headers(ref block, None) => {
+            assert_eq!(block.headers, Some(vec![header(":authority", "www.example.com")]))
+        }
+        _ => panic!("Headers expected"),
+    }
+}
+
+#[test]
+fn http2_failure_continuation_without_headers() {
+    let mut state = Http2State::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let (_, packet) = Http2Packet::parse(&[0x00, 0x00, 0x01, 0x09, 0x04, 0x00, 0x00, 0x00, 0x01, 0x82],
+                                         false,
+                                         Some(&mut state),
+                                         Some(&sender))
+        .unwrap();
+    assert_eq!(packet.frames[0].payload,
+               Http2FramePayload::Continuation(Http2HeaderBlock {
+                   fragment: vec![0x82],
+                   headers: None,
+               }));
+}
@@ -17,6 +17,7 @@ static PACKET_ETH_IPV6_UDP: &'static [u8] =
       0x80, 0xda, 0x3f, 0xfe, 0x05, 0x01, 0x48, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
       0x09, 0x5c, 0x00, 0x35, 0x00, 0x24, 0xf0, 0x09];
 
+#[cfg(feature = "icmpv6")]
 static PACKET_ETH_IPV6_ICMP: &'static [u8] =
     &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
       0x00, 0x24, 0x3a, 0x40, 0x3f, 0xfe, 0x05, 0x07, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x86, 0xff, 0xfe, 0x05,
@@ -25,6 +26,7 @@ static PACKET_ETH_IPV6_ICMP: &'static [u8] =
       0x6b, 0x6c, 0x6d, 0x6e, 0x6f, 0x70, 0x71, 0x72, 0x73, 0x74, 0x75, 0x76, 0x77, 0x61, 0x62, 0x63, 0x64, 0x65,
       0x66, 0x67, 0x68, 0x69];
 
+#[cfg(feature = "http")]
 static TLS_HEADER: &'static [u8] =
     &[0x16, 0x03, 0x01, 0x00, 0xf4, 0x01, 0x00, 0x00, 0xf0, 0x03, 0x03, 0x14, 0x5b, 0x92, 0xc3, 0xcd, 0x27, 0xe0,
       0xa7, 0x09, 0x1d, 0x3a, 0x14, 0xda, 0x13, 0x8f, 0x19, 0x92, 0x9b, 0x5f, 0xd9, 0x75, 0x34, 0xe7, 0x45, 0xd8,
@@ -41,6 +43,14 @@ static TLS_HEADER: &'static [u8] =
       0x2f, 0x33, 0x2e, 0x31, 0x05, 0x68, 0x32, 0x2d, 0x31, 0x34, 0x02, 0x68, 0x32, 0x75, 0x50, 0x00, 0x00, 0x00,
       0x0b, 0x00, 0x02, 0x01, 0x00, 0x00, 0x0a, 0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18];
 
+#[cfg(feature = "tls")]
+static TLS_SERVER_HELLO: &'static [u8] =
+    &[0x16, 0x03, 0x03, 0x00, 0x32, 0x02, 0x00, 0x00, 0x2e, 0x03, 0x03, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+      0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+      0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x00, 0x13, 0x01, 0x00, 0x00, 0x06, 0x00, 0x2b, 0x00, 0x02, 0x03,
+      0x04];
+
+#[cfg(feature = "ntp")]
 static NTP_HEADER: &'static [u8] =
     &[0x23, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
       0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -67,6 +77,18 @@ static PACKET_ETH_IPV4_IPV4: &'static [u8] =
       0x00, 0x14, 0x00, 0x00, 0xff, 0x04, 0xa7, 0x6b, 0x0a, 0x00, 0x00, 0x01, 0x0a, 0x00, 0x00, 0x02, 0x45, 0x00,
       0x01, 0xa5, 0xd6, 0x63, 0x40, 0x00, 0x3f, 0x06, 0x9b, 0xfc, 0xc0, 0xa8, 0x01, 0x0a, 0xad, 0xfc, 0x58, 0x44];
 
+/// Create the reply of an IPv4 based packet by swapping its addresses and ports
+#[cfg(any(feature = "tls", feature = "http", feature = "dns"))]
+fn reply(packet: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::from(packet);
+    for i in 0..4 {
+        packet.swap(26 + i, 30 + i);
+    }
+    packet.swap(34, 36);
+    packet.swap(35, 37);
+    packet
+}
+
 #[test]
 fn peel_success_dot() {
     let mut peel = PeelIp::default();
@@ -74,6 +96,7 @@ fn peel_success_dot() {
 }
 
 #[test]
+#[cfg(all(feature = "tls", feature = "http"))]
 fn peel_success_tcp() {
     let mut peel = PeelIp::default();
     peel.set_log_level(LogLevel::Trace);
@@ -121,10 +144,12 @@ fn peel_success_tcp() {
                        options: vec![1, 1, 8, 10, 0, 2, 44, 44, 99, 147, 241, 91],
                    },
                    path_error: None,
+                   protocols: vec![ApplicationProtocol::Tls, ApplicationProtocol::Http],
                }));
 }
 
 #[test]
+#[cfg(all(feature = "tls", feature = "http"))]
 fn peel_success_tls_http() {
     let mut peel = PeelIp::default();
     peel.set_log_level(LogLevel::Trace);
@@ -132,19 +157,176 @@ fn peel_success_tls_http() {
     packet.extend_from_slice(TLS_HEADER);
     let result = peel.traverse(&packet, vec![]).result;
     assert_eq!(result.len(), 5);
-    assert_eq!(result[3].downcast_ref(),
-               Some(&TlsPacket {
-                   content_type: TlsRecordContentType::Handshake,
-                   version: TlsRecordVersion {
-                       major: 3,
-                       minor: 1,
-                   },
-                   length: 244,
-               }));
+    let tls: &TlsPacket = result[3].downcast_ref().unwrap();
+    assert_eq!(tls.content_type, TlsRecordContentType::Handshake);
+    assert_eq!(tls.version,
+               TlsRecordVersion {
+                   major: 3,
+                   minor: 1,
+               });
+    assert_eq!(tls.length, 244);
     assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any));
 }
 
 #[test]
+#[cfg(feature = "tls")]
+fn peel_success_tls_negotiated_version() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+
+    // The server hello selects the protocol version
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(TLS_SERVER_HELLO);
+    let result = peel.traverse(&packet, vec![]).result;
+    let tls: &TlsPacket = result[3].downcast_ref().unwrap();
+    assert_eq!(tls.negotiated_version, Some(TlsVersion::Tls13));
+
+    // Succeeding records of the connection are related to the negotiated version
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x46]);
+    let result = peel.traverse(&packet, vec![]).result;
+    let tls: &TlsPacket = result[3].downcast_ref().unwrap();
+    assert_eq!(tls.negotiated_version, Some(TlsVersion::Tls13));
+    assert_eq!(tls.content,
+               TlsRecordContent::Alert(TlsAlert {
+                   level: TlsAlertLevel::Fatal,
+                   description: TlsAlertDescription::ProtocolVersion,
+               }));
+}
+
+#[test]
+#[cfg(feature = "tls")]
+fn peel_success_tls_encrypted_heartbeat() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+    let heartbeat = [0x18, 0x03, 0x03, 0x00, 0x03, 0x01, 0x40, 0x00];
+    let content = |peel: &mut PeelIp, packet: &[u8], record: &[u8]| {
+        let mut packet = Vec::from(packet);
+        packet.extend_from_slice(record);
+        let result = peel.traverse(&packet, vec![]).result;
+        match result[3].downcast_ref::<TlsPacket>() {
+            Some(tls) => format!("{:?}", tls.content),
+            None => panic!("TLS packet expected"),
+        }
+    };
+
+    // Only the sender of the ChangeCipherSpec message encrypts its heartbeats
+    assert!(content(&mut peel, PACKET_ETH_IPV4_TCP, &heartbeat).starts_with("Heartbeat"));
+    assert_eq!(content(&mut peel, PACKET_ETH_IPV4_TCP, &[0x14, 0x03, 0x03, 0x00, 0x01, 0x01]),
+               "ChangeCipherSpec");
+    assert_eq!(content(&mut peel, PACKET_ETH_IPV4_TCP, &heartbeat), "Opaque");
+    assert!(content(&mut peel, &reply(PACKET_ETH_IPV4_TCP), &heartbeat).starts_with("Heartbeat"));
+
+    // All heartbeats are encrypted after the negotiation of TLS 1.3
+    assert!(content(&mut peel, &reply(PACKET_ETH_IPV4_TCP), TLS_SERVER_HELLO).starts_with("Handshake"));
+    assert_eq!(content(&mut peel, &reply(PACKET_ETH_IPV4_TCP), &heartbeat), "Opaque");
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn peel_success_http_body_segments() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+
+    // A chunked body spanning two segments
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel");
+    let result = peel.traverse(&packet, vec![]).result;
+    match result[3].downcast_ref() {
+        Some(HttpPacket::Response(response)) => {
+            assert_eq!(response.body.data, b"hel");
+            assert!(!response.body.complete);
+        }
+        _ => panic!("Response expected"),
+    }
+
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"lo\r\n0\r\n\r\n");
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result[3].downcast_ref(),
+               Some(&HttpPacket::Body(HttpBody {
+                   framing: HttpBodyFraming::Chunked,
+                   data: b"lo".to_vec(),
+                   trailers: vec![],
+                   complete: true,
+                   content: Some(b"hello".to_vec()),
+                   transaction: None,
+               })));
+
+    // A close delimited body which ends with a FIN segment
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"HTTP/1.0 200 OK\r\n\r\nabc");
+    peel.traverse(&packet, vec![]);
+
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[47] |= 0x01;
+    packet.extend_from_slice(b"def");
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result[3].downcast_ref(),
+               Some(&HttpPacket::Body(HttpBody {
+                   framing: HttpBodyFraming::CloseDelimited,
+                   data: b"def".to_vec(),
+                   trailers: vec![],
+                   complete: true,
+                   content: Some(b"abcdef".to_vec()),
+                   transaction: None,
+               })));
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn peel_success_http_transactions() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+
+    // Pipelined requests within a single segment
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"POST /a HTTP/1.1\r\nContent-Length: 3\r\n\r\nabcHEAD /b HTTP/1.1\r\n\r\n");
+    let time = std::time::Duration::from_secs(1_500_000_000);
+    let result = peel.traverse_at(&packet, vec![], time).result;
+    assert_eq!(result.len(), 5);
+    match (result[3].downcast_ref(), result[4].downcast_ref()) {
+        (Some(HttpPacket::Request(post)), Some(HttpPacket::Request(head))) => {
+            assert_eq!(post.request_method, HttpRequestMethod::Post);
+            assert_eq!(head.request_method, HttpRequestMethod::Head);
+        }
+        _ => panic!("Requests expected"),
+    }
+
+    // The responses are paired in order, the HEAD response has no body
+    let mut packet = reply(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"HTTP/1.1 100 Continue\r\n\r\n\
+                               HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok\
+                               HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n");
+    let result = peel.traverse_at(&packet, vec![], time + std::time::Duration::from_millis(40)).result;
+    assert_eq!(result.len(), 6);
+    let transactions = result[3..]
+        .iter()
+        .filter_map(|any| match any.downcast_ref() {
+            Some(HttpPacket::Response(response)) => response.body.transaction.as_ref(),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(transactions.len(), 2);
+
+    assert_eq!(transactions[0].method, HttpRequestMethod::Post);
+    assert_eq!(transactions[0].uri, "/a");
+    assert_eq!(transactions[0].status, 201);
+    assert_eq!(transactions[0].interim_statuses, vec![100]);
+    assert_eq!(transactions[0].request_size, 42);
+    assert_eq!(transactions[0].request_body_size, 3);
+    assert_eq!(transactions[0].response_size, 70);
+    assert_eq!(transactions[0].response_body_size, 2);
+    assert_eq!(transactions[0].latency, std::time::Duration::from_millis(40));
+
+    assert_eq!(transactions[1].method, HttpRequestMethod::Head);
+    assert_eq!(transactions[1].uri, "/b");
+    assert_eq!(transactions[1].status, 200);
+    assert_eq!(transactions[1].response_body_size, 0);
+}
+
+#[test]
+#[cfg(feature = "dns")]
 fn peel_success_udp() {
     let mut peel = PeelIp::default();
     peel.set_log_level(LogLevel::Trace);
@@ -176,10 +358,12 @@ fn peel_success_udp() {
                        checksum: 61449,
                    },
                    path_error: None,
+                   protocols: vec![ApplicationProtocol::Dns],
                }));
 }
 
 #[test]
+#[cfg(feature = "ntp")]
 fn peel_success_ntp() {
     let mut peel = PeelIp::default();
     peel.set_log_level(LogLevel::Trace);
@@ -189,9 +373,9 @@ fn peel_success_ntp() {
     assert_eq!(result.len(), 4);
     assert_eq!(result[3].downcast_ref(),
                Some(&NtpPacket {
-                   li: 0,
+                   li: LeapIndicator::NoWarning,
                    version: 4,
-                   mode: 3,
+                   mode: NtpMode::Client,
                    stratum: 0,
                    poll: 0,
                    precision: 0,
@@ -202,11 +386,13 @@ fn peel_success_ntp() {
                    ts_orig: 0,
                    ts_recv: 0,
                    ts_xmit: 14710388140573593600,
+                   extensions: vec![],
                    auth: Some((1, NTP_HEADER[52..].to_vec())),
                }));
 }
 
 #[test]
+#[cfg(feature = "icmpv6")]
 fn peel_success_icmpv6() {
     let mut peel = PeelIp::default();
     peel.set_log_level(LogLevel::Trace);
@@ -297,3 +483,400 @@ fn peel_track_timeout() {
     let result = peel.traverse(PACKET_ETH_IPV4_TCP, vec![]).result;
     assert_eq!(result.len(), 3);
 }
+
+#[test]
+#[cfg(feature = "http2")]
+fn peel_success_http2_prior_knowledge() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+
+    // The client starts with the connection preface and a SETTINGS frame
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(HTTP2_PREFACE);
+    packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref() {
+        Some(Http2Packet { preface: true, frames, .. }) => assert_eq!(frames.len(), 1),
+        _ => panic!("HTTP/2 packet expected"),
+    }
+
+    // The server responds without a preface
+    let mut packet = reply(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref() {
+        Some(Http2Packet { preface: false, frames, .. }) => {
+            assert_eq!(frames[0].frame_type, Http2FrameType::Settings);
+            assert!(frames[0].has_flag(Http2Frame::ACK));
+        }
+        _ => panic!("HTTP/2 packet expected"),
+    }
+}
+
+#[test]
+#[cfg(feature = "http2")]
+fn peel_success_http2_upgrade() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"GET / HTTP/1.1\r\nConnection: Upgrade, HTTP2-Settings\r\nUpgrade: h2c\r\n\r\n");
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+
+    // The frames of the server follow the protocol switch within the same segment
+    let mut packet = reply(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"HTTP/1.1 101 Switching Protocols\r\nConnection: Upgrade\r\nUpgrade: h2c\r\n\r\n");
+    packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 5);
+    match (result[3].downcast_ref(), result[4].downcast_ref()) {
+        (Some(HttpPacket::Response(response)), Some(Http2Packet { frames, .. })) => {
+            assert_eq!(response.code, 101);
+            assert_eq!(frames.len(), 1);
+        }
+        _ => panic!("HTTP response and HTTP/2 packet expected"),
+    }
+
+    // The client continues with the preface
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(HTTP2_PREFACE);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].downcast_ref::<Http2Packet>().is_some());
+}
+
+#[cfg(feature = "compression")]
+#[test]
+#[cfg(feature = "websocket")]
+fn peel_success_websocket_upgrade() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"GET /chat HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                               Sec-WebSocket-Extensions: permessage-deflate\r\n\r\n");
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+
+    // The first compressed message of the server follows the protocol switch
+    let mut packet = reply(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                               Sec-WebSocket-Extensions: permessage-deflate\r\n\r\n");
+    packet.extend_from_slice(&[0xc1, 0x07, 0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 5);
+    match (result[3].downcast_ref(), result[4].downcast_ref::<WebSocketPacket>()) {
+        (Some(HttpPacket::Response(response)), Some(websocket)) => {
+            assert_eq!(response.code, 101);
+            assert_eq!(websocket.messages[0].text(), Some("Hello"));
+        }
+        _ => panic!("HTTP response and WebSocket packet expected"),
+    }
+
+    // The client sends a masked frame
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(&[0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref::<WebSocketPacket>() {
+        Some(websocket) => assert_eq!(websocket.messages[0].text(), Some("Hello")),
+        _ => panic!("WebSocket packet expected"),
+    }
+
+    // The second message of the server refers to the first one
+    let mut packet = reply(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(&[0xc1, 0x05, 0xf2, 0x00, 0x11, 0x00, 0x00]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref::<WebSocketPacket>() {
+        Some(websocket) => assert_eq!(websocket.messages[0].text(), Some("Hello")),
+        _ => panic!("WebSocket packet expected"),
+    }
+}
+
+#[test]
+#[cfg(feature = "dns")]
+fn peel_success_dns_udp() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+    let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+    packet.extend_from_slice(&[0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x61,
+                               0x00, 0x00, 0x01, 0x00, 0x01]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref::<DnsPacket>() {
+        Some(dns) => assert_eq!(dns.questions[0].name, "a"),
+        _ => panic!("DNS packet expected"),
+    }
+}
+
+#[test]
+#[cfg(feature = "dns")]
+fn peel_success_dns_capture_time() {
+    let mut peel = PeelIp::default();
+    let at = |milliseconds: u64| std::time::Duration::from_millis(1_500_000_000_000 + milliseconds);
+    let query = |id: u8, port: u8| {
+        let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+        packet[55] = port;
+        packet.extend_from_slice(&[0x12, id, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x61,
+                                   0x00, 0x00, 0x01, 0x00, 0x01]);
+        packet
+    };
+    let mut transactions = |packet: &[u8], time| {
+        let result = peel.traverse_at(packet, vec![], time).result;
+        match result.last().and_then(|r| r.downcast_ref::<DnsPacket>()) {
+            Some(dns) => dns.transactions.clone(),
+            None => panic!("DNS packet expected"),
+        }
+    };
+
+    // The latency is the difference of the capture times
+    let mut response = query(0x34, 0x5c);
+    for i in 0..16 {
+        response.swap(22 + i, 38 + i);
+    }
+    response.swap(54, 56);
+    response.swap(55, 57);
+    response[64] = 0x81;
+    assert!(transactions(&query(0x34, 0x5c), at(0)).is_empty());
+    let answered = transactions(&response, at(250));
+    assert_eq!(answered[0].status, DnsTransactionStatus::Answered);
+    assert_eq!(answered[0].latency, Some(std::time::Duration::from_millis(250)));
+
+    // A query without response is reported by a message of another connection after the timeout
+    assert!(transactions(&query(0x35, 0x5d), at(1000)).is_empty());
+    assert!(transactions(&query(0x36, 0x5e), at(5500)).is_empty());
+    let unanswered = transactions(&query(0x37, 0x5f), at(6000));
+    assert_eq!(unanswered.len(), 1);
+    assert_eq!(unanswered[0].id, 0x1235);
+    assert_eq!(unanswered[0].client.1, 0x095d);
+    assert_eq!(unanswered[0].status, DnsTransactionStatus::Unanswered);
+}
+
+#[test]
+#[cfg(all(feature = "tls", feature = "http", feature = "dns"))]
+fn peel_success_dispatch_registry() {
+    // TLS is detected by its content on any port, encrypted HTTP only on configured ports
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[36] = 0x20;
+    packet[37] = 0xfb;
+    packet.extend_from_slice(TLS_HEADER);
+    let result = PeelIp::default().traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].is::<TlsPacket>());
+
+    let mut tcp = DispatchRegistry::tcp();
+    tcp.add_port(8443, ApplicationProtocol::Http);
+    let result = PeelIp::with_registries(tcp, DispatchRegistry::udp()).traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 5);
+    assert_eq!(result[4].downcast_ref(), Some(&HttpPacket::Any));
+
+    // Plain text HTTP is detected by its request line on any port
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[36] = 0x1f;
+    packet[37] = 0x40;
+    packet.extend_from_slice(b"HELLO / HTTP/1.1\r\n\r\n");
+    assert_eq!(PeelIp::default().traverse(&packet, vec![]).result.len(), 3);
+    packet[66..71].copy_from_slice(b"POST ");
+    let result = PeelIp::default().traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].is::<HttpPacket>());
+
+    // DNS on a custom port, but not on the well known one anymore
+    let mut udp = DispatchRegistry::udp();
+    udp.remove_port(DNS_PORT, &ApplicationProtocol::Dns).add_port(5300, ApplicationProtocol::Dns);
+    let mut peel = PeelIp::with_registries(DispatchRegistry::tcp(), udp);
+    let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+    packet.extend_from_slice(&[0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x61,
+                               0x00, 0x00, 0x01, 0x00, 0x01]);
+    assert_eq!(peel.traverse(&packet, vec![]).result.len(), 3);
+    packet[56] = 0x14;
+    packet[57] = 0xb4;
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].is::<DnsPacket>());
+}
+
+/// A custom parser which takes the whole payload
+struct CustomParser;
+
+impl Parsable<PathIp> for CustomParser {
+    fn parse<'a>(&mut self,
+                 input: &'a [u8],
+                 _: Option<&ParserResultVec>,
+                 _: Option<&mut PathIp>)
+                 -> IResult<&'a [u8], ParserResult> {
+        IResult::Done(&[], Box::new(input.to_vec()))
+    }
+}
+
+impl fmt::Display for CustomParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Custom")
+    }
+}
+
+#[test]
+#[cfg(all(feature = "tls", feature = "http", feature = "dns"))]
+fn peel_success_builder() {
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(TLS_HEADER);
+
+    // The default tree
+    assert_eq!(PeelIpBuilder::new().build().traverse(&packet, vec![]).result.len(), 5);
+
+    // Disabled protocols
+    let mut peel = PeelIpBuilder::new().disable(Protocol::Http).disable(Protocol::Dns).build();
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].is::<TlsPacket>());
+    let mut peel = PeelIpBuilder::new().disable(Protocol::Http).enable(Protocol::Http).build();
+    assert_eq!(peel.traverse(&packet, vec![]).result.len(), 5);
+
+    // Layer and depth limits
+    let mut peel = PeelIpBuilder::new().max_layer(LayerKind::Transport).build();
+    assert_eq!(peel.traverse(&packet, vec![]).result.len(), 4);
+    let mut peel = PeelIpBuilder::new().max_layer(LayerKind::Internet).build();
+    assert_eq!(peel.traverse(&packet, vec![]).result.len(), 2);
+    let mut peel = PeelIpBuilder::new().max_depth(3).build();
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 3);
+    assert!(result[2].is::<TcpPacket>());
+
+    // Another root
+    let mut peel = PeelIpBuilder::new().root(Protocol::Ipv4).build();
+    let result = peel.traverse(&packet[14..], vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[0].is::<Ipv4Packet>());
+    assert_eq!(peel.data.as_mut().unwrap().connection_count(), 1);
+    let mut peel = PeelIpBuilder::new().disable(Protocol::Ethernet).build();
+    assert!(peel.traverse(&packet, vec![]).error.is_some());
+}
+
+#[test]
+fn peel_success_builder_custom_parser() {
+    let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+    packet.extend_from_slice(b"custom");
+    let mut peel = PeelIpBuilder::new().add_parser(&[Protocol::Udp], CustomParser).build();
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert_eq!(result[3].downcast_ref(), Some(&b"custom".to_vec()));
+
+    // Custom parsers of disabled parents are skipped
+    let mut peel = PeelIpBuilder::new()
+        .disable(Protocol::Udp)
+        .add_parser(&[Protocol::Udp], CustomParser)
+        .build();
+    assert_eq!(peel.traverse(&packet, vec![]).result.len(), 2);
+}
+
+#[test]
+#[cfg(feature = "dns")]
+fn peel_success_dns_tcp() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+    let message = [0x00, 0x13, 0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x61,
+                   0x00, 0x00, 0x01, 0x00, 0x01];
+    let mut client = Vec::from(PACKET_ETH_IPV4_TCP);
+    client[36] = 0x00;
+    client[37] = 0x35;
+
+    // The first message is split over two segments
+    let mut packet = client.clone();
+    packet.extend_from_slice(&message[..5]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 3);
+
+    // The rest of the first message is followed by a second one
+    let mut packet = client.clone();
+    packet.extend_from_slice(&message[5..]);
+    packet.extend_from_slice(&message);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 5);
+    assert!(result[3].downcast_ref::<DnsPacket>().is_some());
+    assert!(result[4].downcast_ref::<DnsPacket>().is_some());
+
+    // The response is matched to the query
+    let mut packet = reply(&client);
+    packet.extend_from_slice(&message[..4]);
+    packet.extend_from_slice(&[0x81, 0x80]);
+    packet.extend_from_slice(&message[6..]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref::<DnsPacket>() {
+        Some(dns) => {
+            assert_eq!(dns.transactions.len(), 1);
+            assert_eq!(dns.transactions[0].status, DnsTransactionStatus::Answered);
+            assert_eq!(dns.transactions[0].statistics.retransmissions, 1);
+        }
+        _ => panic!("DNS packet expected"),
+    }
+}
+
+#[test]
+#[cfg(all(feature = "mdns", feature = "llmnr"))]
+fn peel_success_mdns_llmnr() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+    let query = [0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x68, 0x6f, 0x73,
+                 0x74, 0x00, 0x00, 0x01, 0x80, 0x01];
+
+    let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+    packet[56] = 0x14;
+    packet[57] = 0xe9;
+    packet.extend_from_slice(&query);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref::<MdnsPacket>() {
+        Some(mdns) => assert!(mdns.questions[0].unicast_response),
+        _ => panic!("mDNS packet expected"),
+    }
+
+    packet[57] = 0xeb;
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    assert!(result[3].downcast_ref::<LlmnrPacket>().is_some());
+}
+
+#[test]
+#[cfg(feature = "dhcp")]
+fn peel_success_dhcp() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+    let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+    packet[54] = 0x00;
+    packet[55] = 0x44;
+    packet[56] = 0x00;
+    packet[57] = 0x43;
+    packet.extend_from_slice(&[0x01, 0x01, 0x06, 0x00, 0x39, 0x03, 0xf3, 0x26]);
+    packet.extend_from_slice(&[0x00; 228]);
+    packet.extend_from_slice(&[0x63, 0x82, 0x53, 0x63, 0x35, 0x01, 0x01, 0xff]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref::<DhcpPacket>() {
+        Some(dhcp) => assert_eq!(dhcp.message_type(), Some(&DhcpMessageType::Discover)),
+        _ => panic!("DHCP packet expected"),
+    }
+}
+
+#[test]
+#[cfg(feature = "dhcpv6")]
+fn peel_success_dhcpv6() {
+    let mut peel = PeelIp::default();
+    peel.set_log_level(LogLevel::Trace);
+    let mut packet = Vec::from(PACKET_ETH_IPV6_UDP);
+    packet[54] = 0x02;
+    packet[55] = 0x22;
+    packet[56] = 0x02;
+    packet[57] = 0x23;
+    packet.extend_from_slice(&[0x01, 0x12, 0x34, 0x56, 0x00, 0x08, 0x00, 0x02, 0x00, 0x00]);
+    let result = peel.traverse(&packet, vec![]).result;
+    assert_eq!(result.len(), 4);
+    match result[3].downcast_ref::<Dhcpv6Packet>() {
+        Some(dhcpv6) => assert_eq!(dhcpv6.message_type(), &Dhcpv6MessageType::Solicit),
+        _ => panic!("DHCPv6 packet expected"),
+    }
+}
@@ -0,0 +1,39 @@
+extern crate nom;
+extern crate peel_ip;
+use peel_ip::prelude::*;
+use std::net::Ipv4Addr;
+
+static RESPONSE: &'static [u8] =
+    &[0xab, 0xcd, 0x85, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x68, 0x6f, 0x73, 0x74, 0x00,
+      0x00, 0x01, 0x00, 0x01, 0x04, 0x68, 0x6f, 0x73, 0x74, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x1e,
+      0x00, 0x04, 0x0a, 0x00, 0x00, 0x05];
+
+#[test]
+fn parse_llmnr_success_response() {
+    let mut parser = LlmnrParser;
+    println!("{}", parser);
+    let res = parser.parse(RESPONSE, None, None).unwrap().1;
+    let packet = res.downcast_ref::<LlmnrPacket>().unwrap();
+    assert_eq!(packet.header,
+               LlmnrHeader {
+                   id: 0xabcd,
+                   response: true,
+                   opcode: DnsOpcode::Query,
+                   conflict: true,
+                   truncated: false,
+                   tentative: true,
+                   response_code: DnsResponseCode::NoError,
+                   question_count: 1,
+                   answer_count: 1,
+                   authority_count: 0,
+                   additional_count: 0,
+               });
+    assert_eq!(packet.questions[0].name, "host");
+    assert_eq!(packet.answers[0].data, DnsRecordData::A(Ipv4Addr::new(10, 0, 0, 5)));
+}
+
+#[test]
+fn parse_llmnr_failure_truncated() {
+    let mut parser = LlmnrParser;
+    assert!(parser.parse(&RESPONSE[..20], None, None).is_err());
+}
@@ -0,0 +1,97 @@
+extern crate nom;
+extern crate peel_ip;
+mod common;
+use common::parse_packet;
+use peel_ip::prelude::*;
+use std::net::{IpAddr, Ipv4Addr};
+
+static QUERY: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x5f, 0x69, 0x70, 0x70, 0x04,
+      0x5f, 0x74, 0x63, 0x70, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x0c, 0x80, 0x01];
+
+static RESPONSE: &'static [u8] =
+    &[0x00, 0x00, 0x84, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x04, 0x04, 0x5f, 0x69, 0x70, 0x70, 0x04,
+      0x5f, 0x74, 0x63, 0x70, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x0c, 0x00, 0x01, 0x00, 0x00, 0x11,
+      0x94, 0x00, 0x1c, 0x0a, 0x4d, 0x79, 0x20, 0x50, 0x72, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x04, 0x5f, 0x69, 0x70,
+      0x70, 0x04, 0x5f, 0x74, 0x63, 0x70, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x0a, 0x4d, 0x79, 0x20, 0x50,
+      0x72, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x04, 0x5f, 0x69, 0x70, 0x70, 0x04, 0x5f, 0x74, 0x63, 0x70, 0x05, 0x6c,
+      0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x21, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x15, 0x00, 0x00, 0x00,
+      0x00, 0x02, 0x77, 0x07, 0x70, 0x72, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00,
+      0x0a, 0x4d, 0x79, 0x20, 0x50, 0x72, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x04, 0x5f, 0x69, 0x70, 0x70, 0x04, 0x5f,
+      0x74, 0x63, 0x70, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x10, 0x80, 0x01, 0x00, 0x00, 0x11, 0x94,
+      0x00, 0x19, 0x09, 0x74, 0x78, 0x74, 0x76, 0x65, 0x72, 0x73, 0x3d, 0x31, 0x07, 0x63, 0x6f, 0x6c, 0x6f, 0x72,
+      0x3d, 0x54, 0x06, 0x64, 0x75, 0x70, 0x6c, 0x65, 0x78, 0x07, 0x70, 0x72, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x05,
+      0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00, 0x01, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x04, 0xc0, 0xa8,
+      0x01, 0x14, 0x07, 0x70, 0x72, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x05, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x00, 0x00,
+      0x1c, 0x80, 0x01, 0x00, 0x00, 0x00, 0x78, 0x00, 0x10, 0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+#[test]
+fn parse_mdns_success_query() {
+    let mut parser = MdnsParser;
+    println!("{}", parser);
+    let res = parser.parse(QUERY, None, None).unwrap().1;
+    assert_eq!(res.downcast_ref::<MdnsPacket>().map(|p| &p.questions),
+               Some(&vec![MdnsQuestion {
+                              question: DnsQuestion {
+                                  name: "_ipp._tcp.local".to_owned(),
+                                  qtype: DnsType::Ptr,
+                                  class: DnsClass::In,
+                              },
+                              unicast_response: true,
+                          }]));
+}
+
+#[test]
+fn parse_mdns_success_cache_flush() {
+    let packet = parse_packet::<MdnsPacket>(MdnsParser, RESPONSE);
+    assert!(packet.header.response);
+    assert!(packet.header.authoritative);
+    assert!(!packet.answers[0].cache_flush);
+    assert_eq!(packet.answers[0].record.class, DnsClass::In);
+    assert!(packet.additionals.iter().all(|r| r.cache_flush && r.record.class == DnsClass::In));
+}
+
+#[test]
+fn parse_mdns_success_service_instance() {
+    let packet = parse_packet::<MdnsPacket>(MdnsParser, RESPONSE);
+    assert_eq!(packet.services,
+               vec![DnsSdService {
+                        instance: "My Printer".to_owned(),
+                        service: "_ipp._tcp".to_owned(),
+                        domain: "local".to_owned(),
+                        target: Some("printer.local".to_owned()),
+                        port: Some(631),
+                        attributes: vec![DnsSdAttribute {
+                                             key: "txtvers".to_owned(),
+                                             value: Some(b"1".to_vec()),
+                                         },
+                                         DnsSdAttribute {
+                                             key: "color".to_owned(),
+                                             value: Some(b"T".to_vec()),
+                                         },
+                                         DnsSdAttribute {
+                                             key: "duplex".to_owned(),
+                                             value: None,
+                                         }],
+                        addresses: vec![IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20)),
+                                        "fe80::1".parse().unwrap()],
+                    }]);
+    assert_eq!(packet.services[0].name(), "My\\032Printer._ipp._tcp.local");
+}
+
+#[test]
+fn parse_mdns_success_service_names() {
+    assert_eq!(DnsSdService::from_name("_ipp._tcp.local"), None);
+    assert_eq!(DnsSdService::from_name("a\\.b._http._udp.example.com").map(|s| (s.instance, s.domain)),
+               Some(("a.b".to_owned(), "example.com".to_owned())));
+    assert_eq!(DnsSdService::from_name("Caf\\195\\169._http._tcp.local").map(|s| s.instance),
+               Some("Café".to_owned()));
+    assert_eq!(DnsSdAttribute::parse(b"=value"), None);
+}
+
+#[test]
+fn parse_mdns_failure_truncated() {
+    let mut parser = MdnsParser;
+    assert!(parser.parse(&RESPONSE[..100], None, None).is_err());
+}
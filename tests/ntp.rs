@@ -1,6 +1,9 @@
 extern crate nom;
 extern crate peel_ip;
+mod common;
+use common::parse_packet;
 use peel_ip::prelude::*;
+use std::time::{Duration, UNIX_EPOCH};
 
 static NTP_HEADER: &'static [u8] =
     &[0x23, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -15,9 +18,9 @@ fn parse_ntp_success() {
     let parsing_result = parser.parse(NTP_HEADER, None, None).unwrap().1;
     let res = parsing_result.downcast_ref();
     assert_eq!(Some(&NtpPacket {
-                   li: 0,
+                   li: LeapIndicator::NoWarning,
                    version: 4,
-                   mode: 3,
+                   mode: NtpMode::Client,
                    stratum: 0,
                    poll: 0,
                    precision: 0,
@@ -28,6 +31,7 @@ fn parse_ntp_success() {
                    ts_orig: 0,
                    ts_recv: 0,
                    ts_xmit: 14710388140573593600,
+                   extensions: vec![],
                    auth: Some((1, NTP_HEADER[52..].to_vec())),
                }),
                res);
@@ -38,3 +42,222 @@ fn parse_ntp_failure_too_small() {
     let mut parser = NtpParser;
     assert!(parser.parse(&NTP_HEADER[..47], None, None).to_full_result().is_err());
 }
+
+#[test]
+fn parse_ntp_success_nts_extensions() {
+    let mut input = Vec::from(&NTP_HEADER[..48]);
+    input.extend_from_slice(&[0x01, 0x04, 0x00, 0x24]);
+    input.extend_from_slice(&[0xaa; 32]);
+    input.extend_from_slice(&[0x02, 0x04, 0x00, 0x14]);
+    input.extend_from_slice(&[0xbb; 16]);
+    input.extend_from_slice(&[0x04, 0x04, 0x00, 0x28, 0x00, 0x10, 0x00, 0x0e]);
+    input.extend_from_slice(&[0xcc; 16]);
+    input.extend_from_slice(&[0xdd; 14]);
+    input.extend_from_slice(&[0x00; 2]);
+    let packet: NtpPacket = parse_packet(NtpParser, &input);
+    assert_eq!(packet.extensions,
+               vec![NtpExtensionField::UniqueIdentifier(vec![0xaa; 32]),
+                    NtpExtensionField::NtsCookie(vec![0xbb; 16]),
+                    NtpExtensionField::NtsAuthenticator {
+                        nonce: vec![0xcc; 16],
+                        ciphertext: vec![0xdd; 14],
+                    }]);
+    assert_eq!(packet.auth, None);
+    assert_eq!(packet.mac_algorithm(), None);
+}
+
+#[test]
+fn parse_ntp_success_mac_algorithms() {
+    let packet: NtpPacket = parse_packet(NtpParser, NTP_HEADER);
+    assert_eq!(packet.mac_algorithm(), Some(NtpMacAlgorithm::Md5OrCmac));
+
+    let mut input = Vec::from(NTP_HEADER);
+    input.extend_from_slice(&[0xee; 4]);
+    let packet: NtpPacket = parse_packet(NtpParser, &input);
+    assert_eq!(packet.auth, Some((1, input[52..].to_vec())));
+    assert_eq!(packet.mac_algorithm(), Some(NtpMacAlgorithm::Sha1));
+
+    let packet: NtpPacket = parse_packet(NtpParser, &NTP_HEADER[..52]);
+    assert_eq!(packet.auth, Some((1, vec![])));
+    assert_eq!(packet.mac_algorithm(), Some(NtpMacAlgorithm::CryptoNak));
+}
+
+#[test]
+fn parse_ntp_success_unknown_extension_and_legacy_mac() {
+    // An unknown extension field followed by a MAC
+    let mut input = Vec::from(&NTP_HEADER[..48]);
+    input.extend_from_slice(&[0x12, 0x34, 0x00, 0x10]);
+    input.extend_from_slice(&[0x01; 12]);
+    input.extend_from_slice(&NTP_HEADER[48..]);
+    let packet: NtpPacket = parse_packet(NtpParser, &input);
+    assert_eq!(packet.extensions, vec![NtpExtensionField::Unknown(0x1234, vec![0x01; 12])]);
+    assert_eq!(packet.auth, Some((1, NTP_HEADER[52..].to_vec())));
+
+    // Data which is no valid extension field is treated as legacy MAC
+    let mut input = Vec::from(NTP_HEADER);
+    input.extend_from_slice(&[0xff; 100]);
+    let packet: NtpPacket = parse_packet(NtpParser, &input);
+    assert!(packet.extensions.is_empty());
+    assert_eq!(packet.auth, Some((1, NTP_HEADER[52..].to_vec())));
+}
+
+#[test]
+fn parse_ntp_success_control_read_variables() {
+    let data = b"version=\"ntpd 4.2.8p15, x\", stratum=2,\r\nleap=00, flag";
+    let mut input = vec![0x16, 0x82, 0x00, 0x01, 0x06, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, data.len() as u8];
+    input.extend_from_slice(data);
+    input.extend_from_slice(&[0x00; 3]);
+    let packet: NtpControlPacket = parse_packet(NtpParser, &input);
+    assert_eq!(packet,
+               NtpControlPacket {
+                   li: LeapIndicator::NoWarning,
+                   version: 2,
+                   response: true,
+                   error: false,
+                   more: false,
+                   opcode: NtpControlOpcode::ReadVariables,
+                   sequence: 1,
+                   status: 0x0618,
+                   association_id: 0,
+                   offset: 0,
+                   data: data.to_vec(),
+                   auth: None,
+               });
+    assert_eq!(packet.variables(),
+               vec![NtpVariable {
+                        name: "version".to_owned(),
+                        value: Some("ntpd 4.2.8p15, x".to_owned()),
+                    },
+                    NtpVariable {
+                        name: "stratum".to_owned(),
+                        value: Some("2".to_owned()),
+                    },
+                    NtpVariable {
+                        name: "leap".to_owned(),
+                        value: Some("00".to_owned()),
+                    },
+                    NtpVariable {
+                        name: "flag".to_owned(),
+                        value: None,
+                    }]);
+}
+
+#[test]
+fn parse_ntp_success_private_monlist() {
+    let mut request = vec![0x17, 0x00, 0x03, 0x2a, 0x00, 0x00, 0x00, 0x00];
+    request.extend_from_slice(&[0x00; 40]);
+    let packet: NtpPrivatePacket = parse_packet(NtpParser, &request);
+    assert!(packet.is_monlist_request());
+    assert_eq!(packet.request_code, NtpRequestCode::MonGetList1);
+    assert!(packet.items.is_empty());
+    assert_eq!(packet.data.len(), 40);
+    assert!(packet.monlist().is_empty());
+
+    let mut response = vec![0x97, 0x00, 0x03, 0x2a, 0x00, 0x01, 0x00, 0x48];
+    response.extend_from_slice(&[0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                                 0x00, 0x00, 0x2a, 0xc0, 0xa8, 0x00, 0x01, 0xc0, 0xa8, 0x00, 0x02, 0x00, 0x00,
+                                 0x00, 0x00, 0x00, 0x7b, 0x03, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                                 0x00]);
+    response.extend_from_slice(&[0x00; 32]);
+    let packet: NtpPrivatePacket = parse_packet(NtpParser, &response);
+    assert!(!packet.is_monlist_request());
+    assert_eq!(packet.item_count, 1);
+    assert_eq!(packet.monlist(),
+               vec![NtpMonitorEntry {
+                        last_time: 5,
+                        first_time: 256,
+                        restrict: 0,
+                        count: 42,
+                        address: IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+                        destination: IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2)),
+                        flags: 0,
+                        port: 123,
+                        mode: NtpMode::Client,
+                        version: 4,
+                    }]);
+}
+
+#[test]
+fn parse_ntp_failure_truncated_control() {
+    let mut parser = NtpParser;
+    assert!(parser.parse(&[0x16, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x61],
+                      None,
+                      None)
+        .to_full_result()
+        .is_err());
+    assert!(parser.parse(&[0x97, 0x00, 0x03, 0x2a, 0x00, 0x02, 0x00, 0x48, 0x00], None, None)
+        .to_full_result()
+        .is_err());
+}
+
+#[test]
+fn parse_ntp_success_timestamps() {
+    let packet: NtpPacket = parse_packet(NtpParser, NTP_HEADER);
+    assert_eq!(packet.reference_time(), None);
+    assert_eq!(packet.transmit_time(),
+               Some(UNIX_EPOCH + Duration::new(1216040339, NtpTimestamp(packet.ts_xmit).subsec_nanos())));
+    assert_eq!(NtpTimestamp(packet.ts_xmit).subsec_nanos(), 168_000_221);
+
+    // Timestamps without the most significant bit belong to the next era
+    assert_eq!(NtpTimestamp(1 << 32).unix_seconds(), 2085978497);
+    let time = UNIX_EPOCH + Duration::new(2085978497, 500_000_000);
+    assert_eq!(NtpTimestamp::from_system_time(time), NtpTimestamp(1 << 32 | 1 << 31));
+    assert_eq!(NtpTimestamp(1 << 32 | 1 << 31).system_time(), Some(time));
+
+    // Timestamps before the Unix epoch
+    let time = UNIX_EPOCH - Duration::new(10, 250_000_000);
+    assert_eq!(NtpTimestamp::from_system_time(time).system_time(), Some(time));
+}
+
+#[test]
+fn parse_ntp_success_root_delay_and_dispersion() {
+    let mut packet: NtpPacket = parse_packet(NtpParser, NTP_HEADER);
+    assert_eq!(packet.root_delay_duration(), Duration::new(0, 183105));
+    assert_eq!(packet.root_dispersion_duration(), Duration::new(0, 0));
+    packet.root_dispersion = 0x0001_8000;
+    assert_eq!(packet.root_dispersion_duration(), Duration::from_millis(1500));
+}
+
+#[test]
+fn parse_ntp_success_reference_id() {
+    let mut packet: NtpPacket = parse_packet(NtpParser, NTP_HEADER);
+    packet.ref_id = 0x5241_5445;
+    assert_eq!(packet.reference_id(false), NtpReferenceId::KissOfDeath(NtpKissCode::Rate));
+    assert_eq!(packet.kiss_code(), Some(NtpKissCode::Rate));
+    packet.ref_id = 0x5859_5a57;
+    assert_eq!(packet.kiss_code(), Some(NtpKissCode::Unknown("XYZW".to_owned())));
+
+    packet.stratum = 1;
+    packet.ref_id = 0x4750_5300;
+    assert_eq!(packet.reference_id(false), NtpReferenceId::ReferenceClock("GPS".to_owned()));
+    assert_eq!(packet.kiss_code(), None);
+
+    packet.stratum = 2;
+    packet.ref_id = 0xc0a8_0001;
+    assert_eq!(packet.reference_id(false), NtpReferenceId::Ipv4(Ipv4Addr::new(192, 168, 0, 1)));
+    assert_eq!(packet.reference_id(true), NtpReferenceId::Ipv6Hash(0xc0a8_0001));
+
+    packet.stratum = 16;
+    assert_eq!(packet.reference_id(false), NtpReferenceId::Unknown(0xc0a8_0001));
+}
+
+#[test]
+fn parse_ntp_success_measurement() {
+    let request: NtpPacket = parse_packet(NtpParser, NTP_HEADER);
+    let mut response: NtpPacket = parse_packet(NtpParser, NTP_HEADER);
+    let sent = NtpTimestamp(request.ts_xmit);
+    response.mode = NtpMode::Server;
+    response.ts_orig = request.ts_xmit;
+    response.ts_recv = sent.0 + (5 << 32);
+    response.ts_xmit = sent.0 + (5 << 32) + (1 << 31);
+    let received = sent.system_time().unwrap() + Duration::from_secs(1);
+
+    let measurement = NtpMeasurement::new(&request, &response, received).unwrap();
+    assert!((measurement.offset - 4.75).abs() < 1e-6);
+    assert!((measurement.delay - 0.5).abs() < 1e-6);
+
+    // Responses to other requests are not paired
+    response.ts_orig = 0;
+    assert_eq!(NtpMeasurement::new(&request, &response, received), None);
+    assert_eq!(NtpMeasurement::new(&request, &request, received), None);
+}
@@ -0,0 +1,80 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+static PACKET_ETH_IPV4_IPV6: &'static [u8] =
+    &[0xc2, 0x01, 0x42, 0x02, 0x00, 0x00, 0xc2, 0x00, 0x42, 0x02, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x78,
+      0x00, 0x09, 0x00, 0x00, 0xff, 0x29, 0xa7, 0x51, 0x0a, 0x00, 0x00, 0x01, 0x0a, 0x00, 0x00, 0x02, 0x60, 0x00,
+      0x00, 0x00, 0x00, 0x3c, 0x11, 0x40, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x00, 0x01, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x02];
+
+#[test]
+#[cfg(all(feature = "tls", feature = "http"))]
+fn packet_success_accessors() {
+    let mut input = Vec::from(PACKET_ETH_IPV4_TCP);
+    input.extend_from_slice(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x46]);
+    let packet = Packet::from(PeelIp::default().traverse(&input, vec![]).result);
+    assert_eq!(packet.len(), 5);
+
+    assert_eq!(packet.ethernet().map(|e| &e.ethertype), Some(&EtherType::Ipv4));
+    assert_eq!(packet.ipv4().map(|ip| ip.src), Some(Ipv4Addr::new(10, 0, 0, 101)));
+    assert!(packet.ipv6().is_none());
+    assert_eq!(packet.tcp().map(|tcp| tcp.header.dest_port), Some(443));
+    assert!(packet.udp().is_none());
+    assert_eq!(packet.tls().map(|tls| &tls.content_type), Some(&TlsRecordContentType::Alert));
+    assert_eq!(packet.http(), Some(&HttpPacket::Any));
+
+    assert_eq!(packet.ip_addresses(),
+               Some((IpAddr::V4(Ipv4Addr::new(10, 0, 0, 101)), IpAddr::V4(Ipv4Addr::new(66, 196, 65, 112)))));
+    assert_eq!(packet.ports(), Some((51781, 443)));
+}
+
+#[test]
+fn packet_success_iterators() {
+    let packet = Packet::new(PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result);
+    let protocols: Vec<_> = packet.iter().filter_map(Layer::protocol).collect();
+    assert_eq!(protocols, vec![Protocol::Ethernet, Protocol::Ipv4, Protocol::Tcp]);
+    assert_eq!(packet.iter_kind(LayerKind::Transport).count(), 1);
+    assert!(packet.last().is_some_and(|layer| matches!(layer, Layer::Tcp(_))));
+
+    let mut ports = None;
+    for layer in &packet {
+        if let Layer::Tcp(ref tcp) = *layer {
+            ports = Some((tcp.header.source_port, tcp.header.dest_port));
+        }
+    }
+    assert_eq!(ports, packet.ports());
+
+    let layers: Vec<Layer> = packet.into_iter().collect();
+    assert_eq!(layers.len(), 3);
+}
+
+#[test]
+fn packet_success_tunnel() {
+    let packet = Packet::new(PeelIp::default().traverse(PACKET_ETH_IPV4_IPV6, vec![]).result);
+    assert_eq!(packet.iter_kind(LayerKind::Internet).count(), 2);
+    assert_eq!(packet.ipv4().map(|ip| ip.dst), Some(Ipv4Addr::new(10, 0, 0, 2)));
+    assert_eq!(packet.ip_addresses(),
+               Some((IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 1, 0, 0, 0, 1)),
+                     IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 1, 0, 0, 0, 2)))));
+    assert!(packet.ports().is_none());
+}
+
+#[test]
+fn packet_success_custom() {
+    let mut results = PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result;
+    results.push(Box::new(vec![1u8, 2, 3]));
+    let packet = Packet::new(results);
+    assert_eq!(packet.len(), 4);
+    assert!(packet.last().unwrap().protocol().is_none());
+    assert!(packet.last().unwrap().kind().is_none());
+    let custom: Vec<_> = packet.custom().filter_map(|result| result.downcast_ref::<Vec<u8>>()).collect();
+    assert_eq!(custom, vec![&vec![1u8, 2, 3]]);
+    assert!(Packet::default().is_empty());
+}
@@ -0,0 +1,92 @@
+extern crate peel_ip;
+extern crate serde_json;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+#[test]
+fn serialize_packet_roundtrip() {
+    let result = PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result;
+    let ipv4: &Ipv4Packet = result[1].downcast_ref().unwrap();
+    let json = serde_json::to_string(ipv4).unwrap();
+    assert_eq!(&serde_json::from_str::<Ipv4Packet>(&json).unwrap(), ipv4);
+
+    let tcp: &TcpPacket = result[2].downcast_ref().unwrap();
+    let json = serde_json::to_string(tcp).unwrap();
+    assert_eq!(&serde_json::from_str::<TcpPacket>(&json).unwrap(), tcp);
+}
+
+#[test]
+fn serialize_path_error() {
+    let mut result = PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result;
+    let tcp: &mut TcpPacket = result[2].downcast_mut().unwrap();
+    tcp.path_error = Some(PathErrorType::Timeout);
+    let value = serde_json::to_value(&*tcp).unwrap();
+    assert_eq!(value["path_error"], "Timeout");
+    assert_eq!(&serde_json::from_value::<TcpPacket>(value).unwrap(), &*tcp);
+
+    let mut value = serde_json::to_value(&*tcp).unwrap();
+    value["path_error"] = serde_json::json!("Unknown");
+    assert!(serde_json::from_value::<TcpPacket>(value).is_err());
+}
+
+#[test]
+#[cfg(all(feature = "tls", feature = "http"))]
+fn serialize_tagged_results() {
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet.extend_from_slice(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x46]);
+    let result = PeelIp::default().traverse(&packet, vec![]).result;
+    let value = TaggedResults::new(&Packet::new(result)).to_json_value().unwrap();
+
+    let layers = value["layers"].as_array().unwrap();
+    assert_eq!(layers.len(), 5);
+    let tags: Vec<_> = layers.iter()
+        .map(|l| (l["layer"].as_str().unwrap(), l["protocol"].as_str().unwrap()))
+        .collect();
+    assert_eq!(tags,
+               vec![("link", "ethernet"),
+                    ("internet", "ipv4"),
+                    ("transport", "tcp"),
+                    ("transport", "tls"),
+                    ("application", "http")]);
+    assert_eq!(layers[1]["packet"]["src"], "10.0.0.101");
+    assert_eq!(layers[2]["packet"]["header"]["dest_port"], 443);
+    assert_eq!(layers[2]["packet"]["protocols"], serde_json::json!(["tls", "http"]));
+    assert_eq!(layers[3]["packet"]["content"]["Alert"]["description"], "ProtocolVersion");
+    assert_eq!(layers[4]["packet"], "Any");
+}
+
+#[test]
+fn serialize_tagged_results_custom() {
+    let mut result = PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result;
+    result.insert(1, Box::new(vec![0u8]));
+    let packet = Packet::new(result);
+    let results = TaggedResults::new(&packet);
+    assert_eq!(results.layers.len(), 3);
+    assert_eq!(results.layers[1].protocol, Protocol::Ipv4);
+    assert!(results.to_json().unwrap().starts_with(r#"{"layers":[{"layer":"link","protocol":"ethernet""#));
+}
+
+#[test]
+fn serialize_application_protocol() {
+    assert_eq!(serde_json::to_string(&ApplicationProtocol::Custom("proxy".into())).unwrap(), r#""proxy""#);
+    assert_eq!(serde_json::from_str::<ApplicationProtocol>(r#""dhcpv6""#).unwrap(),
+               ApplicationProtocol::Dhcpv6);
+    assert_eq!(serde_json::from_str::<ApplicationProtocol>(r#""proxy""#).unwrap(),
+               ApplicationProtocol::Custom("proxy".into()));
+    assert!(serde_json::from_str::<ApplicationProtocol>("1").is_err());
+
+    // Packets dispatched to custom protocols survive a round trip
+    let mut registry = DispatchRegistry::tcp();
+    registry.add_port(443, ApplicationProtocol::Custom("proxy".into()));
+    let mut peel = PeelIp::with_registries(registry, DispatchRegistry::udp());
+    let result = peel.traverse(PACKET_ETH_IPV4_TCP, vec![]).result;
+    let tcp: &TcpPacket = result[2].downcast_ref().unwrap();
+    assert!(tcp.dispatches_to(&ApplicationProtocol::Custom("proxy".into())));
+    let json = serde_json::to_string(tcp).unwrap();
+    assert_eq!(&serde_json::from_str::<TcpPacket>(&json).unwrap(), tcp);
+}
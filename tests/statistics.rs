@@ -0,0 +1,77 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+static PACKET_ETH_IPV6_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
+      0x00, 0x24, 0x11, 0x40, 0x3f, 0xfe, 0x05, 0x07, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x86, 0xff, 0xfe, 0x05,
+      0x80, 0xda, 0x3f, 0xfe, 0x05, 0x01, 0x48, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
+      0x09, 0x5c, 0x00, 0x35, 0x00, 0x24, 0xf0, 0x09];
+
+/// The TCP packet in the opposite direction
+fn reply() -> Vec<u8> {
+    let mut packet = Vec::from(PACKET_ETH_IPV4_TCP);
+    packet[26..30].copy_from_slice(&PACKET_ETH_IPV4_TCP[30..34]);
+    packet[30..34].copy_from_slice(&PACKET_ETH_IPV4_TCP[26..30]);
+    packet[34..36].copy_from_slice(&PACKET_ETH_IPV4_TCP[36..38]);
+    packet[36..38].copy_from_slice(&PACKET_ETH_IPV4_TCP[34..36]);
+    packet
+}
+
+fn packets() -> Vec<(Packet, usize)> {
+    let mut peel = PeelIp::default();
+    let reply = reply();
+    [PACKET_ETH_IPV4_TCP, &reply, PACKET_ETH_IPV4_TCP, PACKET_ETH_IPV6_UDP, &PACKET_ETH_IPV4_TCP[..14]]
+        .iter()
+        .map(|input| (Packet::new(peel.traverse(input, vec![]).result), input.len()))
+        .collect()
+}
+
+#[test]
+fn statistics_success_hierarchy() {
+    let mut hierarchy = ProtocolHierarchy::default();
+    for (packet, length) in packets() {
+        hierarchy.add(&packet, length);
+    }
+    assert_eq!(hierarchy.counter,
+               Counter {
+                   packets: 5,
+                   bytes: 66 * 3 + 62 + 14,
+               });
+    assert_eq!(hierarchy.to_string(),
+               "frame packets=5 bytes=274\n  ethernet packets=5 bytes=274\n    ipv4 packets=3 bytes=198\n      \
+                tcp packets=3 bytes=198\n    ipv6 packets=1 bytes=62\n      udp packets=1 bytes=62\n");
+}
+
+#[test]
+fn statistics_success_conversations() {
+    let mut conversations = Conversations::default();
+    for (packet, length) in packets() {
+        conversations.add(&packet, length);
+    }
+    assert_eq!(conversations.list.len(), 2);
+    let tcp = &conversations.list[0];
+    assert_eq!(tcp.protocol, Protocol::Tcp);
+    assert_eq!(tcp.a, (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 101)), 51781));
+    assert_eq!((tcp.a_to_b.packets, tcp.b_to_a.packets), (2, 1));
+    assert_eq!(conversations.to_string(),
+               "tcp 10.0.0.101:51781 <-> 66.196.65.112:443 packets=2/1 bytes=132/66\nudp \
+                [3ffe:507:0:1:200:86ff:fe05:80da]:2396 <-> [3ffe:501:4819::42]:53 packets=1/0 bytes=62/0\n");
+}
+
+#[test]
+fn statistics_success_endpoints() {
+    let mut endpoints = Endpoints::default();
+    for (packet, length) in packets() {
+        endpoints.add(&packet, length);
+    }
+    assert_eq!(endpoints.list.len(), 4);
+    assert_eq!(endpoints.list[0].to_string(), "10.0.0.101 packets=2/1 bytes=132/66");
+    assert_eq!(endpoints.list[1].address, IpAddr::V4(Ipv4Addr::new(66, 196, 65, 112)));
+    assert_eq!(endpoints.list[3].received.packets, 1);
+}
@@ -0,0 +1,91 @@
+extern crate peel_ip;
+use peel_ip::prelude::*;
+
+static PACKET_ETH_IPV4_TCP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x45, 0x00, 0x00, 0x34,
+      0x73, 0x22, 0x40, 0x00, 0x3f, 0x06, 0x3a, 0x09, 0x0a, 0x00, 0x00, 0x65, 0x42, 0xc4, 0x41, 0x70, 0xca, 0x45,
+      0x01, 0xbb, 0x98, 0x66, 0x5f, 0x0a, 0x44, 0x9d, 0x7f, 0x05, 0x80, 0x10, 0x20, 0x00, 0xbf, 0xf2, 0x00, 0x00,
+      0x01, 0x01, 0x08, 0x0a, 0x00, 0x02, 0x2c, 0x2c, 0x63, 0x93, 0xf1, 0x5b];
+
+static PACKET_ETH_IPV6_UDP: &'static [u8] =
+    &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00,
+      0x00, 0x24, 0x11, 0x40, 0x3f, 0xfe, 0x05, 0x07, 0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x86, 0xff, 0xfe, 0x05,
+      0x80, 0xda, 0x3f, 0xfe, 0x05, 0x01, 0x48, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
+      0x09, 0x5c, 0x00, 0x35, 0x00, 0x24, 0xf0, 0x09];
+
+#[cfg(feature = "arp")]
+static PACKET_ETH_ARP: &'static [u8] =
+    &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x07, 0x0d, 0xaf, 0xf4, 0x54, 0x08, 0x06, 0x00, 0x01, 0x08, 0x00,
+      0x06, 0x04, 0x00, 0x01, 0x00, 0x07, 0x0d, 0xaf, 0xf4, 0x54, 0x18, 0xa6, 0xac, 0x01, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x18, 0xa6, 0xad, 0x9f];
+
+#[test]
+fn summary_success_tcp() {
+    let packet = Packet::new(PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result);
+    assert_eq!(packet.summary(Verbosity::Brief), "10.0.0.101:51781 > 66.196.65.112:443 TCP");
+    assert_eq!(packet.to_string(),
+               "10.0.0.101:51781 > 66.196.65.112:443 TCP [ACK] seq=2556845834 ack=1151172357 win=8192");
+}
+
+#[test]
+#[cfg(all(feature = "tls", feature = "http"))]
+fn summary_success_verbose() {
+    let packet = Packet::new(PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result);
+    assert_eq!(format!("{:#}", packet),
+               "10.0.0.101:51781 > 66.196.65.112:443 00:00:00:00:00:00 > 00:00:00:00:00:00 Ethernet Ipv4, IPv4 \
+                proto=Tcp len=52 ttl=63 id=29474 tos=0x0 [DF], TCP [ACK] seq=2556845834 ack=1151172357 win=8192 \
+                offset=32 checksum=0xbff2 dispatch=tls,http");
+}
+
+#[test]
+fn summary_success_layers() {
+    let packet = Packet::new(PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result);
+    let lines: Vec<_> = packet.iter().map(ToString::to_string).collect();
+    assert_eq!(lines,
+               vec!["00:00:00:00:00:00 > 00:00:00:00:00:00 Ethernet Ipv4",
+                    "10.0.0.101 > 66.196.65.112 IPv4 proto=Tcp len=52",
+                    "51781 > 443 TCP [ACK] seq=2556845834 ack=1151172357 win=8192"]);
+    assert_eq!(packet.get(1).map(|layer| layer.summary(Verbosity::Brief)),
+               Some("10.0.0.101 > 66.196.65.112 IPv4".to_owned()));
+}
+
+#[test]
+fn summary_success_ipv6_udp() {
+    let packet = Packet::new(PeelIp::default().traverse(PACKET_ETH_IPV6_UDP, vec![]).result);
+    assert_eq!(packet.to_string(),
+               "[3ffe:507:0:1:200:86ff:fe05:80da]:2396 > [3ffe:501:4819::42]:53 UDP len=36");
+}
+
+#[test]
+#[cfg(feature = "arp")]
+fn summary_success_arp() {
+    let packet = Packet::new(PeelIp::default().traverse(PACKET_ETH_ARP, vec![]).result);
+    assert_eq!(packet.summary(Verbosity::Brief), "ARP");
+    assert_eq!(packet.to_string(), "ARP who-has 24.166.173.159 tell 24.166.172.1");
+    assert_eq!(format!("{:#}", packet),
+               "00:07:0d:af:f4:54 > ff:ff:ff:ff:ff:ff Ethernet Arp, ARP who-has 24.166.173.159 tell 24.166.172.1 \
+                hardware=Ethernet protocol=Ipv4");
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn summary_success_http() {
+    let mut input = Vec::from(PACKET_ETH_IPV4_TCP);
+    input.extend_from_slice(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n");
+    let packet = Packet::new(PeelIp::default().traverse(&input, vec![]).result);
+    assert_eq!(packet.summary(Verbosity::Brief), "10.0.0.101:51781 > 66.196.65.112:443 HTTP");
+    assert_eq!(packet.to_string(),
+               "10.0.0.101:51781 > 66.196.65.112:443 TCP [ACK] seq=2556845834 ack=1151172357 win=8192, HTTP GET /");
+    assert_eq!(packet.http().map(|http| http.summary(Verbosity::Verbose)),
+               Some("HTTP GET / HTTP/1.1 headers=1 body=0".to_owned()));
+}
+
+#[test]
+fn summary_success_custom() {
+    let mut results = PeelIp::default().traverse(PACKET_ETH_IPV4_TCP, vec![]).result;
+    results.push(Box::new(vec![1u8, 2, 3]));
+    let packet = Packet::new(results);
+    assert_eq!(packet.summary(Verbosity::Brief), "10.0.0.101:51781 > 66.196.65.112:443 TCP");
+    assert_eq!(packet.last().map(ToString::to_string), Some("Custom".to_owned()));
+    assert_eq!(Packet::default().to_string(), "");
+}
@@ -7,8 +7,9 @@ static TCP_HEADER: &'static [u8] = &[0xca, 0x45, 0x01, 0xbb, 0x98, 0x66, 0x5f, 0
                                      0x2c, 0x2e, 0x63, 0x93, 0xf1, 0x5b];
 
 #[test]
+#[cfg(all(feature = "tls", feature = "http"))]
 fn parse_tcp_success() {
-    let mut parser = TcpParser;
+    let mut parser = TcpParser::new();
     println!("{}", parser);
     let parsing_result = parser.parse(TCP_HEADER, None, None).unwrap().1;
     let res = parsing_result.downcast_ref();
@@ -32,13 +33,14 @@ fn parse_tcp_success() {
                        options: TCP_HEADER[20..].to_vec(),
                    },
                    path_error: None,
+                   protocols: vec![ApplicationProtocol::Tls, ApplicationProtocol::Http],
                }),
                res);
 }
 
 #[test]
 fn parse_tcp_failure_too_small() {
-    let mut parser = TcpParser;
+    let mut parser = TcpParser::new();
     let mut input = Vec::from(TCP_HEADER);
     input.pop();
     assert!(parser.parse(&input, None, None).to_full_result().is_err());
@@ -46,6 +48,6 @@ fn parse_tcp_failure_too_small() {
 
 #[test]
 fn parse_tcp_failure_wrong_result() {
-    let mut parser = TcpParser;
+    let mut parser = TcpParser::new();
     assert!(parser.parse(TCP_HEADER, Some(&vec![]), None).to_full_result().is_err());
 }
@@ -18,21 +18,133 @@ static TLS_HEADER: &'static [u8] =
       0x2f, 0x33, 0x2e, 0x31, 0x05, 0x68, 0x32, 0x2d, 0x31, 0x34, 0x02, 0x68, 0x32, 0x75, 0x50, 0x00, 0x00, 0x00,
       0x0b, 0x00, 0x02, 0x01, 0x00, 0x00, 0x0a, 0x00, 0x06, 0x00, 0x04, 0x00, 0x17, 0x00, 0x18];
 
+static TLS_SERVER_HELLO: &'static [u8] =
+    &[0x16, 0x03, 0x03, 0x00, 0x32, 0x02, 0x00, 0x00, 0x2e, 0x03, 0x03, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+      0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+      0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x00, 0x13, 0x01, 0x00, 0x00, 0x06, 0x00, 0x2b, 0x00, 0x02, 0x03,
+      0x04];
+
 #[test]
 fn parse_tls_success() {
     let mut parser = TlsParser;
     println!("{}", parser);
     let parsing_result = parser.parse(TLS_HEADER, None, None).unwrap().1;
-    let res = parsing_result.downcast_ref();
-    assert_eq!(Some(&TlsPacket {
-                   content_type: TlsRecordContentType::Handshake,
-                   version: TlsRecordVersion {
-                       major: 3,
-                       minor: 1,
-                   },
-                   length: 244,
-               }),
-               res);
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.content_type, TlsRecordContentType::Handshake);
+    assert_eq!(res.version,
+               TlsRecordVersion {
+                   major: 3,
+                   minor: 1,
+               });
+    assert_eq!(res.length, 244);
+    assert_eq!(res.negotiated_version, None);
+    match res.content {
+        TlsRecordContent::Handshake(ref messages) => {
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].handshake_type, TlsHandshakeType::ClientHello);
+            assert_eq!(messages[0].length, 240);
+            match messages[0].message {
+                TlsHandshakeMessage::ClientHello(ref hello) => {
+                    assert_eq!(hello.legacy_version, 0x0303);
+                    assert_eq!(hello.session_id.len(), 32);
+                    assert_eq!(hello.cipher_suites.len(), 16);
+                    assert_eq!(hello.compression_methods, vec![0]);
+                    assert_eq!(hello.extensions.len(), 12);
+                    assert!(hello.supported_versions().is_empty());
+                }
+                _ => panic!("No client hello found"),
+            }
+        }
+        _ => panic!("No handshake found"),
+    }
+}
+
+#[test]
+fn parse_tls_success_server_hello_version() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(TLS_SERVER_HELLO, None, None).unwrap().1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.negotiated_version, Some(TlsVersion::Tls13));
+    match res.content {
+        TlsRecordContent::Handshake(ref messages) => {
+            match messages[0].message {
+                TlsHandshakeMessage::ServerHello(ref hello) => {
+                    assert_eq!(hello.legacy_version, 0x0303);
+                    assert_eq!(hello.cipher_suite, 0x1301);
+                    assert_eq!(hello.compression_method, 0);
+                    assert_eq!(hello.selected_version(), Some(TlsVersion::Tls13));
+                }
+                _ => panic!("No server hello found"),
+            }
+        }
+        _ => panic!("No handshake found"),
+    }
+}
+
+#[test]
+fn parse_tls_success_server_hello_legacy_version() {
+    let mut parser = TlsParser;
+    let mut input = Vec::from(&TLS_SERVER_HELLO[..TLS_SERVER_HELLO.len() - 8]);
+    input[4] -= 8;
+    input[8] -= 8;
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.negotiated_version, Some(TlsVersion::Tls12));
+}
+
+#[test]
+fn parse_tls_success_alert() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28], None, None).unwrap().1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.content_type, TlsRecordContentType::Alert);
+    assert_eq!(res.content,
+               TlsRecordContent::Alert(TlsAlert {
+                   level: TlsAlertLevel::Fatal,
+                   description: TlsAlertDescription::HandshakeFailure,
+               }));
+}
+
+#[test]
+fn parse_tls_success_encrypted_alert() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&[0x15, 0x03, 0x03, 0x00, 0x04, 0xde, 0xad, 0xbe, 0xef], None, None)
+        .unwrap()
+        .1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.content, TlsRecordContent::Opaque);
+}
+
+#[test]
+fn parse_tls_success_change_cipher_spec() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&[0x14, 0x03, 0x03, 0x00, 0x01, 0x01], None, None).unwrap().1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.content, TlsRecordContent::ChangeCipherSpec);
+}
+
+#[test]
+fn parse_tls_success_encrypted_handshake() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&[0x16, 0x03, 0x03, 0x00, 0x04, 0x14, 0xff, 0xff, 0xff], None, None)
+        .unwrap()
+        .1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.content, TlsRecordContent::Opaque);
+}
+
+#[test]
+fn parse_tls_success_truncated_record() {
+    let mut parser = TlsParser;
+    let (left, parsing_result) = parser.parse(&TLS_HEADER[..100], None, None).unwrap();
+    assert!(left.is_empty());
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    match res.content {
+        TlsRecordContent::Handshake(ref messages) => {
+            assert_eq!(messages[0].message, TlsHandshakeMessage::Incomplete);
+        }
+        _ => panic!("No handshake found"),
+    }
 }
 
 #[test]
@@ -64,3 +176,73 @@ fn parse_tls_failure_too_small() {
     let input = [20, 0];
     assert!(parser.parse(&input, None, None).to_full_result().is_err());
 }
+
+#[test]
+fn parse_tls_success_heartbeat() {
+    let mut parser = TlsParser;
+    let mut input = vec![0x18, 0x03, 0x02, 0x00, 0x17, 0x01, 0x00, 0x04, 0x61, 0x62, 0x63, 0x64];
+    input.extend_from_slice(&[0; 16]);
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.content_type, TlsRecordContentType::Heartbeat);
+    assert_eq!(res.content,
+               TlsRecordContent::Heartbeat(TlsHeartbeat {
+                   message_type: TlsHeartbeatMessageType::Request,
+                   payload_length: 4,
+                   payload: b"abcd".to_vec(),
+                   padding_length: 16,
+                   payload_overflow: false,
+                   padding_shortfall: false,
+               }));
+}
+
+#[test]
+fn parse_tls_success_heartbleed() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&[0x18, 0x03, 0x02, 0x00, 0x03, 0x01, 0x40, 0x00], None, None).unwrap().1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.content,
+               TlsRecordContent::Heartbeat(TlsHeartbeat {
+                   message_type: TlsHeartbeatMessageType::Request,
+                   payload_length: 16384,
+                   payload: vec![],
+                   padding_length: 0,
+                   payload_overflow: true,
+                   padding_shortfall: false,
+               }));
+}
+
+#[test]
+fn parse_tls_success_heartbeat_padding_shortfall() {
+    let mut parser = TlsParser;
+    let input = [0x18, 0x03, 0x02, 0x00, 0x09, 0x01, 0x00, 0x04, 0x61, 0x62, 0x63, 0x64, 0x00, 0x00];
+    let parsing_result = parser.parse(&input, None, None).unwrap().1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    match res.content {
+        TlsRecordContent::Heartbeat(ref heartbeat) => {
+            assert!(!heartbeat.payload_overflow);
+            assert!(heartbeat.padding_shortfall);
+            assert_eq!(heartbeat.padding_length, 2);
+        }
+        _ => panic!("Heartbeat expected"),
+    }
+}
+
+#[test]
+fn parse_tls_success_encrypted_heartbeat() {
+    let mut parser = TlsParser;
+    let parsing_result = parser.parse(&[0x18, 0x03, 0x03, 0x00, 0x03, 0xff, 0x40, 0x00], None, None).unwrap().1;
+    let res: &TlsPacket = parsing_result.downcast_ref().unwrap();
+    assert_eq!(res.content, TlsRecordContent::Opaque);
+}
+
+#[test]
+fn parse_tls_success_application_protocols() {
+    let extensions = vec![TlsExtension {
+                              extension_type: TlsExtension::APPLICATION_LAYER_PROTOCOL_NEGOTIATION,
+                              data: vec![0x00, 0x0c, 0x02, 0x68, 0x32, 0x08, 0x68, 0x74, 0x74, 0x70, 0x2f, 0x31, 0x2e,
+                                         0x31],
+                          }];
+    assert_eq!(TlsExtension::application_protocols(&extensions), vec!["h2", "http/1.1"]);
+    assert!(TlsExtension::application_protocols(&[]).is_empty());
+}
@@ -5,8 +5,9 @@ use peel_ip::prelude::*;
 static UDP_HEADER: &'static [u8] = &[0x00, 0x35, 0xc7, 0xde, 0x00, 0x92, 0xad, 0x1b];
 
 #[test]
+#[cfg(feature = "dns")]
 fn parse_udp_success() {
-    let mut parser = UdpParser;
+    let mut parser = UdpParser::new();
     println!("{}", parser);
     let parsing_result = parser.parse(UDP_HEADER, None, None).unwrap().1;
     let res = parsing_result.downcast_ref();
@@ -18,13 +19,14 @@ fn parse_udp_success() {
                        checksum: 44315,
                    },
                    path_error: None,
+                   protocols: vec![ApplicationProtocol::Dns],
                }),
                res);
 }
 
 #[test]
 fn parse_udp_failure_too_small() {
-    let mut parser = UdpParser;
+    let mut parser = UdpParser::new();
     let mut input = Vec::from(UDP_HEADER);
     input.pop();
     assert!(parser.parse(&input, None, None).to_full_result().is_err());
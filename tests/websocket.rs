@@ -0,0 +1,210 @@
+extern crate nom;
+extern crate peel_ip;
+mod common;
+use common::parse_packet;
+use peel_ip::prelude::*;
+use std::net::{IpAddr, Ipv4Addr};
+
+static TEXT: &'static [u8] = &[0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f];
+
+static TEXT_MASKED: &'static [u8] = &[0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+
+#[test]
+fn parse_websocket_success_text() {
+    let mut parser = WebSocketParser;
+    println!("{}", parser);
+    let res = parser.parse(TEXT, None, None).unwrap().1;
+    assert_eq!(Some(&WebSocketPacket {
+                   frames: vec![WebSocketFrame {
+                                    fin: true,
+                                    reserved: 0,
+                                    opcode: WebSocketOpcode::Text,
+                                    masking_key: None,
+                                    length: 5,
+                                    payload: b"Hello".to_vec(),
+                                }],
+                   messages: vec![WebSocketMessage {
+                                      opcode: WebSocketOpcode::Text,
+                                      data: b"Hello".to_vec(),
+                                      compressed: false,
+                                      content: Some(b"Hello".to_vec()),
+                                  }],
+               }),
+               res.downcast_ref());
+}
+
+#[test]
+fn parse_websocket_success_masked() {
+    let packet = parse_packet::<WebSocketPacket>(WebSocketParser, TEXT_MASKED);
+    assert_eq!(packet.frames[0].masking_key, Some([0x37, 0xfa, 0x21, 0x3d]));
+    assert_eq!(packet.frames[0].payload, b"Hello");
+    assert_eq!(packet.messages[0].text(), Some("Hello"));
+}
+
+#[test]
+fn parse_websocket_success_fragmented() {
+    // A ping is injected between the fragments
+    let input = [0x01, 0x03, 0x48, 0x65, 0x6c, 0x89, 0x00, 0x80, 0x02, 0x6c, 0x6f];
+    let packet = parse_packet::<WebSocketPacket>(WebSocketParser, &input);
+    assert_eq!(packet.frames.len(), 3);
+    assert!(!packet.frames[0].fin);
+    assert_eq!(packet.frames[1].opcode, WebSocketOpcode::Ping);
+    assert_eq!(packet.frames[2].opcode, WebSocketOpcode::Continuation);
+    assert_eq!(packet.messages.len(), 1);
+    assert_eq!(packet.messages[0].text(), Some("Hello"));
+}
+
+#[test]
+fn parse_websocket_success_control_frames() {
+    let input = [0x8a, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58, 0x88, 0x04, 0x03, 0xe8, 0x6f, 0x6b];
+    let packet = parse_packet::<WebSocketPacket>(WebSocketParser, &input);
+    assert_eq!(packet.frames[0].opcode, WebSocketOpcode::Pong);
+    assert_eq!(packet.frames[0].payload, b"Hello");
+    assert_eq!(packet.frames[1].close_status(), Some((1000, "ok".to_owned())));
+    assert!(packet.frames[1].opcode.is_control());
+    assert!(packet.messages.is_empty());
+    assert_eq!(WebSocketOpcode::from_u8(0x3), WebSocketOpcode::Unknown(0x3));
+}
+
+#[test]
+fn parse_websocket_success_extended_lengths() {
+    let mut input = vec![0x82, 0x7e, 0x01, 0x00];
+    input.extend_from_slice(&[0xaa; 0x100]);
+    input.extend_from_slice(&[0x82, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00]);
+    input.extend_from_slice(&[0xbb; 0x10000]);
+    let packet = parse_packet::<WebSocketPacket>(WebSocketParser, &input);
+    assert_eq!(packet.frames[0].length, 0x100);
+    assert_eq!(packet.frames[1].length, 0x10000);
+    assert_eq!(packet.messages[1].opcode, WebSocketOpcode::Binary);
+    assert_eq!(packet.messages[1].data.len(), 0x10000);
+}
+
+#[test]
+fn parse_websocket_success_incomplete_frame_stateless() {
+    let mut parser = WebSocketParser;
+    let mut input = Vec::from(TEXT);
+    input.extend_from_slice(&TEXT_MASKED[..4]);
+    let (left, res) = parser.parse(&input, None, None).unwrap();
+    assert_eq!(res.downcast_ref::<WebSocketPacket>().map(|p| p.frames.len()), Some(1));
+    assert_eq!(left, &TEXT_MASKED[..4]);
+}
+
+#[test]
+fn parse_websocket_success_reassembly() {
+    let mut state = WebSocketState::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+
+    // The frame header and the fragments are split over multiple packets
+    let (_, packet) = WebSocketPacket::parse(&[0x01], Some(&mut state), Some(&sender), false).unwrap();
+    assert!(packet.frames.is_empty());
+    let (_, packet) = WebSocketPacket::parse(&[0x03, 0x48, 0x65, 0x6c], Some(&mut state), Some(&sender), false)
+        .unwrap();
+    assert_eq!(packet.frames.len(), 1);
+    assert!(packet.messages.is_empty());
+    let (left, packet) = WebSocketPacket::parse(&[0x80, 0x02, 0x6c, 0x6f], Some(&mut state), Some(&sender), false)
+        .unwrap();
+    assert!(left.is_empty());
+    assert_eq!(packet.messages[0].text(), Some("Hello"));
+    assert!(state.buffers.is_empty());
+    assert!(state.messages.is_empty());
+}
+
+#[test]
+fn parse_websocket_success_unknown_message_start() {
+    let packet = parse_packet::<WebSocketPacket>(WebSocketParser, &[0x80, 0x02, 0x6c, 0x6f]);
+    assert_eq!(packet.frames.len(), 1);
+    assert!(packet.messages.is_empty());
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn parse_websocket_success_permessage_deflate() {
+    // Single compressed frame
+    let input = [0xc1, 0x07, 0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00];
+    let packet = parse_packet::<WebSocketPacket>(WebSocketParser, &input);
+    assert!(packet.frames[0].is_compressed());
+    assert!(packet.messages[0].compressed);
+    assert_eq!(packet.messages[0].text(), Some("Hello"));
+
+    // Fragmented compressed message
+    let input = [0x41, 0x03, 0xf2, 0x48, 0xcd, 0x80, 0x04, 0xc9, 0xc9, 0x07, 0x00];
+    let packet = parse_packet::<WebSocketPacket>(WebSocketParser, &input);
+    assert_eq!(packet.messages[0].text(), Some("Hello"));
+
+    // Invalid compressed data
+    let packet = parse_packet::<WebSocketPacket>(WebSocketParser, &[0xc1, 0x02, 0xff, 0xff]);
+    assert_eq!(packet.messages[0].content, None);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn parse_websocket_success_context_takeover() {
+    let mut state = WebSocketState::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let first = [0xc1, 0x07, 0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00];
+    let second = [0xc1, 0x05, 0xf2, 0x00, 0x11, 0x00, 0x00];
+
+    // The second message refers to the first one
+    let (_, packet) = WebSocketPacket::parse(&first, Some(&mut state), Some(&sender), true).unwrap();
+    assert_eq!(packet.messages[0].text(), Some("Hello"));
+    let (_, packet) = WebSocketPacket::parse(&second, Some(&mut state), Some(&sender), true).unwrap();
+    assert_eq!(packet.messages[0].text(), Some("Hello"));
+
+    // Without context takeover the window is not kept, so the reference can not be resolved
+    let mut state = WebSocketState::default();
+    WebSocketPacket::parse(&first, Some(&mut state), Some(&sender), false).unwrap();
+    assert_eq!(state.windows.get(&sender), Some(&vec![]));
+    let (_, packet) = WebSocketPacket::parse(&second, Some(&mut state), Some(&sender), false).unwrap();
+    assert_ne!(packet.messages[0].text(), Some("Hello"));
+}
+
+#[test]
+fn parse_websocket_success_deflate_parameters() {
+    let headers = vec![HttpHeader {
+                           key: "Sec-WebSocket-Extensions".to_owned(),
+                           value: "permessage-deflate; client_max_window_bits; server_no_context_takeover".to_owned(),
+                       }];
+    let deflate = WebSocketDeflate::from_headers(&headers).unwrap();
+    assert!(deflate.server_no_context_takeover);
+    assert!(!deflate.client_no_context_takeover);
+    assert!(deflate.context_takeover(true));
+    assert!(!deflate.context_takeover(false));
+    assert_eq!(WebSocketDeflate::from_headers(&[]), None);
+}
+
+#[test]
+fn parse_websocket_success_reassembly_large() {
+    let mut state = WebSocketState::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+
+    // A large message in two fragments, segmented like a TCP stream
+    let fragment = vec![0x61; 0x7_0000];
+    let mut input = vec![0x02, 0x7f, 0, 0, 0, 0, 0, 0x07, 0, 0];
+    input.extend_from_slice(&fragment);
+    input.extend_from_slice(&[0x80, 0x7f, 0, 0, 0, 0, 0, 0x07, 0, 0]);
+    input.extend_from_slice(&fragment);
+    let mut messages = vec![];
+    for segment in input.chunks(1400) {
+        let (_, packet) = WebSocketPacket::parse(segment, Some(&mut state), Some(&sender), false).unwrap();
+        messages.extend(packet.messages);
+    }
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].data.len(), 0xe_0000);
+    assert!(state.buffers.is_empty());
+    assert!(state.messages.is_empty());
+}
+
+#[test]
+fn parse_websocket_failure_oversized_frame() {
+    let mut state = WebSocketState::default();
+    let sender = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 12345);
+    let input = [0x82, 0x7f, 0, 0, 0, 0, 0x10, 0, 0, 0, 0x61, 0x62];
+    let (_, packet) = WebSocketPacket::parse(&input, Some(&mut state), Some(&sender), false).unwrap();
+    assert!(packet.frames.is_empty());
+    assert!(state.buffers.is_empty());
+    assert_eq!(state.desynchronized, vec![sender]);
+
+    // The payload of the frame is not parsed as frames
+    let (_, packet) = WebSocketPacket::parse(&[0x81, 0x01, 0x61], Some(&mut state), Some(&sender), false).unwrap();
+    assert!(packet.frames.is_empty());
+}